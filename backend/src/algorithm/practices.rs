@@ -19,7 +19,7 @@ use rand::{
 
 use crate::{
     AvailabilityWindow, Booking, BusyTeamQueue, CoachConflictLike, FieldLike, Output,
-    PlayableTeamCollection, Reservation, ScheduledInput, TeamLike,
+    PlayableTeamCollection, Reservation, ScheduleMetrics, ScheduledInput, TeamLike,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -41,6 +41,10 @@ impl TeamLike for Team {
 
 pub type ParameterVector = Vec<(TimeSlot, Option<Team>)>;
 
+fn rng(seed: Option<u64>) -> SmallRng {
+    seed.map_or_else(SmallRng::from_entropy, SmallRng::seed_from_u64)
+}
+
 pub struct PracticeScheduleProblem {
     team_collisions: UnGraphMap<Team, ()>,
     rng: Arc<Mutex<SmallRng>>,
@@ -206,7 +210,11 @@ impl Anneal for PracticeScheduleProblem {
 }
 
 /// [`PlayableTeamCollection`] is ignored as practices are scoped by field type, not by arbitrary collections.
-pub fn schedule<T, P, F, C>(input: ScheduledInput<T, P, F, C>) -> Result<Output<T, F>>
+/// `seed` makes the annealing reproducible, otherwise it is seeded from entropy.
+pub fn schedule<T, P, F, C>(
+    input: ScheduledInput<T, P, F, C>,
+    seed: Option<u64>,
+) -> Result<Output<T, F>>
 where
     T: TeamLike + Clone + Debug + PartialEq + Send,
     P: PlayableTeamCollection<Team = T> + Send,
@@ -273,7 +281,7 @@ where
 
     let mut problem = PracticeScheduleProblem {
         team_collisions,
-        rng: Arc::new(Mutex::new(SmallRng::from_entropy())),
+        rng: Arc::new(Mutex::new(rng(seed))),
         teams: teams.into(),
         time_slots: time_slots.clone().into(),
     };
@@ -301,7 +309,8 @@ where
 
     println!("Solving practices (i={max_iters}, temperature={temperature} degrees)");
 
-    let solver = SimulatedAnnealing::new(temperature)?
+    // offset, so the solver doesn't draw the same numbers as the problem
+    let solver = SimulatedAnnealing::new_with_rng(temperature, rng(seed.map(|seed| seed ^ 1)))?
         .with_reannealing_accepted(3 * max_iters / 2)
         .with_reannealing_best(4 * max_iters / 5);
 
@@ -319,10 +328,15 @@ where
                     field: fields[&ts.field_id].clone(),
                 })
                 .collect(),
+            byes: vec![],
+            metrics: ScheduleMetrics::default(),
             unique_id: input.unique_id,
+            rank: 0,
         });
     };
 
+    let booked = winner.iter().filter(|(_, team)| team.is_some()).count();
+
     Ok(Output {
         time_slots: winner
             .iter()
@@ -335,6 +349,9 @@ where
                 field: fields[&ts.field_id].clone(),
             })
             .collect(),
+        byes: vec![],
+        metrics: ScheduleMetrics::practice(booked as f32 / winner.len().max(1) as f32),
         unique_id: input.unique_id,
+        rank: 0,
     })
 }
@@ -1,9 +1,12 @@
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt::Display;
 use std::hash::Hash;
 use std::hash::Hasher;
 use std::num::NonZeroU8;
+use std::num::NonZeroUsize;
 use std::time::Duration;
 use std::time::Instant;
 
@@ -16,6 +19,9 @@ use mcts::transposition_table::*;
 use mcts::tree_policy::*;
 use mcts::*;
 use petgraph::graphmap::UnGraphMap;
+use rand::rngs::SmallRng;
+use rand::seq::IteratorRandom;
+use rand::SeedableRng;
 use tinyvec::tiny_vec;
 use tinyvec::TinyVec;
 
@@ -23,10 +29,24 @@ use crate::window;
 use crate::AvailabilityWindow;
 use crate::CompressionProfile;
 use crate::LossyAvailability;
+use crate::RematchSpacing;
+use crate::SolveOptions;
+use crate::Strategy;
 use crate::TeamLike;
+use crate::Tz;
 
 type TeamId = u8;
 
+/// A round is the 7-day period in which every team is expected to play once.
+/// Rounds are counted from the local date of the earliest time slot of the input.
+pub(crate) const DAYS_IN_ROUND: u16 = 7;
+
+/// Index of the round that this time slot starts in.
+#[inline(always)]
+pub(crate) const fn round_of(slot: &Slot) -> u16 {
+    slot.day / DAYS_IN_ROUND
+}
+
 #[derive(Clone, Copy, Default, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) struct Team {
     id: TeamId,
@@ -108,6 +128,12 @@ impl Game {
     pub const fn team_two(&self) -> &Team {
         &self.team_two
     }
+
+    /// Whether both games are between the same two teams, regardless of home/away.
+    fn same_pairing(&self, other: &Self) -> bool {
+        (self.team_one == other.team_one && self.team_two == other.team_two)
+            || (self.team_one == other.team_two && self.team_two == other.team_one)
+    }
 }
 
 impl Display for Game {
@@ -116,6 +142,23 @@ impl Display for Game {
     }
 }
 
+/// A team sitting out a round because its group has an odd number of teams.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct Bye {
+    round: u16,
+    team: Team,
+}
+
+impl Bye {
+    pub const fn round(&self) -> u16 {
+        self.round
+    }
+
+    pub const fn team(&self) -> &Team {
+        &self.team
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
 pub(crate) struct Reservation {
     slot: Slot,
@@ -136,6 +179,9 @@ impl Reservation {
 pub(crate) struct Slot {
     field_id: u8,
     availability: LossyAvailability,
+    /// Calendar days since the earliest time slot, in the zone of the field. See
+    /// [`CompressionProfile::day_of`].
+    day: u16,
 }
 
 impl Slot {
@@ -157,6 +203,12 @@ pub(crate) struct MCTSState {
     groups: Vec<PlayableGroup>,
     team_collisions: UnGraphMap<Team, ()>,
     teams_len: u8,
+    /// Teams that may not be booked in a given round, see [`MCTSState::assign_byes`]
+    byes: BTreeMap<u16, Vec<Team>>,
+    /// Minimum number of days between two meetings of the same pairing. `0` disables the check.
+    min_rematch_days: u16,
+    /// Strength of every rated team, see [`rating_penalty`]
+    ratings: BTreeMap<Team, i32>,
 }
 
 impl MCTSState {
@@ -190,6 +242,18 @@ impl MCTSState {
         field_id: u8,
         time_slots: impl AsRef<[AvailabilityWindow]>,
         compression_profile: &CompressionProfile,
+    ) {
+        self.add_time_slots_in(field_id, time_slots, compression_profile, &Tz::UTC);
+    }
+
+    /// Same as [`MCTSState::add_time_slots`], but days are counted on the calendar of
+    /// `time_zone`.
+    pub fn add_time_slots_in(
+        &mut self,
+        field_id: u8,
+        time_slots: impl AsRef<[AvailabilityWindow]>,
+        compression_profile: &CompressionProfile,
+        time_zone: &Tz,
     ) {
         for time_slot in time_slots.as_ref() {
             let entry = self
@@ -197,6 +261,7 @@ impl MCTSState {
                 .entry(Slot {
                     field_id,
                     availability: time_slot.as_lossy_window(compression_profile).unwrap(),
+                    day: compression_profile.day_of(time_slot, time_zone),
                 })
                 .or_default();
 
@@ -249,6 +314,88 @@ impl MCTSState {
     pub const fn teams_len(&self) -> u8 {
         self.teams_len
     }
+
+    /// Give one team from every odd-sized group a bye in each round that has time slots.
+    /// Byes rotate through the group in order, so they are spread evenly and no team
+    /// sits out a second time before every team in its group has sat out once.
+    ///
+    /// Must be called after all groups and time slots have been added.
+    pub fn assign_byes(&mut self) {
+        self.byes.clear();
+
+        // slots are ordered by field first, so the same round can show up more than once
+        let rounds = self.games.keys().map(round_of).collect::<BTreeSet<_>>();
+
+        for group in &self.groups {
+            let len = group.teams.len();
+
+            // a single team never plays, so it doesn't make sense to give it byes
+            if len < 3 || len % 2 == 0 {
+                continue;
+            }
+
+            for (i, round) in rounds.iter().enumerate() {
+                self.byes
+                    .entry(*round)
+                    .or_default()
+                    .push(group.teams[i % len].0);
+            }
+        }
+    }
+
+    pub fn set_rating(&mut self, team: TeamId, rating: i32) {
+        self.ratings.insert(Team::new(team), rating);
+    }
+
+    pub fn set_rematch_spacing(&mut self, spacing: RematchSpacing) {
+        self.min_rematch_days = match spacing {
+            RematchSpacing::Days(days) => days,
+            RematchSpacing::Rounds(rounds) => rounds.saturating_mul(DAYS_IN_ROUND),
+        };
+    }
+
+    /// Whether booking `team_one` against `team_two` at `slot` would put this pairing
+    /// closer to one of its other meetings than the configured minimum spacing.
+    fn rematch_too_soon(&self, team_one: &TeamSlot, team_two: &Team, slot: &Slot) -> bool {
+        if self.min_rematch_days == 0 {
+            return false;
+        }
+
+        let candidate = Game {
+            team_one: team_one.0,
+            team_two: *team_two,
+            group_id: NonZeroU8::MIN,
+        };
+
+        team_one.1.iter().any(|booked| {
+            booked.day.abs_diff(slot.day) < self.min_rematch_days
+                && self.games.get(booked).is_some_and(|games| {
+                    games
+                        .iter()
+                        .flatten()
+                        .any(|game| game.same_pairing(&candidate))
+                })
+        })
+    }
+
+    #[inline(always)]
+    fn is_on_bye(&self, team: &Team, slot: &Slot) -> bool {
+        self.byes
+            .get(&round_of(slot))
+            .is_some_and(|teams| teams.contains(team))
+    }
+
+    pub fn byes(&self) -> Vec<Bye> {
+        self.byes
+            .iter()
+            .flat_map(|(round, teams)| {
+                teams.iter().map(|team| Bye {
+                    round: *round,
+                    team: *team,
+                })
+            })
+            .collect()
+    }
 }
 
 impl GameState for MCTSState {
@@ -293,6 +440,14 @@ impl GameState for MCTSState {
                             continue;
                         }
 
+                        if self.is_on_bye(team_one, slot) || self.is_on_bye(team_two, slot) {
+                            continue;
+                        }
+
+                        if self.rematch_too_soon(permutation[0], team_two, slot) {
+                            continue;
+                        }
+
                         let mut t1_iter = t1_avail.iter();
                         let mut t2_iter = t2_avail.iter();
 
@@ -392,7 +547,7 @@ impl Evaluator<SchedulerMCTS> for ScheduleEvaluator {
         moves: &Vec<Reservation>,
         _: Option<SearchHandle<SchedulerMCTS>>,
     ) -> (Vec<()>, i16) {
-        let mut result = 0;
+        let mut result: i16 = 0;
 
         let mut busy: BTreeMap<Team, Vec<Slot>> = BTreeMap::new();
 
@@ -412,6 +567,27 @@ impl Evaluator<SchedulerMCTS> for ScheduleEvaluator {
             }
         }
 
+        if state.min_rematch_days != 0 {
+            let too_soon = rematch_gaps(state)
+                .into_iter()
+                .filter(|gap| *gap < state.min_rematch_days)
+                .count();
+
+            // a long season can have more rematches than an i16 counts
+            let penalty = i16::try_from(too_soon)
+                .unwrap_or(i16::MAX)
+                .saturating_mul(20);
+
+            result = result.saturating_sub(penalty);
+        }
+
+        if state.ratings.len() > 1 {
+            // float to int casts saturate
+            let penalty = (RATING_WEIGHT * rating_penalty(state)).round() as i16;
+
+            result = result.saturating_sub(penalty);
+        }
+
         if !busy.is_empty() {
             let frequency_of_distribution = busy.values().map(Vec::len);
 
@@ -429,7 +605,7 @@ impl Evaluator<SchedulerMCTS> for ScheduleEvaluator {
                     _ => -40,
                 };
 
-                result += weight;
+                result = result.saturating_add(weight);
             }
         }
 
@@ -451,6 +627,88 @@ impl Evaluator<SchedulerMCTS> for ScheduleEvaluator {
     }
 }
 
+/// How much a fully mismatched game in the first round costs, relative to booking a game (`+1`).
+const RATING_WEIGHT: f32 = 3.;
+
+/// Penalty for games that ignore team strength. Ratings are normalized to `0..=1` and
+/// the season progresses from `0` (first round) to `1` (last round). Each game between
+/// two rated teams costs:
+/// - the rating gap, scaled down as the season goes on, so mismatches are avoided early; and
+/// - how far the pairing's average strength is from the season progress, so the strongest
+///   teams meet late in the season.
+fn rating_penalty(state: &MCTSState) -> f32 {
+    let MinMaxResult::MinMax(lowest, highest) = state.ratings.values().minmax() else {
+        return 0.;
+    };
+
+    let spread = (highest - lowest) as f32;
+
+    if spread == 0. {
+        return 0.;
+    }
+
+    let normalized = |team: &Team| {
+        state
+            .ratings
+            .get(team)
+            .map(|rating| (rating - lowest) as f32 / spread)
+    };
+
+    // slots are ordered by field first, so the first and last keys aren't the season's ends
+    let (first_round, last_round) = match state.games.keys().map(round_of).minmax() {
+        MinMaxResult::NoElements => return 0.,
+        MinMaxResult::OneElement(round) => (round, round),
+        MinMaxResult::MinMax(first, last) => (first, last),
+    };
+    let rounds = last_round - first_round;
+
+    let mut penalty = 0.;
+
+    for (slot, games) in &state.games {
+        let progress = if rounds == 0 {
+            0.
+        } else {
+            (round_of(slot) - first_round) as f32 / rounds as f32
+        };
+
+        for game in games.iter().flatten() {
+            let (Some(one), Some(two)) = (normalized(&game.team_one), normalized(&game.team_two))
+            else {
+                continue;
+            };
+
+            penalty += (one - two).abs() * (1. - progress) + ((one + two) / 2. - progress).abs();
+        }
+    }
+
+    penalty
+}
+
+/// Days between consecutive meetings of every pairing that meets more than once.
+fn rematch_gaps(state: &MCTSState) -> Vec<u16> {
+    let mut meetings: BTreeMap<(Team, Team), Vec<u16>> = BTreeMap::new();
+
+    for (slot, games) in &state.games {
+        for game in games.iter().flatten() {
+            let pairing = if game.team_one < game.team_two {
+                (game.team_one, game.team_two)
+            } else {
+                (game.team_two, game.team_one)
+            };
+
+            meetings.entry(pairing).or_default().push(slot.day);
+        }
+    }
+
+    meetings
+        .into_values()
+        .flat_map(|mut days| {
+            days.sort_unstable();
+            days.windows(2).map(|pair| pair[1] - pair[0]).collect_vec()
+        })
+        .collect()
+}
+
 #[derive(Default)]
 struct SchedulerMCTS(usize);
 
@@ -480,8 +738,13 @@ impl MCTS for SchedulerMCTS {
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct Output {
     reservations: Vec<Reservation>,
+    byes: Vec<Bye>,
     time_taken: Duration,
     fillage: f32,
+    /// Days between repeat meetings of a pairing -> how many times that spacing occurs
+    rematch_spacing: BTreeMap<u16, u32>,
+    /// What [`ScheduleEvaluator`] thinks of the final state, higher is better
+    score: i16,
 }
 
 impl Output {
@@ -492,13 +755,47 @@ impl Output {
     pub fn reservations(&self) -> &[Reservation] {
         &self.reservations
     }
+
+    pub fn byes(&self) -> &[Bye] {
+        &self.byes
+    }
+
+    pub const fn fillage(&self) -> f32 {
+        self.fillage
+    }
+
+    pub fn rematch_spacing(&self) -> &BTreeMap<u16, u32> {
+        &self.rematch_spacing
+    }
+
+    pub const fn score(&self) -> i16 {
+        self.score
+    }
+
+    /// Whether both outputs book the same games in the same slots, regardless of order.
+    fn same_bookings(&self, other: &Self) -> bool {
+        self.reservations.len() == other.reservations.len()
+            && self
+                .reservations
+                .iter()
+                .collect::<HashSet<_>>()
+                .is_superset(&other.reservations.iter().collect())
+    }
 }
 
 pub(crate) fn schedule(state: &MCTSState) -> Result<Output> {
-    let mut best: Option<Output> = None;
+    schedule_top_k(state, 1, &SolveOptions::default()).map(|mut outputs| outputs.swap_remove(0))
+}
 
+/// Up to `k` distinct schedules for the same state, best [`Output::score`] first.
+/// Fewer are returned if the search keeps landing on the same schedule.
+pub(crate) fn schedule_top_k(
+    state: &MCTSState,
+    k: usize,
+    options: &SolveOptions,
+) -> Result<Vec<Output>> {
     if state.teams_len() == 0 {
-        return Ok(Output {
+        return Ok(vec![Output {
             fillage: 0.,
             time_taken: Duration::from_millis(0),
             reservations: state
@@ -511,45 +808,103 @@ pub(crate) fn schedule(state: &MCTSState) -> Result<Output> {
                     })
                 })
                 .collect_vec(),
-        });
+            byes: vec![],
+            rematch_spacing: BTreeMap::new(),
+            score: 0,
+        }]);
     }
 
     if state.games.is_empty() {
-        return Ok(Output {
+        return Ok(vec![Output {
             fillage: 0.,
             time_taken: Duration::from_millis(0),
             reservations: vec![],
-        });
+            byes: vec![],
+            rematch_spacing: BTreeMap::new(),
+            score: 0,
+        }]);
     }
 
-    const RETRIES: u8 = 10;
+    const RETRIES: usize = 10;
+
+    let k = k.max(1);
+    let attempts = RETRIES.max(k);
+
+    let time_limit = options
+        .time_limit()
+        .map(|time_limit| time_limit / attempts as u32);
+
+    let mut rng = options
+        .seed()
+        .map_or_else(SmallRng::from_entropy, SmallRng::seed_from_u64);
+
+    let runners = options.runners().map_or_else(
+        || {
+            std::thread::available_parallelism()
+                .expect("could not get thread data")
+                .get()
+        },
+        NonZeroUsize::get,
+    );
+
+    distinct_top_k(k, attempts, || match options.strategy() {
+        Strategy::Mcts => schedule_once(state.clone(), time_limit, runners),
+        Strategy::Greedy => Ok(schedule_greedy(state.clone(), &mut rng)),
+    })
+}
+
+/// Runs `search` up to `attempts` times, or until it found `k` distinct schedules that book
+/// every game, and keeps the best `k` distinct ones.
+fn distinct_top_k(
+    k: usize,
+    attempts: usize,
+    mut search: impl FnMut() -> Result<Output>,
+) -> Result<Vec<Output>> {
+    let mut outputs: Vec<Output> = vec![];
 
-    for i in 1..=RETRIES {
-        let out = schedule_once(state.clone())?;
-        if out.all_booked() {
-            return Ok(out);
+    for i in 1..=attempts {
+        let out = search()?;
+
+        if k == 1 && out.all_booked() {
+            return Ok(vec![out]);
         }
 
-        let percent = out.fillage * 100.;
+        if !out.all_booked() {
+            let percent = out.fillage * 100.;
 
-        log::warn!("Recieved an output that booked {percent:.2}% of all matches, will retry {} more times.", RETRIES - i);
+            log::warn!("Recieved an output that booked {percent:.2}% of all matches, will retry {} more times.", attempts - i);
+        }
 
-        if let Some(ref best_unwrapped) = best {
-            if out.fillage <= best_unwrapped.fillage {
-                log::warn!("Current best is {percent:.2}%");
-                continue;
-            }
+        if outputs.iter().any(|seen| seen.same_bookings(&out)) {
+            log::info!("Discarding a schedule that was already found");
+            continue;
         }
 
-        best = Some(out);
+        outputs.push(out);
+
+        if outputs.iter().filter(|out| out.all_booked()).count() == k {
+            break;
+        }
     }
 
-    log::error!("Returning a sub-optimal schedule: {best:?}");
+    // stable, so ties keep the order they were found in
+    outputs.sort_by_key(|out| std::cmp::Reverse(out.score));
+    outputs.truncate(k);
+
+    if let Some(best) = outputs.first().filter(|best| !best.all_booked()) {
+        log::error!("Returning a sub-optimal schedule: {best:?}");
+    }
 
-    best.ok_or_else(|| unreachable!("the scheduler should have retried"))
+    Ok(outputs)
 }
 
-pub(crate) fn schedule_once(state: MCTSState) -> Result<Output> {
+/// One tree search on `runners` threads. With a `time_limit`, the search runs for that
+/// long instead of a number of iterations picked from the size of the problem.
+pub(crate) fn schedule_once(
+    state: MCTSState,
+    time_limit: Option<Duration>,
+    runners: usize,
+) -> Result<Output> {
     let total_slots = state.games.len();
     let team_len = state.teams_len();
 
@@ -584,15 +939,15 @@ pub(crate) fn schedule_once(state: MCTSState) -> Result<Output> {
         22.. => 2_500_000,
     };
 
-    let runners = std::thread::available_parallelism()
-        .expect("could not get thread data")
-        .get();
-
-    log::info!("Scheduling for {iterations} iterations on {runners} threads.");
-
     let start = Instant::now();
 
-    mcts.playout_n_parallel(iterations, runners);
+    if let Some(time_limit) = time_limit {
+        log::info!("Scheduling for {time_limit:?} on {runners} threads.");
+        mcts.playout_parallel_for(time_limit, runners);
+    } else {
+        log::info!("Scheduling for {iterations} iterations on {runners} threads.");
+        mcts.playout_n_parallel(iterations, runners);
+    }
 
     let end = Instant::now();
 
@@ -601,18 +956,91 @@ pub(crate) fn schedule_once(state: MCTSState) -> Result<Output> {
     log::info!("... Done in {:.3}s", time_taken.as_secs_f32());
 
     let mut result = vec![];
+    let byes = state.byes();
+    let mut final_state = state;
 
     for m in mcts.principal_variation(total_slots) {
+        final_state.make_move(&m);
         result.push(m);
     }
 
+    Ok(finish(final_state, result, byes, total_slots, time_taken))
+}
+
+/// Books the move that the evaluator scores highest until nothing else fits.
+/// Ties are broken with `rng`.
+fn schedule_greedy(state: MCTSState, rng: &mut SmallRng) -> Output {
+    let total_slots = state.games.len();
+    let byes = state.byes();
+
+    let start = Instant::now();
+
+    let mut result = vec![];
+    let mut final_state = state;
+
+    loop {
+        let scored = final_state
+            .available_moves()
+            .into_iter()
+            .map(|m| {
+                let mut next = final_state.clone();
+                next.make_move(&m);
+                let (_, score) = ScheduleEvaluator.evaluate_new_state(&next, &vec![], None);
+                (score, m)
+            })
+            .collect_vec();
+
+        let Some(best) = scored.iter().map(|(score, _)| *score).max() else {
+            break;
+        };
+
+        let Some((_, m)) = scored
+            .into_iter()
+            .filter(|(score, _)| *score == best)
+            .choose(rng)
+        else {
+            unreachable!("the best score belongs to a move");
+        };
+
+        final_state.make_move(&m);
+        result.push(m);
+    }
+
+    let time_taken = Instant::now().duration_since(start);
+
+    log::info!("Greedy search done in {:.3}s", time_taken.as_secs_f32());
+
+    finish(final_state, result, byes, total_slots, time_taken)
+}
+
+/// Measures a finished search. `result` holds the moves that led to `final_state`.
+fn finish(
+    final_state: MCTSState,
+    mut result: Vec<Reservation>,
+    byes: Vec<Bye>,
+    total_slots: usize,
+    time_taken: Duration,
+) -> Output {
     result.sort_by_key(|r| r.slot.availability.0);
 
-    Ok(Output {
+    let mut rematch_spacing = BTreeMap::new();
+
+    for gap in rematch_gaps(&final_state) {
+        *rematch_spacing.entry(gap).or_default() += 1;
+    }
+
+    log::info!("Rematch spacing (days -> occurrences): {rematch_spacing:?}");
+
+    let (_, score) = ScheduleEvaluator.evaluate_new_state(&final_state, &vec![], None);
+
+    Output {
         fillage: result.len() as f32 / total_slots as f32,
         reservations: result,
+        byes,
         time_taken,
-    })
+        rematch_spacing,
+        score,
+    }
 }
 
 pub fn test() -> Result<()> {
@@ -680,6 +1108,7 @@ pub fn test() -> Result<()> {
     }
 
     state.add_group(group_one);
+    state.assign_byes();
 
     let result = schedule(&state)?;
 
@@ -687,3 +1116,361 @@ pub fn test() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, TimeZone, Utc};
+    use chrono_tz::America::Los_Angeles;
+    use mcts::GameState;
+
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    use super::{distinct_top_k, rating_penalty, MCTSState, Output, PlayableGroup};
+    use crate::problem::{
+        ProblemEntry, ProblemField, ProblemTeam, ProblemTeamGroup, ProblemTimeSlot,
+    };
+    use crate::{
+        schedule_with, window, AvailabilityWindow, Booking, CompressionProfile, RematchSpacing,
+        SolveOptions, Strategy,
+    };
+
+    /// Books `early` in the first week and `late` in the second, on `fields[0]` and
+    /// `fields[1]`.
+    fn season(early: (u8, u8), late: (u8, u8), fields: [u8; 2]) -> MCTSState {
+        let mut state = MCTSState::new();
+
+        let profile = CompressionProfile::assume_date(
+            &Utc.with_ymd_and_hms(2024, 9, 7, 9, 0, 0)
+                .earliest()
+                .unwrap(),
+        );
+
+        let weeks = [
+            window!(7/9/2024 from 9:00 to 10:00).unwrap(),
+            window!(14/9/2024 from 9:00 to 10:00).unwrap(),
+        ];
+
+        for (field, week) in fields.into_iter().zip(weeks) {
+            state.add_time_slots(field, [week], &profile);
+        }
+
+        let mut group = PlayableGroup::new(0);
+        for (id, rating) in [(0, 1), (1, 2), (2, 9), (3, 10)] {
+            group.add_team(id);
+            state.set_rating(id, rating);
+        }
+        state.add_group(group);
+
+        // every week has one opening, so the two games land in different weeks
+        for (field, (home, away)) in fields.into_iter().zip([early, late]) {
+            let mov = state
+                .available_moves()
+                .into_iter()
+                .find(|reservation| {
+                    let game = reservation.game().unwrap();
+                    reservation.slot().field_id() == field
+                        && game.team_one().id() == home
+                        && game.team_two().id() == away
+                })
+                .unwrap();
+            state.make_move(&mov);
+        }
+
+        state
+    }
+
+    #[test]
+    fn strongest_teams_meet_late() {
+        let weak_first = season((0, 1), (2, 3), [0, 0]);
+        let strong_first = season((2, 3), (0, 1), [0, 0]);
+
+        assert!(rating_penalty(&weak_first) < rating_penalty(&strong_first));
+    }
+
+    #[test]
+    fn season_spans_every_field() {
+        // the later week is on the field with the lower id
+        let weak_first = season((0, 1), (2, 3), [1, 0]);
+        let strong_first = season((2, 3), (0, 1), [1, 0]);
+
+        assert!(rating_penalty(&weak_first) < rating_penalty(&strong_first));
+        assert_eq!(
+            rating_penalty(&weak_first),
+            rating_penalty(&season((0, 1), (2, 3), [0, 0]))
+        );
+    }
+
+    /// Four teams and three weeks of one field that fits two games at once.
+    fn problem() -> ProblemEntry {
+        const WEEK: i64 = 7 * 24 * 60 * 60;
+
+        ProblemEntry {
+            unique_id: 0,
+            is_practice: false,
+            team_groups: vec![ProblemTeamGroup {
+                teams: (1..=4).map(|id| ProblemTeam { id, rating: None }).collect(),
+            }],
+            fields: vec![ProblemField {
+                id: 1,
+                time_slots: (0..3)
+                    .map(|week| ProblemTimeSlot {
+                        start: 1_725_699_600 + week * WEEK,
+                        end: 1_725_703_200 + week * WEEK,
+                        concurrency: 2,
+                    })
+                    .collect(),
+                time_zone: None,
+            }],
+            coach_conflicts: vec![],
+            rematch_spacing: None,
+            top_k: 1,
+        }
+    }
+
+    #[test]
+    fn seeded_greedy_is_reproducible() {
+        let options = SolveOptions::default()
+            .with_strategy(Strategy::Greedy)
+            .with_seed(Some(7));
+
+        let first = schedule_with(problem().into(), &options).unwrap();
+        let second = schedule_with(problem().into(), &options).unwrap();
+
+        assert_eq!(first, second);
+        assert!(first[0]
+            .time_slots()
+            .iter()
+            .all(|reservation| matches!(reservation.booking(), Booking::Booked { .. })));
+    }
+
+    /// A schedule that only books the `game`th pairing of a four team group, and booked
+    /// everything if `all_booked`.
+    fn output(game: usize, score: i16, all_booked: bool) -> Output {
+        let mut state = MCTSState::new();
+
+        let profile = CompressionProfile::assume_date(
+            &Utc.with_ymd_and_hms(2024, 9, 7, 9, 0, 0)
+                .earliest()
+                .unwrap(),
+        );
+        state.add_time_slots(0, [window!(7/9/2024 from 9:00 to 10:00).unwrap()], &profile);
+
+        let mut group = PlayableGroup::new(0);
+        for id in 0..4 {
+            group.add_team(id);
+        }
+        state.add_group(group);
+
+        Output {
+            reservations: vec![state.available_moves().swap_remove(game)],
+            byes: vec![],
+            time_taken: Duration::ZERO,
+            fillage: if all_booked { 1. } else { 0.5 },
+            rematch_spacing: BTreeMap::new(),
+            score,
+        }
+    }
+
+    #[test]
+    fn top_k_stops_once_k_distinct_schedules_are_found() {
+        let mut found = vec![
+            output(0, 5, true),
+            output(0, 5, true),
+            output(1, 7, true),
+            output(2, 9, true),
+        ]
+        .into_iter();
+        let mut searches = 0;
+
+        let outputs = distinct_top_k(2, 10, || {
+            searches += 1;
+            Ok(found.next().unwrap())
+        })
+        .unwrap();
+
+        assert_eq!(searches, 3);
+        assert_eq!(outputs, [output(1, 7, true), output(0, 5, true)]);
+    }
+
+    #[test]
+    fn top_k_keeps_distinct_schedules_best_score_first() {
+        let mut found = vec![
+            output(0, 1, false),
+            output(1, 3, false),
+            output(0, 1, false),
+            output(2, 2, false),
+        ]
+        .into_iter();
+
+        let outputs = distinct_top_k(3, 4, || Ok(found.next().unwrap())).unwrap();
+
+        assert_eq!(
+            outputs.iter().map(Output::score).collect::<Vec<_>>(),
+            [3, 2, 1]
+        );
+        assert!(outputs
+            .iter()
+            .enumerate()
+            .all(|(i, out)| outputs[..i].iter().all(|seen| !seen.same_bookings(out))));
+    }
+
+    #[test]
+    fn byes_rotate_through_odd_groups() {
+        let mut state = MCTSState::new();
+
+        let profile = CompressionProfile::assume_date(
+            &Utc.with_ymd_and_hms(2024, 9, 7, 9, 0, 0)
+                .earliest()
+                .unwrap(),
+        );
+
+        state.add_time_slots(
+            0,
+            [
+                window!(7/9/2024 from 9:00 to 10:00).unwrap(),
+                window!(14/9/2024 from 9:00 to 10:00).unwrap(),
+                window!(21/9/2024 from 9:00 to 10:00).unwrap(),
+                window!(28/9/2024 from 9:00 to 10:00).unwrap(),
+            ],
+            &profile,
+        );
+
+        let mut odd = PlayableGroup::new(0);
+        for i in 0..3 {
+            odd.add_team(i);
+        }
+        state.add_group(odd);
+
+        let mut even = PlayableGroup::new(3);
+        for i in 3..7 {
+            even.add_team(i);
+        }
+        state.add_group(even);
+
+        state.assign_byes();
+
+        let byes = state
+            .byes()
+            .iter()
+            .map(|bye| (bye.round(), bye.team().id()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(byes, vec![(0, 0), (1, 1), (2, 2), (3, 0)]);
+    }
+
+    #[test]
+    fn byes_follow_rounds_across_fields() {
+        let mut state = MCTSState::new();
+
+        let profile = CompressionProfile::assume_date(
+            &Utc.with_ymd_and_hms(2024, 9, 7, 9, 0, 0)
+                .earliest()
+                .unwrap(),
+        );
+
+        // the later weeks are on the field with the lower id
+        state.add_time_slots(
+            0,
+            [
+                window!(21/9/2024 from 9:00 to 10:00).unwrap(),
+                window!(28/9/2024 from 9:00 to 10:00).unwrap(),
+            ],
+            &profile,
+        );
+        state.add_time_slots(
+            1,
+            [
+                window!(7/9/2024 from 9:00 to 10:00).unwrap(),
+                window!(14/9/2024 from 9:00 to 10:00).unwrap(),
+                window!(21/9/2024 from 11:00 to 12:00).unwrap(),
+            ],
+            &profile,
+        );
+
+        let mut odd = PlayableGroup::new(0);
+        for i in 0..3 {
+            odd.add_team(i);
+        }
+        state.add_group(odd);
+
+        state.assign_byes();
+
+        let byes = state
+            .byes()
+            .iter()
+            .map(|bye| (bye.round(), bye.team().id()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(byes, vec![(0, 0), (1, 1), (2, 2), (3, 0)]);
+    }
+
+    #[test]
+    fn pairing_cannot_repeat_within_spacing() {
+        let mut state = MCTSState::new();
+
+        let profile = CompressionProfile::assume_date(
+            &Utc.with_ymd_and_hms(2024, 9, 7, 9, 0, 0)
+                .earliest()
+                .unwrap(),
+        );
+
+        state.add_time_slots(
+            0,
+            [
+                window!(7/9/2024 from 9:00 to 10:00).unwrap(),
+                window!(10/9/2024 from 9:00 to 10:00).unwrap(),
+                window!(21/9/2024 from 9:00 to 10:00).unwrap(),
+            ],
+            &profile,
+        );
+
+        let mut group = PlayableGroup::new(0);
+        group.add_team(0);
+        group.add_team(1);
+        state.add_group(group);
+        state.set_rematch_spacing(RematchSpacing::Days(7));
+
+        let first = state.available_moves().into_iter().next().unwrap();
+        state.make_move(&first);
+
+        let next_slots = state
+            .available_moves()
+            .into_iter()
+            .map(|reservation| *reservation.slot())
+            .collect::<Vec<_>>();
+
+        assert!(!next_slots.is_empty());
+        assert!(next_slots
+            .iter()
+            .all(|slot| slot.availability().start_data()
+                >= first.slot().availability().start_data() + 2 * 24 * 7));
+    }
+
+    #[test]
+    fn rounds_follow_the_local_calendar() {
+        // DST starts on 10/3/2024 in Los Angeles, so the same time a week later is 167 hours away
+        let first =
+            AvailabilityWindow::single_day_in(&Los_Angeles, 9, 3, 2024, (9, 0), (10, 0)).unwrap();
+        let next_week = first.shifted_in(TimeDelta::days(7), &Los_Angeles).unwrap();
+        let profile = CompressionProfile::assume_date_in(&first.start(), Los_Angeles);
+
+        let mut state = MCTSState::new();
+        state.add_time_slots_in(0, [first, next_week], &profile, &Los_Angeles);
+
+        let mut odd = PlayableGroup::new(0);
+        for i in 0..3 {
+            odd.add_team(i);
+        }
+        state.add_group(odd);
+        state.assign_byes();
+
+        let rounds = state
+            .byes()
+            .iter()
+            .map(|bye| bye.round())
+            .collect::<Vec<_>>();
+
+        assert_eq!(rounds, vec![0, 1]);
+    }
+}
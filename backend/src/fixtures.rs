@@ -0,0 +1,60 @@
+//! Minimal teams, leagues, fields and coach conflicts for tests, identified by their ids only.
+
+use crate::{
+    CoachConflictLike, FieldLike, PlayableTeamCollection, ProtobufAvailabilityWindow, TeamLike,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Team(pub i32);
+
+impl TeamLike for Team {
+    fn unique_id(&self) -> i32 {
+        self.0
+    }
+}
+
+/// A group of teams that play each other.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct League(pub Vec<Team>);
+
+impl PlayableTeamCollection for League {
+    type Team = Team;
+
+    fn teams(&self) -> impl AsRef<[Self::Team]> {
+        &self.0
+    }
+}
+
+/// A field with its time slots and how many games each can hold at once.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Field(pub i32, pub Vec<(ProtobufAvailabilityWindow, u8)>);
+
+impl FieldLike for Field {
+    fn unique_id(&self) -> i32 {
+        self.0
+    }
+
+    fn time_slots(&self) -> impl AsRef<[(ProtobufAvailabilityWindow, u8)]> {
+        &self.1
+    }
+}
+
+/// A coach of every team in it.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Coach(pub Vec<Team>);
+
+impl CoachConflictLike for Coach {
+    type Team = Team;
+
+    fn teams(&self) -> impl AsRef<[Self::Team]> {
+        &self.0
+    }
+
+    fn unique_id(&self) -> i32 {
+        1
+    }
+
+    fn region_id(&self) -> i32 {
+        1
+    }
+}
@@ -1,13 +1,26 @@
 pub mod algorithm;
+pub mod makeups;
+pub mod problem;
+pub mod standings;
+pub mod validation;
+
+#[cfg(test)]
+mod fixtures;
 
 use std::{
     collections::{BTreeMap, HashMap},
     fmt::{Debug, Display},
     hash::Hash,
+    num::NonZeroUsize,
+    time::Duration,
 };
 
 use anyhow::{bail, Context, Result};
-use chrono::{serde::ts_seconds, DateTime, Datelike, TimeDelta, TimeZone, Timelike, Utc};
+use chrono::{
+    serde::ts_seconds, DateTime, Datelike, Days, NaiveDate, NaiveTime, TimeDelta, TimeZone,
+    Timelike, Utc,
+};
+pub use chrono_tz::Tz;
 use itertools::Itertools;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
@@ -23,6 +36,12 @@ pub struct AvailabilityWindow {
 
 pub trait TeamLike {
     fn unique_id(&self) -> i32;
+
+    /// Relative strength of the team, where a higher number is a stronger team.
+    /// Unrated teams do not take part in the strength objective.
+    fn rating(&self) -> Option<i32> {
+        None
+    }
 }
 
 pub type ProtobufAvailabilityWindow = (i64, i64);
@@ -30,9 +49,22 @@ pub type ProtobufAvailabilityWindow = (i64, i64);
 pub trait FieldLike {
     fn unique_id(&self) -> i32;
     fn time_slots(&self) -> impl AsRef<[(ProtobufAvailabilityWindow, u8)]>;
+
+    /// Zone of the region that the field is in. Rules that work per day, like rounds
+    /// and rematch spacing, count days on this zone's calendar.
+    fn time_zone(&self) -> Tz {
+        Tz::UTC
+    }
 }
 
-impl Display for AvailabilityWindow {
+/// An [`AvailabilityWindow`] viewed from a specific time zone.
+/// Created by [`AvailabilityWindow::display_in`].
+pub struct LocalAvailabilityWindow<'a, T: TimeZone> {
+    window: &'a AvailabilityWindow,
+    time_zone: &'a T,
+}
+
+impl<T: TimeZone> Display for LocalAvailabilityWindow<'_, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         fn d<T: TimeZone>(f: &mut std::fmt::Formatter<'_>, date: DateTime<T>) -> std::fmt::Result {
             write!(
@@ -46,12 +78,44 @@ impl Display for AvailabilityWindow {
             )
         }
 
-        d(f, self.start)?;
+        d(f, self.window.start.with_timezone(self.time_zone))?;
         write!(f, " to ")?;
-        d(f, self.end)
+        d(f, self.window.end.with_timezone(self.time_zone))
+    }
+}
+
+impl Display for AvailabilityWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.display_in(&Utc))
     }
 }
 
+/// Move an instant by `delta` as measured on a wall clock in `time_zone`,
+/// so that 9:00 stays 9:00 even if a DST transition happens in between.
+///
+/// If the shifted wall-clock time does not exist (it falls in a "spring forward" gap),
+/// the first valid instant after the gap is used. If it is ambiguous, the earlier instant is used.
+pub fn shift_wall_clock<T: TimeZone>(
+    instant: DateTime<Utc>,
+    delta: TimeDelta,
+    time_zone: &T,
+) -> Option<DateTime<Utc>> {
+    let local = instant
+        .with_timezone(time_zone)
+        .naive_local()
+        .checked_add_signed(delta)?;
+
+    time_zone
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            time_zone
+                .from_local_datetime(&local.checked_add_signed(TimeDelta::hours(1))?)
+                .earliest()
+        })
+        .map(|date| date.to_utc())
+}
+
 impl AvailabilityWindow {
     pub fn single_day(
         day: u32,
@@ -60,14 +124,29 @@ impl AvailabilityWindow {
         start: (u32, u32),
         end: (u32, u32),
     ) -> Result<Self> {
-        let start = Utc
+        Self::single_day_in(&Utc, day, month, year, start, end)
+    }
+
+    /// Same as [`AvailabilityWindow::single_day`], but `start` and `end` are wall-clock
+    /// times in `time_zone` rather than UTC.
+    pub fn single_day_in<T: TimeZone>(
+        time_zone: &T,
+        day: u32,
+        month: u32,
+        year: u32,
+        start: (u32, u32),
+        end: (u32, u32),
+    ) -> Result<Self> {
+        let start = time_zone
             .with_ymd_and_hms(year.try_into()?, month, day, start.0, start.1, 0)
             .earliest()
-            .context("ambiguous start date")?;
-        let end = Utc
+            .context("start time does not exist in this time zone")?
+            .to_utc();
+        let end = time_zone
             .with_ymd_and_hms(year.try_into()?, month, day, end.0, end.1, 0)
             .earliest()
-            .context("ambiguous end date")?;
+            .context("end time does not exist in this time zone")?
+            .to_utc();
 
         if end < start {
             bail!("End time ({end:?}) is before ({start:?})");
@@ -76,6 +155,39 @@ impl AvailabilityWindow {
         Ok(Self { start, end })
     }
 
+    pub const fn start(&self) -> DateTime<Utc> {
+        self.start
+    }
+
+    pub const fn end(&self) -> DateTime<Utc> {
+        self.end
+    }
+
+    /// Format this window using wall-clock times in `time_zone`.
+    pub fn display_in<'a, T: TimeZone>(
+        &'a self,
+        time_zone: &'a T,
+    ) -> LocalAvailabilityWindow<'a, T> {
+        LocalAvailabilityWindow {
+            window: self,
+            time_zone,
+        }
+    }
+
+    /// The calendar day this window starts on, in `time_zone`.
+    /// Use this instead of `self.start().date_naive()` for any rule that works per day.
+    pub fn local_date<T: TimeZone>(&self, time_zone: &T) -> NaiveDate {
+        self.start.with_timezone(time_zone).date_naive()
+    }
+
+    /// Move this window by `delta` on the wall clock of `time_zone`. See [`shift_wall_clock`].
+    pub fn shifted_in<T: TimeZone>(&self, delta: TimeDelta, time_zone: &T) -> Result<Self> {
+        Self::new(
+            shift_wall_clock(self.start, delta, time_zone).context("start out of range")?,
+            shift_wall_clock(self.end, delta, time_zone).context("end out of range")?,
+        )
+    }
+
     pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Self> {
         if end < start {
             bail!("End time ({end:?}) is before ({start:?})");
@@ -224,6 +336,70 @@ mod compressed_availability_window {
     }
 }
 
+#[cfg(test)]
+mod time_zones {
+    use chrono::{TimeDelta, TimeZone};
+    use chrono_tz::America::Los_Angeles;
+
+    use crate::{AvailabilityWindow, CompressionProfile};
+
+    #[test]
+    fn local_day_across_dst() {
+        // DST ends on 11/3/2024 in Los Angeles
+        let before =
+            AvailabilityWindow::single_day_in(&Los_Angeles, 2, 11, 2024, (9, 0), (10, 0)).unwrap();
+        let after = before.shifted_in(TimeDelta::days(7), &Los_Angeles).unwrap();
+
+        assert_eq!(
+            after,
+            AvailabilityWindow::single_day_in(&Los_Angeles, 9, 11, 2024, (9, 0), (10, 0)).unwrap()
+        );
+        assert_eq!(after.start() - before.start(), TimeDelta::hours(7 * 24 + 1));
+        assert_eq!(
+            after.display_in(&Los_Angeles).to_string(),
+            "09:00 9/11/2024 to 10:00 9/11/2024"
+        );
+    }
+
+    #[test]
+    fn local_date_is_not_utc_date() {
+        // 17:00 in Los Angeles is already the next day in UTC
+        let window =
+            AvailabilityWindow::single_day_in(&Los_Angeles, 1, 6, 2024, (17, 0), (18, 0)).unwrap();
+
+        assert_eq!(window.local_date(&Los_Angeles).to_string(), "2024-06-01");
+        assert_eq!(window.start().date_naive().to_string(), "2024-06-02");
+    }
+
+    #[test]
+    fn days_follow_the_local_calendar() {
+        // DST starts on 10/3/2024 in Los Angeles, so the same time a week later is 167 hours away
+        let first =
+            AvailabilityWindow::single_day_in(&Los_Angeles, 9, 3, 2024, (9, 0), (10, 0)).unwrap();
+        let next_week = first.shifted_in(TimeDelta::days(7), &Los_Angeles).unwrap();
+        let profile = CompressionProfile::assume_date_in(&first.start(), Los_Angeles);
+
+        assert_eq!(
+            next_week.start() - first.start(),
+            TimeDelta::hours(7 * 24 - 1)
+        );
+        assert_eq!(profile.day_of(&first, &Los_Angeles), 0);
+        assert_eq!(profile.day_of(&next_week, &Los_Angeles), 7);
+
+        let midnight = |day| {
+            Los_Angeles
+                .with_ymd_and_hms(2024, 3, day, 0, 0, 0)
+                .unwrap()
+                .to_utc()
+        };
+
+        assert_eq!(
+            profile.round_window(1),
+            AvailabilityWindow::new(midnight(16), midnight(23)).unwrap()
+        );
+    }
+}
+
 /// # Lossy compression for a date range.
 ///
 /// Some caveats: compression cannot represent a date range longer than ~3 years.
@@ -253,14 +429,72 @@ mod compressed_availability_window {
 pub struct CompressionProfile {
     /// UNIX timestamp
     earliest_date: i64,
+    /// Zone of the field with the earliest time slot, which decides where day `0` starts
+    time_zone: Tz,
+}
+
+/// The first instant of `date` in `time_zone`. Midnight is skipped by DST in a few
+/// zones, in which case the day starts an hour later.
+fn local_midnight(date: NaiveDate, time_zone: &Tz) -> Option<DateTime<Utc>> {
+    let midnight = date.and_time(NaiveTime::MIN);
+
+    time_zone
+        .from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| {
+            time_zone
+                .from_local_datetime(&midnight.checked_add_signed(TimeDelta::hours(1))?)
+                .earliest()
+        })
+        .map(|date| date.to_utc())
 }
 
 impl CompressionProfile {
     pub fn assume_date(time_slot: &DateTime<Utc>) -> Self {
+        Self::assume_date_in(time_slot, Tz::UTC)
+    }
+
+    /// Same as [`CompressionProfile::assume_date`], but days are counted on the calendar
+    /// of `time_zone`.
+    pub fn assume_date_in(time_slot: &DateTime<Utc>, time_zone: Tz) -> Self {
         Self {
             earliest_date: time_slot.timestamp(),
+            time_zone,
         }
     }
+
+    fn first_day(&self) -> NaiveDate {
+        DateTime::from_timestamp(self.earliest_date, 0)
+            .expect("earliest date came from a valid timestamp")
+            .with_timezone(&self.time_zone)
+            .date_naive()
+    }
+
+    /// Calendar days between the earliest time slot and the start of `window`, both
+    /// counted in `time_zone`.
+    pub(crate) fn day_of(&self, window: &AvailabilityWindow, time_zone: &Tz) -> u16 {
+        let days = (window.local_date(time_zone) - self.first_day()).num_days();
+
+        days.clamp(0, u16::MAX.into()) as u16
+    }
+
+    /// The full span of a scheduler round, from local midnight to local midnight.
+    pub(crate) fn round_window(&self, round: u16) -> AvailabilityWindow {
+        let day = |offset: u16| {
+            self.first_day()
+                .checked_add_days(Days::new(offset.into()))
+                .and_then(|date| local_midnight(date, &self.time_zone))
+                .expect("round is within the compressed range")
+        };
+
+        let first = round.saturating_mul(algorithm::v2::DAYS_IN_ROUND);
+
+        AvailabilityWindow::new(
+            day(first),
+            day(first.saturating_add(algorithm::v2::DAYS_IN_ROUND)),
+        )
+        .expect("rounds move forward in time")
+    }
 }
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, Default)]
@@ -454,6 +688,80 @@ where
     fn region_id(&self) -> i32;
 }
 
+/// How the game scheduler searches for a schedule. Practices always use simulated annealing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Strategy {
+    /// Monte Carlo tree search
+    #[default]
+    Mcts,
+    /// Book whichever game the evaluator likes best at every step. Much faster than
+    /// [`Strategy::Mcts`], but the schedules are usually worse.
+    Greedy,
+}
+
+/// Knobs for a single run of the scheduler, as opposed to the problem being solved.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SolveOptions {
+    strategy: Strategy,
+    seed: Option<u64>,
+    time_limit: Option<Duration>,
+    runners: Option<NonZeroUsize>,
+}
+
+impl SolveOptions {
+    pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Makes the greedy strategy and the practice solver reproducible. Tree search is
+    /// multi-threaded and always varies between runs.
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Upper bound on tree search time for each input, spread across its retries.
+    /// Without one, the search runs for a fixed number of iterations.
+    pub fn with_time_limit(mut self, time_limit: Option<Duration>) -> Self {
+        self.time_limit = time_limit;
+        self
+    }
+
+    /// Threads that each tree search runs on. Defaults to every available core, which
+    /// oversubscribes the machine when several inputs are solved at once.
+    pub fn with_runners(mut self, runners: Option<NonZeroUsize>) -> Self {
+        self.runners = runners;
+        self
+    }
+
+    pub const fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    pub const fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub const fn time_limit(&self) -> Option<Duration> {
+        self.time_limit
+    }
+
+    pub const fn runners(&self) -> Option<NonZeroUsize> {
+        self.runners
+    }
+}
+
+/// The least amount of time allowed between two meetings of the same pair of teams.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RematchSpacing {
+    /// Calendar days in the zone of the field, so a Saturday game is always 7 days
+    /// from the next Saturday, even across DST.
+    Days(u16),
+    /// A round is one week, counted from the local date of the earliest time slot.
+    Rounds(u16),
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ScheduledInput<T, P, F, C>
 where
@@ -467,6 +775,10 @@ where
     unique_id: i32,
     coach_conflicts: Vec<C>,
     is_practice: bool,
+    #[serde(default)]
+    rematch_spacing: Option<RematchSpacing>,
+    #[serde(default)]
+    top_k: u8,
 }
 
 impl<T, P, F, C> ScheduledInput<T, P, F, C>
@@ -488,6 +800,8 @@ where
             fields: fields.as_ref().to_vec(),
             coach_conflicts: coach_conflicts.as_ref().to_vec(),
             is_practice: false,
+            rematch_spacing: None,
+            top_k: 1,
         }
     }
 
@@ -503,33 +817,61 @@ where
             fields: fields.as_ref().to_vec(),
             coach_conflicts: coach_conflicts.as_ref().to_vec(),
             is_practice: true,
+            rematch_spacing: None,
+            top_k: 1,
         }
     }
 
+    /// Keep repeat meetings of the same pairing at least this far apart.
+    /// Has no effect on practices.
+    pub fn with_rematch_spacing(mut self, rematch_spacing: Option<RematchSpacing>) -> Self {
+        self.rematch_spacing = rematch_spacing;
+        self
+    }
+
+    /// Ask [`schedule_alternatives`] for up to this many distinct schedules instead of
+    /// only the best one. Practices always produce a single schedule.
+    pub fn with_top_k(mut self, top_k: u8) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    pub const fn top_k(&self) -> u8 {
+        self.top_k
+    }
+
     pub fn get_compression_profile(&self) -> Result<Option<CompressionProfile>> {
         let earliest_date = self
             .fields
             .iter()
             .flat_map(|field| {
+                let time_zone = field.time_zone();
+
                 field
                     .time_slots()
                     .as_ref()
                     .iter()
-                    .map(|(time_slot, _)| time_slot.0)
+                    .map(|(time_slot, _)| (time_slot.0, time_zone))
                     .collect_vec()
             })
-            .minmax();
+            .minmax_by_key(|(start, _)| *start);
 
         match earliest_date {
             itertools::MinMaxResult::NoElements => Ok(None),
-            itertools::MinMaxResult::OneElement(element) => Ok(Some(CompressionProfile {
-                earliest_date: element,
-            })),
-            itertools::MinMaxResult::MinMax(min, max) => {
+            itertools::MinMaxResult::OneElement((element, time_zone)) => {
+                Ok(Some(CompressionProfile {
+                    earliest_date: element,
+                    time_zone,
+                }))
+            }
+            itertools::MinMaxResult::MinMax((min, time_zone), (max, _)) => {
                 const SECONDS_IN_15_BIT_HOUR_MAX: i64 = ((1 << 15) - 1) * SECONDS_TO_HOURS;
 
                 if let 2..=SECONDS_IN_15_BIT_HOUR_MAX = max - min {
-                    Ok(Some(CompressionProfile { earliest_date: min }))
+                    Ok(Some(CompressionProfile {
+                        earliest_date: min,
+                        time_zone,
+                    }))
                 } else {
                     bail!("Cannot use date compression, as the breadth of input time slots exceeds {SECONDS_IN_15_BIT_HOUR_MAX} seconds (~3.7 years): {min} & {max}");
                 }
@@ -565,7 +907,7 @@ where
                 as_windows.push(AvailabilityWindow::new_unix(start, end)?);
             }
 
-            result.add_time_slots(byte, as_windows, compression_profile);
+            result.add_time_slots_in(byte, as_windows, compression_profile, &field.time_zone());
             scheduler_field_id_to_field_id.insert(byte, field.unique_id());
         }
 
@@ -585,12 +927,21 @@ where
                 playable_group_index_to_team_index.insert(g_id, team.unique_id());
                 team_index_to_playable_group_index.insert(team.unique_id(), g_id);
                 playable_group.add_team(g_id);
+                if let Some(rating) = team.rating() {
+                    result.set_rating(g_id, rating);
+                }
                 this_team_index += 1;
             }
 
             result.add_group(playable_group);
         }
 
+        result.assign_byes();
+
+        if let Some(rematch_spacing) = self.rematch_spacing {
+            result.set_rematch_spacing(rematch_spacing);
+        }
+
         for coach_conflict in self.coach_conflicts() {
             result.add_team_collisions(
                 coach_conflict.teams(),
@@ -628,9 +979,17 @@ where
         result
     }
 
+    pub const fn unique_id(&self) -> i32 {
+        self.unique_id
+    }
+
     pub const fn is_practice(&self) -> bool {
         self.is_practice
     }
+
+    pub const fn rematch_spacing(&self) -> Option<RematchSpacing> {
+        self.rematch_spacing
+    }
 }
 
 #[derive(Debug)]
@@ -712,7 +1071,7 @@ where
     }
 
     fn transform_v2(
-        self,
+        &self,
         input: algorithm::v2::Output,
         compression_profile: &CompressionProfile,
     ) -> Output<T, F> {
@@ -722,9 +1081,27 @@ where
             time_slots.push(self.transform_v2_reservation(reservation, compression_profile));
         }
 
+        let byes = input
+            .byes()
+            .iter()
+            .map(|bye| Bye {
+                team: self
+                    .team_from_schedule_id(bye.team().id())
+                    .expect("team was not mapped properly"),
+                availability: compression_profile.round_window(bye.round()),
+            })
+            .collect();
+
         Output {
             time_slots,
+            byes,
+            metrics: ScheduleMetrics {
+                fillage: input.fillage(),
+                rematch_spacing: input.rematch_spacing().clone(),
+                score: input.score(),
+            },
             unique_id: self.unique_id,
+            rank: 0,
         }
     }
 }
@@ -755,6 +1132,14 @@ where
     T: TeamLike + Clone + Debug + PartialEq,
     F: FieldLike + Clone + Debug + PartialEq,
 {
+    pub const fn new(field: F, availability: AvailabilityWindow, booking: Booking<T>) -> Self {
+        Self {
+            field,
+            availability,
+            booking,
+        }
+    }
+
     pub const fn field(&self) -> &F {
         &self.field
     }
@@ -772,14 +1157,79 @@ where
     }
 }
 
+/// A team that sits out a whole round, because its group has an odd number of teams.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Bye<T>
+where
+    T: TeamLike + Clone + Debug + PartialEq,
+{
+    team: T,
+    availability: AvailabilityWindow,
+}
+
+impl<T> Bye<T>
+where
+    T: TeamLike + Clone + Debug + PartialEq,
+{
+    pub const fn team(&self) -> &T {
+        &self.team
+    }
+
+    pub const fn start(&self) -> i64 {
+        self.availability.start.timestamp()
+    }
+
+    pub const fn end(&self) -> i64 {
+        self.availability.end.timestamp()
+    }
+}
+
+/// Quality measurements for a finished schedule.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleMetrics {
+    /// Fraction of the available time slots that were booked, from `0` to `1`
+    fillage: f32,
+    /// Days between repeat meetings of a pairing -> how many times that spacing occurs
+    rematch_spacing: BTreeMap<u16, u32>,
+    /// Evaluator score used to rank alternative schedules, higher is better
+    #[serde(default)]
+    score: i16,
+}
+
+impl ScheduleMetrics {
+    /// Practices never pair teams up, so only fillage applies.
+    pub(crate) fn practice(fillage: f32) -> Self {
+        Self {
+            fillage,
+            ..Default::default()
+        }
+    }
+
+    pub const fn fillage(&self) -> f32 {
+        self.fillage
+    }
+
+    pub const fn rematch_spacing(&self) -> &BTreeMap<u16, u32> {
+        &self.rematch_spacing
+    }
+
+    pub const fn score(&self) -> i16 {
+        self.score
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Output<T, F>
 where
     T: TeamLike + Clone + Debug + PartialEq,
     F: FieldLike + Clone + Debug + PartialEq,
 {
     time_slots: Vec<Reservation<T, F>>,
+    byes: Vec<Bye<T>>,
+    metrics: ScheduleMetrics,
     unique_id: i32,
+    /// Position among the alternatives for the same input, `0` being the best
+    rank: u8,
 }
 
 impl<T, F> Output<T, F>
@@ -791,9 +1241,21 @@ where
         &self.time_slots
     }
 
+    pub fn byes(&self) -> &[Bye<T>] {
+        &self.byes
+    }
+
+    pub const fn metrics(&self) -> &ScheduleMetrics {
+        &self.metrics
+    }
+
     pub const fn unique_id(&self) -> i32 {
         self.unique_id
     }
+
+    pub const fn rank(&self) -> u8 {
+        self.rank
+    }
 }
 
 #[derive(Default)]
@@ -837,6 +1299,34 @@ impl BusyTeamQueue {
 }
 
 pub fn schedule<T, P, F, C>(input: ScheduledInput<T, P, F, C>) -> Result<Output<T, F>>
+where
+    T: TeamLike + Clone + Debug + PartialEq + Send,
+    P: PlayableTeamCollection<Team = T> + Send,
+    F: FieldLike + Clone + Debug + PartialEq + Send,
+    C: CoachConflictLike + Send,
+{
+    schedule_alternatives(input.with_top_k(1)).map(|mut outputs| outputs.swap_remove(0))
+}
+
+/// Up to [`ScheduledInput::top_k`] distinct schedules, ranked by evaluator score.
+/// There is always at least one output, and [`Output::rank`] is `0` for the best.
+pub fn schedule_alternatives<T, P, F, C>(
+    input: ScheduledInput<T, P, F, C>,
+) -> Result<Vec<Output<T, F>>>
+where
+    T: TeamLike + Clone + Debug + PartialEq + Send,
+    P: PlayableTeamCollection<Team = T> + Send,
+    F: FieldLike + Clone + Debug + PartialEq + Send,
+    C: CoachConflictLike + Send,
+{
+    schedule_with(input, &SolveOptions::default())
+}
+
+/// [`schedule_alternatives`], with control over how the scheduler searches.
+pub fn schedule_with<T, P, F, C>(
+    input: ScheduledInput<T, P, F, C>,
+    options: &SolveOptions,
+) -> Result<Vec<Output<T, F>>>
 where
     T: TeamLike + Clone + Debug + PartialEq + Send,
     P: PlayableTeamCollection<Team = T> + Send,
@@ -844,11 +1334,11 @@ where
     C: CoachConflictLike + Send,
 {
     if input.is_practice() {
-        return algorithm::practices::schedule(input);
+        return algorithm::practices::schedule(input, options.seed).map(|output| vec![output]);
     }
 
     let Some(compression_profile) = input.get_compression_profile()? else {
-        return Ok(Output {
+        return Ok(vec![Output {
             time_slots: input
                 .fields()
                 .iter()
@@ -866,12 +1356,24 @@ where
                         .collect_vec()
                 })
                 .collect_vec(),
+            byes: vec![],
+            metrics: ScheduleMetrics::default(),
             unique_id: input.unique_id,
-        });
+            rank: 0,
+        }]);
     };
 
+    let top_k = input.top_k.into();
     let transformer = input.into_transformer(&compression_profile)?;
 
-    let output = algorithm::v2::schedule(transformer.scheduler_state())?;
-    Ok(transformer.transform_v2(output, &compression_profile))
+    let outputs = algorithm::v2::schedule_top_k(transformer.scheduler_state(), top_k, options)?;
+
+    Ok(outputs
+        .into_iter()
+        .zip(0..)
+        .map(|(output, rank)| Output {
+            rank,
+            ..transformer.transform_v2(output, &compression_profile)
+        })
+        .collect())
 }
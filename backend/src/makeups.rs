@@ -0,0 +1,342 @@
+//! Places games that were called off, like rainouts, into what is left of the fields'
+//! availability, without moving any other game.
+
+use std::collections::HashSet;
+
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+
+use crate::{AvailabilityWindow, CoachConflictLike, TeamLike};
+
+/// Search steps before the best plan found so far is returned.
+const MAX_STEPS: usize = 100_000;
+
+/// A time slot that makeup games can start at, with how many games it allows at once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MakeupSlot {
+    field: i32,
+    window: AvailabilityWindow,
+    concurrency: u8,
+}
+
+impl MakeupSlot {
+    pub fn new(field: i32, window: AvailabilityWindow, concurrency: u8) -> Self {
+        Self {
+            field,
+            window,
+            concurrency,
+        }
+    }
+}
+
+/// A game that stays where it is. Teams are referred to by their `unique_id`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FixedGame {
+    field: i32,
+    window: AvailabilityWindow,
+    teams: Vec<i32>,
+}
+
+impl FixedGame {
+    pub fn new(field: i32, window: AvailabilityWindow, teams: Vec<i32>) -> Self {
+        Self {
+            field,
+            window,
+            teams,
+        }
+    }
+}
+
+/// A game to place. It keeps its length, and starts at the start of one of `slots`, which
+/// are indices into the slots given to [`place_makeups`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MakeupGame {
+    id: i32,
+    home: i32,
+    away: i32,
+    length: TimeDelta,
+    slots: Vec<usize>,
+}
+
+impl MakeupGame {
+    pub fn new(id: i32, home: i32, away: i32, length: TimeDelta, slots: Vec<usize>) -> Self {
+        Self {
+            id,
+            home,
+            away,
+            length,
+            slots,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MakeupAssignment {
+    game: i32,
+    field: i32,
+    window: AvailabilityWindow,
+}
+
+impl MakeupAssignment {
+    pub fn game(&self) -> i32 {
+        self.game
+    }
+
+    pub fn field(&self) -> i32 {
+        self.field
+    }
+
+    pub fn window(&self) -> &AvailabilityWindow {
+        &self.window
+    }
+}
+
+/// Where every game that could be placed goes, by game id.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MakeupPlan {
+    assignments: Vec<MakeupAssignment>,
+    /// Games that did not fit anywhere
+    unplaced: Vec<i32>,
+}
+
+impl MakeupPlan {
+    pub fn assignments(&self) -> &[MakeupAssignment] {
+        &self.assignments
+    }
+
+    pub fn unplaced(&self) -> &[i32] {
+        &self.unplaced
+    }
+}
+
+/// Unlike [`AvailabilityWindow::overlap_fast`], back-to-back windows do not overlap.
+fn overlaps(lhs: &AvailabilityWindow, rhs: &AvailabilityWindow) -> bool {
+    lhs.start < rhs.end && rhs.start < lhs.end
+}
+
+struct Search<'a> {
+    slots: &'a [MakeupSlot],
+    fixed: &'a [FixedGame],
+    /// Per game, the teams that must be free while it is played
+    busy_teams: Vec<HashSet<i32>>,
+    /// Per game, the slots and windows it can be played in, earliest first
+    candidates: Vec<Vec<(usize, AvailabilityWindow)>>,
+    games: Vec<&'a MakeupGame>,
+    placed: Vec<Option<(usize, AvailabilityWindow)>>,
+    best: Vec<Option<(usize, AvailabilityWindow)>>,
+    steps: usize,
+}
+
+impl Search<'_> {
+    fn fits(&self, game: usize, slot: usize, window: &AvailabilityWindow) -> bool {
+        let field = self.slots[slot].field;
+
+        let placed = self
+            .placed
+            .iter()
+            .enumerate()
+            .filter_map(|(i, placed)| placed.as_ref().map(|(slot, window)| (i, *slot, window)));
+
+        let booked = self
+            .fixed
+            .iter()
+            .filter(|fixed| fixed.field == field && overlaps(&fixed.window, window))
+            .count()
+            + placed
+                .clone()
+                .filter(|(_, slot, other)| {
+                    self.slots[*slot].field == field && overlaps(other, window)
+                })
+                .count();
+
+        if booked >= usize::from(self.slots[slot].concurrency) {
+            return false;
+        }
+
+        let busy = &self.busy_teams[game];
+
+        let fixed_clash = self.fixed.iter().any(|fixed| {
+            overlaps(&fixed.window, window) && fixed.teams.iter().any(|team| busy.contains(team))
+        });
+
+        let placed_clash = placed.into_iter().any(|(other, _, other_window)| {
+            overlaps(other_window, window)
+                && [self.games[other].home, self.games[other].away]
+                    .iter()
+                    .any(|team| busy.contains(team))
+        });
+
+        !fixed_clash && !placed_clash
+    }
+
+    /// Depth first, trying to place every game before leaving any out.
+    fn run(&mut self, game: usize) {
+        let count = |plan: &[Option<_>]| plan.iter().filter(|placed| placed.is_some()).count();
+
+        if game == self.games.len() {
+            if count(&self.placed) > count(&self.best) {
+                self.best = self.placed.clone();
+            }
+            return;
+        }
+
+        // the best plan cannot improve from here
+        if count(&self.placed) + (self.games.len() - game) <= count(&self.best) {
+            return;
+        }
+
+        for i in 0..self.candidates[game].len() {
+            if self.steps >= MAX_STEPS || count(&self.best) == self.games.len() {
+                return;
+            }
+            self.steps += 1;
+
+            let (slot, window) = self.candidates[game][i].clone();
+
+            if self.fits(game, slot, &window) {
+                self.placed[game] = Some((slot, window));
+                self.run(game + 1);
+                self.placed[game] = None;
+            }
+        }
+
+        self.run(game + 1);
+    }
+}
+
+/// Places as many of `games` as possible, as early as possible. A game can only go where
+/// its slot has room left, and where neither of its teams, nor a team that shares a
+/// coach with one of them, is playing at the same time.
+pub fn place_makeups<C>(
+    slots: &[MakeupSlot],
+    fixed: &[FixedGame],
+    coach_conflicts: &[C],
+    games: &[MakeupGame],
+) -> MakeupPlan
+where
+    C: CoachConflictLike,
+{
+    let coach_conflicts = coach_conflicts
+        .iter()
+        .map(|coach_conflict| {
+            coach_conflict
+                .teams()
+                .as_ref()
+                .iter()
+                .map(TeamLike::unique_id)
+                .collect::<HashSet<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let candidates = |game: &MakeupGame| {
+        let mut candidates = game
+            .slots
+            .iter()
+            .filter_map(|&slot| {
+                let available = &slots.get(slot)?.window;
+                let window =
+                    AvailabilityWindow::new(available.start, available.start + game.length).ok()?;
+
+                (window.end <= available.end).then_some((slot, window))
+            })
+            .collect::<Vec<_>>();
+
+        candidates.sort_by_key(|(slot, window)| (window.clone(), slots[*slot].field));
+        candidates
+    };
+
+    // the games with the fewest options go first
+    let mut games = games.iter().collect::<Vec<_>>();
+    games.sort_by_key(|game| (candidates(game).len(), game.id));
+
+    let busy_teams = games
+        .iter()
+        .map(|game| {
+            let mut busy = HashSet::from([game.home, game.away]);
+
+            for coach_conflict in &coach_conflicts {
+                if coach_conflict.contains(&game.home) || coach_conflict.contains(&game.away) {
+                    busy.extend(coach_conflict);
+                }
+            }
+
+            busy
+        })
+        .collect();
+
+    let mut search = Search {
+        slots,
+        fixed,
+        busy_teams,
+        candidates: games.iter().map(|game| candidates(game)).collect(),
+        placed: vec![None; games.len()],
+        best: vec![None; games.len()],
+        games,
+        steps: 0,
+    };
+
+    search.run(0);
+
+    let mut plan = MakeupPlan::default();
+
+    for (game, placed) in search.games.iter().zip(search.best) {
+        match placed {
+            Some((slot, window)) => plan.assignments.push(MakeupAssignment {
+                game: game.id,
+                field: slots[slot].field,
+                window,
+            }),
+            None => plan.unplaced.push(game.id),
+        }
+    }
+
+    plan.assignments.sort_by_key(|assignment| assignment.game);
+    plan.unplaced.sort_unstable();
+
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::{place_makeups, FixedGame, MakeupGame, MakeupSlot};
+    use crate::fixtures::{Coach, Team};
+    use crate::window;
+
+    #[test]
+    fn fills_free_capacity_around_fixed_games() {
+        let nine = window!(7/9/2024 from 9:00 to 10:00).unwrap();
+        let ten = window!(7/9/2024 from 10:00 to 11:00).unwrap();
+        let eleven = window!(7/9/2024 from 11:00 to 12:00).unwrap();
+
+        let slots = [
+            MakeupSlot::new(1, nine.clone(), 1),
+            MakeupSlot::new(1, ten.clone(), 2),
+            MakeupSlot::new(1, eleven.clone(), 1),
+        ];
+        let fixed = [FixedGame::new(1, nine, vec![1, 2])];
+        let coaches = [Coach(vec![Team(1), Team(5)])];
+
+        let hour = TimeDelta::hours(1);
+        let games = [
+            MakeupGame::new(10, 1, 3, hour, vec![0, 1, 2]),
+            MakeupGame::new(11, 2, 4, hour, vec![0, 1, 2]),
+            // shares a coach with team 1, so it cannot be played at ten
+            MakeupGame::new(12, 5, 6, hour, vec![0, 1, 2]),
+            // only fits where a fixed game already is
+            MakeupGame::new(13, 7, 8, hour, vec![0]),
+        ];
+
+        let plan = place_makeups(&slots, &fixed, &coaches, &games);
+
+        let placed = plan
+            .assignments()
+            .iter()
+            .map(|assignment| (assignment.game(), assignment.window().clone()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(placed, vec![(10, ten.clone()), (11, ten), (12, eleven)]);
+        assert_eq!(plan.unplaced(), [13]);
+    }
+}
@@ -0,0 +1,528 @@
+//! A self-contained JSON format for scheduling problems and their results.
+//!
+//! [`ScheduledInput`] and [`Output`] are generic over whatever the caller uses for teams
+//! and fields, so they can't be written to disk as-is. A [`ProblemFile`] is a snapshot of
+//! the inputs with everything reduced to ids and UNIX timestamps, which can be attached
+//! to bug reports or solved somewhere else. A [`ResultFile`] holds what came out.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    AvailabilityWindow, Booking, Bye, CoachConflictLike, FieldLike, Output, PlayableTeamCollection,
+    ProtobufAvailabilityWindow, RematchSpacing, Reservation, ScheduleMetrics, ScheduledInput,
+    TeamLike, Tz,
+};
+
+/// Bump this when the format changes, and teach [`ProblemFile::from_json`] and
+/// [`ResultFile::from_json`] to read the older version.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Error, Debug)]
+pub enum ProblemFileError {
+    #[error("file format version {0} is newer than this build supports ({FORMAT_VERSION})")]
+    UnsupportedVersion(u32),
+    #[error("malformed file: {0}")]
+    Malformed(#[from] serde_json::Error),
+    #[error("reservation refers to field {0}, which is not part of the problem")]
+    UnknownField(i32),
+    #[error("invalid time range: {0}")]
+    InvalidTime(String),
+}
+
+/// Only the version is read first, so that a future format can be told apart before
+/// the rest of the file is parsed.
+#[derive(Deserialize)]
+struct Versioned {
+    version: u32,
+}
+
+fn check_version(json: &str) -> Result<(), ProblemFileError> {
+    let Versioned { version } = serde_json::from_str(json)?;
+
+    if version > FORMAT_VERSION {
+        return Err(ProblemFileError::UnsupportedVersion(version));
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProblemTeam {
+    pub id: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<i32>,
+}
+
+impl TeamLike for ProblemTeam {
+    fn unique_id(&self) -> i32 {
+        self.id
+    }
+
+    fn rating(&self) -> Option<i32> {
+        self.rating
+    }
+}
+
+impl ProblemTeam {
+    fn from_team_like(team: &impl TeamLike) -> Self {
+        Self {
+            id: team.unique_id(),
+            rating: team.rating(),
+        }
+    }
+}
+
+/// Start and end are UNIX timestamps in seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProblemTimeSlot {
+    pub start: i64,
+    pub end: i64,
+    pub concurrency: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProblemField {
+    pub id: i32,
+    pub time_slots: Vec<ProblemTimeSlot>,
+    /// IANA name of the field's time zone. Files without one are read as UTC.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_zone: Option<String>,
+}
+
+impl FieldLike for ProblemField {
+    fn unique_id(&self) -> i32 {
+        self.id
+    }
+
+    fn time_slots(&self) -> impl AsRef<[(ProtobufAvailabilityWindow, u8)]> {
+        self.time_slots
+            .iter()
+            .map(|time_slot| ((time_slot.start, time_slot.end), time_slot.concurrency))
+            .collect::<Vec<_>>()
+    }
+
+    fn time_zone(&self) -> Tz {
+        self.time_zone
+            .as_deref()
+            .and_then(|name| name.parse().ok())
+            .unwrap_or(Tz::UTC)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProblemTeamGroup {
+    pub teams: Vec<ProblemTeam>,
+}
+
+impl PlayableTeamCollection for ProblemTeamGroup {
+    type Team = ProblemTeam;
+
+    fn teams(&self) -> impl AsRef<[Self::Team]> {
+        &self.teams
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProblemCoachConflict {
+    pub id: i32,
+    pub region_id: i32,
+    pub teams: Vec<ProblemTeam>,
+}
+
+impl CoachConflictLike for ProblemCoachConflict {
+    type Team = ProblemTeam;
+
+    fn teams(&self) -> impl AsRef<[Self::Team]> {
+        &self.teams
+    }
+
+    fn unique_id(&self) -> i32 {
+        self.id
+    }
+
+    fn region_id(&self) -> i32 {
+        self.region_id
+    }
+}
+
+pub type ProblemInput =
+    ScheduledInput<ProblemTeam, ProblemTeamGroup, ProblemField, ProblemCoachConflict>;
+
+/// One [`ScheduledInput`], written out with concrete types.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProblemEntry {
+    pub unique_id: i32,
+    pub is_practice: bool,
+    pub team_groups: Vec<ProblemTeamGroup>,
+    pub fields: Vec<ProblemField>,
+    pub coach_conflicts: Vec<ProblemCoachConflict>,
+    #[serde(default)]
+    pub rematch_spacing: Option<RematchSpacing>,
+    #[serde(default = "default_top_k")]
+    pub top_k: u8,
+}
+
+const fn default_top_k() -> u8 {
+    1
+}
+
+impl<T, P, F, C> From<&ScheduledInput<T, P, F, C>> for ProblemEntry
+where
+    T: TeamLike + Clone + Debug + PartialEq + Send,
+    P: PlayableTeamCollection<Team = T> + Send,
+    F: FieldLike + Clone + Debug + PartialEq + Send,
+    C: CoachConflictLike + Send,
+{
+    fn from(value: &ScheduledInput<T, P, F, C>) -> Self {
+        Self {
+            unique_id: value.unique_id,
+            is_practice: value.is_practice,
+            team_groups: value
+                .team_groups
+                .iter()
+                .map(|group| ProblemTeamGroup {
+                    teams: group
+                        .teams()
+                        .as_ref()
+                        .iter()
+                        .map(ProblemTeam::from_team_like)
+                        .collect(),
+                })
+                .collect(),
+            fields: value
+                .fields
+                .iter()
+                .map(|field| ProblemField {
+                    id: field.unique_id(),
+                    time_slots: field
+                        .time_slots()
+                        .as_ref()
+                        .iter()
+                        .map(|((start, end), concurrency)| ProblemTimeSlot {
+                            start: *start,
+                            end: *end,
+                            concurrency: *concurrency,
+                        })
+                        .collect(),
+                    time_zone: Some(field.time_zone().name().to_owned()),
+                })
+                .collect(),
+            coach_conflicts: value
+                .coach_conflicts
+                .iter()
+                .map(|coach_conflict| ProblemCoachConflict {
+                    id: coach_conflict.unique_id(),
+                    region_id: coach_conflict.region_id(),
+                    teams: coach_conflict
+                        .teams()
+                        .as_ref()
+                        .iter()
+                        .map(ProblemTeam::from_team_like)
+                        .collect(),
+                })
+                .collect(),
+            rematch_spacing: value.rematch_spacing,
+            top_k: value.top_k,
+        }
+    }
+}
+
+impl From<ProblemEntry> for ProblemInput {
+    fn from(value: ProblemEntry) -> Self {
+        let constructor = if value.is_practice {
+            ScheduledInput::new_practice
+        } else {
+            ScheduledInput::new
+        };
+
+        constructor(
+            value.unique_id,
+            value.team_groups,
+            value.fields,
+            value.coach_conflicts,
+        )
+        .with_rematch_spacing(value.rematch_spacing)
+        .with_top_k(value.top_k)
+    }
+}
+
+/// Every input of one scheduling run.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProblemFile {
+    pub version: u32,
+    pub inputs: Vec<ProblemEntry>,
+}
+
+impl ProblemFile {
+    pub fn new<'a, T, P, F, C>(
+        inputs: impl IntoIterator<Item = &'a ScheduledInput<T, P, F, C>>,
+    ) -> Self
+    where
+        T: TeamLike + Clone + Debug + PartialEq + Send + 'a,
+        P: PlayableTeamCollection<Team = T> + Send + 'a,
+        F: FieldLike + Clone + Debug + PartialEq + Send + 'a,
+        C: CoachConflictLike + Send + 'a,
+    {
+        Self {
+            version: FORMAT_VERSION,
+            inputs: inputs.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ProblemFileError> {
+        check_version(json)?;
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, ProblemFileError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn into_inputs(self) -> Vec<ProblemInput> {
+        self.inputs.into_iter().map(Into::into).collect()
+    }
+
+    /// The fields of every input, so that results can be turned back into [`Output`]s.
+    pub fn fields(&self) -> HashMap<i32, &ProblemField> {
+        self.inputs
+            .iter()
+            .flat_map(|input| &input.fields)
+            .map(|field| (field.id, field))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ResultBooking {
+    Empty,
+    Game { home_team: i32, away_team: i32 },
+    Practice { team: i32 },
+}
+
+/// Start and end are UNIX timestamps in seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ResultReservation {
+    pub field: i32,
+    pub start: i64,
+    pub end: i64,
+    pub booking: ResultBooking,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ResultBye {
+    pub team: i32,
+    pub start: i64,
+    pub end: i64,
+}
+
+/// One [`Output`], written out with concrete types.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResultEntry {
+    pub unique_id: i32,
+    #[serde(default)]
+    pub rank: u8,
+    pub reservations: Vec<ResultReservation>,
+    #[serde(default)]
+    pub byes: Vec<ResultBye>,
+    #[serde(default)]
+    pub metrics: ScheduleMetrics,
+}
+
+impl<T, F> From<&Output<T, F>> for ResultEntry
+where
+    T: TeamLike + Clone + Debug + PartialEq,
+    F: FieldLike + Clone + Debug + PartialEq,
+{
+    fn from(value: &Output<T, F>) -> Self {
+        Self {
+            unique_id: value.unique_id,
+            rank: value.rank,
+            reservations: value
+                .time_slots
+                .iter()
+                .map(|reservation| ResultReservation {
+                    field: reservation.field.unique_id(),
+                    start: reservation.start(),
+                    end: reservation.end(),
+                    booking: match &reservation.booking {
+                        Booking::Empty => ResultBooking::Empty,
+                        Booking::Booked {
+                            home_team,
+                            away_team,
+                        } => ResultBooking::Game {
+                            home_team: home_team.unique_id(),
+                            away_team: away_team.unique_id(),
+                        },
+                        Booking::Practice(team) => ResultBooking::Practice {
+                            team: team.unique_id(),
+                        },
+                    },
+                })
+                .collect(),
+            byes: value
+                .byes
+                .iter()
+                .map(|bye| ResultBye {
+                    team: bye.team.unique_id(),
+                    start: bye.start(),
+                    end: bye.end(),
+                })
+                .collect(),
+            metrics: value.metrics.clone(),
+        }
+    }
+}
+
+impl ResultEntry {
+    /// Rebuilds the [`Output`], looking fields up in the problem that was solved.
+    /// Team ratings are not part of a result, so the teams come back unrated.
+    pub fn into_output(
+        self,
+        fields: &HashMap<i32, &ProblemField>,
+    ) -> Result<Output<ProblemTeam, ProblemField>, ProblemFileError> {
+        let team = |id| ProblemTeam { id, rating: None };
+        let window = |start, end| {
+            AvailabilityWindow::new_unix(start, end)
+                .map_err(|e| ProblemFileError::InvalidTime(e.to_string()))
+        };
+
+        let time_slots = self
+            .reservations
+            .into_iter()
+            .map(|reservation| {
+                let field = fields
+                    .get(&reservation.field)
+                    .ok_or(ProblemFileError::UnknownField(reservation.field))?;
+
+                Ok(Reservation {
+                    field: (*field).clone(),
+                    availability: window(reservation.start, reservation.end)?,
+                    booking: match reservation.booking {
+                        ResultBooking::Empty => Booking::Empty,
+                        ResultBooking::Game {
+                            home_team,
+                            away_team,
+                        } => Booking::Booked {
+                            home_team: team(home_team),
+                            away_team: team(away_team),
+                        },
+                        ResultBooking::Practice { team: id } => Booking::Practice(team(id)),
+                    },
+                })
+            })
+            .collect::<Result<Vec<_>, ProblemFileError>>()?;
+
+        let byes = self
+            .byes
+            .into_iter()
+            .map(|bye| {
+                Ok(Bye {
+                    team: team(bye.team),
+                    availability: window(bye.start, bye.end)?,
+                })
+            })
+            .collect::<Result<Vec<_>, ProblemFileError>>()?;
+
+        Ok(Output {
+            time_slots,
+            byes,
+            metrics: self.metrics,
+            unique_id: self.unique_id,
+            rank: self.rank,
+        })
+    }
+}
+
+/// Every output of one scheduling run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResultFile {
+    pub version: u32,
+    pub outputs: Vec<ResultEntry>,
+}
+
+impl ResultFile {
+    pub fn new<'a, T, F>(outputs: impl IntoIterator<Item = &'a Output<T, F>>) -> Self
+    where
+        T: TeamLike + Clone + Debug + PartialEq + 'a,
+        F: FieldLike + Clone + Debug + PartialEq + 'a,
+    {
+        Self {
+            version: FORMAT_VERSION,
+            outputs: outputs.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ProblemFileError> {
+        check_version(json)?;
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, ProblemFileError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ProblemEntry, ProblemField, ProblemFile, ProblemFileError, ProblemTeam, ProblemTeamGroup,
+        ProblemTimeSlot, FORMAT_VERSION,
+    };
+
+    #[test]
+    fn round_trips_and_rejects_newer_versions() {
+        let file = ProblemFile {
+            version: FORMAT_VERSION,
+            inputs: vec![ProblemEntry {
+                unique_id: 0,
+                is_practice: false,
+                team_groups: vec![ProblemTeamGroup {
+                    teams: vec![
+                        ProblemTeam {
+                            id: 1,
+                            rating: Some(4),
+                        },
+                        ProblemTeam {
+                            id: 2,
+                            rating: None,
+                        },
+                    ],
+                }],
+                fields: vec![ProblemField {
+                    id: 7,
+                    time_slots: vec![ProblemTimeSlot {
+                        start: 1_725_699_600,
+                        end: 1_725_703_200,
+                        concurrency: 1,
+                    }],
+                    time_zone: Some("America/Los_Angeles".to_owned()),
+                }],
+                coach_conflicts: vec![],
+                rematch_spacing: None,
+                top_k: 1,
+            }],
+        };
+
+        let json = file.to_json().unwrap();
+        let parsed = ProblemFile::from_json(&json).unwrap();
+        assert_eq!(parsed, file);
+
+        let input = parsed.clone().into_inputs().remove(0);
+        assert_eq!(ProblemEntry::from(&input), file.inputs[0]);
+
+        let newer = json.replacen(
+            &format!("\"version\": {FORMAT_VERSION}"),
+            &format!("\"version\": {}", FORMAT_VERSION + 1),
+            1,
+        );
+        assert!(matches!(
+            ProblemFile::from_json(&newer),
+            Err(ProblemFileError::UnsupportedVersion(_))
+        ));
+    }
+}
@@ -0,0 +1,389 @@
+//! League tables from game results.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{PlayableTeamCollection, TeamLike};
+
+/// How a game that counts towards the standings ended. Teams are referred to by their
+/// `unique_id`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameOutcome {
+    Played {
+        home: i32,
+        away: i32,
+        home_score: u16,
+        away_score: u16,
+    },
+    /// `forfeited_by` loses, and `awarded_to` wins with [`StandingsConfig`]'s forfeit score
+    Forfeit { forfeited_by: i32, awarded_to: i32 },
+}
+
+/// Ways to order teams that have the same number of points, applied in order until the
+/// tie is broken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TieBreaker {
+    /// Points from the games between the tied teams only
+    HeadToHead,
+    GoalDifference,
+    GoalsFor,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StandingsConfig {
+    pub points_for_win: i32,
+    pub points_for_draw: i32,
+    pub points_for_loss: i32,
+    /// Usually the same as a loss, some leagues take a point away
+    pub points_for_forfeit: i32,
+    /// Goals the winner of a forfeit is credited with, against none
+    pub forfeit_score: u16,
+    pub tie_breakers: Vec<TieBreaker>,
+}
+
+impl Default for StandingsConfig {
+    fn default() -> Self {
+        Self {
+            points_for_win: 3,
+            points_for_draw: 1,
+            points_for_loss: 0,
+            points_for_forfeit: 0,
+            forfeit_score: 3,
+            tie_breakers: vec![
+                TieBreaker::HeadToHead,
+                TieBreaker::GoalDifference,
+                TieBreaker::GoalsFor,
+            ],
+        }
+    }
+}
+
+/// One team's line in the table.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StandingsRow {
+    team: i32,
+    /// Starts at 1. Teams that are tied on points and every tie-breaker share a rank
+    rank: usize,
+    played: u32,
+    wins: u32,
+    draws: u32,
+    losses: u32,
+    goals_for: u32,
+    goals_against: u32,
+    points: i32,
+}
+
+impl StandingsRow {
+    pub fn team(&self) -> i32 {
+        self.team
+    }
+
+    pub fn rank(&self) -> usize {
+        self.rank
+    }
+
+    pub fn played(&self) -> u32 {
+        self.played
+    }
+
+    pub fn wins(&self) -> u32 {
+        self.wins
+    }
+
+    pub fn draws(&self) -> u32 {
+        self.draws
+    }
+
+    pub fn losses(&self) -> u32 {
+        self.losses
+    }
+
+    pub fn goals_for(&self) -> u32 {
+        self.goals_for
+    }
+
+    pub fn goals_against(&self) -> u32 {
+        self.goals_against
+    }
+
+    pub fn goal_difference(&self) -> i64 {
+        i64::from(self.goals_for) - i64::from(self.goals_against)
+    }
+
+    pub fn points(&self) -> i32 {
+        self.points
+    }
+}
+
+/// Per team, the goals scored and conceded, the points earned and whether it won, drew or
+/// lost one game.
+fn score(outcome: &GameOutcome, config: &StandingsConfig) -> [(i32, u16, u16, i32, Ordering); 2] {
+    match *outcome {
+        GameOutcome::Played {
+            home,
+            away,
+            home_score,
+            away_score,
+        } => {
+            let result = home_score.cmp(&away_score);
+            let points = |result| match result {
+                Ordering::Greater => config.points_for_win,
+                Ordering::Equal => config.points_for_draw,
+                Ordering::Less => config.points_for_loss,
+            };
+
+            [
+                (home, home_score, away_score, points(result), result),
+                (
+                    away,
+                    away_score,
+                    home_score,
+                    points(result.reverse()),
+                    result.reverse(),
+                ),
+            ]
+        }
+        GameOutcome::Forfeit {
+            forfeited_by,
+            awarded_to,
+        } => [
+            (
+                awarded_to,
+                config.forfeit_score,
+                0,
+                config.points_for_win,
+                Ordering::Greater,
+            ),
+            (
+                forfeited_by,
+                0,
+                config.forfeit_score,
+                config.points_for_forfeit,
+                Ordering::Less,
+            ),
+        ],
+    }
+}
+
+fn teams_of(outcome: &GameOutcome) -> [i32; 2] {
+    match *outcome {
+        GameOutcome::Played { home, away, .. } => [home, away],
+        GameOutcome::Forfeit {
+            forfeited_by,
+            awarded_to,
+        } => [forfeited_by, awarded_to],
+    }
+}
+
+/// Orders `tied`, which are indices into `rows`, with `tie_breakers`. Returns groups that
+/// are still tied after every tie-breaker, best first.
+fn break_ties(
+    tied: Vec<usize>,
+    rows: &[StandingsRow],
+    games: &[&GameOutcome],
+    tie_breakers: &[TieBreaker],
+    config: &StandingsConfig,
+) -> Vec<Vec<usize>> {
+    let Some((tie_breaker, rest)) = tie_breakers.split_first() else {
+        return vec![tied];
+    };
+
+    if tied.len() < 2 {
+        return vec![tied];
+    }
+
+    let metric: BTreeMap<usize, i64> = match tie_breaker {
+        TieBreaker::HeadToHead => {
+            let teams = tied.iter().map(|&i| rows[i].team).collect::<HashSet<_>>();
+            let mut points = tied.iter().map(|&i| (i, 0)).collect::<BTreeMap<_, _>>();
+
+            for game in games {
+                if !teams_of(game).iter().all(|team| teams.contains(team)) {
+                    continue;
+                }
+
+                for (team, _, _, earned, _) in score(game, config) {
+                    if let Some(&i) = tied.iter().find(|&&i| rows[i].team == team) {
+                        *points.entry(i).or_default() += i64::from(earned);
+                    }
+                }
+            }
+
+            points
+        }
+        TieBreaker::GoalDifference => tied
+            .iter()
+            .map(|&i| (i, rows[i].goal_difference()))
+            .collect(),
+        TieBreaker::GoalsFor => tied
+            .iter()
+            .map(|&i| (i, i64::from(rows[i].goals_for)))
+            .collect(),
+    };
+
+    let mut tied = tied;
+    tied.sort_by_key(|i| Reverse(metric[i]));
+
+    tied.chunk_by(|a, b| metric[a] == metric[b])
+        .flat_map(|group| break_ties(group.to_vec(), rows, games, rest, config))
+        .collect()
+}
+
+/// The table of the teams in `collection`, best first. Only games between two teams of
+/// the collection count.
+pub fn standings<P>(
+    collection: &P,
+    outcomes: &[GameOutcome],
+    config: &StandingsConfig,
+) -> Vec<StandingsRow>
+where
+    P: PlayableTeamCollection,
+{
+    let mut rows = collection
+        .teams()
+        .as_ref()
+        .iter()
+        .map(TeamLike::unique_id)
+        .collect::<Vec<_>>();
+    rows.sort_unstable();
+    rows.dedup();
+
+    let mut rows = rows
+        .into_iter()
+        .map(|team| StandingsRow {
+            team,
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+
+    let index = |team: i32| rows.iter().position(|row| row.team == team);
+
+    let games = outcomes
+        .iter()
+        .filter(|game| teams_of(game).iter().all(|&team| index(team).is_some()))
+        .collect::<Vec<_>>();
+
+    for game in &games {
+        for (team, scored, conceded, earned, result) in score(game, config) {
+            let Some(i) = rows.iter().position(|row| row.team == team) else {
+                continue;
+            };
+
+            let row = &mut rows[i];
+            row.played += 1;
+            row.goals_for += u32::from(scored);
+            row.goals_against += u32::from(conceded);
+            row.points += earned;
+
+            match result {
+                Ordering::Greater => row.wins += 1,
+                Ordering::Equal => row.draws += 1,
+                Ordering::Less => row.losses += 1,
+            }
+        }
+    }
+
+    let mut by_points = (0..rows.len()).collect::<Vec<_>>();
+    by_points.sort_by_key(|&i| (Reverse(rows[i].points), rows[i].team));
+
+    let groups = by_points
+        .chunk_by(|&a, &b| rows[a].points == rows[b].points)
+        .flat_map(|group| break_ties(group.to_vec(), &rows, &games, &config.tie_breakers, config))
+        .collect::<Vec<_>>();
+
+    let mut ordered = Vec::with_capacity(rows.len());
+    let mut rank = 1;
+
+    for group in groups {
+        for &i in &group {
+            ordered.push(StandingsRow {
+                rank,
+                ..rows[i].clone()
+            });
+        }
+
+        rank += group.len();
+    }
+
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{standings, GameOutcome, StandingsConfig, TieBreaker};
+    use crate::fixtures::{League, Team};
+
+    fn played(home: i32, away: i32, home_score: u16, away_score: u16) -> GameOutcome {
+        GameOutcome::Played {
+            home,
+            away,
+            home_score,
+            away_score,
+        }
+    }
+
+    fn order(outcomes: &[GameOutcome], config: &StandingsConfig) -> Vec<(i32, usize)> {
+        let league = League((1..=4).map(Team).collect());
+
+        standings(&league, outcomes, config)
+            .iter()
+            .map(|row| (row.team(), row.rank()))
+            .collect()
+    }
+
+    #[test]
+    fn counts_results_and_forfeits() {
+        let outcomes = [
+            played(1, 2, 2, 0),
+            played(3, 4, 1, 1),
+            GameOutcome::Forfeit {
+                forfeited_by: 2,
+                awarded_to: 3,
+            },
+            // team 5 is not in the league
+            played(1, 5, 0, 9),
+        ];
+
+        let league = League((1..=4).map(Team).collect());
+        let rows = standings(&league, &outcomes, &StandingsConfig::default());
+
+        let three = rows.iter().find(|row| row.team() == 3).unwrap();
+        assert_eq!(
+            (three.wins(), three.draws(), three.losses(), three.points()),
+            (1, 1, 0, 4)
+        );
+        assert_eq!((three.goals_for(), three.goals_against()), (4, 1));
+
+        let one = rows.iter().find(|row| row.team() == 1).unwrap();
+        assert_eq!((one.played(), one.goal_difference()), (1, 2));
+
+        assert_eq!(rows[0].team(), 3);
+    }
+
+    #[test]
+    fn head_to_head_before_goal_difference() {
+        // 1 and 2 both win one game, 2 by more, but 1 beat 2
+        let outcomes = [played(1, 2, 1, 0), played(2, 3, 5, 0), played(3, 4, 0, 0)];
+
+        let config = StandingsConfig::default();
+        assert_eq!(order(&outcomes, &config)[..2], [(1, 1), (2, 2)]);
+
+        let config = StandingsConfig {
+            tie_breakers: vec![TieBreaker::GoalDifference],
+            ..Default::default()
+        };
+        assert_eq!(order(&outcomes, &config)[0], (2, 1));
+    }
+
+    #[test]
+    fn unbroken_ties_share_a_rank() {
+        let outcomes = [played(1, 2, 1, 1), played(3, 4, 0, 2)];
+
+        assert_eq!(
+            order(&outcomes, &StandingsConfig::default()),
+            [(4, 1), (1, 2), (2, 2), (3, 4)]
+        );
+    }
+}
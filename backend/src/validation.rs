@@ -0,0 +1,372 @@
+//! Checks a finished schedule against the constraints it was built from.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Debug;
+
+use chrono::TimeDelta;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    AvailabilityWindow, Booking, CoachConflictLike, FieldLike, PlayableTeamCollection, Reservation,
+    ScheduledInput, TeamLike,
+};
+
+/// A rule that a schedule breaks. Teams, fields and coach conflicts are referred to by
+/// their `unique_id`.
+#[derive(Error, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Violation {
+    #[error("team {team} is booked for two games at the same time")]
+    TeamDoubleBooked {
+        team: i32,
+        first: AvailabilityWindow,
+        second: AvailabilityWindow,
+    },
+    #[error("teams {team_one} and {team_two} share a coach (conflict {coach_conflict}) but play at the same time")]
+    CoachConflict {
+        coach_conflict: i32,
+        team_one: i32,
+        team_two: i32,
+        first: AvailabilityWindow,
+        second: AvailabilityWindow,
+    },
+    #[error("a game on field {field} is not inside any of the field's time slots")]
+    OutsideFieldAvailability {
+        field: i32,
+        availability: AvailabilityWindow,
+    },
+    #[error("field {field} has {booked} games in a time slot that allows {allowed}")]
+    ConcurrencyExceeded {
+        field: i32,
+        availability: AvailabilityWindow,
+        booked: usize,
+        allowed: u8,
+    },
+    #[error("team {team} only rests {rest_minutes} minutes between games")]
+    RestPeriodBreached {
+        team: i32,
+        first: AvailabilityWindow,
+        second: AvailabilityWindow,
+        rest_minutes: i64,
+    },
+}
+
+/// Unlike [`AvailabilityWindow::overlap_fast`], back-to-back windows do not overlap.
+fn overlaps(lhs: &AvailabilityWindow, rhs: &AvailabilityWindow) -> bool {
+    lhs.start < rhs.end && rhs.start < lhs.end
+}
+
+fn contains(outer: &AvailabilityWindow, inner: &AvailabilityWindow) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end
+}
+
+/// A booked reservation, reduced to what the rules look at.
+struct Booked<'a> {
+    field: i32,
+    availability: &'a AvailabilityWindow,
+    teams: Vec<i32>,
+}
+
+pub struct ScheduleValidator<'a, F, C>
+where
+    F: FieldLike,
+    C: CoachConflictLike,
+{
+    fields: &'a [F],
+    coach_conflicts: &'a [C],
+    min_rest: Option<TimeDelta>,
+}
+
+impl<'a, F, C> ScheduleValidator<'a, F, C>
+where
+    F: FieldLike,
+    C: CoachConflictLike,
+{
+    pub fn new(fields: &'a [F], coach_conflicts: &'a [C]) -> Self {
+        Self {
+            fields,
+            coach_conflicts,
+            min_rest: None,
+        }
+    }
+
+    /// The least amount of time a team must have between the end of one game and the
+    /// start of its next. `None` turns the check off.
+    pub fn with_min_rest(mut self, min_rest: Option<TimeDelta>) -> Self {
+        self.min_rest = min_rest;
+        self
+    }
+
+    /// Every violation in `reservations`. Empty reservations are ignored, and an empty
+    /// result means the schedule follows every rule.
+    pub fn validate<T, G>(&self, reservations: &[Reservation<T, G>]) -> Vec<Violation>
+    where
+        T: TeamLike + Clone + Debug + PartialEq,
+        G: FieldLike + Clone + Debug + PartialEq,
+    {
+        let booked = reservations
+            .iter()
+            .filter_map(|reservation| {
+                let teams = match reservation.booking() {
+                    Booking::Booked {
+                        home_team,
+                        away_team,
+                    } => vec![home_team.unique_id(), away_team.unique_id()],
+                    Booking::Practice(team) => vec![team.unique_id()],
+                    Booking::Empty => return None,
+                };
+
+                Some(Booked {
+                    field: reservation.field().unique_id(),
+                    availability: &reservation.availability,
+                    teams,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut violations = vec![];
+
+        self.check_teams(&booked, &mut violations);
+        self.check_coach_conflicts(&booked, &mut violations);
+        self.check_fields(&booked, &mut violations);
+
+        violations
+    }
+
+    /// Double booking and rest periods, which both compare a team's games in order.
+    fn check_teams(&self, booked: &[Booked], violations: &mut Vec<Violation>) {
+        let mut by_team: BTreeMap<i32, Vec<&AvailabilityWindow>> = BTreeMap::new();
+
+        for booking in booked {
+            for team in &booking.teams {
+                by_team.entry(*team).or_default().push(booking.availability);
+            }
+        }
+
+        for (team, mut games) in by_team {
+            games.sort();
+
+            for (i, first) in games.iter().enumerate() {
+                for second in games[i + 1..]
+                    .iter()
+                    .take_while(|second| second.start < first.end)
+                {
+                    violations.push(Violation::TeamDoubleBooked {
+                        team,
+                        first: (*first).clone(),
+                        second: (*second).clone(),
+                    });
+                }
+            }
+
+            let Some(min_rest) = self.min_rest else {
+                continue;
+            };
+
+            for pair in games.windows(2) {
+                let rest = pair[1].start - pair[0].end;
+
+                // overlapping games were already reported as double booked
+                if rest >= TimeDelta::zero() && rest < min_rest {
+                    violations.push(Violation::RestPeriodBreached {
+                        team,
+                        first: pair[0].clone(),
+                        second: pair[1].clone(),
+                        rest_minutes: rest.num_minutes(),
+                    });
+                }
+            }
+        }
+    }
+
+    fn check_coach_conflicts(&self, booked: &[Booked], violations: &mut Vec<Violation>) {
+        for coach_conflict in self.coach_conflicts {
+            let teams = coach_conflict
+                .teams()
+                .as_ref()
+                .iter()
+                .map(TeamLike::unique_id)
+                .collect::<HashSet<_>>();
+
+            let games = booked
+                .iter()
+                .enumerate()
+                .flat_map(|(game, booking)| {
+                    booking
+                        .teams
+                        .iter()
+                        .filter(|team| teams.contains(team))
+                        .map(move |team| (game, *team, booking.availability))
+                })
+                .collect::<Vec<_>>();
+
+            for (i, (game_one, team_one, first)) in games.iter().enumerate() {
+                for (game_two, team_two, second) in &games[i + 1..] {
+                    // the same team twice is a double booking, and a coach can watch
+                    // both of their teams play each other
+                    if game_one != game_two && team_one != team_two && overlaps(first, second) {
+                        violations.push(Violation::CoachConflict {
+                            coach_conflict: coach_conflict.unique_id(),
+                            team_one: *team_one,
+                            team_two: *team_two,
+                            first: (*first).clone(),
+                            second: (*second).clone(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Availability and concurrency, which both compare games with a field's time slots.
+    fn check_fields(&self, booked: &[Booked], violations: &mut Vec<Violation>) {
+        let mut time_slots: BTreeMap<i32, Vec<(AvailabilityWindow, u8)>> = BTreeMap::new();
+
+        for field in self.fields {
+            time_slots.entry(field.unique_id()).or_default().extend(
+                field
+                    .time_slots()
+                    .as_ref()
+                    .iter()
+                    .filter_map(|((start, end), concurrency)| {
+                        AvailabilityWindow::new_unix(*start, *end)
+                            .ok()
+                            .map(|window| (window, *concurrency))
+                    }),
+            );
+        }
+
+        for booking in booked {
+            let inside = time_slots.get(&booking.field).is_some_and(|time_slots| {
+                time_slots
+                    .iter()
+                    .any(|(window, _)| contains(window, booking.availability))
+            });
+
+            if !inside {
+                violations.push(Violation::OutsideFieldAvailability {
+                    field: booking.field,
+                    availability: booking.availability.clone(),
+                });
+            }
+        }
+
+        for (field, time_slots) in &time_slots {
+            for (window, concurrency) in time_slots {
+                let count = booked
+                    .iter()
+                    .filter(|booking| {
+                        booking.field == *field && overlaps(window, booking.availability)
+                    })
+                    .count();
+
+                if count > usize::from(*concurrency) {
+                    violations.push(Violation::ConcurrencyExceeded {
+                        field: *field,
+                        availability: window.clone(),
+                        booked: count,
+                        allowed: *concurrency,
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl<T, P, F, C> ScheduledInput<T, P, F, C>
+where
+    T: TeamLike + Clone + Debug + PartialEq + Send,
+    P: PlayableTeamCollection<Team = T> + Send,
+    F: FieldLike + Clone + Debug + PartialEq + Send,
+    C: CoachConflictLike + Send,
+{
+    /// A validator for schedules built from this input.
+    pub fn validator(&self) -> ScheduleValidator<'_, F, C> {
+        ScheduleValidator::new(&self.fields, &self.coach_conflicts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::{ScheduleValidator, Violation};
+    use crate::fixtures::{Coach, Field, Team};
+    use crate::{window, AvailabilityWindow, Booking, Reservation};
+
+    fn game(
+        field: &Field,
+        window: AvailabilityWindow,
+        home: i32,
+        away: i32,
+    ) -> Reservation<Team, Field> {
+        Reservation::new(
+            field.clone(),
+            window,
+            Booking::Booked {
+                home_team: Team(home),
+                away_team: Team(away),
+            },
+        )
+    }
+
+    #[test]
+    fn reports_every_violation() {
+        let nine = window!(7/9/2024 from 9:00 to 10:00).unwrap();
+        let ten = window!(7/9/2024 from 10:00 to 11:00).unwrap();
+        let noon = window!(7/9/2024 from 12:00 to 13:00).unwrap();
+
+        let field = Field(
+            1,
+            vec![
+                (nine.to_protobuf_window(), 1),
+                (ten.to_protobuf_window(), 2),
+            ],
+        );
+
+        let reservations = [
+            // 1 and 3 share a coach, and 1 is also booked twice
+            game(&field, nine.clone(), 1, 2),
+            game(&field, nine.clone(), 3, 4),
+            game(&field, nine.clone(), 1, 5),
+            // 2 plays right after its first game, and a coach can watch 3 play 4
+            game(&field, ten.clone(), 2, 6),
+            game(&field, noon.clone(), 3, 4),
+        ];
+
+        let fields = [field];
+        let coaches = [Coach(vec![Team(1), Team(3)])];
+
+        let violations = ScheduleValidator::new(&fields, &coaches)
+            .with_min_rest(Some(TimeDelta::minutes(30)))
+            .validate(&reservations);
+
+        let count = |f: fn(&Violation) -> bool| violations.iter().filter(|v| f(v)).count();
+
+        assert_eq!(
+            count(|v| matches!(v, Violation::TeamDoubleBooked { team: 1, .. })),
+            1
+        );
+        assert_eq!(count(|v| matches!(v, Violation::CoachConflict { .. })), 2);
+        assert_eq!(
+            count(|v| matches!(v, Violation::ConcurrencyExceeded { booked: 3, .. })),
+            1
+        );
+        assert_eq!(
+            count(|v| matches!(v, Violation::OutsideFieldAvailability { .. })),
+            1
+        );
+        assert_eq!(
+            count(|v| matches!(
+                v,
+                Violation::RestPeriodBreached {
+                    team: 2,
+                    rest_minutes: 0,
+                    ..
+                }
+            )),
+            1
+        );
+        assert_eq!(violations.len(), 6);
+    }
+}
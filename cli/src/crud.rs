@@ -0,0 +1,674 @@
+//! `fieldz db ...`: create, read, update and delete the entities of a profile.
+//!
+//! Every entity can be managed with flags, which print JSON so that profile setup can be
+//! scripted. Leaving out the operation falls back to an interactive prompt.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::Subcommand;
+use db::{
+    field::Model as Field, region::Model as Region, AuditQuery, Client, CreateFieldInput,
+    CreateRegionInput, CreateTeamInput, CreateTimeSlotInput, EditRegionInput, EditTeamInput,
+    MoveTimeSlotInput, TeamExtension, TimeSlotExtension, Validator,
+};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use serde::Serialize;
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum DbCommand {
+    /// Regions, which own fields and teams
+    Region {
+        #[command(subcommand)]
+        op: Option<RegionOp>,
+    },
+    /// Fields in a region
+    Field {
+        #[command(subcommand)]
+        op: Option<FieldOp>,
+    },
+    /// Teams in a region
+    Team {
+        #[command(subcommand)]
+        op: Option<TeamOp>,
+    },
+    /// Time slots on a field
+    TimeSlot {
+        #[command(subcommand)]
+        op: Option<TimeSlotOp>,
+    },
+    /// Groups that teams are tagged with
+    Group {
+        #[command(subcommand)]
+        op: Option<GroupOp>,
+    },
+    /// Undo the last edit
+    Undo,
+    /// Redo the edit that was undone last
+    Redo,
+    /// Edits that can be undone or redone, newest first
+    History,
+    /// Every change to the profile, oldest first
+    Audit {
+        /// Table name, e.g. `schedule_game`
+        #[arg(long)]
+        entity: Option<String>,
+        #[arg(long)]
+        id: Option<i32>,
+        /// First UTC date, YYYY-MM-DD
+        #[arg(long)]
+        from: Option<NaiveDate>,
+        /// Last UTC date, YYYY-MM-DD
+        #[arg(long)]
+        to: Option<NaiveDate>,
+        /// Print CSV instead of JSON
+        #[arg(long)]
+        csv: bool,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum RegionOp {
+    List,
+    Create {
+        title: String,
+        /// IANA time zone, e.g. `America/Los_Angeles`. Defaults to UTC
+        #[arg(long = "time-zone")]
+        time_zone: Option<String>,
+    },
+    Update {
+        id: i32,
+        #[arg(long)]
+        title: Option<String>,
+        #[arg(long = "time-zone")]
+        time_zone: Option<String>,
+    },
+    Delete {
+        id: i32,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum FieldOp {
+    List {
+        #[arg(long)]
+        region: i32,
+    },
+    Create {
+        name: String,
+        #[arg(long)]
+        region: i32,
+    },
+    Delete {
+        id: i32,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TeamOp {
+    List {
+        #[arg(long)]
+        region: i32,
+    },
+    Create {
+        name: String,
+        #[arg(long)]
+        region: i32,
+        /// Existing group to add the team to. Can be repeated
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        #[arg(long)]
+        rating: Option<i32>,
+    },
+    Update {
+        id: i32,
+        #[arg(long)]
+        name: Option<String>,
+        /// Replaces every group of the team. Can be repeated
+        #[arg(long = "tag")]
+        tags: Option<Vec<String>>,
+        #[arg(long, conflicts_with = "clear_rating")]
+        rating: Option<i32>,
+        #[arg(long = "clear-rating")]
+        clear_rating: bool,
+    },
+    Delete {
+        id: i32,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TimeSlotOp {
+    List {
+        #[arg(long)]
+        field: i32,
+    },
+    Create {
+        #[arg(long)]
+        field: i32,
+        #[arg(long = "reservation-type")]
+        reservation_type: i32,
+        /// RFC 3339, e.g. `2024-09-07T09:00:00-07:00`
+        #[arg(long)]
+        start: String,
+        /// RFC 3339, e.g. `2024-09-07T10:30:00-07:00`
+        #[arg(long)]
+        end: String,
+    },
+    /// Move a time slot to a new start and end on the same field
+    Update {
+        id: i32,
+        /// The field that the time slot is on
+        #[arg(long)]
+        field: i32,
+        #[arg(long)]
+        start: String,
+        #[arg(long)]
+        end: String,
+    },
+    Delete {
+        id: i32,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum GroupOp {
+    List,
+    Create { name: String },
+    Delete { id: i32 },
+}
+
+#[derive(Serialize)]
+struct Deleted {
+    id: i32,
+    deleted: u64,
+}
+
+fn print_json(value: &impl Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Fails if nothing was deleted, so that scripts notice an id that does not exist.
+fn print_deleted(kind: &str, id: i32, deleted: u64) -> Result<()> {
+    if deleted == 0 {
+        bail!("there is no {kind} with id {id}");
+    }
+
+    print_json(&Deleted { id, deleted })
+}
+
+fn parse_time(input: &str) -> Result<DateTime<Utc>> {
+    Ok(DateTime::parse_from_rfc3339(input.trim())
+        .with_context(|| format!("`{input}` is not an RFC 3339 date"))?
+        .to_utc())
+}
+
+pub async fn run(client: &Client, command: DbCommand) -> Result<()> {
+    match command {
+        DbCommand::Region { op: Some(op) } => region(client, op).await,
+        DbCommand::Field { op: Some(op) } => field(client, op).await,
+        DbCommand::Team { op: Some(op) } => team(client, op).await,
+        DbCommand::TimeSlot { op: Some(op) } => time_slot(client, op).await,
+        DbCommand::Group { op: Some(op) } => group(client, op).await,
+        DbCommand::Undo => print_json(&client.undo().await?),
+        DbCommand::Redo => print_json(&client.redo().await?),
+        DbCommand::History => print_json(&client.journal_history().await?),
+        DbCommand::Audit {
+            entity,
+            id,
+            from,
+            to,
+            csv,
+        } => {
+            let query = AuditQuery::new(entity, id, from, to);
+            if csv {
+                print!("{}", client.export_audit_log_csv(&query).await?);
+                Ok(())
+            } else {
+                print_json(&client.get_audit_log(&query).await?)
+            }
+        }
+        command => interactive(client, command).await,
+    }
+}
+
+async fn region(client: &Client, op: RegionOp) -> Result<()> {
+    match op {
+        RegionOp::List => print_json(&client.get_regions().await?),
+        RegionOp::Create { title, time_zone } => {
+            let input = CreateRegionInput::new(title, time_zone);
+            input.validate()?;
+            print_json(&client.create_region(input).await?)
+        }
+        RegionOp::Update {
+            id,
+            title,
+            time_zone,
+        } => print_json(
+            &client
+                .edit_region(EditRegionInput::new(id, title, time_zone))
+                .await?,
+        ),
+        RegionOp::Delete { id } => {
+            print_deleted("region", id, client.delete_region(id).await?.rows_affected)
+        }
+    }
+}
+
+async fn field(client: &Client, op: FieldOp) -> Result<()> {
+    match op {
+        FieldOp::List { region } => print_json(&client.get_fields(region).await?),
+        FieldOp::Create { name, region } => {
+            let input = CreateFieldInput::new(name, region);
+            input.validate()?;
+            print_json(&client.create_field(input).await?)
+        }
+        FieldOp::Delete { id } => {
+            print_deleted("field", id, client.delete_field(id).await?.rows_affected)
+        }
+    }
+}
+
+async fn team(client: &Client, op: TeamOp) -> Result<()> {
+    match op {
+        TeamOp::List { region } => print_json(&client.get_teams_with_tags(region).await?),
+        TeamOp::Create {
+            name,
+            region,
+            tags,
+            rating,
+        } => {
+            let input = CreateTeamInput::new(name, region, tags, rating);
+            input.validate()?;
+            print_json(&client.create_team(input).await?)
+        }
+        TeamOp::Update {
+            id,
+            name,
+            tags,
+            rating,
+            clear_rating,
+        } => {
+            let rating = if clear_rating {
+                Some(None)
+            } else {
+                rating.map(Some)
+            };
+            print_json(
+                &client
+                    .edit_team(EditTeamInput::new(id, name, tags, rating))
+                    .await?,
+            )
+        }
+        TeamOp::Delete { id } => {
+            print_deleted("team", id, client.delete_team(id).await?.rows_affected)
+        }
+    }
+}
+
+async fn time_slot(client: &Client, op: TimeSlotOp) -> Result<()> {
+    match op {
+        TimeSlotOp::List { field } => print_json(&client.get_time_slots(field).await?),
+        TimeSlotOp::Create {
+            field,
+            reservation_type,
+            start,
+            end,
+        } => {
+            let input = CreateTimeSlotInput::new(
+                field,
+                reservation_type,
+                parse_time(&start)?,
+                parse_time(&end)?,
+            );
+            print_json(&client.create_time_slot(input).await?)
+        }
+        TimeSlotOp::Update {
+            id,
+            field,
+            start,
+            end,
+        } => {
+            let input = MoveTimeSlotInput::new(id, field, parse_time(&start)?, parse_time(&end)?);
+            client.move_time_slot(input).await?;
+
+            let time_slots = client.get_time_slots(field).await?;
+            let moved = time_slots
+                .iter()
+                .find(|time_slot| time_slot.time_slot().id == id)
+                .with_context(|| format!("time slot {id} is not on field {field}"))?;
+
+            print_json(moved)
+        }
+        TimeSlotOp::Delete { id } => print_deleted(
+            "time slot",
+            id,
+            client.delete_time_slot(id, None).await?.rows_affected,
+        ),
+    }
+}
+
+async fn group(client: &Client, op: GroupOp) -> Result<()> {
+    match op {
+        GroupOp::List => print_json(&client.get_groups().await?),
+        GroupOp::Create { name } => print_json(&client.create_group(name).await?),
+        GroupOp::Delete { id } => {
+            print_deleted("group", id, client.delete_group(id).await?.rows_affected)
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Operation {
+    Create,
+    Read,
+    Update,
+    Delete,
+}
+
+impl Operation {
+    const ALL: [Self; 4] = [Self::Create, Self::Read, Self::Update, Self::Delete];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Read => "read",
+            Self::Update => "update",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+fn theme() -> ColorfulTheme {
+    ColorfulTheme::default()
+}
+
+fn select<T>(prompt: &str, items: &[T], label: impl Fn(&T) -> String) -> Result<usize> {
+    if items.is_empty() {
+        bail!("there is nothing to pick from for \"{prompt}\"");
+    }
+
+    let labels = items.iter().map(label).collect::<Vec<_>>();
+
+    Ok(Select::with_theme(&theme())
+        .with_prompt(prompt)
+        .default(0)
+        .items(&labels)
+        .interact()?)
+}
+
+fn text(prompt: &str) -> Result<String> {
+    Ok(Input::with_theme(&theme())
+        .with_prompt(prompt)
+        .interact_text()?)
+}
+
+/// Empty input means "leave it as it is".
+fn optional_text(prompt: &str) -> Result<Option<String>> {
+    let input: String = Input::with_theme(&theme())
+        .with_prompt(prompt)
+        .allow_empty(true)
+        .interact_text()?;
+
+    Ok(Some(input).filter(|input| !input.trim().is_empty()))
+}
+
+fn confirm(prompt: &str) -> Result<bool> {
+    Ok(Confirm::with_theme(&theme())
+        .with_prompt(prompt)
+        .default(false)
+        .interact()?)
+}
+
+fn region_label(region: &Region) -> String {
+    format!("#{} {} ({})", region.id, region.title, region.time_zone)
+}
+
+fn field_label(field: &Field) -> String {
+    format!("#{} {}", field.id, field.name)
+}
+
+fn team_label(team: &TeamExtension) -> String {
+    let tags = team
+        .tags()
+        .iter()
+        .map(|tag| tag.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("#{} {} [{tags}]", team.team().id, team.team().name)
+}
+
+/// Time slots store UNIX milliseconds.
+fn format_millis(millis: i64) -> String {
+    DateTime::from_timestamp_millis(millis)
+        .map_or_else(|| millis.to_string(), |date_time| date_time.to_rfc3339())
+}
+
+fn time_slot_label(time_slot: &TimeSlotExtension) -> String {
+    format!(
+        "#{} {} to {} ({})",
+        time_slot.time_slot().id,
+        format_millis(time_slot.time_slot().start),
+        format_millis(time_slot.time_slot().end),
+        time_slot.reservation_type().name,
+    )
+}
+
+async fn pick_region(client: &Client) -> Result<Region> {
+    let mut regions = client.get_regions().await?;
+    let index = select("Region", &regions, region_label)?;
+    Ok(regions.swap_remove(index))
+}
+
+async fn pick_field(client: &Client) -> Result<Field> {
+    let region = pick_region(client).await?;
+    let mut fields = client.get_fields(region.id).await?;
+    let index = select("Field", &fields, field_label)?;
+    Ok(fields.swap_remove(index))
+}
+
+async fn interactive(client: &Client, command: DbCommand) -> Result<()> {
+    let index = select(
+        &format!("Enter operation for \"{command:?}\""),
+        &Operation::ALL,
+        |operation| operation.label().to_owned(),
+    )?;
+
+    let operation = Operation::ALL[index];
+
+    match command {
+        DbCommand::Region { .. } => interactive_region(client, operation).await,
+        DbCommand::Field { .. } => interactive_field(client, operation).await,
+        DbCommand::Team { .. } => interactive_team(client, operation).await,
+        DbCommand::TimeSlot { .. } => interactive_time_slot(client, operation).await,
+        DbCommand::Group { .. } => interactive_group(client, operation).await,
+        DbCommand::Undo | DbCommand::Redo | DbCommand::History | DbCommand::Audit { .. } => {
+            unreachable!("{command:?} never prompts")
+        }
+    }
+}
+
+async fn interactive_region(client: &Client, operation: Operation) -> Result<()> {
+    let op = match operation {
+        Operation::Create => RegionOp::Create {
+            title: text("Title")?,
+            time_zone: optional_text("Time zone (empty for UTC)")?,
+        },
+        Operation::Read => RegionOp::List,
+        Operation::Update => RegionOp::Update {
+            id: pick_region(client).await?.id,
+            title: optional_text("New title (empty to keep)")?,
+            time_zone: optional_text("New time zone (empty to keep)")?,
+        },
+        Operation::Delete => {
+            let region = pick_region(client).await?;
+            if !confirm(&format!(
+                "Delete {} and all of its fields and teams?",
+                region_label(&region)
+            ))? {
+                return Ok(());
+            }
+            RegionOp::Delete { id: region.id }
+        }
+    };
+
+    region(client, op).await
+}
+
+async fn interactive_field(client: &Client, operation: Operation) -> Result<()> {
+    let op = match operation {
+        Operation::Create => FieldOp::Create {
+            region: pick_region(client).await?.id,
+            name: text("Name")?,
+        },
+        Operation::Read => FieldOp::List {
+            region: pick_region(client).await?.id,
+        },
+        Operation::Update => bail!("fields only have a name, delete and create them instead"),
+        Operation::Delete => {
+            let field = pick_field(client).await?;
+            if !confirm(&format!("Delete {}?", field_label(&field)))? {
+                return Ok(());
+            }
+            FieldOp::Delete { id: field.id }
+        }
+    };
+
+    field(client, op).await
+}
+
+fn tags(input: Option<String>) -> Option<Vec<String>> {
+    input.map(|input| {
+        input
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_owned)
+            .collect()
+    })
+}
+
+fn rating(input: Option<String>) -> Result<Option<i32>> {
+    input
+        .map(|input| {
+            input
+                .trim()
+                .parse()
+                .context("a rating must be a whole number")
+        })
+        .transpose()
+}
+
+async fn interactive_team(client: &Client, operation: Operation) -> Result<()> {
+    let op = match operation {
+        Operation::Create => TeamOp::Create {
+            region: pick_region(client).await?.id,
+            name: text("Name")?,
+            tags: tags(optional_text("Groups, separated by commas")?).unwrap_or_default(),
+            rating: rating(optional_text("Rating (empty for none)")?)?,
+        },
+        Operation::Read => TeamOp::List {
+            region: pick_region(client).await?.id,
+        },
+        Operation::Update => {
+            let region = pick_region(client).await?;
+            let teams = client.get_teams_with_tags(region.id).await?;
+            let index = select("Team", &teams, team_label)?;
+
+            TeamOp::Update {
+                id: teams[index].team().id,
+                name: optional_text("New name (empty to keep)")?,
+                tags: tags(optional_text(
+                    "New groups, separated by commas (empty to keep)",
+                )?),
+                rating: rating(optional_text("New rating (empty to keep)")?)?,
+                clear_rating: false,
+            }
+        }
+        Operation::Delete => {
+            let region = pick_region(client).await?;
+            let teams = client.get_teams_with_tags(region.id).await?;
+            let index = select("Team", &teams, team_label)?;
+            if !confirm(&format!("Delete {}?", team_label(&teams[index])))? {
+                return Ok(());
+            }
+            TeamOp::Delete {
+                id: teams[index].team().id,
+            }
+        }
+    };
+
+    team(client, op).await
+}
+
+async fn interactive_time_slot(client: &Client, operation: Operation) -> Result<()> {
+    let op = match operation {
+        Operation::Create => {
+            let field = pick_field(client).await?;
+            let reservation_types = client.get_reservation_types(None).await?;
+            let index = select("Reservation type", &reservation_types, |reservation_type| {
+                format!("#{} {}", reservation_type.id, reservation_type.name)
+            })?;
+
+            TimeSlotOp::Create {
+                field: field.id,
+                reservation_type: reservation_types[index].id,
+                start: text("Start (RFC 3339)")?,
+                end: text("End (RFC 3339)")?,
+            }
+        }
+        Operation::Read => TimeSlotOp::List {
+            field: pick_field(client).await?.id,
+        },
+        Operation::Update => {
+            let field = pick_field(client).await?;
+            let time_slots = client.get_time_slots(field.id).await?;
+            let index = select("Time slot", &time_slots, time_slot_label)?;
+
+            TimeSlotOp::Update {
+                id: time_slots[index].time_slot().id,
+                field: field.id,
+                start: text("New start (RFC 3339)")?,
+                end: text("New end (RFC 3339)")?,
+            }
+        }
+        Operation::Delete => {
+            let field = pick_field(client).await?;
+            let time_slots = client.get_time_slots(field.id).await?;
+            let index = select("Time slot", &time_slots, time_slot_label)?;
+            if !confirm(&format!("Delete {}?", time_slot_label(&time_slots[index])))? {
+                return Ok(());
+            }
+            TimeSlotOp::Delete {
+                id: time_slots[index].time_slot().id,
+            }
+        }
+    };
+
+    time_slot(client, op).await
+}
+
+async fn interactive_group(client: &Client, operation: Operation) -> Result<()> {
+    let op = match operation {
+        Operation::Create => GroupOp::Create {
+            name: text("Name")?,
+        },
+        Operation::Read => GroupOp::List,
+        Operation::Update => bail!("groups only have a name, delete and create them instead"),
+        Operation::Delete => {
+            let groups = client.get_groups().await?;
+            let index = select("Group", &groups, |group| {
+                format!("#{} {} ({} teams)", group.id, group.name, group.usages)
+            })?;
+            if !confirm(&format!("Delete {}?", groups[index].name))? {
+                return Ok(());
+            }
+            GroupOp::Delete {
+                id: groups[index].id,
+            }
+        }
+    };
+
+    group(client, op).await
+}
@@ -1,11 +1,24 @@
-use anyhow::{anyhow, Context, Result};
-use clap::{Parser, Subcommand};
-use db::Client;
-use dialoguer::{theme::ColorfulTheme, Select};
-use std::{fmt::Debug, path::Path};
+use anyhow::{anyhow, bail, Context, Result};
+use backend::problem::{ProblemField, ProblemFile, ProblemTeam, ResultFile};
+use backend::{Booking, Output, SolveOptions, Strategy};
+use clap::{Parser, Subcommand, ValueEnum};
+use crud::DbCommand;
+use db::{
+    Client, CompiledSchedule, ImportTeamsInput, PreScheduleReportInput, ScheduleExportFilter,
+    TeamImportColumns,
+};
+use grpc_server::proto::algo_input;
+use std::{
+    fmt::Debug,
+    num::NonZeroU8,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
 use log::{Level, Metadata, Record};
 
+mod crud;
+
 struct SimpleLogger;
 
 impl log::Log for SimpleLogger {
@@ -15,7 +28,7 @@ impl log::Log for SimpleLogger {
 
     fn log(&self, record: &Record) {
         if self.enabled(record.metadata()) {
-            println!("{} {}", record.level(), record.args());
+            eprintln!("{} {}", record.level(), record.args());
         }
     }
 
@@ -32,19 +45,155 @@ struct Args {
 #[derive(Subcommand, Debug, Clone)]
 enum Commands {
     Playground,
-    /// All subcommands related to regions
+    /// Create, read, update and delete regions, fields, teams, time slots and groups
     Db {
         #[command(subcommand)]
         cmd: DbCommand,
         #[arg(long = "db", required(false))]
         db_path: Option<String>,
     },
+    /// Solve a problem file locally and write the result next to it
+    Solve {
+        /// Problem file, as written by `export-problem` or the desktop app
+        problem: PathBuf,
+        #[arg(long, value_enum, default_value_t = StrategyArg::Mcts)]
+        strategy: StrategyArg,
+        /// Makes the greedy strategy and practices reproducible
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Seconds of tree search allowed for each input
+        #[arg(long = "time-limit")]
+        time_limit: Option<u64>,
+        /// Where to write the result file. Defaults to `<problem>.result.json`
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Generate a schedule from a profile and save it as a draft
+    Schedule(ScheduleArgs),
+    /// Create teams from the rows of a CSV file
+    ImportTeams {
+        file: PathBuf,
+        #[arg(long = "db", required(false))]
+        db_path: Option<String>,
+        /// Region id for rows that do not name a region
+        #[arg(long)]
+        region: Option<i32>,
+        #[arg(long = "name-column", default_value = "name")]
+        name_column: String,
+        /// Region title or id
+        #[arg(long = "region-column", default_value = "region")]
+        region_column: String,
+        /// Group tags, separated by `;` or `,`. Missing groups are created
+        #[arg(long = "groups-column", default_value = "groups")]
+        groups_column: String,
+        /// Teams with the same coach in a region share a coach conflict
+        #[arg(long = "coach-column", default_value = "coach")]
+        coach_column: String,
+        /// Check the file and print what would be created without saving anything
+        #[arg(long = "dry-run")]
+        dry_run: bool,
+    },
+    /// Write every scheduler input of a profile to a problem file
+    ExportProblem {
+        /// Where to write the problem file
+        output: PathBuf,
+        #[arg(long = "db", required(false))]
+        db_path: Option<String>,
+    },
+    /// Write the games of a saved schedule as CSV
+    ExportSchedule {
+        schedule_id: i32,
+        /// Where to write the CSV. Defaults to standard output
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        filter: ExportFilterArgs,
+        #[arg(long = "db", required(false))]
+        db_path: Option<String>,
+    },
+    /// Write a printable HTML booklet of a saved schedule
+    ExportBooklet {
+        schedule_id: i32,
+        /// Where to write the booklet
+        output: PathBuf,
+        #[command(flatten)]
+        filter: ExportFilterArgs,
+        #[arg(long = "db", required(false))]
+        db_path: Option<String>,
+    },
 }
 
-#[derive(Subcommand, Debug, Clone)]
-enum DbCommand {
-    Region,
-    Field,
+#[derive(clap::Args, Debug, Clone)]
+struct ExportFilterArgs {
+    /// Only games where this team id plays
+    #[arg(long)]
+    team: Option<i32>,
+    /// Only games on this field id
+    #[arg(long)]
+    field: Option<i32>,
+    /// Only games where a team is tagged with this group id
+    #[arg(long)]
+    group: Option<i32>,
+    /// First local date to include, as `YYYY-MM-DD`
+    #[arg(long)]
+    from: Option<chrono::NaiveDate>,
+    /// Last local date to include, as `YYYY-MM-DD`
+    #[arg(long)]
+    to: Option<chrono::NaiveDate>,
+}
+
+impl From<ExportFilterArgs> for ScheduleExportFilter {
+    fn from(value: ExportFilterArgs) -> Self {
+        ScheduleExportFilter::new(value.team, value.field, value.group, value.from, value.to)
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct ScheduleArgs {
+    #[arg(long = "db", required(false))]
+    db_path: Option<String>,
+    /// Solve on this scheduler server instead of locally
+    #[arg(long)]
+    endpoint: Option<String>,
+    /// Bearer token sent to `--endpoint`
+    #[arg(long, requires = "endpoint")]
+    token: Option<String>,
+    /// Only used when solving locally
+    #[arg(long, value_enum, default_value_t = StrategyArg::Mcts)]
+    strategy: StrategyArg,
+    /// Only used when solving locally
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Seconds of tree search allowed for each input. Only used when solving locally
+    #[arg(long = "time-limit")]
+    time_limit: Option<u64>,
+    /// How many distinct schedules to keep. Alternatives are saved as sibling drafts
+    #[arg(long = "top-k", default_value_t = 1)]
+    top_k: u8,
+    /// How many times each pairing should play, for the pre-schedule report
+    #[arg(long = "matches-to-play", default_value_t = NonZeroU8::MIN)]
+    matches_to_play: NonZeroU8,
+    /// Let teams from different regions play each other in the pre-schedule report
+    #[arg(long)]
+    interregional: bool,
+    /// Solve and print the result without saving anything
+    #[arg(long = "dry-run")]
+    dry_run: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum StrategyArg {
+    Mcts,
+    Greedy,
+}
+
+impl From<StrategyArg> for Strategy {
+    fn from(value: StrategyArg) -> Self {
+        match value {
+            StrategyArg::Mcts => Self::Mcts,
+            StrategyArg::Greedy => Self::Greedy,
+        }
+    }
 }
 
 fn test_schedule() -> Result<()> {
@@ -122,38 +271,341 @@ fn test_schedule() -> Result<()> {
      */
 }
 
-async fn db_command(command: DbCommand, db_path: Option<String>) -> Result<()> {
+async fn connect(db_path: Option<String>) -> Result<Client> {
     let db_path = if let Ok(from_env) = std::env::var("DATABASE_URL") {
         from_env
     } else {
-        db_path
-            .context("`DATABASE_URL` was not set, and no database path was supplied via `--db`")?
+        db_path.context(
+            "`DATABASE_URL` was not set in the environment or a `.env` file, and no \
+                 database path was supplied via `--db`",
+        )?
     };
 
     let config = db::Config::new(db_path);
 
-    let client = Client::new(&config).await?;
+    Client::new(&config).await
+}
+
+async fn solve(
+    problem_path: PathBuf,
+    options: SolveOptions,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let json = std::fs::read_to_string(&problem_path)
+        .with_context(|| format!("could not read problem file {}", problem_path.display()))?;
+
+    let problem = ProblemFile::from_json(&json)?;
+
+    let (outputs, rows) = tokio::task::spawn_blocking(move || -> Result<_> {
+        let mut outputs = vec![];
+        let mut rows = vec![];
+
+        for input in problem.into_inputs() {
+            let is_practice = input.is_practice();
+
+            let start = Instant::now();
+            let result = backend::schedule_with(input, &options)?;
+            let time_taken = start.elapsed();
+
+            for output in &result {
+                rows.push(SummaryRow::new(output, is_practice, time_taken));
+            }
+
+            outputs.extend(result);
+        }
+
+        Ok((outputs, rows))
+    })
+    .await??;
+
+    let output_path = output.unwrap_or_else(|| problem_path.with_extension("result.json"));
+
+    std::fs::write(&output_path, ResultFile::new(&outputs).to_json()?)
+        .with_context(|| format!("could not write result file to {}", output_path.display()))?;
+
+    print_summary(&rows);
+
+    println!("\nWrote result to {}", output_path.display());
+
+    Ok(())
+}
+
+struct SummaryRow {
+    unique_id: i32,
+    rank: u8,
+    kind: &'static str,
+    booked: usize,
+    reservations: usize,
+    byes: usize,
+    fillage: f32,
+    score: i16,
+    time_taken: Duration,
+}
+
+impl SummaryRow {
+    fn new(
+        output: &Output<ProblemTeam, ProblemField>,
+        is_practice: bool,
+        time_taken: Duration,
+    ) -> Self {
+        Self {
+            unique_id: output.unique_id(),
+            rank: output.rank(),
+            kind: if is_practice { "practice" } else { "games" },
+            booked: output
+                .time_slots()
+                .iter()
+                .filter(|reservation| !matches!(reservation.booking(), Booking::Empty))
+                .count(),
+            reservations: output.time_slots().len(),
+            byes: output.byes().len(),
+            fillage: output.metrics().fillage(),
+            score: output.metrics().score(),
+            time_taken,
+        }
+    }
+}
+
+fn print_summary(rows: &[SummaryRow]) {
+    println!(
+        "{:>5} {:>4} {:<8} {:>13} {:>7} {:>5} {:>6} {:>9}",
+        "input", "rank", "kind", "booked", "fillage", "byes", "score", "time"
+    );
+
+    for row in rows {
+        println!(
+            "{:>5} {:>4} {:<8} {:>13} {:>6.1}% {:>5} {:>6} {:>8.2}s",
+            row.unique_id,
+            row.rank,
+            row.kind,
+            format!("{}/{}", row.booked, row.reservations),
+            row.fillage * 100.,
+            row.byes,
+            row.score,
+            row.time_taken.as_secs_f32(),
+        );
+    }
+
+    let reservations = rows.iter().filter(|row| row.rank == 0);
+    let (booked, total) = reservations.fold((0, 0), |(booked, total), row| {
+        (booked + row.booked, total + row.reservations)
+    });
+
+    println!("\nBooked {booked} of {total} reservations across the best schedules");
+}
+
+async fn schedule(args: ScheduleArgs) -> Result<()> {
+    let client = connect(args.db_path).await?;
 
-    let crud_options = &["create", "read", "delete", "update"];
+    let report = client
+        .generate_pre_schedule_report(PreScheduleReportInput::new(
+            args.matches_to_play,
+            args.interregional,
+        ))
+        .await?;
 
-    let selection: isize = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt(format!("Enter operation for \"{command:?}\""))
-        .default(0)
-        .items(crud_options)
-        .interact()?
-        .try_into()?;
+    println!("Pre-schedule report\n{report}");
 
-    println!("{selection}, {client:?}");
+    let inputs = client
+        .get_scheduled_inputs()
+        .await?
+        .into_iter()
+        .map(|input| input.with_top_k(args.top_k))
+        .collect::<Vec<_>>();
 
-    todo!();
+    let outputs = if let Some(endpoint) = args.endpoint {
+        println!("Solving {} input(s) on {endpoint}", inputs.len());
+
+        let messages = inputs.iter().map(Into::into).collect();
+        solve_remote(endpoint, args.token, messages).await?
+    } else {
+        println!("Solving {} input(s) locally", inputs.len());
+
+        let options = SolveOptions::default()
+            .with_strategy(args.strategy.into())
+            .with_seed(args.seed)
+            .with_time_limit(args.time_limit.map(Duration::from_secs));
+
+        tokio::task::spawn_blocking(move || -> Result<Vec<algo_input::ScheduledOutput>> {
+            let mut outputs = vec![];
+
+            for input in inputs {
+                for output in backend::schedule_with(input, &options)? {
+                    outputs.push(output.into());
+                }
+            }
+
+            Ok(outputs)
+        })
+        .await??
+    };
+
+    for output in &outputs {
+        let booked = output
+            .time_slots
+            .iter()
+            .filter(|reservation| reservation.booking.is_some())
+            .count();
+
+        println!(
+            "input {} rank {}: booked {booked} of {} reservations, {} byes",
+            output.unique_id,
+            output.rank,
+            output.time_slots.len(),
+            output.byes.len(),
+        );
+    }
+
+    if args.dry_run {
+        println!("\nDry run, nothing was saved");
+        return Ok(());
+    }
+
+    let schedule = client.save_schedule(CompiledSchedule::new(outputs)).await?;
+
+    println!("\nSaved schedule #{} \"{}\"", schedule.id, schedule.name);
+
+    Ok(())
+}
+
+async fn solve_remote(
+    endpoint: String,
+    token: Option<String>,
+    messages: Vec<algo_input::ScheduledInput>,
+) -> Result<Vec<algo_input::ScheduledOutput>> {
+    let mut scheduler = grpc_server::client::SchedulerClient::connect(endpoint)
+        .await
+        .context("could not connect to the scheduler")?;
+
+    let mut request = grpc_server::Request::new(tokio_stream::iter(messages));
+
+    if let Some(token) = token {
+        request.metadata_mut().append(
+            "authorization",
+            format!("Bearer {token}")
+                .parse()
+                .context("the token is not a valid header")?,
+        );
+    }
+
+    let mut inbound = scheduler.schedule(request).await?.into_inner();
+
+    let mut outputs = vec![];
+
+    while let Some(output) = inbound.message().await? {
+        outputs.push(output);
+    }
+
+    Ok(outputs)
+}
+
+async fn import_teams(
+    file: PathBuf,
+    db_path: Option<String>,
+    columns: TeamImportColumns,
+    region: Option<i32>,
+    dry_run: bool,
+) -> Result<()> {
+    let csv = std::fs::read_to_string(&file)
+        .with_context(|| format!("could not read {}", file.display()))?;
+
+    let client = connect(db_path).await?;
+
+    let report = client
+        .import_teams(ImportTeamsInput::new(csv, columns, region, dry_run))
+        .await?;
+
+    print!("{report}");
+
+    if !report.errors().is_empty() {
+        bail!(
+            "{} row(s) have errors, nothing was imported",
+            report.errors().len()
+        );
+    }
+
+    if dry_run {
+        println!("Dry run, nothing was saved");
+    } else if report.committed() {
+        println!("Imported {}", file.display());
+    }
+
+    Ok(())
+}
+
+async fn export_problem(output: PathBuf, db_path: Option<String>) -> Result<()> {
+    let client = connect(db_path).await?;
+
+    let inputs = client.get_scheduled_inputs().await?;
+
+    let json = backend::problem::ProblemFile::new(&inputs).to_json()?;
+
+    std::fs::write(&output, json)
+        .with_context(|| format!("could not write problem file to {}", output.display()))?;
+
+    println!(
+        "Wrote {} scheduler input(s) to {}",
+        inputs.len(),
+        output.display()
+    );
+
+    Ok(())
+}
+
+async fn export_schedule(
+    schedule_id: i32,
+    output: Option<PathBuf>,
+    filter: ScheduleExportFilter,
+    db_path: Option<String>,
+) -> Result<()> {
+    let client = connect(db_path).await?;
+
+    let csv = client.export_schedule_csv(schedule_id, &filter).await?;
+
+    match output {
+        Some(output) => {
+            std::fs::write(&output, csv)
+                .with_context(|| format!("could not write schedule to {}", output.display()))?;
+            eprintln!("Wrote schedule {schedule_id} to {}", output.display());
+        }
+        None => print!("{csv}"),
+    }
+
+    Ok(())
+}
+
+async fn export_booklet(
+    schedule_id: i32,
+    output: PathBuf,
+    filter: ScheduleExportFilter,
+    db_path: Option<String>,
+) -> Result<()> {
+    let client = connect(db_path).await?;
+
+    let html = client.export_schedule_booklet(schedule_id, &filter).await?;
+
+    std::fs::write(&output, html)
+        .with_context(|| format!("could not write booklet to {}", output.display()))?;
+
+    println!("Wrote schedule {schedule_id} to {}", output.display());
+
+    Ok(())
+}
+
+async fn db_command(command: DbCommand, db_path: Option<String>) -> Result<()> {
+    let client = connect(db_path).await?;
+
+    crud::run(&client, command).await
 }
 
 static LOGGER: SimpleLogger = SimpleLogger;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // only the commands that open a database need `DATABASE_URL`, so a missing `.env`
+    // is not an error
     if dotenv::from_path(Path::new(module_path!()).join(".env")).is_err() {
-        dotenv::dotenv()?;
+        dotenv::dotenv().ok();
     }
 
     log::set_logger(&LOGGER)
@@ -166,6 +618,56 @@ async fn main() -> Result<()> {
     match args.cmd {
         Commands::Playground => test_schedule()?,
         Commands::Db { cmd, db_path } => db_command(cmd, db_path).await?,
+        Commands::Solve {
+            problem,
+            strategy,
+            seed,
+            time_limit,
+            output,
+        } => {
+            solve(
+                problem,
+                SolveOptions::default()
+                    .with_strategy(strategy.into())
+                    .with_seed(seed)
+                    .with_time_limit(time_limit.map(Duration::from_secs)),
+                output,
+            )
+            .await?
+        }
+        Commands::Schedule(args) => schedule(args).await?,
+        Commands::ImportTeams {
+            file,
+            db_path,
+            region,
+            name_column,
+            region_column,
+            groups_column,
+            coach_column,
+            dry_run,
+        } => {
+            let columns = TeamImportColumns::new(
+                name_column,
+                Some(region_column),
+                Some(groups_column),
+                Some(coach_column),
+            );
+
+            import_teams(file, db_path, columns, region, dry_run).await?
+        }
+        Commands::ExportProblem { output, db_path } => export_problem(output, db_path).await?,
+        Commands::ExportSchedule {
+            schedule_id,
+            output,
+            filter,
+            db_path,
+        } => export_schedule(schedule_id, output, filter.into(), db_path).await?,
+        Commands::ExportBooklet {
+            schedule_id,
+            output,
+            filter,
+            db_path,
+        } => export_booklet(schedule_id, output, filter.into(), db_path).await?,
     }
 
     Ok(())
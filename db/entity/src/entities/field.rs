@@ -24,6 +24,8 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Region,
+    #[sea_orm(has_many = "super::field_closure::Entity")]
+    FieldClosure,
     #[sea_orm(has_many = "super::reservation_type_field_size_join::Entity")]
     ReservationTypeFieldSizeJoin,
     #[sea_orm(has_many = "super::time_slot::Entity")]
@@ -36,6 +38,12 @@ impl Related<super::region::Entity> for Entity {
     }
 }
 
+impl Related<super::field_closure::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FieldClosure.def()
+    }
+}
+
 impl Related<super::reservation_type_field_size_join::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::ReservationTypeFieldSizeJoin.def()
@@ -2,15 +2,24 @@
 
 pub mod prelude;
 
+pub mod audit_context;
+pub mod audit_log;
+pub mod blackout;
 pub mod coach_conflict;
 pub mod coach_conflict_team_join;
 pub mod field;
+pub mod field_closure;
+pub mod journal_entry;
+pub mod journal_recording;
+pub mod journal_step;
 pub mod region;
 pub mod reservation_type;
 pub mod reservation_type_field_size_join;
 pub mod reservation_type_time_slot_join;
 pub mod schedule;
 pub mod schedule_game;
+pub mod schedule_revision;
+pub mod schedule_revision_game;
 pub mod target;
 pub mod target_group_join;
 pub mod team;
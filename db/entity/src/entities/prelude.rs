@@ -1,14 +1,23 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
+pub use super::audit_context::Entity as AuditContext;
+pub use super::audit_log::Entity as AuditLog;
+pub use super::blackout::Entity as Blackout;
 pub use super::coach_conflict::Entity as CoachConflict;
 pub use super::coach_conflict_team_join::Entity as CoachConflictTeamJoin;
 pub use super::field::Entity as Field;
+pub use super::field_closure::Entity as FieldClosure;
+pub use super::journal_entry::Entity as JournalEntry;
+pub use super::journal_recording::Entity as JournalRecording;
+pub use super::journal_step::Entity as JournalStep;
 pub use super::region::Entity as Region;
 pub use super::reservation_type::Entity as ReservationType;
 pub use super::reservation_type_field_size_join::Entity as ReservationTypeFieldSizeJoin;
 pub use super::reservation_type_time_slot_join::Entity as ReservationTypeTimeSlotJoin;
 pub use super::schedule::Entity as Schedule;
 pub use super::schedule_game::Entity as ScheduleGame;
+pub use super::schedule_revision::Entity as ScheduleRevision;
+pub use super::schedule_revision_game::Entity as ScheduleRevisionGame;
 pub use super::target::Entity as Target;
 pub use super::target_group_join::Entity as TargetGroupJoin;
 pub use super::team::Entity as Team;
@@ -11,10 +11,13 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub title: String,
+    pub time_zone: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::blackout::Entity")]
+    Blackout,
     #[sea_orm(has_many = "super::coach_conflict::Entity")]
     CoachConflict,
     #[sea_orm(has_many = "super::field::Entity")]
@@ -23,6 +26,12 @@ pub enum Relation {
     Team,
 }
 
+impl Related<super::blackout::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Blackout.def()
+    }
+}
+
 impl Related<super::coach_conflict::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::CoachConflict.def()
@@ -15,10 +15,14 @@ pub struct Model {
     pub color: String,
     pub default_sizing: i32,
     pub is_practice: bool,
+    pub rematch_spacing_days: Option<i32>,
+    pub rematch_spacing_rounds: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::blackout::Entity")]
+    Blackout,
     #[sea_orm(has_many = "super::reservation_type_field_size_join::Entity")]
     ReservationTypeFieldSizeJoin,
     #[sea_orm(has_many = "super::reservation_type_time_slot_join::Entity")]
@@ -27,6 +31,12 @@ pub enum Relation {
     Target,
 }
 
+impl Related<super::blackout::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Blackout.def()
+    }
+}
+
 impl Related<super::reservation_type_field_size_join::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::ReservationTypeFieldSizeJoin.def()
@@ -19,6 +19,8 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::schedule_game::Entity")]
     ScheduleGame,
+    #[sea_orm(has_many = "super::schedule_revision::Entity")]
+    ScheduleRevision,
 }
 
 impl Related<super::schedule_game::Entity> for Entity {
@@ -27,4 +29,10 @@ impl Related<super::schedule_game::Entity> for Entity {
     }
 }
 
+impl Related<super::schedule_revision::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ScheduleRevision.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
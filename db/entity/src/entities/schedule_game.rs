@@ -11,11 +11,16 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: i32,
     pub schedule_id: i32,
-    pub start: String,
-    pub end: String,
+    pub start: i64,
+    pub end: i64,
     pub team_one: Option<i32>,
     pub team_two: Option<i32>,
     pub field_id: Option<i32>,
+    pub is_bye: bool,
+    pub sequence: i32,
+    pub status: String,
+    pub home_score: Option<i32>,
+    pub away_score: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -12,6 +12,7 @@ pub struct Model {
     pub id: i32,
     pub name: String,
     pub region_owner: i32,
+    pub rating: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -11,8 +11,8 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub field_id: i32,
-    pub start: String,
-    pub end: String,
+    pub start: i64,
+    pub end: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -12,6 +12,18 @@ mod m20240507_015703_create_schedule_results;
 mod m20240702_184439_create_coach_conflicts;
 mod m20240812_163502_create_practice;
 mod m20241021_031741_add_field_to_schedule_game;
+mod m20241103_201524_create_blackout;
+mod m20241110_174233_add_time_zone_to_region;
+mod m20241117_220915_add_bye_to_schedule_game;
+mod m20241124_184052_add_rating_to_team;
+mod m20241201_154210_add_sequence_to_schedule_game;
+mod m20241208_190412_create_journal;
+mod m20241215_142731_create_audit_log;
+mod m20241222_103318_create_schedule_revision;
+mod m20241229_171204_add_result_to_schedule_game;
+mod m20250105_091522_create_field_closure;
+mod m20250112_143007_typed_timestamps;
+mod m20250119_160245_add_rematch_spacing_to_reservation_type;
 
 pub struct Migrator;
 
@@ -31,6 +43,18 @@ impl MigratorTrait for Migrator {
             Box::new(m20240702_184439_create_coach_conflicts::Migration),
             Box::new(m20240812_163502_create_practice::Migration),
             Box::new(m20241021_031741_add_field_to_schedule_game::Migration),
+            Box::new(m20241103_201524_create_blackout::Migration),
+            Box::new(m20241110_174233_add_time_zone_to_region::Migration),
+            Box::new(m20241117_220915_add_bye_to_schedule_game::Migration),
+            Box::new(m20241124_184052_add_rating_to_team::Migration),
+            Box::new(m20241201_154210_add_sequence_to_schedule_game::Migration),
+            Box::new(m20241208_190412_create_journal::Migration),
+            Box::new(m20241215_142731_create_audit_log::Migration),
+            Box::new(m20241222_103318_create_schedule_revision::Migration),
+            Box::new(m20241229_171204_add_result_to_schedule_game::Migration),
+            Box::new(m20250105_091522_create_field_closure::Migration),
+            Box::new(m20250112_143007_typed_timestamps::Migration),
+            Box::new(m20250119_160245_add_rematch_spacing_to_reservation_type::Migration),
         ]
     }
 }
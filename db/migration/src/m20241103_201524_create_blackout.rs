@@ -0,0 +1,68 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{
+    m20220101_000001_create_region::Region,
+    m20240331_003613_create_reservation_type::ReservationType,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .if_not_exists()
+                    .table(Blackout::Table)
+                    .col(
+                        ColumnDef::new(Blackout::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Blackout::Name).string().not_null())
+                    .col(ColumnDef::new(Blackout::Region).integer().null())
+                    .col(ColumnDef::new(Blackout::ReservationType).integer().null())
+                    .col(ColumnDef::new(Blackout::Start).string().not_null())
+                    .col(ColumnDef::new(Blackout::End).string().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_blackout_region")
+                            .from(Blackout::Table, Blackout::Region)
+                            .to(Region::Table, Region::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_blackout_reservation-type")
+                            .from(Blackout::Table, Blackout::ReservationType)
+                            .to(ReservationType::Table, ReservationType::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .take(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().if_exists().table(Blackout::Table).take())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Blackout {
+    Table,
+    Id,
+    Name,
+    Region,
+    ReservationType,
+    Start,
+    End,
+}
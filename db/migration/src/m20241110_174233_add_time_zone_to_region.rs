@@ -0,0 +1,30 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_region::Region;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Regions created before this migration keep the old behavior of treating every date as UTC.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Region::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Alias::new("time_zone"))
+                            .string()
+                            .not_null()
+                            .default("UTC"),
+                    )
+                    .take(),
+            )
+            .await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
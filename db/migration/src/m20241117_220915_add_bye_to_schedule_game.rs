@@ -0,0 +1,30 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240507_015703_create_schedule_results::ScheduleGame;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ScheduleGame::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Alias::new("is_bye"))
+                            .boolean()
+                            .not_null()
+                            .check(Expr::col(Alias::new("is_bye")).is_in([0, 1]))
+                            .default(Value::Int(Some(0))),
+                    )
+                    .take(),
+            )
+            .await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
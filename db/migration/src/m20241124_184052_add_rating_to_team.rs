@@ -0,0 +1,25 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240223_053746_create_team::Team;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing teams are unrated, which the scheduler treats as "no preference".
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Team::Table)
+                    .add_column_if_not_exists(ColumnDef::new(Alias::new("rating")).integer().null())
+                    .take(),
+            )
+            .await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240507_015703_create_schedule_results::ScheduleGame;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Counts how many times a game was moved, for the SEQUENCE of calendar exports.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ScheduleGame::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Alias::new("sequence"))
+                            .integer()
+                            .not_null()
+                            .default(Value::Int(Some(0))),
+                    )
+                    .take(),
+            )
+            .await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
@@ -0,0 +1,321 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Every table that can be undone, with all of its columns when the journal was created.
+/// `true` if the table has an `id` primary key, which is then the `rowid`; join tables keep
+/// their `rowid` explicitly.
+///
+/// The triggers list columns by name, so a migration that adds or renames a column of
+/// one of these tables has to recreate its triggers with [`journal_triggers`]. The same
+/// tables are audited, see the `create_audit_log` migration.
+pub(crate) const JOURNALED_TABLES: [(&str, &[&str], bool); 16] = [
+    ("region", &["id", "title", "time_zone"], true),
+    ("field", &["id", "name", "region_owner"], true),
+    ("team", &["id", "name", "region_owner", "rating"], true),
+    ("team_group", &["id", "name", "usages"], true),
+    ("team_group_join", &["team", "group"], false),
+    ("target", &["id", "maybe_reservation_type"], true),
+    ("target_group_join", &["target", "group"], false),
+    (
+        "reservation_type",
+        &[
+            "id",
+            "name",
+            "description",
+            "color",
+            "default_sizing",
+            "is_practice",
+        ],
+        true,
+    ),
+    (
+        "reservation_type_field_size_join",
+        &["field", "reservation_type", "size"],
+        false,
+    ),
+    (
+        "reservation_type_time_slot_join",
+        &["time_slot", "reservation_type"],
+        false,
+    ),
+    ("time_slot", &["id", "field_id", "start", "end"], true),
+    ("coach_conflict", &["id", "region", "coach_name"], true),
+    (
+        "coach_conflict_team_join",
+        &["coach_conflict", "team"],
+        false,
+    ),
+    (
+        "blackout",
+        &["id", "name", "region", "reservation_type", "start", "end"],
+        true,
+    ),
+    ("schedule", &["id", "name", "created", "last_edit"], true),
+    (
+        "schedule_game",
+        &[
+            "id",
+            "schedule_id",
+            "start",
+            "end",
+            "team_one",
+            "team_two",
+            "field_id",
+            "is_bye",
+            "sequence",
+        ],
+        true,
+    ),
+];
+
+/// Counts how often a row changed, like `schedule_game.sequence` does for calendar apps.
+/// Undoing or redoing an update is one more change, so it counts up instead of being
+/// restored.
+const INCREMENTED_COLUMN: &str = "sequence";
+
+/// SQL that re-inserts the `OLD` row, built inside the trigger with `quote()`.
+fn reinsert_statement(table: &str, columns: &[&str], has_id: bool) -> String {
+    let mut names = columns
+        .iter()
+        .map(|c| format!("\"{c}\""))
+        .collect::<Vec<_>>();
+    let mut values = columns
+        .iter()
+        .map(|c| format!("quote(OLD.\"{c}\")"))
+        .collect::<Vec<_>>();
+
+    if !has_id {
+        names.insert(0, "rowid".to_owned());
+        values.insert(0, "OLD.rowid".to_owned());
+    }
+
+    format!(
+        "'INSERT INTO \"{table}\" ({}) VALUES (' || {} || ')'",
+        names.join(", "),
+        values.join(" || ', ' || ")
+    )
+}
+
+/// `CREATE TRIGGER` statements that write the inverse of every change to `table` into
+/// `journal_entry`, as part of the same statement, while a journal step is recording.
+pub(crate) fn journal_triggers(table: &str, columns: &[&str], has_id: bool) -> Vec<String> {
+    let record = |name: &str, event: &str, statement: String| {
+        format!(
+            "CREATE TRIGGER IF NOT EXISTS \"journal_{table}_{name}\" AFTER {event} ON \"{table}\" \
+             BEGIN \
+             INSERT INTO journal_entry (step, kind, statement) \
+             SELECT step, kind, {statement} FROM journal_recording WHERE step IS NOT NULL; \
+             END"
+        )
+    };
+
+    let restore = columns
+        .iter()
+        .map(|c| {
+            if *c == INCREMENTED_COLUMN {
+                format!("'\"{c}\" = \"{c}\" + 1'")
+            } else {
+                format!("'\"{c}\" = ' || quote(OLD.\"{c}\")")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" || ', ' || ");
+
+    vec![
+        record(
+            "insert",
+            "INSERT",
+            format!("'DELETE FROM \"{table}\" WHERE rowid = ' || NEW.rowid"),
+        ),
+        record(
+            "update",
+            "UPDATE",
+            format!("'UPDATE \"{table}\" SET ' || {restore} || ' WHERE rowid = ' || NEW.rowid"),
+        ),
+        record(
+            "delete",
+            "DELETE",
+            reinsert_statement(table, columns, has_id),
+        ),
+    ]
+}
+
+/// The columns of `table` in [`JOURNALED_TABLES`], and whether it has an `id`, for a
+/// migration that goes back to the triggers that the journal was created with.
+pub(crate) fn journaled_columns(table: &str) -> Result<(&'static [&'static str], bool), DbErr> {
+    JOURNALED_TABLES
+        .into_iter()
+        .find(|(name, _, _)| *name == table)
+        .map(|(_, columns, has_id)| (columns, has_id))
+        .ok_or_else(|| DbErr::Migration(format!("{table} is not journaled")))
+}
+
+pub(crate) fn drop_journal_triggers(table: &str) -> Vec<String> {
+    ["insert", "update", "delete"]
+        .iter()
+        .map(|name| format!("DROP TRIGGER IF EXISTS \"journal_{table}_{name}\""))
+        .collect()
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .if_not_exists()
+                    .table(JournalStep::Table)
+                    .col(
+                        ColumnDef::new(JournalStep::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(JournalStep::Label).string().not_null())
+                    .col(ColumnDef::new(JournalStep::Created).string().not_null())
+                    .col(
+                        ColumnDef::new(JournalStep::Undone)
+                            .boolean()
+                            .not_null()
+                            .default(Value::Int(Some(0))),
+                    )
+                    .take(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .if_not_exists()
+                    .table(JournalEntry::Table)
+                    .col(
+                        ColumnDef::new(JournalEntry::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(JournalEntry::Step).integer().not_null())
+                    .col(ColumnDef::new(JournalEntry::Kind).string().not_null())
+                    .col(ColumnDef::new(JournalEntry::Statement).string().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_journal-entry_step")
+                            .from(JournalEntry::Table, JournalEntry::Step)
+                            .to(JournalStep::Table, JournalStep::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .take(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("IX_JournalEntry_step")
+                    .table(JournalEntry::Table)
+                    .col(JournalEntry::Step)
+                    .take(),
+            )
+            .await?;
+
+        // a single row, which points the triggers at the step that is recording
+        manager
+            .create_table(
+                Table::create()
+                    .if_not_exists()
+                    .table(JournalRecording::Table)
+                    .col(
+                        ColumnDef::new(JournalRecording::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(JournalRecording::Step).integer().null())
+                    .col(ColumnDef::new(JournalRecording::Kind).string().null())
+                    .take(),
+            )
+            .await?;
+
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(JournalRecording::Table)
+                    .columns([JournalRecording::Id])
+                    .values_panic([1.into()])
+                    .on_conflict(
+                        OnConflict::column(JournalRecording::Id)
+                            .do_nothing()
+                            .to_owned(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let connection = manager.get_connection();
+
+        for (table, columns, has_id) in JOURNALED_TABLES {
+            for trigger in journal_triggers(table, columns, has_id) {
+                connection.execute_unprepared(&trigger).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let connection = manager.get_connection();
+
+        for (table, _, _) in JOURNALED_TABLES {
+            for trigger in drop_journal_triggers(table) {
+                connection.execute_unprepared(&trigger).await?;
+            }
+        }
+
+        manager
+            .drop_table(
+                Table::drop()
+                    .if_exists()
+                    .table(JournalRecording::Table)
+                    .take(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().if_exists().table(JournalEntry::Table).take())
+            .await?;
+        manager
+            .drop_table(Table::drop().if_exists().table(JournalStep::Table).take())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum JournalStep {
+    Table,
+    Id,
+    Label,
+    Created,
+    Undone,
+}
+
+#[derive(DeriveIden)]
+pub enum JournalEntry {
+    Table,
+    Id,
+    Step,
+    Kind,
+    Statement,
+}
+
+#[derive(DeriveIden)]
+pub enum JournalRecording {
+    Table,
+    Id,
+    Step,
+    Kind,
+}
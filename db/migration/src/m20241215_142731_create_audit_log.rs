@@ -0,0 +1,207 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20241208_190412_create_journal::JOURNALED_TABLES;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// `json_object(...)` of every column of the `row` (`OLD` or `NEW`) of a trigger.
+fn json_row(row: &str, columns: &[&str]) -> String {
+    let pairs = columns
+        .iter()
+        .map(|c| format!("'{c}', {row}.\"{c}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!("json_object({pairs})")
+}
+
+/// `CREATE TRIGGER` statements that append every change to `table` to `audit_log`, as
+/// part of the same statement, with the user from `audit_context`.
+///
+/// Like the journal triggers, these list columns by name, so a migration that changes
+/// the columns of `table` has to recreate them.
+pub(crate) fn audit_triggers(table: &str, columns: &[&str], has_id: bool) -> Vec<String> {
+    let record = |name: &str, event: &str, row: &str, before: String, after: String| {
+        let entity_id = if has_id {
+            format!("{row}.\"id\"")
+        } else {
+            "NULL".to_owned()
+        };
+
+        format!(
+            "CREATE TRIGGER IF NOT EXISTS \"audit_{table}_{name}\" AFTER {event} ON \"{table}\" \
+             BEGIN \
+             INSERT INTO audit_log (entity, entity_id, action, \"before\", \"after\", timestamp, user) \
+             SELECT '{table}', {entity_id}, '{name}', {before}, {after}, \
+             strftime('%Y-%m-%dT%H:%M:%fZ', 'now'), user FROM audit_context WHERE id = 1; \
+             END"
+        )
+    };
+
+    vec![
+        record(
+            "insert",
+            "INSERT",
+            "NEW",
+            "NULL".to_owned(),
+            json_row("NEW", columns),
+        ),
+        record(
+            "update",
+            "UPDATE",
+            "NEW",
+            json_row("OLD", columns),
+            json_row("NEW", columns),
+        ),
+        record(
+            "delete",
+            "DELETE",
+            "OLD",
+            json_row("OLD", columns),
+            "NULL".to_owned(),
+        ),
+    ]
+}
+
+pub(crate) fn drop_audit_triggers(table: &str) -> Vec<String> {
+    ["insert", "update", "delete"]
+        .iter()
+        .map(|name| format!("DROP TRIGGER IF EXISTS \"audit_{table}_{name}\""))
+        .collect()
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .if_not_exists()
+                    .table(AuditLog::Table)
+                    .col(
+                        ColumnDef::new(AuditLog::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuditLog::Entity).string().not_null())
+                    .col(ColumnDef::new(AuditLog::EntityId).integer().null())
+                    .col(ColumnDef::new(AuditLog::Action).string().not_null())
+                    .col(ColumnDef::new(AuditLog::Before).string().null())
+                    .col(ColumnDef::new(AuditLog::After).string().null())
+                    .col(ColumnDef::new(AuditLog::Timestamp).string().not_null())
+                    .col(ColumnDef::new(AuditLog::User).string().null())
+                    .take(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("IX_AuditLog_entity")
+                    .table(AuditLog::Table)
+                    .col(AuditLog::Entity)
+                    .col(AuditLog::EntityId)
+                    .take(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("IX_AuditLog_timestamp")
+                    .table(AuditLog::Table)
+                    .col(AuditLog::Timestamp)
+                    .take(),
+            )
+            .await?;
+
+        // a single row, with the user that the triggers attribute changes to
+        manager
+            .create_table(
+                Table::create()
+                    .if_not_exists()
+                    .table(AuditContext::Table)
+                    .col(
+                        ColumnDef::new(AuditContext::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuditContext::User).string().null())
+                    .take(),
+            )
+            .await?;
+
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(AuditContext::Table)
+                    .columns([AuditContext::Id])
+                    .values_panic([1.into()])
+                    .on_conflict(OnConflict::column(AuditContext::Id).do_nothing().to_owned())
+                    .to_owned(),
+            )
+            .await?;
+
+        let connection = manager.get_connection();
+
+        for statement in [
+            "CREATE TRIGGER IF NOT EXISTS \"audit_log_no_update\" BEFORE UPDATE ON audit_log \
+             BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END",
+            "CREATE TRIGGER IF NOT EXISTS \"audit_log_no_delete\" BEFORE DELETE ON audit_log \
+             BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END",
+        ] {
+            connection.execute_unprepared(statement).await?;
+        }
+
+        for (table, columns, has_id) in JOURNALED_TABLES {
+            for trigger in audit_triggers(table, columns, has_id) {
+                connection.execute_unprepared(&trigger).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let connection = manager.get_connection();
+
+        for (table, _, _) in JOURNALED_TABLES {
+            for trigger in drop_audit_triggers(table) {
+                connection.execute_unprepared(&trigger).await?;
+            }
+        }
+
+        manager
+            .drop_table(Table::drop().if_exists().table(AuditContext::Table).take())
+            .await?;
+        manager
+            .drop_table(Table::drop().if_exists().table(AuditLog::Table).take())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum AuditLog {
+    Table,
+    Id,
+    Entity,
+    EntityId,
+    Action,
+    Before,
+    After,
+    Timestamp,
+    User,
+}
+
+#[derive(DeriveIden)]
+pub enum AuditContext {
+    Table,
+    Id,
+    User,
+}
@@ -0,0 +1,222 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240507_015703_create_schedule_results::Schedule;
+use crate::m20241215_142731_create_audit_log::{audit_triggers, drop_audit_triggers};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+pub(crate) const SCHEDULE_REVISION_COLUMNS: [&str; 4] = ["id", "schedule_id", "label", "created"];
+
+pub(crate) const SCHEDULE_REVISION_GAME_COLUMNS: [&str; 9] = [
+    "id",
+    "revision_id",
+    "game_id",
+    "start",
+    "end",
+    "team_one",
+    "team_two",
+    "field_id",
+    "is_bye",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .if_not_exists()
+                    .table(ScheduleRevision::Table)
+                    .col(
+                        ColumnDef::new(ScheduleRevision::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleRevision::ScheduleId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ScheduleRevision::Label).string().null())
+                    .col(
+                        ColumnDef::new(ScheduleRevision::Created)
+                            .string()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_schedule-revision_schedule")
+                            .from(ScheduleRevision::Table, ScheduleRevision::ScheduleId)
+                            .to(Schedule::Table, Schedule::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .take(),
+            )
+            .await?;
+
+        // Copies of `schedule_game` rows. Teams and fields are not foreign keys, so that
+        // a revision still describes what was published after they are deleted.
+        manager
+            .create_table(
+                Table::create()
+                    .if_not_exists()
+                    .table(ScheduleRevisionGame::Table)
+                    .col(
+                        ColumnDef::new(ScheduleRevisionGame::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleRevisionGame::RevisionId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleRevisionGame::GameId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleRevisionGame::Start)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleRevisionGame::End)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleRevisionGame::TeamOne)
+                            .integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleRevisionGame::TeamTwo)
+                            .integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleRevisionGame::FieldId)
+                            .integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScheduleRevisionGame::IsBye)
+                            .boolean()
+                            .not_null()
+                            .default(Value::Int(Some(0))),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_schedule-revision-game_revision")
+                            .from(
+                                ScheduleRevisionGame::Table,
+                                ScheduleRevisionGame::RevisionId,
+                            )
+                            .to(ScheduleRevision::Table, ScheduleRevision::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .take(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("IX_ScheduleRevision_schedule_id")
+                    .table(ScheduleRevision::Table)
+                    .col(ScheduleRevision::ScheduleId)
+                    .take(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("IX_ScheduleRevisionGame_revision_id")
+                    .table(ScheduleRevisionGame::Table)
+                    .col(ScheduleRevisionGame::RevisionId)
+                    .take(),
+            )
+            .await?;
+
+        // revisions are not journaled, since a snapshot is never edited, but who took or
+        // deleted one is still worth keeping
+        let connection = manager.get_connection();
+
+        let statements = audit_triggers("schedule_revision", &SCHEDULE_REVISION_COLUMNS, true)
+            .into_iter()
+            .chain(audit_triggers(
+                "schedule_revision_game",
+                &SCHEDULE_REVISION_GAME_COLUMNS,
+                true,
+            ));
+
+        for statement in statements {
+            connection.execute_unprepared(&statement).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let connection = manager.get_connection();
+
+        for statement in drop_audit_triggers("schedule_revision_game")
+            .into_iter()
+            .chain(drop_audit_triggers("schedule_revision"))
+        {
+            connection.execute_unprepared(&statement).await?;
+        }
+
+        manager
+            .drop_table(
+                Table::drop()
+                    .if_exists()
+                    .table(ScheduleRevisionGame::Table)
+                    .take(),
+            )
+            .await?;
+        manager
+            .drop_table(
+                Table::drop()
+                    .if_exists()
+                    .table(ScheduleRevision::Table)
+                    .take(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum ScheduleRevision {
+    Table,
+    Id,
+    ScheduleId,
+    Label,
+    Created,
+}
+
+#[derive(DeriveIden)]
+pub enum ScheduleRevisionGame {
+    Table,
+    Id,
+    RevisionId,
+    GameId,
+    Start,
+    End,
+    TeamOne,
+    TeamTwo,
+    FieldId,
+    IsBye,
+}
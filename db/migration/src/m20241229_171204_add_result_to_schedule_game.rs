@@ -0,0 +1,116 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240507_015703_create_schedule_results::ScheduleGame;
+use crate::m20241208_190412_create_journal::{
+    drop_journal_triggers, journal_triggers, journaled_columns,
+};
+use crate::m20241215_142731_create_audit_log::{audit_triggers, drop_audit_triggers};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Every column of `schedule_game` after this migration, for its journal and audit triggers.
+pub(crate) const SCHEDULE_GAME_COLUMNS: [&str; 12] = [
+    "id",
+    "schedule_id",
+    "start",
+    "end",
+    "team_one",
+    "team_two",
+    "field_id",
+    "is_bye",
+    "sequence",
+    "status",
+    "home_score",
+    "away_score",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // `scheduled`, `played`, `home_forfeit`, `away_forfeit` or `cancelled`
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ScheduleGame::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Alias::new("status"))
+                            .string()
+                            .not_null()
+                            .default("scheduled"),
+                    )
+                    .take(),
+            )
+            .await?;
+
+        // SQLite can only add one column per statement
+        for column in ["home_score", "away_score"] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ScheduleGame::Table)
+                        .add_column_if_not_exists(
+                            ColumnDef::new(Alias::new(column)).integer().null(),
+                        )
+                        .take(),
+                )
+                .await?;
+        }
+
+        let connection = manager.get_connection();
+
+        let statements = drop_journal_triggers("schedule_game")
+            .into_iter()
+            .chain(drop_audit_triggers("schedule_game"))
+            .chain(journal_triggers(
+                "schedule_game",
+                &SCHEDULE_GAME_COLUMNS,
+                true,
+            ))
+            .chain(audit_triggers(
+                "schedule_game",
+                &SCHEDULE_GAME_COLUMNS,
+                true,
+            ));
+
+        for statement in statements {
+            connection.execute_unprepared(&statement).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let connection = manager.get_connection();
+
+        // the triggers name the columns, so they go before the columns do
+        for statement in drop_journal_triggers("schedule_game")
+            .into_iter()
+            .chain(drop_audit_triggers("schedule_game"))
+        {
+            connection.execute_unprepared(&statement).await?;
+        }
+
+        for column in ["status", "home_score", "away_score"] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ScheduleGame::Table)
+                        .drop_column(Alias::new(column))
+                        .take(),
+                )
+                .await?;
+        }
+
+        let (columns, has_id) = journaled_columns("schedule_game")?;
+
+        for statement in journal_triggers("schedule_game", columns, has_id)
+            .into_iter()
+            .chain(audit_triggers("schedule_game", columns, has_id))
+        {
+            connection.execute_unprepared(&statement).await?;
+        }
+
+        Ok(())
+    }
+}
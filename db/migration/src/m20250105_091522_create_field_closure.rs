@@ -0,0 +1,95 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240221_004555_create_field::Field;
+use crate::m20241208_190412_create_journal::{drop_journal_triggers, journal_triggers};
+use crate::m20241215_142731_create_audit_log::{audit_triggers, drop_audit_triggers};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+pub(crate) const FIELD_CLOSURE_COLUMNS: [&str; 5] = ["id", "field_id", "start", "end", "reason"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .if_not_exists()
+                    .table(FieldClosure::Table)
+                    .col(
+                        ColumnDef::new(FieldClosure::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(FieldClosure::FieldId).integer().not_null())
+                    .col(ColumnDef::new(FieldClosure::Start).string().not_null())
+                    .col(ColumnDef::new(FieldClosure::End).string().not_null())
+                    .col(ColumnDef::new(FieldClosure::Reason).string().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_field-closure_field")
+                            .from(FieldClosure::Table, FieldClosure::FieldId)
+                            .to(Field::Table, Field::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .take(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("IX_FieldClosure_field_id")
+                    .table(FieldClosure::Table)
+                    .col(FieldClosure::FieldId)
+                    .take(),
+            )
+            .await?;
+
+        let connection = manager.get_connection();
+
+        let statements = journal_triggers("field_closure", &FIELD_CLOSURE_COLUMNS, true)
+            .into_iter()
+            .chain(audit_triggers(
+                "field_closure",
+                &FIELD_CLOSURE_COLUMNS,
+                true,
+            ));
+
+        for statement in statements {
+            connection.execute_unprepared(&statement).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let connection = manager.get_connection();
+
+        for statement in drop_journal_triggers("field_closure")
+            .into_iter()
+            .chain(drop_audit_triggers("field_closure"))
+        {
+            connection.execute_unprepared(&statement).await?;
+        }
+
+        manager
+            .drop_table(Table::drop().if_exists().table(FieldClosure::Table).take())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum FieldClosure {
+    Table,
+    Id,
+    FieldId,
+    Start,
+    End,
+    Reason,
+}
@@ -0,0 +1,458 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_orm::Statement;
+
+use crate::m20241208_190412_create_journal::{drop_journal_triggers, journal_triggers};
+use crate::m20241215_142731_create_audit_log::{audit_triggers, drop_audit_triggers};
+use crate::m20241222_103318_create_schedule_revision::{
+    SCHEDULE_REVISION_COLUMNS, SCHEDULE_REVISION_GAME_COLUMNS,
+};
+use crate::m20241229_171204_add_result_to_schedule_game::SCHEDULE_GAME_COLUMNS;
+use crate::m20250105_091522_create_field_closure::FIELD_CLOSURE_COLUMNS;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const TIME_SLOT_COLUMNS: [&str; 4] = ["id", "field_id", "start", "end"];
+const BLACKOUT_COLUMNS: [&str; 6] = ["id", "name", "region", "reservation_type", "start", "end"];
+
+/// A time column, and its index if it has one.
+type Time = (&'static str, Option<&'static str>);
+
+/// Every table with text times: the columns its triggers list, each time with its
+/// index, and whether it is journaled. Revisions are only audited.
+const TYPED_TABLES: [(&str, &[&str], &[Time], bool); 6] = [
+    (
+        "time_slot",
+        &TIME_SLOT_COLUMNS,
+        &[
+            ("start", Some("IX_TimeSlot_start")),
+            ("end", Some("IX_TimeSlot_end")),
+        ],
+        true,
+    ),
+    (
+        "schedule_game",
+        &SCHEDULE_GAME_COLUMNS,
+        &[
+            ("start", Some("IX_ScheduleGame_start")),
+            ("end", Some("IX_ScheduleGame_end")),
+        ],
+        true,
+    ),
+    (
+        "blackout",
+        &BLACKOUT_COLUMNS,
+        &[
+            ("start", Some("IX_Blackout_start")),
+            ("end", Some("IX_Blackout_end")),
+        ],
+        true,
+    ),
+    (
+        "field_closure",
+        &FIELD_CLOSURE_COLUMNS,
+        &[
+            ("start", Some("IX_FieldClosure_start")),
+            ("end", Some("IX_FieldClosure_end")),
+        ],
+        true,
+    ),
+    (
+        "schedule_revision",
+        &SCHEDULE_REVISION_COLUMNS,
+        &[("created", None)],
+        false,
+    ),
+    (
+        "schedule_revision_game",
+        &SCHEDULE_REVISION_GAME_COLUMNS,
+        &[("start", None), ("end", None)],
+        false,
+    ),
+];
+
+/// How the times of [`TYPED_TABLES`] are stored, before and after this migration.
+#[derive(Clone, Copy)]
+enum Storage {
+    /// RFC 3339 text
+    Text,
+    /// UNIX milliseconds
+    Millis,
+}
+
+impl Storage {
+    /// A new column that holds times this way.
+    fn column(self, name: &str) -> ColumnDef {
+        let mut column = ColumnDef::new(Alias::new(name));
+
+        match self {
+            Self::Text => column.string().not_null().default(""),
+            Self::Millis => column.big_integer().not_null().default(0),
+        };
+
+        column
+    }
+
+    /// SQL that reads `column`, stored the other way, as this storage.
+    fn convert(self, column: &str) -> String {
+        match self {
+            Self::Text => format!(
+                "strftime('%Y-%m-%dT%H:%M:%f+00:00', \"{column}\" / 1000.0, 'unixepoch')"
+            ),
+            Self::Millis => to_millis(column),
+        }
+    }
+}
+
+/// RFC 3339 text to UNIX milliseconds. `julianday` understands every offset, and is
+/// `NULL` for text that is not a date.
+fn to_millis(column: &str) -> String {
+    format!("CAST(ROUND((julianday(\"{column}\") - 2440587.5) * 86400000) AS INTEGER)")
+}
+
+/// Ids of the rows of `table` with a time that is not a date, which [`migrate_table`]
+/// could not convert.
+async fn unreadable_rows(
+    manager: &SchemaManager<'_>,
+    table: &str,
+    times: &[Time],
+) -> Result<Vec<i32>, DbErr> {
+    let connection = manager.get_connection();
+
+    let unreadable = times
+        .iter()
+        .map(|(column, _)| format!("{} IS NULL", to_millis(column)))
+        .collect::<Vec<_>>()
+        .join(" OR ");
+
+    connection
+        .query_all(Statement::from_string(
+            connection.get_database_backend(),
+            format!("SELECT \"id\" FROM \"{table}\" WHERE {unreadable}"),
+        ))
+        .await?
+        .into_iter()
+        .map(|row| row.try_get::<i32>("", "id"))
+        .collect()
+}
+
+/// Fails if any row of [`TYPED_TABLES`] can't be converted by [`migrate_table`]. SQLite
+/// migrations are not run in a transaction, so this has to run before anything is changed.
+async fn check_readable(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    let mut unreadable = vec![];
+
+    for (table, _, times, _) in TYPED_TABLES {
+        let ids = unreadable_rows(manager, table, times).await?;
+
+        if !ids.is_empty() {
+            unreadable.push(format!("{table} {ids:?}"));
+        }
+    }
+
+    if unreadable.is_empty() {
+        return Ok(());
+    }
+
+    Err(DbErr::Migration(format!(
+        "these rows have a time that is not a date, fix or delete them and open the \
+         profile again: {}",
+        unreadable.join(", ")
+    )))
+}
+
+/// Drops every undo step. Their statements hold times the old way, which would no longer
+/// fit the converted columns.
+async fn clear_journal(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    let connection = manager.get_connection();
+
+    connection
+        .execute_unprepared("UPDATE \"journal_recording\" SET \"step\" = NULL, \"kind\" = NULL")
+        .await?;
+
+    for table in ["journal_entry", "journal_step"] {
+        connection
+            .execute_unprepared(&format!("DELETE FROM \"{table}\""))
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the `times` of `table` with columns of the same name, that hold times the
+/// `to` way.
+async fn migrate_table(
+    manager: &SchemaManager<'_>,
+    table: &str,
+    columns: &[&str],
+    times: &[Time],
+    journaled: bool,
+    to: Storage,
+) -> Result<(), DbErr> {
+    let connection = manager.get_connection();
+
+    // SQLite cannot drop a column that a trigger or an index uses
+    for statement in drop_journal_triggers(table)
+        .into_iter()
+        .chain(drop_audit_triggers(table))
+    {
+        connection.execute_unprepared(&statement).await?;
+    }
+
+    for index in times.iter().filter_map(|(_, index)| *index) {
+        manager
+            .drop_index(Index::drop().if_exists().name(index).to_owned())
+            .await?;
+    }
+
+    for (column, _) in times {
+        let converted = format!("{column}_converted");
+
+        // SQLite can only change one column per statement
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Alias::new(table))
+                    .add_column(&mut to.column(&converted))
+                    .take(),
+            )
+            .await?;
+
+        connection
+            .execute_unprepared(&format!(
+                "UPDATE \"{table}\" SET \"{converted}\" = {}",
+                to.convert(column)
+            ))
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Alias::new(table))
+                    .drop_column(Alias::new(*column))
+                    .take(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Alias::new(table))
+                    .rename_column(Alias::new(&converted), Alias::new(*column))
+                    .take(),
+            )
+            .await?;
+    }
+
+    for (column, index) in times {
+        let Some(index) = index else {
+            continue;
+        };
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name(*index)
+                    .table(Alias::new(table))
+                    .col(Alias::new(*column))
+                    .take(),
+            )
+            .await?;
+    }
+
+    let journal = if journaled {
+        journal_triggers(table, columns, true)
+    } else {
+        vec![]
+    };
+
+    for statement in journal
+        .into_iter()
+        .chain(audit_triggers(table, columns, true))
+    {
+        connection.execute_unprepared(&statement).await?;
+    }
+
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        check_readable(manager).await?;
+        clear_journal(manager).await?;
+
+        for (table, columns, times, journaled) in TYPED_TABLES {
+            migrate_table(manager, table, columns, times, journaled, Storage::Millis).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // every time is a date by now, but in UTC: the original offsets are not kept
+        clear_journal(manager).await?;
+
+        for (table, columns, times, journaled) in TYPED_TABLES {
+            migrate_table(manager, table, columns, times, journaled, Storage::Text).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm_migration::sea_orm::{
+        ConnectionTrait, Database, DatabaseConnection, DbErr, Statement,
+    };
+    use sea_orm_migration::{MigrationName, MigratorTrait};
+
+    use super::Migration;
+    use crate::Migrator;
+
+    /// A database migrated up to just before `typed_timestamps`, with text times in
+    /// `rows`.
+    async fn seeded(rows: &[&str]) -> DatabaseConnection {
+        let connection = Database::connect("sqlite::memory:").await.unwrap();
+
+        let before = Migrator::migrations()
+            .iter()
+            .position(|migration| migration.name() == Migration.name())
+            .unwrap();
+
+        Migrator::up(&connection, Some(before as u32))
+            .await
+            .unwrap();
+
+        for statement in rows {
+            connection.execute_unprepared(statement).await.unwrap();
+        }
+
+        connection
+    }
+
+    async fn millis(connection: &DatabaseConnection, table: &str, column: &str) -> i64 {
+        connection
+            .query_one(Statement::from_string(
+                connection.get_database_backend(),
+                format!("SELECT \"{column}\" FROM \"{table}\""),
+            ))
+            .await
+            .unwrap()
+            .unwrap()
+            .try_get_by_index(0)
+            .unwrap()
+    }
+
+    const SEED: [&str; 6] = [
+        "INSERT INTO region (id, title) VALUES (1, 'Region')",
+        "INSERT INTO field (id, name, region_owner) VALUES (1, 'Field', 1)",
+        "INSERT INTO time_slot (id, field_id, start, \"end\") \
+         VALUES (1, 1, '2024-06-01T10:00:00-07:00', '2024-06-01T18:00:00+00:00')",
+        "INSERT INTO schedule (id, name, created, last_edit) \
+         VALUES (1, 'Schedule', '2024-06-01T00:00:00+00:00', '2024-06-01T00:00:00+00:00')",
+        "INSERT INTO schedule_revision (id, schedule_id, label, created) \
+         VALUES (1, 1, 'Published', '2024-06-01T12:30:00.250+00:00')",
+        "INSERT INTO schedule_revision_game (id, revision_id, game_id, start, \"end\") \
+         VALUES (1, 1, 1, '2024-06-01T10:00:00-07:00', '2024-06-01T11:00:00-07:00')",
+    ];
+
+    #[async_std::test]
+    async fn converts_text_times_to_millis() {
+        let connection = seeded(&SEED).await;
+
+        Migrator::up(&connection, Some(1)).await.unwrap();
+
+        // 2024-06-01T17:00:00Z
+        let start = 1_717_261_200_000;
+        let hour = 3_600_000;
+
+        for table in ["time_slot", "schedule_revision_game"] {
+            assert_eq!(millis(&connection, table, "start").await, start);
+            assert_eq!(millis(&connection, table, "end").await, start + hour);
+        }
+
+        assert_eq!(
+            millis(&connection, "schedule_revision", "created").await,
+            start - 4 * hour - hour / 2 + 250
+        );
+    }
+
+    #[async_std::test]
+    async fn refuses_rows_that_are_not_dates() {
+        let connection = seeded(&SEED).await;
+
+        connection
+            .execute_unprepared("UPDATE schedule_revision SET created = 'yesterday'")
+            .await
+            .unwrap();
+
+        let Err(DbErr::Migration(message)) = Migrator::up(&connection, Some(1)).await else {
+            panic!("migrated a revision that is not a date");
+        };
+
+        assert!(message.contains("schedule_revision [1]"), "{message}");
+
+        // nothing was changed, so the migration can run again once the row is fixed
+        connection
+            .execute_unprepared(
+                "UPDATE schedule_revision SET created = '2024-06-01T12:30:00.250+00:00'",
+            )
+            .await
+            .unwrap();
+
+        Migrator::up(&connection, Some(1)).await.unwrap();
+    }
+
+    #[async_std::test]
+    async fn reverts_millis_to_text() {
+        let connection = seeded(&SEED).await;
+
+        Migrator::up(&connection, Some(1)).await.unwrap();
+        Migrator::down(&connection, Some(1)).await.unwrap();
+
+        let created: String = connection
+            .query_one(Statement::from_string(
+                connection.get_database_backend(),
+                "SELECT created FROM schedule_revision".to_owned(),
+            ))
+            .await
+            .unwrap()
+            .unwrap()
+            .try_get_by_index(0)
+            .unwrap();
+
+        assert_eq!(created, "2024-06-01T12:30:00.250+00:00");
+
+        // and the text converts to the same millis again
+        Migrator::up(&connection, Some(1)).await.unwrap();
+
+        assert_eq!(
+            millis(&connection, "time_slot", "start").await,
+            1_717_261_200_000
+        );
+    }
+
+    #[async_std::test]
+    async fn refreshes_a_migrated_database() {
+        let connection = seeded(&SEED).await;
+
+        Migrator::up(&connection, None).await.unwrap();
+        Migrator::refresh(&connection).await.unwrap();
+
+        let regions = connection
+            .query_all(Statement::from_string(
+                connection.get_database_backend(),
+                "SELECT id FROM region".to_owned(),
+            ))
+            .await
+            .unwrap();
+
+        assert!(regions.is_empty());
+        assert!(Migrator::get_pending_migrations(&connection)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,97 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240331_003613_create_reservation_type::ReservationType;
+use crate::m20241208_190412_create_journal::{
+    drop_journal_triggers, journal_triggers, journaled_columns,
+};
+use crate::m20241215_142731_create_audit_log::{audit_triggers, drop_audit_triggers};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Every column of `reservation_type` after this migration, for its journal and audit triggers.
+pub(crate) const RESERVATION_TYPE_COLUMNS: [&str; 8] = [
+    "id",
+    "name",
+    "description",
+    "color",
+    "default_sizing",
+    "is_practice",
+    "rematch_spacing_days",
+    "rematch_spacing_rounds",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // At most one of the two is set. Neither means pairings may meet again at any time.
+        for column in ["rematch_spacing_days", "rematch_spacing_rounds"] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ReservationType::Table)
+                        .add_column_if_not_exists(
+                            ColumnDef::new(Alias::new(column)).integer().null(),
+                        )
+                        .take(),
+                )
+                .await?;
+        }
+
+        let connection = manager.get_connection();
+
+        let statements = drop_journal_triggers("reservation_type")
+            .into_iter()
+            .chain(drop_audit_triggers("reservation_type"))
+            .chain(journal_triggers(
+                "reservation_type",
+                &RESERVATION_TYPE_COLUMNS,
+                true,
+            ))
+            .chain(audit_triggers(
+                "reservation_type",
+                &RESERVATION_TYPE_COLUMNS,
+                true,
+            ));
+
+        for statement in statements {
+            connection.execute_unprepared(&statement).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let connection = manager.get_connection();
+
+        // the triggers name the columns, so they go before the columns do
+        for statement in drop_journal_triggers("reservation_type")
+            .into_iter()
+            .chain(drop_audit_triggers("reservation_type"))
+        {
+            connection.execute_unprepared(&statement).await?;
+        }
+
+        for column in ["rematch_spacing_days", "rematch_spacing_rounds"] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(ReservationType::Table)
+                        .drop_column(Alias::new(column))
+                        .take(),
+                )
+                .await?;
+        }
+
+        let (columns, has_id) = journaled_columns("reservation_type")?;
+
+        for statement in journal_triggers("reservation_type", columns, has_id)
+            .into_iter()
+            .chain(audit_triggers("reservation_type", columns, has_id))
+        {
+            connection.execute_unprepared(&statement).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,243 @@
+//! Who changed what, and when.
+//!
+//! Every change to a journaled table, and to schedule revisions, is appended to
+//! `audit_log` by triggers (see the `create_audit_log` migration), along with the row
+//! before and after as JSON and the user in `audit_context`. Changes made by foreign key
+//! actions are logged as well. The log cannot be edited; triggers reject updates and
+//! deletes.
+
+use chrono::{NaiveDate, NaiveTime};
+use entity::{audit_context, audit_log};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::AuditError;
+
+const CSV_HEADERS: [&str; 8] = [
+    "id",
+    "timestamp",
+    "user",
+    "entity",
+    "entity_id",
+    "action",
+    "before",
+    "after",
+];
+
+/// Narrows the audit log down. Every filter that is set must match.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditQuery {
+    /// Table name, e.g. `schedule_game`
+    entity: Option<String>,
+    entity_id: Option<i32>,
+    /// First UTC date to include
+    from: Option<NaiveDate>,
+    /// Last UTC date to include
+    to: Option<NaiveDate>,
+}
+
+impl AuditQuery {
+    pub fn new(
+        entity: Option<String>,
+        entity_id: Option<i32>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Self {
+        Self {
+            entity,
+            entity_id,
+            from,
+            to,
+        }
+    }
+
+    pub fn entity(&self) -> Option<&str> {
+        self.entity.as_deref()
+    }
+
+    pub fn entity_id(&self) -> Option<i32> {
+        self.entity_id
+    }
+}
+
+/// Timestamps are stored as `%Y-%m-%dT%H:%M:%S%.3fZ`, so they sort as text.
+fn timestamp(date: NaiveDate) -> String {
+    date.and_time(NaiveTime::MIN)
+        .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+        .to_string()
+}
+
+/// Attributes the changes that follow to `user`.
+pub(crate) async fn set_user<C>(connection: &C, user: Option<String>) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    audit_context::ActiveModel {
+        id: Set(1),
+        user: Set(user),
+    }
+    .update(connection)
+    .await
+    .map(|_| ())
+}
+
+/// Entries that match `query`, oldest first.
+pub(crate) async fn query<C>(
+    connection: &C,
+    query: &AuditQuery,
+) -> Result<Vec<audit_log::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let mut select = audit_log::Entity::find();
+
+    if let Some(entity) = &query.entity {
+        select = select.filter(audit_log::Column::Entity.eq(entity));
+    }
+
+    if let Some(id) = query.entity_id {
+        select = select.filter(audit_log::Column::EntityId.eq(id));
+    }
+
+    if let Some(from) = query.from {
+        select = select.filter(audit_log::Column::Timestamp.gte(timestamp(from)));
+    }
+
+    if let Some(to) = query.to.and_then(|to| to.succ_opt()) {
+        select = select.filter(audit_log::Column::Timestamp.lt(timestamp(to)));
+    }
+
+    select
+        .order_by_asc(audit_log::Column::Id)
+        .all(connection)
+        .await
+}
+
+/// Writes `entries` as CSV, with a header row even if there are no entries.
+pub(crate) fn to_csv(entries: &[audit_log::Model]) -> Result<String, AuditError> {
+    let csv_error = |e: csv::Error| AuditError::Csv(e.to_string());
+
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(vec![]);
+
+    writer.write_record(CSV_HEADERS).map_err(csv_error)?;
+
+    for entry in entries {
+        writer
+            .write_record([
+                entry.id.to_string(),
+                entry.timestamp.clone(),
+                entry.user.clone().unwrap_or_default(),
+                entry.entity.clone(),
+                entry.entity_id.map(|id| id.to_string()).unwrap_or_default(),
+                entry.action.clone(),
+                entry.before.clone().unwrap_or_default(),
+                entry.after.clone().unwrap_or_default(),
+            ])
+            .map_err(csv_error)?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| AuditError::Csv(e.to_string()))?;
+
+    String::from_utf8(bytes).map_err(|e| AuditError::Csv(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Days, Utc};
+    use sea_orm::ConnectionTrait;
+
+    use super::AuditQuery;
+    use crate::fixtures::{client, region, team};
+    use crate::Client;
+
+    /// `(action, user)` of the entries of `entity`, oldest first.
+    async fn actions(client: &Client, entity: &str) -> Vec<(String, Option<String>)> {
+        client
+            .get_audit_log(&AuditQuery::new(Some(entity.to_owned()), None, None, None))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.action, entry.user))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn logs_who_changed_what() {
+        let client = client().await;
+
+        client.set_audit_user(Some("ana".to_owned())).await.unwrap();
+        let region = region(&client).await;
+        let team = team(&client, region, "Rockies").await;
+
+        // the team goes with its region, by a foreign key action
+        client.set_audit_user(None).await.unwrap();
+        client.delete_region(region).await.unwrap();
+
+        let ana = || Some("ana".to_owned());
+        assert_eq!(
+            actions(&client, "region").await,
+            [("insert".to_owned(), ana()), ("delete".to_owned(), None)]
+        );
+        assert_eq!(
+            actions(&client, "team").await,
+            [("insert".to_owned(), ana()), ("delete".to_owned(), None)]
+        );
+
+        let entries = client
+            .get_audit_log(&AuditQuery::new(
+                Some("team".to_owned()),
+                Some(team),
+                None,
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(entries[0].before, None);
+        assert!(entries[0].after.as_ref().unwrap().contains("\"Rockies\""));
+        assert_eq!(entries[1].before, entries[0].after);
+        assert_eq!(entries[1].after, None);
+
+        let today = Utc::now().date_naive();
+        let yesterday = today.checked_sub_days(Days::new(1));
+        for (from, to, entries) in [(Some(today), Some(today), 4), (None, yesterday, 0)] {
+            let query = AuditQuery::new(None, None, from, to);
+            assert_eq!(client.get_audit_log(&query).await.unwrap().len(), entries);
+        }
+
+        let csv = client
+            .export_audit_log_csv(&AuditQuery::default())
+            .await
+            .unwrap();
+        assert!(csv.starts_with("id,timestamp,user,entity,entity_id,action,before,after\n"));
+        assert_eq!(csv.lines().count(), 5);
+    }
+
+    #[tokio::test]
+    async fn log_is_append_only() {
+        let client = client().await;
+        region(&client).await;
+
+        for statement in [
+            "UPDATE audit_log SET user = 'mallory'",
+            "DELETE FROM audit_log",
+        ] {
+            assert!(client
+                .connection
+                .execute_unprepared(statement)
+                .await
+                .is_err());
+        }
+
+        assert_eq!(
+            actions(&client, "region").await,
+            [("insert".to_owned(), None)]
+        );
+    }
+}
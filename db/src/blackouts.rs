@@ -0,0 +1,259 @@
+//! Blackout dates are spans of time (holidays, tournaments, school events...)
+//! during which no games may be scheduled. They apply to a single region, or
+//! to every region when no region is given, and may optionally be limited to
+//! one reservation type. Time slots are never deleted by a blackout; they are
+//! simply hidden from the scheduler and the Pre-Schedule Report.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use entity::field;
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+
+use crate::entity_local_exports::{Blackout, BlackoutEntity, FieldEntity};
+use crate::timestamps::Timespan;
+use crate::TimeSlotExtension;
+
+/// Capacity that a single blackout took away from the schedule.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlackoutImpact {
+    blackout: Blackout,
+    time_slots_removed: u64,
+    matches_removed: u64,
+}
+
+impl Display for BlackoutImpact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "blackout \"{}\" removes {} time slots ({} matches)",
+            self.blackout.name, self.time_slots_removed, self.matches_removed
+        )
+    }
+}
+
+/// Every blackout in the database, pre-parsed so that many time slots can
+/// be checked against it without going back to the database.
+pub(crate) struct BlackoutFilter {
+    blackouts: Vec<(Blackout, DateTime<Utc>, DateTime<Utc>)>,
+    field_to_region: BTreeMap<i32, i32>,
+}
+
+impl BlackoutFilter {
+    pub(crate) async fn load<C>(connection: &C) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let blackouts = BlackoutEntity::find()
+            .order_by_asc(entity::blackout::Column::Id)
+            .all(connection)
+            .await?
+            .into_iter()
+            .map(|blackout| {
+                let (Some(start), Some(end)) = (blackout.start_time(), blackout.end_time()) else {
+                    return Err(DbErr::Type(format!(
+                        "blackout {} has a malformed start or end",
+                        blackout.id
+                    )));
+                };
+
+                Ok((blackout, start, end))
+            })
+            .collect::<Result<Vec<_>, DbErr>>()?;
+
+        let field_to_region = BTreeMap::from_iter(
+            FieldEntity::find()
+                .select_only()
+                .column(field::Column::Id)
+                .column(field::Column::RegionOwner)
+                .into_tuple::<(i32, i32)>()
+                .all(connection)
+                .await?,
+        );
+
+        Ok(Self {
+            blackouts,
+            field_to_region,
+        })
+    }
+
+    /// Returns the first blackout (by id) that covers any part of this time slot.
+    pub(crate) fn blackout_for(&self, time_slot_ext: &TimeSlotExtension) -> Option<&Blackout> {
+        let (Some(start), Some(end)) = (
+            time_slot_ext.time_slot.start_time(),
+            time_slot_ext.time_slot.end_time(),
+        ) else {
+            return None;
+        };
+
+        let region_id = self.field_to_region.get(&time_slot_ext.time_slot.field_id);
+
+        self.blackouts
+            .iter()
+            .find(|(blackout, b_start, b_end)| {
+                let same_region =
+                    blackout.region.is_none() || blackout.region == region_id.copied();
+                let same_type = blackout.reservation_type.is_none()
+                    || blackout.reservation_type == Some(time_slot_ext.reservation_type.id);
+
+                same_region && same_type && start < *b_end && *b_start < end
+            })
+            .map(|(blackout, ..)| blackout)
+    }
+
+    pub(crate) fn is_blacked_out(&self, time_slot_ext: &TimeSlotExtension) -> bool {
+        self.blackout_for(time_slot_ext).is_some()
+    }
+
+    /// Split time slots into those that remain usable and a tally of what each blackout removed.
+    /// Blackouts that did not remove anything are still listed, with zeroed counts.
+    pub(crate) fn partition(
+        &self,
+        time_slots: Vec<TimeSlotExtension>,
+    ) -> (Vec<TimeSlotExtension>, Vec<BlackoutImpact>) {
+        let mut impact: Vec<BlackoutImpact> = self
+            .blackouts
+            .iter()
+            .map(|(blackout, ..)| BlackoutImpact {
+                blackout: blackout.clone(),
+                time_slots_removed: 0,
+                matches_removed: 0,
+            })
+            .collect();
+
+        let mut kept = Vec::with_capacity(time_slots.len());
+
+        for time_slot_ext in time_slots {
+            let Some(blackout) = self.blackout_for(&time_slot_ext) else {
+                kept.push(time_slot_ext);
+                continue;
+            };
+
+            let entry = impact
+                .iter_mut()
+                .find(|entry| entry.blackout.id == blackout.id)
+                .expect("impact was built from the same blackouts");
+
+            entry.time_slots_removed += 1;
+            entry.matches_removed += u64::try_from(time_slot_ext.matches_played()).unwrap_or(0);
+        }
+
+        (kept, impact)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlackoutFilter;
+    use crate::errors::BlackoutError;
+    use crate::fixtures::{at, client, field, region, time_slot};
+    use crate::{Client, CreateBlackoutInput, NameMax64};
+
+    fn input(
+        name: &str,
+        region_id: Option<i32>,
+        reservation_type_id: Option<i32>,
+        hours: (u32, u32),
+    ) -> CreateBlackoutInput {
+        CreateBlackoutInput {
+            name: NameMax64(name.to_owned()),
+            region_id,
+            reservation_type_id,
+            start: at(hours.0),
+            end: at(hours.1),
+        }
+    }
+
+    async fn names(client: &Client, region: Option<i32>) -> Vec<String> {
+        client
+            .get_blackouts(region)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|blackout| blackout.name)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn hides_time_slots_of_its_region_and_reservation_type() {
+        let client = client().await;
+        let north = region(&client).await;
+        let south = region(&client).await;
+        let north_field = field(&client, north).await;
+        let south_field = field(&client, south).await;
+
+        for field in [north_field, south_field] {
+            for hour in [9, 12] {
+                time_slot(&client, field, hour).await;
+            }
+        }
+
+        for blackout in [
+            input("Tournament", Some(north), None, (9, 10)),
+            input("Holiday", None, None, (12, 13)),
+            // the tournament already hides the north's 9 o'clock slot
+            input("Clinic", None, Some(north_field.1), (9, 10)),
+            // the south has no time slots of this reservation type
+            input("Camp", Some(south), Some(north_field.1), (0, 23)),
+        ] {
+            client.create_blackout(blackout).await.unwrap();
+        }
+
+        let mut time_slots = client.get_time_slots(north_field.0).await.unwrap();
+        time_slots.extend(client.get_time_slots(south_field.0).await.unwrap());
+
+        let filter = BlackoutFilter::load(&client.connection).await.unwrap();
+        let (kept, impact) = filter.partition(time_slots);
+
+        assert_eq!(
+            kept.iter()
+                .map(|time_slot_ext| (
+                    time_slot_ext.time_slot.field_id,
+                    time_slot_ext.time_slot.start
+                ))
+                .collect::<Vec<_>>(),
+            [(south_field.0, at(9).timestamp_millis())]
+        );
+        assert_eq!(
+            impact
+                .iter()
+                .map(|impact| (impact.blackout.name.as_str(), impact.time_slots_removed))
+                .collect::<Vec<_>>(),
+            [
+                ("Tournament", 1),
+                ("Holiday", 2),
+                ("Clinic", 0),
+                ("Camp", 0)
+            ]
+        );
+        assert_eq!(
+            impact[1].to_string(),
+            format!(
+                "blackout \"Holiday\" removes 2 time slots ({} matches)",
+                impact[1].matches_removed
+            )
+        );
+
+        // blackouts of every region apply to each of them, earliest first
+        assert_eq!(
+            names(&client, Some(south)).await,
+            ["Camp", "Clinic", "Holiday"]
+        );
+        assert_eq!(names(&client, None).await.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn must_end_after_it_starts() {
+        let client = client().await;
+
+        assert!(matches!(
+            client
+                .create_blackout(input("Backwards", None, None, (10, 9)))
+                .await,
+            Err(BlackoutError::InvalidRange { .. })
+        ));
+        assert!(names(&client, None).await.is_empty());
+    }
+}
@@ -0,0 +1,444 @@
+//! Printable HTML booklets of a saved schedule: a league overview, a page for every
+//! team, and a grid for every game day with a column for each field. The output is a
+//! single self-contained file that can be opened in any browser and printed.
+//!
+//! The markup lives in `templates/schedule_booklet.html`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use askama::Template;
+use backend::Tz;
+use chrono::{NaiveDate, NaiveTime, Utc};
+use itertools::Itertools;
+
+use crate::entity_local_exports::{ReservationType, Schedule};
+use crate::errors::ExportScheduleError;
+use crate::{ExportedGame, GameStatus, TeamExtension};
+
+/// Used when a game's reservation type is unknown, or its color is not a hex color.
+const FALLBACK_COLOR: &str = "#d4d4d8";
+
+/// A reservation type, with a text color that can be read on top of it.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Swatch {
+    name: String,
+    color: String,
+    text_color: &'static str,
+}
+
+impl Swatch {
+    fn new(reservation_type: Option<&ReservationType>) -> Self {
+        let Some(reservation_type) = reservation_type else {
+            return Self {
+                name: String::new(),
+                color: FALLBACK_COLOR.to_owned(),
+                text_color: "#000000",
+            };
+        };
+
+        // colors end up in a `style` attribute, so only plain `#rrggbb` values are kept
+        let channels = reservation_type
+            .color
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .map(|hex| {
+                [0, 2, 4].map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or_default())
+            });
+
+        let (color, text_color) = match channels {
+            Some([r, g, b]) => {
+                let luma = 0.299 * f64::from(r) + 0.587 * f64::from(g) + 0.114 * f64::from(b);
+                let text_color = if luma > 140.0 { "#000000" } else { "#ffffff" };
+                (reservation_type.color.to_ascii_lowercase(), text_color)
+            }
+            None => (FALLBACK_COLOR.to_owned(), "#000000"),
+        };
+
+        Self {
+            name: reservation_type.name.clone(),
+            color,
+            text_color,
+        }
+    }
+}
+
+struct TeamSummary {
+    anchor: String,
+    name: String,
+    groups: String,
+    games: usize,
+}
+
+struct TeamRow {
+    date: String,
+    start: String,
+    end: String,
+    field: String,
+    swatch: Swatch,
+    /// Empty for practices
+    opponent: String,
+    side: &'static str,
+    cancelled: bool,
+}
+
+struct TeamPage {
+    anchor: String,
+    name: String,
+    groups: String,
+    rows: Vec<TeamRow>,
+}
+
+struct GridCell {
+    start: String,
+    end: String,
+    label: String,
+    swatch: Swatch,
+    cancelled: bool,
+}
+
+struct GridRow {
+    time: String,
+    /// One entry per column of [`DayGrid`]; a field can hold more than one game at once
+    cells: Vec<Vec<GridCell>>,
+}
+
+struct DayGrid {
+    anchor: String,
+    date: String,
+    fields: Vec<String>,
+    rows: Vec<GridRow>,
+}
+
+#[derive(Template)]
+#[template(path = "schedule_booklet.html")]
+struct BookletTemplate {
+    title: String,
+    generated: String,
+    /// Zones that the times are in, from the regions of the fields
+    time_zones: String,
+    first_day: String,
+    last_day: String,
+    game_count: usize,
+    legend: Vec<Swatch>,
+    teams: Vec<TeamSummary>,
+    fields: Vec<String>,
+    team_pages: Vec<TeamPage>,
+    days: Vec<DayGrid>,
+}
+
+fn format_day(date: NaiveDate) -> String {
+    date.format("%A, %B %-d, %Y").to_string()
+}
+
+fn format_time(time: NaiveTime) -> String {
+    time.format("%-I:%M %p").to_string()
+}
+
+fn team_name(team: Option<&TeamExtension>) -> String {
+    team.map(|team| team.team().name.clone())
+        .unwrap_or_default()
+}
+
+/// Cancelled games stay in the booklet, struck through, so that families who printed
+/// an earlier copy can see what changed.
+fn is_cancelled(game: &ExportedGame) -> bool {
+    GameStatus::of(game.game()) == GameStatus::Cancelled
+}
+
+fn groups(team: &TeamExtension) -> String {
+    team.tags()
+        .iter()
+        .map(|tag| tag.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn team_pages(games: &[ExportedGame]) -> Vec<TeamPage> {
+    let mut pages = BTreeMap::new();
+
+    for game in games {
+        let sides = [
+            (game.home(), game.away(), "Home"),
+            (game.away(), game.home(), "Away"),
+        ];
+
+        for (team, opponent, side) in sides {
+            let Some(team) = team else {
+                continue;
+            };
+
+            let page = pages
+                .entry((team.team().name.clone(), team.team().id))
+                .or_insert_with(|| TeamPage {
+                    anchor: format!("team-{}", team.team().id),
+                    name: team.team().name.clone(),
+                    groups: groups(team),
+                    rows: vec![],
+                });
+
+            page.rows.push(TeamRow {
+                date: format_day(game.start().date_naive()),
+                start: format_time(game.start().time()),
+                end: format_time(game.end().time()),
+                field: game.field().name.clone(),
+                swatch: Swatch::new(game.reservation_type()),
+                opponent: team_name(opponent),
+                side: if opponent.is_some() { side } else { "" },
+                cancelled: is_cancelled(game),
+            });
+        }
+    }
+
+    pages.into_values().collect()
+}
+
+fn day_grids(games: &[ExportedGame]) -> Vec<DayGrid> {
+    let mut days = BTreeMap::<NaiveDate, Vec<&ExportedGame>>::new();
+
+    for game in games {
+        days.entry(game.start().date_naive())
+            .or_default()
+            .push(game);
+    }
+
+    days.into_iter()
+        .map(|(date, games)| {
+            let fields = games
+                .iter()
+                .map(|game| (game.field().name.clone(), game.field().id))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>();
+
+            let mut rows = BTreeMap::<NaiveTime, Vec<Vec<GridCell>>>::new();
+
+            for game in games {
+                let column = fields
+                    .iter()
+                    .position(|(_, id)| *id == game.field().id)
+                    .unwrap_or_default();
+
+                let cells = rows
+                    .entry(game.start().time())
+                    .or_insert_with(|| fields.iter().map(|_| vec![]).collect());
+
+                let label = match (game.home(), game.away()) {
+                    (Some(home), Some(away)) => {
+                        format!("{} vs {}", home.team().name, away.team().name)
+                    }
+                    (home, _) => team_name(home),
+                };
+
+                cells[column].push(GridCell {
+                    start: format_time(game.start().time()),
+                    end: format_time(game.end().time()),
+                    label,
+                    swatch: Swatch::new(game.reservation_type()),
+                    cancelled: is_cancelled(game),
+                });
+            }
+
+            DayGrid {
+                anchor: format!("day-{}", date.format("%Y-%m-%d")),
+                date: format_day(date),
+                fields: fields.into_iter().map(|(name, _)| name).collect(),
+                rows: rows
+                    .into_iter()
+                    .map(|(time, cells)| GridRow {
+                        time: format_time(time),
+                        cells,
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Renders `games`, which should all belong to `schedule`, as a booklet.
+pub(crate) fn render(
+    schedule: &Schedule,
+    games: &[ExportedGame],
+) -> Result<String, ExportScheduleError> {
+    let team_pages = team_pages(games);
+    let days = day_grids(games);
+
+    let legend = games
+        .iter()
+        .filter(|game| game.reservation_type().is_some())
+        .map(|game| Swatch::new(game.reservation_type()))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let teams = team_pages
+        .iter()
+        .map(|page| TeamSummary {
+            anchor: page.anchor.clone(),
+            name: page.name.clone(),
+            groups: page.groups.clone(),
+            games: page.rows.iter().filter(|row| !row.cancelled).count(),
+        })
+        .collect();
+
+    let fields = games
+        .iter()
+        .map(|game| game.field().name.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let time_zones = games
+        .iter()
+        .map(|game| game.start().timezone())
+        .unique()
+        .sorted_by_key(|time_zone| time_zone.name())
+        .collect::<Vec<_>>();
+
+    // The stamp is only local when there is a single local time to use
+    let generated = match time_zones.iter().exactly_one() {
+        Ok(time_zone) => Utc::now()
+            .with_timezone(time_zone)
+            .format("%Y-%m-%d %H:%M %Z")
+            .to_string(),
+        Err(_) => Utc::now().format("%Y-%m-%d %H:%M UTC").to_string(),
+    };
+
+    let dates = games.iter().map(|game| game.start().date_naive());
+
+    BookletTemplate {
+        title: schedule.name.clone(),
+        generated,
+        time_zones: time_zones.into_iter().map(Tz::name).join(", "),
+        first_day: dates.clone().min().map(format_day).unwrap_or_default(),
+        last_day: dates.max().map(format_day).unwrap_or_default(),
+        game_count: games.iter().filter(|game| !is_cancelled(game)).count(),
+        legend,
+        teams,
+        fields,
+        team_pages,
+        days,
+    }
+    .render()
+    .map_err(|e| ExportScheduleError::Render(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::EntityTrait;
+
+    use super::{day_grids, render, team_pages, Swatch, FALLBACK_COLOR};
+    use crate::entity_local_exports::{ReservationType, ScheduleEntity};
+    use crate::fixtures::{client, field, game, region_in, schedule, team};
+    use crate::{GameStatus, RecordGameResultInput, ScheduleExportFilter};
+
+    fn reservation_type(color: &str) -> ReservationType {
+        ReservationType {
+            id: 1,
+            name: "U10".to_owned(),
+            description: None,
+            color: color.to_owned(),
+            default_sizing: 2,
+            is_practice: false,
+            rematch_spacing_days: None,
+            rematch_spacing_rounds: None,
+        }
+    }
+
+    #[test]
+    fn swatches_stay_readable() {
+        let swatch = |color: &str| {
+            let swatch = Swatch::new(Some(&reservation_type(color)));
+            (swatch.color, swatch.text_color)
+        };
+
+        assert_eq!(swatch("#1E3A8A"), ("#1e3a8a".to_owned(), "#ffffff"));
+        assert_eq!(swatch("#FDE68A"), ("#fde68a".to_owned(), "#000000"));
+        // anything else would end up in a `style` attribute as is
+        assert_eq!(swatch("red; x: y"), (FALLBACK_COLOR.to_owned(), "#000000"));
+        assert_eq!(Swatch::new(None).color, FALLBACK_COLOR);
+    }
+
+    #[tokio::test]
+    async fn lays_out_team_pages_and_day_grids() {
+        let client = client().await;
+        // 16:00 UTC is past midnight in Tokyo
+        let region = region_in(&client, "Asia/Tokyo").await;
+        let first = field(&client, region).await;
+        let second = field(&client, region).await;
+
+        let a = team(&client, region, "A").await;
+        let b = team(&client, region, "<B>").await;
+        let c = team(&client, region, "C").await;
+
+        let schedule = schedule(&client).await;
+        game(&client, schedule, first.0, (a, Some(b)), 9).await;
+        game(&client, schedule, second.0, (c, None), 9).await;
+        let cancelled = game(&client, schedule, first.0, (b, Some(c)), 16).await;
+
+        client
+            .record_game_result(RecordGameResultInput::new(
+                cancelled,
+                GameStatus::Cancelled,
+                None,
+                None,
+            ))
+            .await
+            .unwrap();
+
+        let games = client
+            .export_schedule_games(schedule, &ScheduleExportFilter::default())
+            .await
+            .unwrap();
+
+        let pages = team_pages(&games);
+        assert_eq!(
+            pages
+                .iter()
+                .map(|page| (
+                    page.name.as_str(),
+                    page.rows
+                        .iter()
+                        .map(|row| (row.opponent.as_str(), row.side, row.cancelled))
+                        .collect::<Vec<_>>()
+                ))
+                .collect::<Vec<_>>(),
+            [
+                ("<B>", vec![("A", "Away", false), ("C", "Home", true)]),
+                ("A", vec![("<B>", "Home", false)]),
+                ("C", vec![("", "", false), ("<B>", "Away", true)]),
+            ]
+        );
+
+        // both fields are called `Field`, so they are told apart by their column
+        let days = day_grids(&games);
+        assert_eq!(
+            days.iter()
+                .map(|day| (day.date.as_str(), day.fields.len(), day.rows.len()))
+                .collect::<Vec<_>>(),
+            [
+                ("Saturday, June 1, 2024", 2, 1),
+                ("Sunday, June 2, 2024", 1, 1)
+            ]
+        );
+        assert_eq!(
+            days[0].rows[0]
+                .cells
+                .iter()
+                .map(|cell| cell.iter().map(|game| game.label.as_str()).collect())
+                .collect::<Vec<Vec<_>>>(),
+            [vec!["A vs <B>"], vec!["C"]]
+        );
+
+        let schedule = ScheduleEntity::find_by_id(schedule)
+            .one(&client.connection)
+            .await
+            .unwrap()
+            .unwrap();
+        let html = render(&schedule, &games).unwrap();
+
+        // cancelled games are shown, but not counted
+        assert!(html.contains(" 2 game(s) "), "{html}");
+        assert!(html.contains("A vs &lt;B&gt;"), "{html}");
+        assert!(!html.contains("<B>"), "{html}");
+    }
+}
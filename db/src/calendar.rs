@@ -0,0 +1,423 @@
+//! RFC 5545 calendars of a saved schedule, one per team or field.
+//!
+//! Every event's `UID` comes from its `schedule_game.id`, so importing a calendar
+//! again updates the events that a calendar app already has instead of adding copies.
+//! `SEQUENCE` is the number of times the game was moved, cancelled or reinstated,
+//! which tells calendar apps that the newer event wins. Cancelled games stay in the
+//! calendar with `STATUS:CANCELLED`, so that apps take them off instead of keeping
+//! the event they already have. A team's byes are all-day events on the date of their
+//! round, with a `UID` from their `schedule_game.id` as well.
+
+use chrono::{DateTime, Days, Utc};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::schedule_export::ExportedBye;
+use crate::{ExportedGame, GameStatus};
+
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const DATE_FORMAT: &str = "%Y%m%d";
+
+/// Whose games a calendar has.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CalendarOwner {
+    Team(i32),
+    Field(i32),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleCalendar {
+    owner: CalendarOwner,
+    name: String,
+    ics: String,
+}
+
+impl ScheduleCalendar {
+    pub fn owner(&self) -> CalendarOwner {
+        self.owner
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn ics(&self) -> &str {
+        &self.ics
+    }
+
+    /// A file name that is safe on every platform, such as `team-3-purple_dragons.ics`.
+    pub fn file_name(&self) -> String {
+        let (kind, id) = match self.owner {
+            CalendarOwner::Team(id) => ("team", id),
+            CalendarOwner::Field(id) => ("field", id),
+        };
+
+        let name = self
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c.to_ascii_lowercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+
+        format!("{kind}-{id}-{name}.ics")
+    }
+}
+
+/// Escapes a TEXT value, see RFC 5545 section 3.3.11.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Writes a content line, folded so that no line is longer than 75 octets.
+fn push_line(ics: &mut String, line: &str) {
+    let mut octets = 0;
+
+    for c in line.chars() {
+        if octets + c.len_utf8() > 75 {
+            ics.push_str("\r\n ");
+            // the leading space of a continuation line counts
+            octets = 1;
+        }
+        ics.push(c);
+        octets += c.len_utf8();
+    }
+
+    ics.push_str("\r\n");
+}
+
+fn summary(game: &ExportedGame) -> String {
+    let home = game.home().map(|team| team.team().name.as_str());
+    let away = game.away().map(|team| team.team().name.as_str());
+    let reservation_type = game
+        .reservation_type()
+        .map(|reservation_type| reservation_type.name.as_str());
+
+    match (home, away, reservation_type) {
+        (Some(home), Some(away), _) => format!("{home} vs {away}"),
+        (Some(team), None, Some(reservation_type)) => format!("{team} - {reservation_type}"),
+        (Some(team), None, None) => format!("{team} - Practice"),
+        (None, _, Some(reservation_type)) => reservation_type.to_owned(),
+        (None, _, None) => "Reserved".to_owned(),
+    }
+}
+
+fn location(game: &ExportedGame) -> String {
+    match game.region() {
+        Some(region) => format!("{}, {}", game.field().name, region.title),
+        None => game.field().name.clone(),
+    }
+}
+
+fn description(game: &ExportedGame) -> String {
+    let mut lines = vec![];
+
+    if let Some(reservation_type) = game.reservation_type() {
+        lines.push(reservation_type.name.clone());
+    }
+    if let Some(home) = game.home() {
+        lines.push(format!("Home: {}", home.team().name));
+    }
+    if let Some(away) = game.away() {
+        lines.push(format!("Away: {}", away.team().name));
+    }
+
+    lines.join("\n")
+}
+
+/// Writes `games` and `byes` as a calendar named `name`. `stamp` is when the calendar
+/// was made.
+fn to_ics(
+    name: &str,
+    games: &[&ExportedGame],
+    byes: &[&ExportedBye],
+    stamp: DateTime<Utc>,
+) -> String {
+    let mut ics = String::new();
+
+    push_line(&mut ics, "BEGIN:VCALENDAR");
+    push_line(&mut ics, "VERSION:2.0");
+    push_line(&mut ics, "PRODID:-//fieldz//fieldz-desktop//EN");
+    push_line(&mut ics, "CALSCALE:GREGORIAN");
+    push_line(&mut ics, "METHOD:PUBLISH");
+    push_line(&mut ics, &format!("X-WR-CALNAME:{}", escape(name)));
+
+    // Times are written in UTC, but calendar apps show the events in this zone
+    // when every game is in the same region
+    if let Ok(time_zone) = games
+        .iter()
+        .map(|game| game.start().timezone())
+        .unique()
+        .exactly_one()
+    {
+        push_line(&mut ics, &format!("X-WR-TIMEZONE:{}", time_zone.name()));
+    }
+
+    let stamp = stamp.format(DATE_TIME_FORMAT);
+
+    for game in games {
+        push_line(&mut ics, "BEGIN:VEVENT");
+        push_line(
+            &mut ics,
+            &format!("UID:schedule-game-{}@fieldz", game.game().id),
+        );
+        push_line(&mut ics, &format!("DTSTAMP:{stamp}"));
+        push_line(&mut ics, &format!("SEQUENCE:{}", game.game().sequence));
+        if GameStatus::of(game.game()) == GameStatus::Cancelled {
+            push_line(&mut ics, "STATUS:CANCELLED");
+        }
+        push_line(
+            &mut ics,
+            &format!("DTSTART:{}", game.start().to_utc().format(DATE_TIME_FORMAT)),
+        );
+        push_line(
+            &mut ics,
+            &format!("DTEND:{}", game.end().to_utc().format(DATE_TIME_FORMAT)),
+        );
+        push_line(&mut ics, &format!("SUMMARY:{}", escape(&summary(game))));
+        push_line(&mut ics, &format!("LOCATION:{}", escape(&location(game))));
+        push_line(
+            &mut ics,
+            &format!("DESCRIPTION:{}", escape(&description(game))),
+        );
+        push_line(&mut ics, "END:VEVENT");
+    }
+
+    for bye in byes {
+        // an all-day event ends on the day after
+        let end = bye.date() + Days::new(1);
+
+        push_line(&mut ics, "BEGIN:VEVENT");
+        push_line(
+            &mut ics,
+            &format!("UID:schedule-game-{}@fieldz", bye.game().id),
+        );
+        push_line(&mut ics, &format!("DTSTAMP:{stamp}"));
+        push_line(&mut ics, &format!("SEQUENCE:{}", bye.game().sequence));
+        push_line(
+            &mut ics,
+            &format!("DTSTART;VALUE=DATE:{}", bye.date().format(DATE_FORMAT)),
+        );
+        push_line(
+            &mut ics,
+            &format!("DTEND;VALUE=DATE:{}", end.format(DATE_FORMAT)),
+        );
+        push_line(
+            &mut ics,
+            &format!(
+                "SUMMARY:{}",
+                escape(&format!("{} - Bye", bye.team().team().name))
+            ),
+        );
+        // a bye does not keep anyone busy
+        push_line(&mut ics, "TRANSP:TRANSPARENT");
+        push_line(&mut ics, "END:VEVENT");
+    }
+
+    push_line(&mut ics, "END:VCALENDAR");
+
+    ics
+}
+
+pub(crate) fn calendar(
+    owner: CalendarOwner,
+    name: String,
+    games: &[ExportedGame],
+    byes: &[ExportedBye],
+    stamp: DateTime<Utc>,
+) -> ScheduleCalendar {
+    let games = games
+        .iter()
+        .filter(|game| match owner {
+            CalendarOwner::Team(id) => game
+                .home()
+                .into_iter()
+                .chain(game.away())
+                .any(|team| team.team().id == id),
+            CalendarOwner::Field(id) => game.field().id == id,
+        })
+        .collect::<Vec<_>>();
+
+    let byes = byes
+        .iter()
+        .filter(|bye| owner == CalendarOwner::Team(bye.team().team().id))
+        .collect::<Vec<_>>();
+
+    let ics = to_ics(&name, &games, &byes, stamp);
+
+    ScheduleCalendar { owner, name, ics }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{ActiveModelTrait, Set};
+
+    use super::{escape, push_line, CalendarOwner};
+    use crate::fixtures::{at, client, field, region, team, time_slot};
+    use crate::{schedule, schedule_game, Client, GameStatus, MoveTimeSlotInput};
+
+    /// Undoes the folding of [`push_line`], and checks that no line is too long.
+    fn unfold(ics: &str) -> String {
+        for line in ics.split("\r\n") {
+            assert!(line.len() <= 75, "{line:?} is {} octets", line.len());
+        }
+
+        ics.replace("\r\n ", "")
+    }
+
+    #[test]
+    fn escapes_text() {
+        assert_eq!(
+            escape("Field 1; North, \\ back\r\nGate"),
+            r"Field 1\; North\, \\ back\nGate"
+        );
+    }
+
+    #[test]
+    fn short_lines_are_not_folded() {
+        let line = "a".repeat(75);
+        let mut ics = String::new();
+        push_line(&mut ics, &line);
+
+        assert_eq!(ics, format!("{line}\r\n"));
+    }
+
+    #[test]
+    fn folds_long_lines() {
+        let line = "a".repeat(200);
+        let mut ics = String::new();
+        push_line(&mut ics, &line);
+
+        // the leading space takes one octet of every continuation line
+        assert_eq!(ics.matches("\r\n ").count(), 2);
+        assert!(ics.starts_with(&format!("{}\r\n {}\r\n ", "a".repeat(75), "a".repeat(74))));
+        assert_eq!(unfold(&ics), format!("{line}\r\n"));
+    }
+
+    #[test]
+    fn never_splits_a_character() {
+        for c in ['é', '€', '🏈'] {
+            // the character starts on the 75th octet and does not fit
+            let line = format!("{}{c}{}", "a".repeat(74), "b".repeat(80));
+            let mut ics = String::new();
+            push_line(&mut ics, &line);
+
+            assert!(ics.starts_with(&format!("{}\r\n {c}", "a".repeat(74))));
+            assert_eq!(unfold(&ics), format!("{line}\r\n"));
+        }
+    }
+
+    /// A schedule with `home` against `away` from 9 to 10, and a bye for `bye` that
+    /// round.
+    async fn schedule(client: &Client, field: i32, home: i32, away: i32, bye: i32) {
+        schedule::ActiveModel {
+            id: Set(1),
+            name: Set("Spring".to_owned()),
+            created: Set(at(0).to_rfc3339()),
+            last_edit: Set(at(0).to_rfc3339()),
+        }
+        .insert(&client.connection)
+        .await
+        .unwrap();
+
+        for (id, teams, field_id) in [
+            (1, (Some(home), Some(away)), Some(field)),
+            (2, (Some(bye), None), None),
+        ] {
+            schedule_game::ActiveModel {
+                id: Set(id),
+                schedule_id: Set(1),
+                start: Set(at(9).timestamp_millis()),
+                end: Set(at(10).timestamp_millis()),
+                team_one: Set(teams.0),
+                team_two: Set(teams.1),
+                field_id: Set(field_id),
+                is_bye: Set(field_id.is_none()),
+                sequence: Set(0),
+                status: Set(GameStatus::Scheduled.as_str().to_owned()),
+                home_score: Set(None),
+                away_score: Set(None),
+            }
+            .insert(&client.connection)
+            .await
+            .unwrap();
+        }
+    }
+
+    async fn ics(client: &Client, owner: CalendarOwner) -> String {
+        let calendars = client.export_schedule_calendars(1, &[owner]).await.unwrap();
+        assert_eq!(calendars.len(), 1);
+
+        unfold(calendars[0].ics())
+    }
+
+    #[tokio::test]
+    async fn moved_games_keep_their_uid() {
+        let client = client().await;
+        let region = region(&client).await;
+        let field = field(&client, region).await;
+        time_slot(&client, field, 9).await;
+        time_slot(&client, field, 10).await;
+        let home = team(&client, region, "Home").await;
+        let away = team(&client, region, "Away").await;
+        let bye = team(&client, region, "Bye").await;
+        schedule(&client, field.0, home, away, bye).await;
+
+        let before = ics(&client, CalendarOwner::Team(home)).await;
+        assert!(before.contains("UID:schedule-game-1@fieldz\r\nDTSTAMP:"));
+        assert!(before.contains("SEQUENCE:0\r\nDTSTART:20240601T090000Z\r\n"));
+        assert!(before.contains("SUMMARY:Home vs Away\r\n"));
+
+        client
+            .move_time_slot(MoveTimeSlotInput::new_game(1, 1, at(10), at(11), false))
+            .await
+            .unwrap();
+
+        let after = ics(&client, CalendarOwner::Team(home)).await;
+        assert!(after.contains("UID:schedule-game-1@fieldz\r\nDTSTAMP:"));
+        assert!(after.contains("SEQUENCE:1\r\nDTSTART:20240601T100000Z\r\n"));
+        assert_eq!(after.matches("BEGIN:VEVENT").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn byes_are_on_team_calendars() {
+        let client = client().await;
+        let region = region(&client).await;
+        let field = field(&client, region).await;
+        time_slot(&client, field, 9).await;
+        let home = team(&client, region, "Home").await;
+        let away = team(&client, region, "Away").await;
+        let bye = team(&client, region, "Bye").await;
+        schedule(&client, field.0, home, away, bye).await;
+
+        let team = ics(&client, CalendarOwner::Team(bye)).await;
+        assert_eq!(team.matches("BEGIN:VEVENT").count(), 1);
+        assert!(team.contains("UID:schedule-game-2@fieldz\r\n"));
+        assert!(team.contains("DTSTART;VALUE=DATE:20240601\r\nDTEND;VALUE=DATE:20240602\r\n"));
+        assert!(team.contains("SUMMARY:Bye - Bye\r\n"));
+
+        let field = ics(&client, CalendarOwner::Field(field.0)).await;
+        assert!(!field.contains("schedule-game-2@fieldz"));
+
+        // every team with a game or a bye gets a calendar
+        let calendars = client.export_schedule_calendars(1, &[]).await.unwrap();
+        assert_eq!(calendars.len(), 4);
+    }
+}
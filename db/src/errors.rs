@@ -1,11 +1,16 @@
 use chrono::{serde::ts_milliseconds, DateTime, Utc};
+use sea_orm::{DbErr, TransactionError};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::EditReport;
+
 #[derive(Error, Debug, Serialize, Deserialize)]
 pub enum RegionValidationError {
     #[error(transparent)]
     Name(#[from] RegionNameValidationError),
+    #[error(transparent)]
+    TimeZone(#[from] TimeZoneValidationError),
 }
 
 #[derive(Error, Debug, Serialize, Deserialize)]
@@ -26,6 +31,12 @@ pub enum RegionNameValidationError {
     NameTooLong { len: usize },
 }
 
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub enum TimeZoneValidationError {
+    #[error("`{0}` is not a known IANA time zone")]
+    Unknown(String),
+}
+
 #[derive(Debug, Error, Serialize, Deserialize)]
 pub enum FieldValidationError {
     #[error("field name cannot be empty")]
@@ -34,7 +45,7 @@ pub enum FieldValidationError {
     NameTooLong { len: usize },
 }
 
-#[derive(Debug, Error, Serialize, Deserialize)]
+#[derive(Clone, Debug, Error, PartialEq, Eq, Serialize, Deserialize)]
 pub enum NameMax64ValidationError {
     #[error("field name cannot be empty")]
     EmptyName,
@@ -83,6 +94,16 @@ pub enum TimeSlotError {
     DatabaseError(String),
     #[error("could not parse date: `{0}`")]
     ParseError(String),
+    #[error("schedule game with id {0} not found")]
+    NotFound(i32),
+    #[error("schedule with id {0} not found")]
+    ScheduleNotFound(i32),
+    #[error("schedule game {0} has a malformed start or end")]
+    MalformedGame(i32),
+    #[error("schedule game {0} is a bye, which cannot be moved")]
+    IsBye(i32),
+    #[error("the edit breaks the rules of the schedule, force it to apply it anyway")]
+    Rejected(EditReport),
 }
 
 #[derive(Error, Debug, Serialize, Deserialize)]
@@ -91,6 +112,8 @@ pub enum EditRegionError {
     NoDatabase,
     #[error(transparent)]
     Name(#[from] RegionNameValidationError),
+    #[error(transparent)]
+    TimeZone(#[from] TimeZoneValidationError),
     #[error("database operation failed: `{0}`")]
     DatabaseError(String),
     #[error("region with id {0} not found")]
@@ -229,6 +252,8 @@ pub enum GetScheduledInputsError {
     NoDatabase,
     #[error("database operation failed: `{0}`")]
     DatabaseError(String),
+    #[error("time slot {0} has a malformed start or end, or more matches than can be scheduled")]
+    UnusableTimeSlot(i32),
 }
 
 #[derive(Error, Debug, Serialize, Deserialize)]
@@ -253,6 +278,18 @@ pub enum EditScheduleError {
     NotFound(i32),
 }
 
+#[derive(Error, Debug, Serialize, Deserialize)]
+pub enum ValidateScheduleError {
+    #[error("database was not initialized")]
+    NoDatabase,
+    #[error("database operation failed: `{0}`")]
+    DatabaseError(String),
+    #[error("schedule with id {0} not found")]
+    NotFound(i32),
+    #[error("schedule game {0} has a malformed start or end")]
+    MalformedGame(i32),
+}
+
 #[derive(Error, Debug, Serialize, Deserialize)]
 pub enum LoadScheduleError {
     #[error("database was not initialized")]
@@ -287,6 +324,10 @@ pub enum CopyTimeSlotsError {
     },
     #[error("the time slots provided had different field ids")]
     FieldMismatch,
+    #[error("time slot {0} has a malformed start or end")]
+    MalformedTimeSlot(i32),
+    #[error("time slot {0} would start or end out of range once copied")]
+    OutOfRange(i32),
 }
 
 #[derive(Error, Debug, Serialize, Deserialize)]
@@ -306,6 +347,8 @@ pub enum DeleteTimeSlotsError {
     },
     #[error("the time slots provided had different field ids")]
     FieldMismatch,
+    #[error("time slot {0} has a malformed start or end")]
+    MalformedTimeSlot(i32),
 }
 
 #[derive(Error, Debug, Serialize, Deserialize)]
@@ -321,3 +364,225 @@ pub enum CoachConflictError {
     #[error("the coach conflict and team are from different regions")]
     RegionMismatch,
 }
+
+#[derive(Error, Debug, Serialize, Deserialize)]
+pub enum BlackoutError {
+    #[error("database was not initialized")]
+    NoDatabase,
+    #[error("bad input")]
+    ValidationError(NameMax64ValidationError),
+    #[error("a blackout must end after it starts (got {start} to {end})")]
+    InvalidRange {
+        #[serde(with = "ts_milliseconds")]
+        start: DateTime<Utc>,
+        #[serde(with = "ts_milliseconds")]
+        end: DateTime<Utc>,
+    },
+    #[error("database operation failed: `{0}`")]
+    DatabaseError(String),
+    #[error("blackout with id {0} not found")]
+    NotFound(i32),
+}
+
+#[derive(Error, Debug, Serialize, Deserialize)]
+pub enum ImportTeamsError {
+    #[error("database was not initialized")]
+    NoDatabase,
+    #[error("database operation failed: `{0}`")]
+    DatabaseError(String),
+    #[error("the file has no `{0}` column")]
+    MissingColumn(String),
+    #[error("could not read the file as CSV: {0}")]
+    Csv(String),
+    #[error("group `{0}` was not found")]
+    GroupNotFound(String),
+    #[error("coach `{0}` was not found")]
+    CoachNotFound(String),
+}
+
+#[derive(Error, Debug, Serialize, Deserialize)]
+pub enum ExportScheduleError {
+    #[error("database was not initialized")]
+    NoDatabase,
+    #[error("database operation failed: `{0}`")]
+    DatabaseError(String),
+    #[error("schedule with id {0} not found")]
+    NotFound(i32),
+    #[error("schedule game {0} has a malformed start or end")]
+    MalformedGame(i32),
+    #[error("team with id {0} not found")]
+    TeamNotFound(i32),
+    #[error("field with id {0} not found")]
+    FieldNotFound(i32),
+    #[error("could not write CSV: {0}")]
+    Csv(String),
+    #[error("could not render the booklet: {0}")]
+    Render(String),
+    #[error("could not write the export: {0}")]
+    IOError(String),
+}
+
+#[derive(Error, Debug, Serialize, Deserialize)]
+pub enum ProfileBundleError {
+    #[error("database was not initialized")]
+    NoDatabase,
+    #[error("database operation failed: `{0}`")]
+    DatabaseError(String),
+    #[error("bundle version {0} is not supported by this build")]
+    UnsupportedVersion(u32),
+    #[error("malformed bundle: {0}")]
+    Malformed(String),
+    #[error("bundle refers to {table} {id}, which is not part of the bundle")]
+    MissingReference { table: String, id: i32 },
+    #[error("bundles can only be imported into an empty profile")]
+    NotEmpty,
+    #[error("IO Operation failed: {0}")]
+    IOError(String),
+}
+
+#[derive(Error, Debug, Serialize, Deserialize)]
+pub enum JournalError {
+    #[error("database was not initialized")]
+    NoDatabase,
+    #[error("database operation failed: `{0}`")]
+    DatabaseError(String),
+}
+
+impl JournalError {
+    fn into_message(self) -> String {
+        match self {
+            Self::DatabaseError(message) => message,
+            e => e.to_string(),
+        }
+    }
+}
+
+/// Journaled edits fail with the error of the edit, so a journal that could not be
+/// opened or closed is reported as one of its database errors.
+macro_rules! from_journal_error {
+    ($($error:ty),* $(,)?) => {
+        $(
+            impl From<JournalError> for $error {
+                fn from(value: JournalError) -> Self {
+                    Self::DatabaseError(value.into_message())
+                }
+            }
+        )*
+    };
+}
+
+from_journal_error!(
+    CreateTeamError,
+    ImportTeamsError,
+    CreateGroupError,
+    TimeSlotError,
+    EditRegionError,
+    EditTeamError,
+    TargetOpError,
+    CreateReservationTypeError,
+    SaveScheduleError,
+    EditScheduleError,
+    GameResultError,
+    RainoutError,
+    ProfileBundleError,
+    CopyTimeSlotsError,
+    DeleteTimeSlotsError,
+    CoachConflictError,
+    BlackoutError,
+    FieldClosureError,
+);
+
+impl From<JournalError> for DbErr {
+    fn from(value: JournalError) -> Self {
+        DbErr::Custom(value.into_message())
+    }
+}
+
+impl From<JournalError> for TransactionError<DbErr> {
+    fn from(value: JournalError) -> Self {
+        TransactionError::Connection(value.into())
+    }
+}
+
+impl From<JournalError> for String {
+    fn from(value: JournalError) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Error, Debug, Serialize, Deserialize)]
+pub enum AuditError {
+    #[error("database was not initialized")]
+    NoDatabase,
+    #[error("database operation failed: `{0}`")]
+    DatabaseError(String),
+    #[error("could not write CSV: {0}")]
+    Csv(String),
+    #[error("could not write the export: {0}")]
+    IOError(String),
+}
+
+#[derive(Error, Debug, Serialize, Deserialize)]
+pub enum ScheduleRevisionError {
+    #[error("database was not initialized")]
+    NoDatabase,
+    #[error("database operation failed: `{0}`")]
+    DatabaseError(String),
+    #[error("schedule with id {0} not found")]
+    ScheduleNotFound(i32),
+    #[error("schedule revision with id {0} not found")]
+    RevisionNotFound(i32),
+    #[error("schedule game {0} has a malformed start or end")]
+    MalformedGame(i32),
+}
+
+#[derive(Error, Debug, Serialize, Deserialize)]
+pub enum RainoutError {
+    #[error("database was not initialized")]
+    NoDatabase,
+    #[error("database operation failed: `{0}`")]
+    DatabaseError(String),
+    #[error("schedule with id {0} not found")]
+    ScheduleNotFound(i32),
+    #[error("schedule game {0} has a malformed start or end")]
+    MalformedGame(i32),
+    #[error("field closure with id {0} not found")]
+    ClosureNotFound(i32),
+}
+
+#[derive(Error, Debug, Serialize, Deserialize)]
+pub enum FieldClosureError {
+    #[error("database was not initialized")]
+    NoDatabase,
+    #[error("a field closure must end after it starts (got {start} to {end})")]
+    InvalidRange {
+        #[serde(with = "ts_milliseconds")]
+        start: DateTime<Utc>,
+        #[serde(with = "ts_milliseconds")]
+        end: DateTime<Utc>,
+    },
+    #[error("database operation failed: `{0}`")]
+    DatabaseError(String),
+    #[error("field with id {0} not found")]
+    FieldNotFound(i32),
+    #[error("field closure with id {0} not found")]
+    NotFound(i32),
+}
+
+#[derive(Error, Debug, Serialize, Deserialize)]
+pub enum GameResultError {
+    #[error("database was not initialized")]
+    NoDatabase,
+    #[error("database operation failed: `{0}`")]
+    DatabaseError(String),
+    #[error("schedule with id {0} not found")]
+    ScheduleNotFound(i32),
+    #[error("schedule game with id {0} not found")]
+    GameNotFound(i32),
+    #[error("schedule game {0} is a bye or is missing a team, so it cannot be won or lost")]
+    NotAMatch(i32),
+    #[error("a played game needs a score for both teams")]
+    ScoresRequired,
+    #[error("only played games have scores")]
+    UnexpectedScores,
+}
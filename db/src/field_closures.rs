@@ -0,0 +1,326 @@
+//! Field closures take a single field out of play for a span of time, like maintenance in
+//! the middle of a season. Unlike blackouts, they hide the field's time slots from every
+//! availability query while they exist, and the time slots come back when the closure is
+//! deleted. Games already scheduled on the field are reported, so that they can be called
+//! off and placed again, see [`rainouts`](crate::rainouts).
+
+use chrono::{serde::ts_milliseconds, DateTime, Utc};
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+use crate::entity_local_exports::{
+    FieldClosure, FieldClosureEntity, ScheduleGame, ScheduleGameEntity,
+};
+use crate::errors::{FieldClosureError, RainoutError};
+use crate::rainouts::{call_off, Rainout};
+use crate::timestamps::Timespan;
+use crate::{
+    field, field_closure, schedule_game, select_time_slot_extension, time_slot, GameStatus,
+    TimeSlotExtension, TimeSlotSelectionTypeAggregate, Validator,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreateFieldClosureInput {
+    field_id: i32,
+    #[serde(with = "ts_milliseconds")]
+    start: DateTime<Utc>,
+    #[serde(with = "ts_milliseconds")]
+    end: DateTime<Utc>,
+    reason: Option<String>,
+}
+
+impl CreateFieldClosureInput {
+    pub fn new(
+        field_id: i32,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        reason: Option<String>,
+    ) -> Self {
+        Self {
+            field_id,
+            start,
+            end,
+            reason,
+        }
+    }
+
+    pub fn field_id(&self) -> i32 {
+        self.field_id
+    }
+
+    pub fn start(&self) -> DateTime<Utc> {
+        self.start
+    }
+
+    pub fn end(&self) -> DateTime<Utc> {
+        self.end
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+}
+
+impl Validator for CreateFieldClosureInput {
+    type Error = FieldClosureError;
+    fn validate(&self) -> Result<(), Self::Error> {
+        if self.start >= self.end {
+            return Err(FieldClosureError::InvalidRange {
+                start: self.start,
+                end: self.end,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// What a closure takes away: the time slots of the field that overlap it, and the
+/// scheduled games on the field during it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldClosureReport {
+    closure: FieldClosure,
+    time_slots: Vec<TimeSlotExtension>,
+    games: Vec<ScheduleGame>,
+}
+
+impl FieldClosureReport {
+    pub fn closure(&self) -> &FieldClosure {
+        &self.closure
+    }
+
+    pub fn time_slots(&self) -> &[TimeSlotExtension] {
+        &self.time_slots
+    }
+
+    pub fn games(&self) -> &[ScheduleGame] {
+        &self.games
+    }
+}
+
+/// `true` if `span` overlaps the closure. Back-to-back spans do not overlap.
+fn overlaps(closure: &FieldClosure, span: &impl Timespan) -> bool {
+    let ((closure_start, closure_end), (start, end)) = (closure.millis(), span.millis());
+
+    start < closure_end && closure_start < end
+}
+
+/// Every field closure in the database, to hide the time slots that they cover.
+pub(crate) struct ClosureFilter {
+    closures: Vec<FieldClosure>,
+}
+
+impl ClosureFilter {
+    pub(crate) async fn load<C>(connection: &C) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let closures = FieldClosureEntity::find()
+            .order_by_asc(field_closure::Column::Id)
+            .all(connection)
+            .await?;
+
+        Ok(Self { closures })
+    }
+
+    pub(crate) fn is_closed(&self, time_slot_ext: &TimeSlotExtension) -> bool {
+        let time_slot = &time_slot_ext.time_slot;
+
+        self.closures
+            .iter()
+            .any(|closure| closure.field_id == time_slot.field_id && overlaps(closure, time_slot))
+    }
+}
+
+/// The time slots and games that `closure` covers. Time slots are read past every closure,
+/// since they are hidden once the closure is saved.
+pub(crate) async fn report<C>(
+    connection: &C,
+    closure: FieldClosure,
+) -> Result<FieldClosureReport, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let (start, end) = closure.millis();
+
+    let time_slots = select_time_slot_extension()
+        .filter(field::Column::Id.eq(closure.field_id))
+        .filter(time_slot::Column::Start.lt(end))
+        .filter(time_slot::Column::End.gt(start))
+        .order_by_asc(time_slot::Column::Start)
+        .into_model::<TimeSlotSelectionTypeAggregate>()
+        .all(connection)
+        .await?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+    let games = ScheduleGameEntity::find()
+        .filter(schedule_game::Column::FieldId.eq(closure.field_id))
+        .filter(schedule_game::Column::IsBye.eq(false))
+        .filter(schedule_game::Column::Status.eq(GameStatus::Scheduled.as_str()))
+        .filter(schedule_game::Column::Start.lt(end))
+        .filter(schedule_game::Column::End.gt(start))
+        .order_by_asc(schedule_game::Column::ScheduleId)
+        .order_by_asc(schedule_game::Column::Start)
+        .all(connection)
+        .await?;
+
+    Ok(FieldClosureReport {
+        closure,
+        time_slots,
+        games,
+    })
+}
+
+/// Cancels the games of `schedule_id` that `closure_id` covers and proposes makeups for
+/// them, like a rainout.
+pub(crate) async fn replace_closed_games<C>(
+    connection: &C,
+    closure_id: i32,
+    schedule_id: i32,
+    not_before: DateTime<Utc>,
+) -> Result<Rainout, RainoutError>
+where
+    C: ConnectionTrait,
+{
+    let closure = FieldClosureEntity::find_by_id(closure_id)
+        .one(connection)
+        .await
+        .map_err(|e| RainoutError::DatabaseError(e.to_string()))?
+        .ok_or(RainoutError::ClosureNotFound(closure_id))?;
+
+    let (Some(closure_start), Some(closure_end)) = (closure.start_time(), closure.end_time())
+    else {
+        return Err(RainoutError::DatabaseError(format!(
+            "field closure {closure_id} has a malformed start or end"
+        )));
+    };
+
+    call_off(
+        connection,
+        schedule_id,
+        |field_id, window, _| {
+            field_id == closure.field_id
+                && window.start() < closure_end
+                && closure_start < window.end()
+        },
+        not_before,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{replace_closed_games, CreateFieldClosureInput};
+    use crate::errors::FieldClosureError;
+    use crate::fixtures::{at, client, field, game, region, schedule, team, time_slot};
+    use crate::Client;
+
+    async fn open_hours(client: &Client, field: i32) -> Vec<i64> {
+        client
+            .get_time_slots(field)
+            .await
+            .unwrap()
+            .iter()
+            .map(|time_slot_ext| {
+                (time_slot_ext.time_slot.start - at(0).timestamp_millis()) / 3_600_000
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn hides_time_slots_and_replaces_games_until_deleted() {
+        let client = client().await;
+        let region = region(&client).await;
+        let field = field(&client, region).await;
+        for hour in [9, 10, 11] {
+            time_slot(&client, field, hour).await;
+        }
+
+        let a = team(&client, region, "A").await;
+        let b = team(&client, region, "B").await;
+        let schedule = schedule(&client).await;
+        let closed = game(&client, schedule, field.0, (a, Some(b)), 9).await;
+
+        let report = client
+            .close_field(CreateFieldClosureInput::new(
+                field.0,
+                at(9),
+                at(10),
+                Some("Maintenance".to_owned()),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(report.time_slots().len(), 1);
+        assert_eq!(
+            report
+                .games()
+                .iter()
+                .map(|game| game.id)
+                .collect::<Vec<_>>(),
+            [closed]
+        );
+        assert_eq!(open_hours(&client, field.0).await, [10, 11]);
+
+        let rainout =
+            replace_closed_games(&client.connection, report.closure().id, schedule, at(0))
+                .await
+                .unwrap();
+
+        assert_eq!(
+            rainout
+                .cancelled()
+                .iter()
+                .map(|game| game.id)
+                .collect::<Vec<_>>(),
+            [closed]
+        );
+        assert!(rainout.unplaced().is_empty());
+        assert_eq!(rainout.proposals().len(), 1);
+        assert!(rainout
+            .proposals()
+            .iter()
+            .all(|proposal| proposal.field_id() == field.0 && proposal.start() >= at(10)));
+
+        // a cancelled game is no longer in the way of the closure
+        let report = client
+            .get_field_closure_report(report.closure().id)
+            .await
+            .unwrap();
+        assert!(report.games().is_empty());
+
+        client
+            .delete_field_closure(report.closure().id)
+            .await
+            .unwrap();
+        assert_eq!(open_hours(&client, field.0).await, [9, 10, 11]);
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_closures() {
+        let client = client().await;
+        let region = region(&client).await;
+        let field = field(&client, region).await;
+
+        let close = |field_id: i32, hours: (u32, u32)| {
+            client.close_field(CreateFieldClosureInput::new(
+                field_id,
+                at(hours.0),
+                at(hours.1),
+                None,
+            ))
+        };
+
+        assert!(matches!(
+            close(field.0, (10, 9)).await,
+            Err(FieldClosureError::InvalidRange { .. })
+        ));
+        assert!(matches!(
+            close(field.0 + 1, (9, 10)).await,
+            Err(FieldClosureError::FieldNotFound(_))
+        ));
+        assert!(client.get_field_closures(None).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+//! An in-memory profile and the rows that most tests need.
+
+use chrono::{DateTime, TimeZone, Utc};
+use sea_orm::{ActiveModelTrait, Set};
+
+use crate::entity_local_exports::{ActiveSchedule, ActiveScheduleGame};
+use crate::{
+    Client, Color, Config, CreateFieldInput, CreateRegionInput, CreateReservationTypeInput,
+    CreateTeamInput, CreateTimeSlotInput, NameMax64,
+};
+
+/// A migrated profile that only lives as long as the client.
+pub(crate) async fn client() -> Client {
+    Client::new(&Config::new("sqlite::memory:")).await.unwrap()
+}
+
+/// `hour` o'clock on the day that tests schedule on.
+pub(crate) fn at(hour: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 6, 1, hour, 0, 0).unwrap()
+}
+
+pub(crate) async fn region(client: &Client) -> i32 {
+    client
+        .create_region(CreateRegionInput::new("Region", None))
+        .await
+        .unwrap()
+        .id
+}
+
+/// A region whose clocks run in `time_zone`.
+pub(crate) async fn region_in(client: &Client, time_zone: &str) -> i32 {
+    client
+        .create_region(CreateRegionInput::new("Region", Some(time_zone.to_owned())))
+        .await
+        .unwrap()
+        .id
+}
+
+pub(crate) async fn team(client: &Client, region: i32, name: &str) -> i32 {
+    client
+        .create_team(CreateTeamInput::new(name, region, vec![], None))
+        .await
+        .unwrap()
+        .team
+        .id
+}
+
+/// A field in `region` and a reservation type to book it with.
+pub(crate) async fn field(client: &Client, region: i32) -> (i32, i32) {
+    let field = client
+        .create_field(CreateFieldInput::new("Field", region))
+        .await
+        .unwrap();
+
+    let reservation_type = client
+        .create_reservation_type(CreateReservationTypeInput {
+            name: NameMax64("U10".to_owned()),
+            description: None,
+            color: Color::new("#000000".to_owned()).unwrap(),
+        })
+        .await
+        .unwrap();
+
+    (field.id, reservation_type.id)
+}
+
+/// An hour long time slot on a `field` from [`field`].
+pub(crate) async fn time_slot(client: &Client, field: (i32, i32), hour: u32) -> i32 {
+    client
+        .create_time_slot(CreateTimeSlotInput::new(
+            field.0,
+            field.1,
+            at(hour),
+            at(hour + 1),
+        ))
+        .await
+        .unwrap()
+        .time_slot()
+        .id
+}
+
+/// A saved schedule without games.
+pub(crate) async fn schedule(client: &Client) -> i32 {
+    ActiveSchedule {
+        name: Set("Schedule".to_owned()),
+        created: Set(at(0).to_rfc3339()),
+        last_edit: Set(at(0).to_rfc3339()),
+        ..Default::default()
+    }
+    .insert(&client.connection)
+    .await
+    .unwrap()
+    .id
+}
+
+/// An hour long game of `schedule` on `field`, between `teams` or a practice of one team.
+pub(crate) async fn game(
+    client: &Client,
+    schedule: i32,
+    field: i32,
+    teams: (i32, Option<i32>),
+    hour: u32,
+) -> i32 {
+    ActiveScheduleGame {
+        schedule_id: Set(schedule),
+        start: Set(at(hour).timestamp_millis()),
+        end: Set(at(hour + 1).timestamp_millis()),
+        team_one: Set(Some(teams.0)),
+        team_two: Set(teams.1),
+        field_id: Set(Some(field)),
+        is_bye: Set(false),
+        ..Default::default()
+    }
+    .insert(&client.connection)
+    .await
+    .unwrap()
+    .id
+}
@@ -0,0 +1,269 @@
+//! Results of saved games, and the standings that they add up to.
+//!
+//! Results are stored on `schedule_game`. The table of every target is computed with
+//! [`backend::standings`] from the games between teams of that target.
+
+use backend::standings::{standings, GameOutcome, StandingsConfig, StandingsRow};
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DbErr, EntityTrait, ModelTrait, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::entity_local_exports::{
+    ActiveScheduleGame, ScheduleEntity, ScheduleGame, ScheduleGameEntity, Team, TeamGroup,
+};
+use crate::errors::GameResultError;
+use crate::{TargetEntity, TargetExtension, TeamCollection, Validator};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameStatus {
+    /// Not played yet
+    #[default]
+    Scheduled,
+    Played,
+    /// The home team (`team_one`) did not show up
+    HomeForfeit,
+    AwayForfeit,
+    /// Will not be played, e.g. because of weather. Does not count towards the standings
+    Cancelled,
+}
+
+impl GameStatus {
+    /// The value stored in `schedule_game.status`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Scheduled => "scheduled",
+            Self::Played => "played",
+            Self::HomeForfeit => "home_forfeit",
+            Self::AwayForfeit => "away_forfeit",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Unknown values are read as [`GameStatus::Scheduled`].
+    pub fn of(game: &ScheduleGame) -> Self {
+        match game.status.as_str() {
+            "played" => Self::Played,
+            "home_forfeit" => Self::HomeForfeit,
+            "away_forfeit" => Self::AwayForfeit,
+            "cancelled" => Self::Cancelled,
+            _ => Self::Scheduled,
+        }
+    }
+}
+
+/// Sets the result of a saved game. Scores are required for [`GameStatus::Played`], and
+/// not allowed otherwise; recording [`GameStatus::Scheduled`] clears a result.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordGameResultInput {
+    game_id: i32,
+    status: GameStatus,
+    home_score: Option<u16>,
+    away_score: Option<u16>,
+}
+
+impl RecordGameResultInput {
+    pub fn new(
+        game_id: i32,
+        status: GameStatus,
+        home_score: Option<u16>,
+        away_score: Option<u16>,
+    ) -> Self {
+        Self {
+            game_id,
+            status,
+            home_score,
+            away_score,
+        }
+    }
+}
+
+impl Validator for RecordGameResultInput {
+    type Error = GameResultError;
+
+    fn validate(&self) -> Result<(), Self::Error> {
+        let has_scores = (self.home_score.is_some(), self.away_score.is_some());
+
+        match (self.status, has_scores) {
+            (GameStatus::Played, (true, true)) => Ok(()),
+            (GameStatus::Played, _) => Err(GameResultError::ScoresRequired),
+            (_, (false, false)) => Ok(()),
+            (_, _) => Err(GameResultError::UnexpectedScores),
+        }
+    }
+}
+
+/// The table of one target.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Standings {
+    target_id: i32,
+    groups: Vec<TeamGroup>,
+    rows: Vec<StandingsLine>,
+}
+
+impl Standings {
+    pub fn target_id(&self) -> i32 {
+        self.target_id
+    }
+
+    pub fn groups(&self) -> &[TeamGroup] {
+        &self.groups
+    }
+
+    pub fn rows(&self) -> &[StandingsLine] {
+        &self.rows
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StandingsLine {
+    team: Team,
+    #[serde(flatten)]
+    row: StandingsRow,
+}
+
+impl StandingsLine {
+    pub fn team(&self) -> &Team {
+        &self.team
+    }
+
+    pub fn row(&self) -> &StandingsRow {
+        &self.row
+    }
+}
+
+fn db_error(e: DbErr) -> GameResultError {
+    GameResultError::DatabaseError(e.to_string())
+}
+
+pub(crate) async fn record<C>(
+    connection: &C,
+    input: RecordGameResultInput,
+) -> Result<ScheduleGame, GameResultError>
+where
+    C: ConnectionTrait,
+{
+    input.validate()?;
+
+    let game = ScheduleGameEntity::find_by_id(input.game_id)
+        .one(connection)
+        .await
+        .map_err(db_error)?
+        .ok_or(GameResultError::GameNotFound(input.game_id))?;
+
+    let decided = matches!(
+        input.status,
+        GameStatus::Played | GameStatus::HomeForfeit | GameStatus::AwayForfeit
+    );
+
+    if decided && (game.is_bye || game.team_one.is_none() || game.team_two.is_none()) {
+        return Err(GameResultError::NotAMatch(game.id));
+    }
+
+    // calendars show cancelled games, so cancelling one counts as a change of the event
+    let was_cancelled = GameStatus::of(&game) == GameStatus::Cancelled;
+    let sequence = if was_cancelled == (input.status == GameStatus::Cancelled) {
+        game.sequence
+    } else {
+        game.sequence + 1
+    };
+
+    ActiveScheduleGame {
+        id: Set(game.id),
+        status: Set(input.status.as_str().to_owned()),
+        sequence: Set(sequence),
+        home_score: Set(input.home_score.map(i32::from)),
+        away_score: Set(input.away_score.map(i32::from)),
+        ..Default::default()
+    }
+    .update(connection)
+    .await
+    .map_err(db_error)
+}
+
+fn outcome(game: &ScheduleGame) -> Option<GameOutcome> {
+    let (home, away) = (game.team_one?, game.team_two?);
+
+    match GameStatus::of(game) {
+        GameStatus::Played => Some(GameOutcome::Played {
+            home,
+            away,
+            home_score: u16::try_from(game.home_score?).ok()?,
+            away_score: u16::try_from(game.away_score?).ok()?,
+        }),
+        GameStatus::HomeForfeit => Some(GameOutcome::Forfeit {
+            forfeited_by: home,
+            awarded_to: away,
+        }),
+        GameStatus::AwayForfeit => Some(GameOutcome::Forfeit {
+            forfeited_by: away,
+            awarded_to: home,
+        }),
+        GameStatus::Scheduled | GameStatus::Cancelled => None,
+    }
+}
+
+/// The table of every target from the results of `schedule_id`.
+pub(crate) async fn compute<C>(
+    connection: &C,
+    schedule_id: i32,
+    config: &StandingsConfig,
+) -> Result<Vec<Standings>, GameResultError>
+where
+    C: ConnectionTrait,
+{
+    let schedule = ScheduleEntity::find_by_id(schedule_id)
+        .one(connection)
+        .await
+        .map_err(db_error)?
+        .ok_or(GameResultError::ScheduleNotFound(schedule_id))?;
+
+    let outcomes = schedule
+        .find_related(ScheduleGameEntity)
+        .all(connection)
+        .await
+        .map_err(db_error)?
+        .iter()
+        .filter(|game| !game.is_bye)
+        .filter_map(outcome)
+        .collect::<Vec<_>>();
+
+    let targets = TargetExtension::many_new(
+        TargetEntity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        connection,
+    )
+    .await
+    .map_err(db_error)?;
+
+    let mut tables = vec![];
+
+    for target in targets {
+        let teams = target.teams(connection).await.map_err(db_error)?;
+        let collection = TeamCollection::new(target.groups.clone(), teams);
+
+        let rows = standings(&collection, &outcomes, config)
+            .into_iter()
+            .filter_map(|row| {
+                let team = collection
+                    .teams
+                    .iter()
+                    .find(|team| team.team.id == row.team())?;
+
+                Some(StandingsLine {
+                    team: team.team.clone(),
+                    row,
+                })
+            })
+            .collect();
+
+        tables.push(Standings {
+            target_id: target.target.id,
+            groups: target.groups,
+            rows,
+        });
+    }
+
+    Ok(tables)
+}
@@ -0,0 +1,398 @@
+//! Undo and redo of data edits.
+//!
+//! Every journaled table has triggers (see the `create_journal` migration) that write
+//! the SQL which reverses a change into `journal_entry`, in the same statement as the
+//! change itself. Changes made by foreign key actions, such as the cascade when a team is
+//! deleted, are journaled the same way. The triggers only record while
+//! `journal_recording` points at a step.
+//!
+//! Each [`Client`](crate::Client) edit starts a step with [`begin`] and closes it with
+//! [`settle`] once it is done, in the same transaction as the edit itself. An edit that
+//! fails rolls back its step too, and no other edit can write while a step records.
+//! Undoing a step runs its entries newest first while the triggers record into the same
+//! step as `redo` entries, and redoing it runs those and records fresh `undo` entries. A
+//! new edit after an undo drops the steps that could have been redone. Only the last
+//! [`JOURNAL_LIMIT`] steps are kept.
+
+use chrono::Utc;
+use entity::{journal_entry, journal_recording, journal_step};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set, Statement,
+};
+
+/// How many steps can be undone.
+pub const JOURNAL_LIMIT: u64 = 100;
+
+const UNDO: &str = "undo";
+const REDO: &str = "redo";
+
+async fn record<C>(connection: &C, step: Option<(i32, &str)>) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    journal_recording::ActiveModel {
+        id: Set(1),
+        step: Set(step.map(|(id, _)| id)),
+        kind: Set(step.map(|(_, kind)| kind.to_owned())),
+    }
+    .update(connection)
+    .await
+    .map(|_| ())
+}
+
+/// Closes the step that is recording, if any. If it recorded something, the steps that
+/// were undone before it can no longer be redone; if it did not, it is removed.
+pub(crate) async fn settle<C>(connection: &C) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    let recording = journal_recording::Entity::find_by_id(1)
+        .one(connection)
+        .await?
+        .ok_or(DbErr::RecordNotFound("journal_recording".to_owned()))?;
+
+    record(connection, None).await?;
+
+    let Some(step) = recording.step else {
+        return Ok(());
+    };
+
+    let entries = journal_entry::Entity::find()
+        .filter(journal_entry::Column::Step.eq(step))
+        .count(connection)
+        .await?;
+
+    if entries == 0 {
+        journal_step::Entity::delete_by_id(step)
+            .exec(connection)
+            .await?;
+    } else {
+        journal_step::Entity::delete_many()
+            .filter(journal_step::Column::Undone.eq(true))
+            .exec(connection)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// [`settle`] for a database that may not have been migrated yet, so that no step is
+/// recording while the migrations run.
+pub(crate) async fn settle_leftover<C>(connection: &C) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    let journal = connection
+        .query_one(Statement::from_string(
+            connection.get_database_backend(),
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'journal_recording'",
+        ))
+        .await?;
+
+    if journal.is_none() {
+        return Ok(());
+    }
+
+    settle(connection).await
+}
+
+/// Starts a step called `label`. Everything written to a journaled table until it is
+/// settled, or until the next step starts, is undone together.
+pub(crate) async fn begin<C>(connection: &C, label: &str) -> Result<(), DbErr>
+where
+    C: ConnectionTrait,
+{
+    settle(connection).await?;
+
+    let step = journal_step::ActiveModel {
+        label: Set(label.to_owned()),
+        created: Set(Utc::now().to_rfc3339()),
+        undone: Set(false),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await?;
+
+    // the oldest step that is kept; SQLite needs a `LIMIT` with every `OFFSET`
+    let oldest = journal_step::Entity::find()
+        .select_only()
+        .column(journal_step::Column::Id)
+        .order_by_desc(journal_step::Column::Id)
+        .offset(JOURNAL_LIMIT - 1)
+        .limit(1)
+        .into_tuple::<i32>()
+        .one(connection)
+        .await?;
+
+    if let Some(oldest) = oldest {
+        journal_step::Entity::delete_many()
+            .filter(journal_step::Column::Id.lt(oldest))
+            .exec(connection)
+            .await?;
+    }
+
+    record(connection, Some((step.id, UNDO))).await
+}
+
+/// Runs the `kind` entries of `step`, newest first, while recording their inverse, and
+/// returns the step with its new `undone` flag.
+async fn replay<C>(
+    connection: &C,
+    step: &journal_step::Model,
+    kind: &str,
+    inverse: &str,
+) -> Result<journal_step::Model, DbErr>
+where
+    C: ConnectionTrait,
+{
+    journal_entry::Entity::delete_many()
+        .filter(journal_entry::Column::Step.eq(step.id))
+        .filter(journal_entry::Column::Kind.eq(inverse))
+        .exec(connection)
+        .await?;
+
+    let entries = journal_entry::Entity::find()
+        .filter(journal_entry::Column::Step.eq(step.id))
+        .filter(journal_entry::Column::Kind.eq(kind))
+        .order_by_desc(journal_entry::Column::Id)
+        .all(connection)
+        .await?;
+
+    // rows come back in the reverse order of how they were removed, which is not
+    // always parent first
+    connection
+        .execute_unprepared("PRAGMA defer_foreign_keys = ON")
+        .await?;
+
+    record(connection, Some((step.id, inverse))).await?;
+
+    for entry in entries {
+        connection.execute_unprepared(&entry.statement).await?;
+    }
+
+    record(connection, None).await?;
+
+    journal_step::ActiveModel {
+        id: Set(step.id),
+        undone: Set(kind == UNDO),
+        ..Default::default()
+    }
+    .update(connection)
+    .await
+}
+
+/// Undoes the newest step that is not undone yet. Run this in a transaction.
+pub(crate) async fn undo<C>(connection: &C) -> Result<Option<journal_step::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    settle(connection).await?;
+
+    let Some(step) = journal_step::Entity::find()
+        .filter(journal_step::Column::Undone.eq(false))
+        .order_by_desc(journal_step::Column::Id)
+        .one(connection)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    replay(connection, &step, UNDO, REDO).await.map(Some)
+}
+
+/// Redoes the step that was undone last. Run this in a transaction.
+pub(crate) async fn redo<C>(connection: &C) -> Result<Option<journal_step::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    settle(connection).await?;
+
+    let Some(step) = journal_step::Entity::find()
+        .filter(journal_step::Column::Undone.eq(true))
+        .order_by_asc(journal_step::Column::Id)
+        .one(connection)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    replay(connection, &step, REDO, UNDO).await.map(Some)
+}
+
+/// Every step that can be undone or redone, newest first. Edits settle their step when
+/// they commit, so this only reads.
+pub(crate) async fn history<C>(connection: &C) -> Result<Vec<journal_step::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    journal_step::Entity::find()
+        .order_by_desc(journal_step::Column::Id)
+        .all(connection)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JOURNAL_LIMIT;
+    use crate::fixtures::{at, client, field, region, time_slot};
+    use crate::{Client, CreateRegionInput, CreateTeamInput, MoveTimeSlotInput};
+
+    /// The id and start of every time slot on the field, with its reservation type.
+    async fn time_slots(client: &Client, field: (i32, i32)) -> Vec<(i32, i64, i32)> {
+        client
+            .get_time_slots(field.0)
+            .await
+            .unwrap()
+            .iter()
+            .map(|time_slot_ext| {
+                (
+                    time_slot_ext.time_slot().id,
+                    time_slot_ext.time_slot().start,
+                    time_slot_ext.reservation_type().id,
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn delete_team() {
+        let client = client().await;
+
+        let region = region(&client).await;
+        let group = client.create_group("U10".to_owned()).await.unwrap();
+        let team = client
+            .create_team(CreateTeamInput::new(
+                "Team",
+                region,
+                vec!["U10".to_owned()],
+                None,
+            ))
+            .await
+            .unwrap();
+
+        let state = || async {
+            let teams = client
+                .get_teams_with_tags(region)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|team_ext| {
+                    let tags = team_ext.tags.iter().map(|tag| tag.id).collect::<Vec<_>>();
+                    (team_ext.team.id, tags)
+                })
+                .collect::<Vec<_>>();
+            let usages = client
+                .get_groups()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|group| group.usages)
+                .collect::<Vec<_>>();
+
+            (teams, usages)
+        };
+
+        let before = state().await;
+        assert_eq!(before.0, vec![(team.team.id, vec![group.id])]);
+        assert_eq!(before.1, vec![1]);
+
+        client.delete_team(team.team.id).await.unwrap();
+        let deleted = state().await;
+        assert!(deleted.0.is_empty());
+        assert_eq!(deleted.1, vec![0]);
+
+        let undone = client.undo().await.unwrap().unwrap();
+        assert_eq!(undone.label, "Delete team");
+        assert!(undone.undone);
+        assert_eq!(state().await, before);
+
+        let redone = client.redo().await.unwrap().unwrap();
+        assert!(!redone.undone);
+        assert_eq!(state().await, deleted);
+    }
+
+    #[tokio::test]
+    async fn move_time_slot() {
+        let client = client().await;
+        let field = field(&client, region(&client).await).await;
+        let id = time_slot(&client, field, 9).await;
+
+        client
+            .move_time_slot(MoveTimeSlotInput::new(id, field.0, at(11), at(12)))
+            .await
+            .unwrap();
+        let moved = vec![(id, at(11).timestamp_millis(), field.1)];
+        assert_eq!(time_slots(&client, field).await, moved);
+
+        client.undo().await.unwrap().unwrap();
+        assert_eq!(
+            time_slots(&client, field).await,
+            vec![(id, at(9).timestamp_millis(), field.1)]
+        );
+
+        client.redo().await.unwrap().unwrap();
+        assert_eq!(time_slots(&client, field).await, moved);
+    }
+
+    #[tokio::test]
+    async fn delete_time_slots() {
+        let client = client().await;
+        let field = field(&client, region(&client).await).await;
+        let first = time_slot(&client, field, 9).await;
+        let second = time_slot(&client, field, 11).await;
+        time_slot(&client, field, 13).await;
+
+        let before = time_slots(&client, field).await;
+        assert_eq!(before.len(), 3);
+
+        client.delete_time_slots(first, second).await.unwrap();
+        let deleted = time_slots(&client, field).await;
+        assert_eq!(deleted, before[2..]);
+
+        // the reservation type joins were removed by the cascade, and come back with it
+        client.undo().await.unwrap().unwrap();
+        assert_eq!(time_slots(&client, field).await, before);
+
+        client.redo().await.unwrap().unwrap();
+        assert_eq!(time_slots(&client, field).await, deleted);
+    }
+
+    #[tokio::test]
+    async fn failed_edit_leaves_no_step() {
+        let client = client().await;
+        let field = field(&client, region(&client).await).await;
+        let id = time_slot(&client, field, 9).await;
+
+        let history = client.journal_history().await.unwrap();
+
+        assert!(client.delete_time_slots(id, id + 1).await.is_err());
+        assert_eq!(client.journal_history().await.unwrap(), history);
+        assert_eq!(time_slots(&client, field).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn history_is_bounded() {
+        let client = client().await;
+        let extra = 5;
+
+        for i in 0..JOURNAL_LIMIT + extra {
+            client
+                .create_region(CreateRegionInput::new(format!("Region {i}"), None))
+                .await
+                .unwrap();
+        }
+
+        let history = client.journal_history().await.unwrap();
+        assert_eq!(history.len() as u64, JOURNAL_LIMIT);
+        assert_eq!(history[0].label, "Create region");
+
+        for _ in 0..JOURNAL_LIMIT {
+            assert!(client.undo().await.unwrap().is_some());
+        }
+        assert!(client.undo().await.unwrap().is_none());
+
+        // the oldest edits can no longer be undone
+        assert_eq!(client.get_regions().await.unwrap().len() as u64, extra);
+    }
+}
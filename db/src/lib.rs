@@ -1,32 +1,78 @@
+mod audit;
+mod blackouts;
+mod booklet;
+mod calendar;
+mod field_closures;
+#[cfg(test)]
+mod fixtures;
+mod game_results;
+mod journal;
 mod pre_schedule_report;
-
+mod profile_bundle;
+mod rainouts;
+mod schedule_edits;
+mod schedule_export;
+mod schedule_revisions;
+mod team_import;
+mod time_zones;
+mod timestamps;
+
+use backend::standings::StandingsConfig;
+use backend::validation::Violation;
 use backend::{
     CoachConflictLike, FieldLike, PlayableTeamCollection, ProtobufAvailabilityWindow,
-    ScheduledInput, TeamLike,
+    RematchSpacing, ScheduledInput, TeamLike, Tz,
 };
 // use communication::{FieldLike, ProtobufAvailabilityWindow, TeamLike};
+pub use audit::AuditQuery;
+pub use blackouts::*;
+pub use calendar::{CalendarOwner, ScheduleCalendar};
+use field_closures::ClosureFilter;
+pub use field_closures::{CreateFieldClosureInput, FieldClosureReport};
+pub use game_results::{GameStatus, RecordGameResultInput, Standings, StandingsLine};
 use itertools::Itertools;
+pub use journal::JOURNAL_LIMIT;
 pub use pre_schedule_report::*;
+pub use profile_bundle::{ProfileBundle, ProfileImportReport, PROFILE_BUNDLE_VERSION};
+pub use rainouts::{MakeupProposal, Rainout, RainoutInput};
+use schedule_edits::RuleContext;
+pub use schedule_edits::{EditIssue, EditIssueKind, EditReport, EditSeverity, ScheduleGameEdit};
+pub use schedule_export::{to_csv, ExportedGame, ScheduleExportFilter, ScheduleExportRow};
+pub use schedule_revisions::{DiffGame, GameChange, GameChangeKind, ScheduleDiff, ScheduleSource};
+pub use team_import::{
+    ImportTeamsInput, TeamImportCoach, TeamImportColumns, TeamImportReport, TeamImportRow,
+    TeamImportRowError, TeamImportRowErrorKind,
+};
+pub use time_zones::*;
+use timestamps::{from_millis, Timespan};
 
 pub mod errors;
 use errors::*;
 
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::future::Future;
+use std::marker::PhantomData;
 use std::ops::Deref;
-use std::str::FromStr;
+use std::pin::Pin;
 
 use anyhow::{anyhow, bail, Context, Result};
-use chrono::{serde::ts_milliseconds, DateTime};
+use chrono::{serde::ts_milliseconds, DateTime, TimeDelta};
 use chrono::{Local, Utc};
 
 #[allow(unused_imports)]
 pub(crate) mod entity_local_exports {
+    pub use blackout::{
+        ActiveModel as ActiveBlackout, Entity as BlackoutEntity, Model as Blackout,
+    };
     pub use coach_conflict::{
         ActiveModel as ActiveCoachConflict, Entity as CoachConflictEntity,
         Model as CoachConflictModel,
     };
     use entity::*;
     pub use field::{ActiveModel as ActiveField, Entity as FieldEntity, Model as Field};
+    pub use field_closure::{
+        ActiveModel as ActiveFieldClosure, Entity as FieldClosureEntity, Model as FieldClosure,
+    };
     pub use region::{ActiveModel as ActiveRegion, Entity as RegionEntity, Model as Region};
     pub use reservation_type::{
         ActiveModel as ActiveReservationType, Entity as ReservationTypeEntity,
@@ -51,15 +97,15 @@ pub(crate) mod entity_local_exports {
 use entity_local_exports::*;
 
 use migration::{Expr, IntoCondition, Migrator, MigratorTrait};
+use sea_orm::QueryOrder;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, FromQueryResult, IntoActiveModel,
     JoinType, Order, PaginatorTrait, QueryFilter, QuerySelect, RelationTrait, Select, Set,
     TransactionError, TransactionTrait, TryIntoModel, UpdateResult, Value,
 };
-use sea_orm::{Database, DatabaseConnection, EntityTrait};
+use sea_orm::{Database, DatabaseConnection, DatabaseTransaction, EntityTrait};
 pub use sea_orm::{DbErr, DeleteResult};
 use sea_orm::{EntityOrSelect, ModelTrait};
-use sea_orm::{IntoSimpleExpr, QueryOrder};
 
 pub use entity::*;
 use serde::{Deserialize, Serialize};
@@ -96,6 +142,9 @@ pub trait Validator {
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CreateRegionInput {
     title: RegionName,
+    /// [`Option#None`] means UTC
+    #[serde(default)]
+    time_zone: Option<TimeZoneName>,
 }
 
 impl Validator for RegionName {
@@ -119,10 +168,22 @@ impl Validator for RegionName {
     }
 }
 
+impl CreateRegionInput {
+    pub fn new(title: impl Into<String>, time_zone: Option<String>) -> Self {
+        Self {
+            title: RegionName(title.into()),
+            time_zone: time_zone.map(TimeZoneName),
+        }
+    }
+}
+
 impl Validator for CreateRegionInput {
     type Error = RegionValidationError;
     fn validate(&self) -> Result<(), Self::Error> {
         self.title.validate()?;
+        if let Some(ref time_zone) = self.time_zone {
+            time_zone.validate()?;
+        }
         Ok(())
     }
 }
@@ -134,6 +195,13 @@ pub struct CreateFieldInput {
 }
 
 impl CreateFieldInput {
+    pub fn new(name: impl Into<String>, region_id: i32) -> Self {
+        Self {
+            name: name.into(),
+            region_id,
+        }
+    }
+
     pub fn validate(&self) -> Result<(), FieldValidationError> {
         let len = self.name.len();
 
@@ -167,6 +235,8 @@ pub struct CreateTeamInput {
     name: NameMax64,
     region_id: i32,
     tags: Vec<String>,
+    #[serde(default)]
+    rating: Option<i32>,
 }
 
 impl Validator for NameMax64 {
@@ -192,6 +262,20 @@ impl Validator for NameMax64 {
 }
 
 impl CreateTeamInput {
+    pub fn new(
+        name: impl Into<String>,
+        region_id: i32,
+        tags: Vec<String>,
+        rating: Option<i32>,
+    ) -> Self {
+        Self {
+            name: NameMax64(name.into()),
+            region_id,
+            tags,
+            rating,
+        }
+    }
+
     pub fn validate(&self) -> Result<(), NameMax64ValidationError> {
         self.name.validate()?;
 
@@ -209,6 +293,10 @@ impl TeamLike for TeamExtension {
     fn unique_id(&self) -> i32 {
         self.team.id
     }
+
+    fn rating(&self) -> Option<i32> {
+        self.team.rating
+    }
 }
 
 impl TeamExtension {
@@ -216,28 +304,72 @@ impl TeamExtension {
         tags.sort_by_key(|group| group.id);
         Self { tags, team }
     }
+
+    pub fn team(&self) -> &Team {
+        &self.team
+    }
+
+    pub fn tags(&self) -> &[TeamGroup] {
+        &self.tags
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FieldExtension {
     field_id: i32,
     time_slots: Vec<TimeSlotExtension>,
+    /// IANA name of the zone of the field's region
+    time_zone: String,
+}
+
+impl FieldExtension {
+    /// Fails with the id of a time slot that the scheduler can't take, see
+    /// [`TimeSlotExtension::to_scheduler_input`].
+    pub(crate) fn new(
+        field_id: i32,
+        time_slots: Vec<TimeSlotExtension>,
+        time_zone: Tz,
+    ) -> Result<Self, i32> {
+        if let Some(unusable) = time_slots
+            .iter()
+            .find(|time_slot| time_slot.to_scheduler_input().is_none())
+        {
+            return Err(unusable.time_slot.id);
+        }
+
+        Ok(Self {
+            field_id,
+            time_slots,
+            time_zone: time_zone.name().to_owned(),
+        })
+    }
+
+    pub(crate) fn without_time_slots(field_id: i32) -> Self {
+        Self {
+            field_id,
+            time_slots: vec![],
+            time_zone: Tz::UTC.name().to_owned(),
+        }
+    }
 }
 
 impl FieldLike for FieldExtension {
     fn time_slots(&self) -> impl AsRef<[(ProtobufAvailabilityWindow, u8)]> {
+        // every time slot was checked by `FieldExtension::new`
         self.time_slots
             .iter()
-            .map(|not_ready| {
-                let (window, concurrency) = not_ready.to_scheduler_input();
-                (window.to_protobuf_window(), concurrency)
-            })
+            .filter_map(TimeSlotExtension::to_scheduler_input)
+            .map(|(window, concurrency)| (window.to_protobuf_window(), concurrency))
             .collect::<Vec<_>>()
     }
 
     fn unique_id(&self) -> i32 {
         self.field_id
     }
+
+    fn time_zone(&self) -> Tz {
+        parse_time_zone(&self.time_zone)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -269,6 +401,22 @@ pub struct CreateTimeSlotInput {
     end: DateTime<Utc>,
 }
 
+impl CreateTimeSlotInput {
+    pub fn new(
+        field_id: i32,
+        reservation_type_id: i32,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            field_id,
+            reservation_type_id,
+            start,
+            end,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TimeSlotExtension {
     time_slot: TimeSlot,
@@ -278,6 +426,14 @@ pub struct TimeSlotExtension {
 }
 
 impl TimeSlotExtension {
+    pub fn time_slot(&self) -> &TimeSlot {
+        &self.time_slot
+    }
+
+    pub fn reservation_type(&self) -> &ReservationType {
+        &self.reservation_type
+    }
+
     pub(crate) fn matches_played(&self) -> i32 {
         if let Some(matches) = self.custom_matches {
             matches
@@ -286,25 +442,13 @@ impl TimeSlotExtension {
         }
     }
 
-    pub(crate) fn to_scheduler_input(&self) -> (backend::AvailabilityWindow, u8) {
-        let start_str = &self.time_slot.start;
-        let end_str = &self.time_slot.end;
-
-        let start = DateTime::parse_from_rfc3339(start_str)
-            .expect("time slot START is malformatted")
-            .to_utc();
-        let end = DateTime::parse_from_rfc3339(end_str)
-            .expect("time slot END is malformatted")
-            .to_utc();
-
-        (
-            backend::AvailabilityWindow::new(start, end)
-                .context("Could not transform time slot from record to scheduler parameter")
-                .unwrap(),
-            self.matches_played()
-                .try_into()
-                .expect("matches played could not fit into an 8-bit int"),
-        )
+    /// `None` for a time slot with a malformed start or end, or with more matches than
+    /// fit in a `u8`.
+    pub(crate) fn to_scheduler_input(&self) -> Option<(backend::AvailabilityWindow, u8)> {
+        Some((
+            self.time_slot.window()?,
+            self.matches_played().try_into().ok()?,
+        ))
     }
 }
 
@@ -315,9 +459,9 @@ pub(crate) struct TimeSlotSelectionTypeAggregate {
     /// [`TimeSlot`]
     field_id: i32,
     /// [`TimeSlot`]
-    start: String,
+    start: i64,
     /// [`TimeSlot`]
-    end: String,
+    end: i64,
     /// [`TimeSlot`]
     custom_matches: Option<i32>,
     /// [`ReservationType`]
@@ -332,6 +476,10 @@ pub(crate) struct TimeSlotSelectionTypeAggregate {
     default_sizing: i32,
     /// [`ReservationType`]
     is_practice: bool,
+    /// [`ReservationType`]
+    rematch_spacing_days: Option<i32>,
+    /// [`ReservationType`]
+    rematch_spacing_rounds: Option<i32>,
 }
 
 /// To selects everything needed to build a [`TimeSlotSelectionTypeAggregate`].
@@ -384,6 +532,8 @@ pub(crate) fn select_time_slot_extension() -> Select<TimeSlotEntity> {
             "custom_matches",
         )
         .column_as(R::IsPractice, "is_practice")
+        .column_as(R::RematchSpacingDays, "rematch_spacing_days")
+        .column_as(R::RematchSpacingRounds, "rematch_spacing_rounds")
         .join(JoinType::LeftJoin, time_slot::Relation::Field.def())
         .join(
             JoinType::LeftJoin,
@@ -418,6 +568,8 @@ impl From<TimeSlotSelectionTypeAggregate> for TimeSlotExtension {
                 name: value.name,
                 default_sizing: value.default_sizing,
                 is_practice: value.is_practice,
+                rematch_spacing_days: value.rematch_spacing_days,
+                rematch_spacing_rounds: value.rematch_spacing_rounds,
             },
             time_slot: TimeSlot {
                 id: value.time_slot_id,
@@ -430,6 +582,21 @@ impl From<TimeSlotSelectionTypeAggregate> for TimeSlotExtension {
     }
 }
 
+/// The spacing that [`Client::set_reservation_type_rematch_spacing`] stored. Values that
+/// don't fit are clamped, since a wider spacing than a season can't be met anyway.
+fn stored_rematch_spacing(reservation_type: &ReservationType) -> Option<RematchSpacing> {
+    let clamp = |value: i32| u16::try_from(value.max(0)).unwrap_or(u16::MAX);
+
+    match (
+        reservation_type.rematch_spacing_days,
+        reservation_type.rematch_spacing_rounds,
+    ) {
+        (Some(days), _) => Some(RematchSpacing::Days(clamp(days))),
+        (None, Some(rounds)) => Some(RematchSpacing::Rounds(clamp(rounds))),
+        (None, None) => None,
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MoveTimeSlotInput {
     field_id: Option<i32>,
@@ -439,6 +606,42 @@ pub struct MoveTimeSlotInput {
     new_start: DateTime<Utc>,
     #[serde(with = "ts_milliseconds")]
     new_end: DateTime<Utc>,
+    /// Moves a game even if it breaks the rules of its schedule, see [`EditReport`]
+    #[serde(default)]
+    force: bool,
+}
+
+impl MoveTimeSlotInput {
+    /// Moves a time slot on `field_id`, rather than a game in a schedule.
+    pub fn new(id: i32, field_id: i32, new_start: DateTime<Utc>, new_end: DateTime<Utc>) -> Self {
+        Self {
+            field_id: Some(field_id),
+            schedule_id: None,
+            id,
+            new_start,
+            new_end,
+            force: false,
+        }
+    }
+
+    /// Moves game `id` of `schedule_id`, on the same field. A `force`d move is applied even
+    /// if it breaks the rules of the schedule.
+    pub fn new_game(
+        id: i32,
+        schedule_id: i32,
+        new_start: DateTime<Utc>,
+        new_end: DateTime<Utc>,
+        force: bool,
+    ) -> Self {
+        Self {
+            field_id: None,
+            schedule_id: Some(schedule_id),
+            id,
+            new_start,
+            new_end,
+            force,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -453,6 +656,18 @@ pub struct ListReservationsBetweenInput {
 pub struct EditRegionInput {
     id: i32,
     name: Option<RegionName>,
+    #[serde(default)]
+    time_zone: Option<TimeZoneName>,
+}
+
+impl EditRegionInput {
+    pub fn new(id: i32, name: Option<String>, time_zone: Option<String>) -> Self {
+        Self {
+            id,
+            name: name.map(RegionName),
+            time_zone: time_zone.map(TimeZoneName),
+        }
+    }
 }
 
 impl Validator for EditRegionInput {
@@ -462,6 +677,10 @@ impl Validator for EditRegionInput {
             name.validate()?;
         }
 
+        if let Some(ref time_zone) = self.time_zone {
+            time_zone.validate()?;
+        }
+
         Ok(())
     }
 }
@@ -471,6 +690,35 @@ pub struct EditTeamInput {
     id: i32,
     name: Option<NameMax64>,
     tags: Option<Vec<String>>,
+    /// `None` leaves the rating untouched, `Some(None)` clears it.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    rating: Option<Option<i32>>,
+}
+
+/// Lets an explicit `null` through as `Some(None)`, so that it can be told apart
+/// from a missing field when combined with `#[serde(default)]`.
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+impl EditTeamInput {
+    pub fn new(
+        id: i32,
+        name: Option<String>,
+        tags: Option<Vec<String>>,
+        rating: Option<Option<i32>>,
+    ) -> Self {
+        Self {
+            id,
+            name: name.map(NameMax64),
+            tags,
+            rating,
+        }
+    }
 }
 
 impl Validator for EditTeamInput {
@@ -510,6 +758,28 @@ pub struct TargetExtension {
 }
 
 impl TargetExtension {
+    /// Teams that are tagged with every group of this target.
+    pub(crate) async fn teams<C>(&self, connection: &C) -> DBResult<Vec<TeamExtension>>
+    where
+        C: ConnectionTrait,
+    {
+        Ok(TeamEntity::find()
+            .find_with_related(TeamGroupEntity)
+            .filter(team_group::Column::Id.is_in(self.groups.iter().map(|g| g.id)))
+            .group_by(team::Column::Id)
+            .having(
+                team_group::Column::Id
+                    .into_expr()
+                    .count_distinct()
+                    .eq(i32::try_from(self.groups.len()).unwrap()),
+            )
+            .all(connection)
+            .await?
+            .into_iter()
+            .map(|(team, tags)| TeamExtension::new(team, tags))
+            .collect_vec())
+    }
+
     pub async fn new<C>(target: Target, connection: &C) -> DBResult<Self>
     where
         C: ConnectionTrait,
@@ -744,6 +1014,33 @@ impl CompiledSchedule {
 
         CompiledScheduleDependents::new(field_ids, team_ids)
     }
+
+    /// Groups the outputs into whole schedules, best first. Alternative `n` is made of the
+    /// `n`th ranked output of every input, or that input's best output if it has fewer
+    /// alternatives.
+    fn variants(&self) -> Vec<Vec<&grpc_server::proto::algo_input::ScheduledOutput>> {
+        let mut by_input: BTreeMap<u32, Vec<&grpc_server::proto::algo_input::ScheduledOutput>> =
+            BTreeMap::new();
+
+        for output in &self.outputs {
+            by_input.entry(output.unique_id).or_default().push(output);
+        }
+
+        for outputs in by_input.values_mut() {
+            outputs.sort_by_key(|output| output.rank);
+        }
+
+        let count = by_input.values().map(Vec::len).max().unwrap_or(1);
+
+        (0..count)
+            .map(|rank| {
+                by_input
+                    .values()
+                    .map(|outputs| *outputs.get(rank).unwrap_or(&outputs[0]))
+                    .collect()
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -762,7 +1059,7 @@ pub struct CoachConflict {
     teams: Vec<Team>,
 }
 
-#[derive(Clone, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub struct TeamModelWrapper(Team);
 
 impl Deref for TeamModelWrapper {
@@ -776,6 +1073,10 @@ impl TeamLike for TeamModelWrapper {
     fn unique_id(&self) -> i32 {
         self.id
     }
+
+    fn rating(&self) -> Option<i32> {
+        self.rating
+    }
 }
 
 impl CoachConflictLike for CoachConflict {
@@ -824,6 +1125,37 @@ pub struct RegionMetadata {
     time_slot_count: u64,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreateBlackoutInput {
+    name: NameMax64,
+    /// [`Option#None`] blacks out every region
+    region_id: Option<i32>,
+    /// [`Option#None`] blacks out every reservation type
+    reservation_type_id: Option<i32>,
+    #[serde(with = "ts_milliseconds")]
+    start: DateTime<Utc>,
+    #[serde(with = "ts_milliseconds")]
+    end: DateTime<Utc>,
+}
+
+impl Validator for CreateBlackoutInput {
+    type Error = BlackoutError;
+    fn validate(&self) -> Result<(), Self::Error> {
+        self.name
+            .validate()
+            .map_err(BlackoutError::ValidationError)?;
+
+        if self.start >= self.end {
+            return Err(BlackoutError::InvalidRange {
+                start: self.start,
+                end: self.end,
+            });
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub enum ConflictTimeSlotSource {
     Field,
@@ -840,8 +1172,15 @@ impl Client {
 
         let result = Client { connection: db };
 
+        // a step that was still recording when the last session ended, say because it
+        // crashed, would otherwise record the migrations
+        journal::settle_leftover(&result.connection).await?;
+
         result.up().await?;
 
+        // the user is set per session, never carried over from the last one
+        audit::set_user(&result.connection, None).await?;
+
         Ok(result)
     }
 
@@ -849,6 +1188,99 @@ impl Client {
         Migrator::up(&self.connection, None).await
     }
 
+    /// Runs `edit` in a transaction as an undo step called `label`, see [`journal`]. The
+    /// step is settled and committed with the edit if it returns [`Ok`], otherwise both
+    /// are rolled back. Like [`TransactionTrait::transaction`], `edit` returns a boxed
+    /// future; its second argument only lets that future borrow from the caller.
+    async fn journaled<'a, T, E, F>(&self, label: &str, edit: F) -> Result<T, E>
+    where
+        E: From<JournalError>,
+        F: for<'c> FnOnce(
+            &'c DatabaseTransaction,
+            PhantomData<&'c &'a ()>,
+        ) -> Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'c>>,
+    {
+        let transaction =
+            self.connection.begin().await.map_err(|e| {
+                JournalError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+            })?;
+
+        journal::begin(&transaction, label)
+            .await
+            .map_err(|e| JournalError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?;
+
+        let output = match edit(&transaction, PhantomData).await {
+            Ok(output) => output,
+            Err(e) => {
+                // the error of the edit is the one worth reporting
+                let _ = transaction.rollback().await;
+                return Err(e);
+            }
+        };
+
+        // writes that are not edits, such as migrations, must never join the step
+        journal::settle(&transaction)
+            .await
+            .map_err(|e| JournalError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?;
+
+        transaction
+            .commit()
+            .await
+            .map_err(|e| JournalError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?;
+
+        Ok(output)
+    }
+
+    /// Reverts the newest edit that was not undone yet, and returns it.
+    pub async fn undo(&self) -> Result<Option<journal_step::Model>, JournalError> {
+        self.connection
+            .transaction(|transaction| Box::pin(async move { journal::undo(transaction).await }))
+            .await
+            .map_err(|e: TransactionError<DbErr>| {
+                JournalError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+            })
+    }
+
+    /// Applies the edit that was undone last again, and returns it.
+    pub async fn redo(&self) -> Result<Option<journal_step::Model>, JournalError> {
+        self.connection
+            .transaction(|transaction| Box::pin(async move { journal::redo(transaction).await }))
+            .await
+            .map_err(|e: TransactionError<DbErr>| {
+                JournalError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+            })
+    }
+
+    /// The edits that can be undone or redone, newest first.
+    pub async fn journal_history(&self) -> Result<Vec<journal_step::Model>, JournalError> {
+        journal::history(&self.connection)
+            .await
+            .map_err(|e| JournalError::DatabaseError(format!("{e} {}:{}", line!(), column!())))
+    }
+
+    /// Attributes the edits that follow to `user`, e.g. the account that signed in, until
+    /// this is called again. [`None`] leaves them unattributed.
+    pub async fn set_audit_user(&self, user: Option<String>) -> Result<(), AuditError> {
+        audit::set_user(&self.connection, user)
+            .await
+            .map_err(|e| AuditError::DatabaseError(format!("{e} {}:{}", line!(), column!())))
+    }
+
+    /// Audit log entries that match `query`, oldest first.
+    pub async fn get_audit_log(
+        &self,
+        query: &AuditQuery,
+    ) -> Result<Vec<audit_log::Model>, AuditError> {
+        audit::query(&self.connection, query)
+            .await
+            .map_err(|e| AuditError::DatabaseError(format!("{e} {}:{}", line!(), column!())))
+    }
+
+    /// [`Client::get_audit_log`] as CSV, one row per entry.
+    pub async fn export_audit_log_csv(&self, query: &AuditQuery) -> Result<String, AuditError> {
+        audit::to_csv(&self.get_audit_log(query).await?)
+    }
+
     pub async fn refresh(&self) -> DBResult<()> {
         Migrator::refresh(&self.connection).await
     }
@@ -862,64 +1294,82 @@ impl Client {
     }
 
     pub async fn create_region(&self, input: CreateRegionInput) -> DBResult<Region> {
-        RegionEntity::insert(ActiveRegion {
-            title: Set(input.title.0),
-            ..Default::default()
+        self.journaled("Create region", |connection, _| {
+            Box::pin(async move {
+                RegionEntity::insert(ActiveRegion {
+                    title: Set(input.title.0),
+                    time_zone: Set(input
+                        .time_zone
+                        .map_or_else(|| backend::Tz::UTC.name().to_owned(), |tz| tz.0)),
+                    ..Default::default()
+                })
+                .exec_with_returning(connection)
+                .await
+            })
         })
-        .exec_with_returning(&self.connection)
         .await
     }
 
     pub async fn delete_regions(&self) -> DBResult<DeleteResult> {
-        RegionEntity::delete_many().exec(&self.connection).await
+        self.journaled("Delete all regions", |connection, _| {
+            Box::pin(async move { RegionEntity::delete_many().exec(connection).await })
+        })
+        .await
     }
 
     pub async fn delete_region(&self, id: i32) -> Result<DeleteResult, TransactionError<DbErr>> {
-        self.connection
-            .transaction(|transaction| {
-                Box::pin(async move {
-                    let stmt = TeamGroupEntity::find()
-                        .join(
-                            JoinType::LeftJoin,
-                            team_group::Relation::TeamGroupJoin.def(),
-                        )
-                        .join(JoinType::LeftJoin, team_group_join::Relation::Team.def())
-                        .join(JoinType::LeftJoin, team::Relation::Region.def())
-                        .filter(Condition::all().add(region::Column::Id.eq(id)))
-                        .order_by_asc(team_group::Column::Id)
-                        .all(transaction)
-                        .await?;
-
-                    let mut iterable = stmt.iter().map(|x| x.id);
-
-                    if let Some(mut last) = iterable.next() {
-                        let mut to_sweep = 1;
-
-                        for id in iterable {
-                            if id != last {
-                                Self::decrement_group_count(transaction, [last], to_sweep).await?;
-
-                                last = id;
-                                to_sweep = 1;
-                            } else {
-                                to_sweep += 1;
+        self.journaled("Delete region", |connection, _| {
+            Box::pin(async move {
+                connection
+                    .transaction(|transaction| {
+                        Box::pin(async move {
+                            let stmt = TeamGroupEntity::find()
+                                .join(
+                                    JoinType::LeftJoin,
+                                    team_group::Relation::TeamGroupJoin.def(),
+                                )
+                                .join(JoinType::LeftJoin, team_group_join::Relation::Team.def())
+                                .join(JoinType::LeftJoin, team::Relation::Region.def())
+                                .filter(Condition::all().add(region::Column::Id.eq(id)))
+                                .order_by_asc(team_group::Column::Id)
+                                .all(transaction)
+                                .await?;
+
+                            let mut iterable = stmt.iter().map(|x| x.id);
+
+                            if let Some(mut last) = iterable.next() {
+                                let mut to_sweep = 1;
+
+                                for id in iterable {
+                                    if id != last {
+                                        Self::decrement_group_count(transaction, [last], to_sweep)
+                                            .await?;
+
+                                        last = id;
+                                        to_sweep = 1;
+                                    } else {
+                                        to_sweep += 1;
+                                    }
+                                }
+
+                                if to_sweep > 1 {
+                                    Self::decrement_group_count(transaction, [last], to_sweep)
+                                        .await?;
+                                }
                             }
-                        }
 
-                        if to_sweep > 1 {
-                            Self::decrement_group_count(transaction, [last], to_sweep).await?;
-                        }
-                    }
-
-                    RegionEntity::delete(ActiveRegion {
-                        id: Set(id),
-                        ..Default::default()
+                            RegionEntity::delete(ActiveRegion {
+                                id: Set(id),
+                                ..Default::default()
+                            })
+                            .exec(transaction)
+                            .await
+                        })
                     })
-                    .exec(transaction)
                     .await
-                })
             })
-            .await
+        })
+        .await
     }
 
     pub async fn get_fields(&self, region_id: i32) -> Result<Vec<Field>> {
@@ -942,21 +1392,31 @@ impl Client {
     }
 
     pub async fn create_field(&self, input: CreateFieldInput) -> DBResult<Field> {
-        FieldEntity::insert(ActiveField {
-            name: Set(input.name),
-            region_owner: Set(input.region_id),
-            ..Default::default()
+        self.journaled("Create field", |connection, _| {
+            Box::pin(async move {
+                FieldEntity::insert(ActiveField {
+                    name: Set(input.name),
+                    region_owner: Set(input.region_id),
+                    ..Default::default()
+                })
+                .exec_with_returning(connection)
+                .await
+            })
         })
-        .exec_with_returning(&self.connection)
         .await
     }
 
     pub async fn delete_field(&self, id: i32) -> DBResult<DeleteResult> {
-        FieldEntity::delete(ActiveField {
-            id: Set(id),
-            ..Default::default()
+        self.journaled("Delete field", |connection, _| {
+            Box::pin(async move {
+                FieldEntity::delete(ActiveField {
+                    id: Set(id),
+                    ..Default::default()
+                })
+                .exec(connection)
+                .await
+            })
         })
-        .exec(&self.connection)
         .await
     }
 
@@ -964,105 +1424,156 @@ impl Client {
         &self,
         input: CreateTeamInput,
     ) -> Result<TeamExtension, CreateTeamError> {
-        self.connection
-            .transaction(|transaction| {
-                Box::pin(async move {
-                    if !input.tags.is_empty() {
-                        let _ = TeamGroupEntity::update_many()
-                            .filter(team_group::Column::Name.is_in(&input.tags))
-                            .col_expr(
-                                team_group::Column::Usages,
-                                Expr::add(Expr::col(team_group::Column::Usages), 1),
-                            )
-                            .exec(transaction)
-                            .await
-                            .map_err(|e| CreateTeamError::DatabaseError(e.to_string()))?;
-                    }
+        self.journaled("Create team", |connection, _| {
+            Box::pin(async move {
+                connection
+                    .transaction(|transaction| {
+                        Box::pin(async move {
+                            if !input.tags.is_empty() {
+                                let _ = TeamGroupEntity::update_many()
+                                    .filter(team_group::Column::Name.is_in(&input.tags))
+                                    .col_expr(
+                                        team_group::Column::Usages,
+                                        Expr::add(Expr::col(team_group::Column::Usages), 1),
+                                    )
+                                    .exec(transaction)
+                                    .await
+                                    .map_err(|e| CreateTeamError::DatabaseError(e.to_string()))?;
+                            }
 
-                    // This is not slow, since the result of the update (if carried out) was cached.
-                    let groups = TeamGroupEntity::find()
-                        .filter(team_group::Column::Name.is_in(&input.tags))
-                        .all(transaction)
-                        .await
-                        .map_err(|e| CreateTeamError::DatabaseError(e.to_string()))?;
+                            // This is not slow, since the result of the update (if carried out) was cached.
+                            let groups = TeamGroupEntity::find()
+                                .filter(team_group::Column::Name.is_in(&input.tags))
+                                .all(transaction)
+                                .await
+                                .map_err(|e| CreateTeamError::DatabaseError(e.to_string()))?;
 
-                    if groups.len() != input.tags.len() {
-                        // Tag does not exist
-                        let tags: HashSet<&String> = input.tags.iter().collect();
-                        let groups: HashSet<&String> = groups.iter().map(|x| &x.name).collect();
+                            if groups.len() != input.tags.len() {
+                                // Tag does not exist
+                                let tags: HashSet<&String> = input.tags.iter().collect();
+                                let groups: HashSet<&String> =
+                                    groups.iter().map(|x| &x.name).collect();
 
-                        let out: Vec<String> =
-                            tags.difference(&groups).map(|x| (*x).clone()).collect();
-                        return Err(CreateTeamError::MissingTags(out));
-                    }
-                    let team = ActiveTeam {
-                        name: Set(input.name.0),
-                        region_owner: Set(input.region_id),
-                        ..Default::default()
-                    }
-                    .save(transaction)
-                    .await
-                    .map_err(|e| {
-                        CreateTeamError::DatabaseError(format!("{}:{} {e}", file!(), line!()))
-                    })?;
+                                let out: Vec<String> =
+                                    tags.difference(&groups).map(|x| (*x).clone()).collect();
+                                return Err(CreateTeamError::MissingTags(out));
+                            }
+                            let team = ActiveTeam {
+                                name: Set(input.name.0),
+                                region_owner: Set(input.region_id),
+                                rating: Set(input.rating),
+                                ..Default::default()
+                            }
+                            .save(transaction)
+                            .await
+                            .map_err(|e| {
+                                CreateTeamError::DatabaseError(format!(
+                                    "{}:{} {e}",
+                                    file!(),
+                                    line!()
+                                ))
+                            })?;
 
-                    let Value::Int(Some(team_id)) =
-                        team.id
-                            .clone()
-                            .into_value()
-                            .ok_or(CreateTeamError::DatabaseError(
-                                "team id was not set".to_owned(),
-                            ))?
-                    else {
-                        return Err(CreateTeamError::DatabaseError(
-                            "team id is not an int or null".to_owned(),
-                        ));
-                    };
-
-                    let (team, tags) = if !groups.is_empty() {
-                        let mut active_models = Vec::with_capacity(groups.len());
-
-                        for group in groups {
-                            active_models.push(team_group_join::ActiveModel {
-                                group: Set(group.id),
-                                team: Set(team_id),
-                            });
+                            let Value::Int(Some(team_id)) = team.id.clone().into_value().ok_or(
+                                CreateTeamError::DatabaseError("team id was not set".to_owned()),
+                            )?
+                            else {
+                                return Err(CreateTeamError::DatabaseError(
+                                    "team id is not an int or null".to_owned(),
+                                ));
+                            };
+
+                            let (team, tags) = if !groups.is_empty() {
+                                let mut active_models = Vec::with_capacity(groups.len());
+
+                                for group in groups {
+                                    active_models.push(team_group_join::ActiveModel {
+                                        group: Set(group.id),
+                                        team: Set(team_id),
+                                    });
+                                }
+
+                                team_group_join::Entity::insert_many(active_models)
+                                    .exec(transaction)
+                                    .await
+                                    .map_err(|e| CreateTeamError::DatabaseError(e.to_string()))?;
+
+                                let mut result = TeamEntity::find_by_id(team_id)
+                                    .find_with_related(TeamGroupEntity)
+                                    .all(transaction)
+                                    .await
+                                    .map_err(|e| CreateTeamError::DatabaseError(e.to_string()))?;
+
+                                if result.len() != 1 {
+                                    return Err(CreateTeamError::DatabaseError(format!(
+                                        "Did not select one team/tags pair. Got: {result:?}"
+                                    )));
+                                }
+
+                                result.remove(0)
+                            } else {
+                                (
+                                    team.try_into_model().map_err(|e| {
+                                        CreateTeamError::DatabaseError(e.to_string())
+                                    })?,
+                                    vec![],
+                                )
+                            };
+
+                            Ok(TeamExtension { team, tags })
+                        })
+                    })
+                    .await
+                    .map_err(|e| match e {
+                        TransactionError::Connection(db) => {
+                            CreateTeamError::DatabaseError(db.to_string())
                         }
+                        TransactionError::Transaction(t) => t,
+                    })
+            })
+        })
+        .await
+    }
 
-                        team_group_join::Entity::insert_many(active_models)
-                            .exec(transaction)
-                            .await
-                            .map_err(|e| CreateTeamError::DatabaseError(e.to_string()))?;
+    /// Creates a team for every row of a CSV file. Nothing is written for a dry run, or if
+    /// any row is invalid; the report lists what would have been created either way.
+    pub async fn import_teams(
+        &self,
+        input: ImportTeamsInput,
+    ) -> Result<TeamImportReport, ImportTeamsError> {
+        let regions = self.get_regions().await.map_err(|e| {
+            ImportTeamsError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+        })?;
 
-                        let mut result = TeamEntity::find_by_id(team_id)
-                            .find_with_related(TeamGroupEntity)
-                            .all(transaction)
-                            .await
-                            .map_err(|e| CreateTeamError::DatabaseError(e.to_string()))?;
+        let (teams, errors) = team_import::parse(&input, &regions)?;
+        let dry_run = input.dry_run();
 
-                        if result.len() != 1 {
-                            return Err(CreateTeamError::DatabaseError(format!(
-                                "Did not select one team/tags pair. Got: {result:?}"
-                            )));
-                        }
+        // planned in the same transaction as the commit, so that the groups and coaches
+        // it reuses can't be deleted in between
+        self.journaled("Import teams", |connection, _| {
+            Box::pin(async move {
+                connection
+                    .transaction(|transaction| {
+                        Box::pin(async move {
+                            let report = team_import::plan(transaction, teams, errors).await?;
 
-                        result.remove(0)
-                    } else {
-                        (
-                            team.try_into_model()
-                                .map_err(|e| CreateTeamError::DatabaseError(e.to_string()))?,
-                            vec![],
-                        )
-                    };
+                            if dry_run || !report.errors().is_empty() {
+                                return Ok(report);
+                            }
 
-                    Ok(TeamExtension { team, tags })
-                })
-            })
-            .await
-            .map_err(|e| match e {
-                TransactionError::Connection(db) => CreateTeamError::DatabaseError(db.to_string()),
-                TransactionError::Transaction(t) => t,
+                            team_import::commit(transaction, report).await
+                        })
+                    })
+                    .await
+                    .map_err(|e| match e {
+                        TransactionError::Connection(e) => ImportTeamsError::DatabaseError(
+                            format!("{e} {}:{}", line!(), column!()),
+                        ),
+                        TransactionError::Transaction(e) => e,
+                    })
             })
+        })
+        .await
     }
 
     pub async fn get_teams(&self, region_id: i32) -> Result<Vec<Team>> {
@@ -1115,29 +1626,34 @@ impl Client {
     }
 
     pub async fn delete_team(&self, id: i32) -> Result<DeleteResult, TransactionError<DbErr>> {
-        self.connection
-            .transaction(|transaction| {
-                Box::pin(async move {
-                    // SQLite does not universally support `JOIN` statements in updates.
-                    let ids_to_decrement = team_group_join::Entity::find()
-                        .filter(team_group_join::Column::Team.eq(id))
-                        .all(transaction)
-                        .await?
-                        .iter()
-                        .map(|jt| jt.group)
-                        .collect::<Vec<_>>();
-
-                    Self::decrement_group_count(transaction, ids_to_decrement, 1).await?;
-
-                    TeamEntity::delete(ActiveTeam {
-                        id: Set(id),
-                        ..Default::default()
+        self.journaled("Delete team", |connection, _| {
+            Box::pin(async move {
+                connection
+                    .transaction(|transaction| {
+                        Box::pin(async move {
+                            // SQLite does not universally support `JOIN` statements in updates.
+                            let ids_to_decrement = team_group_join::Entity::find()
+                                .filter(team_group_join::Column::Team.eq(id))
+                                .all(transaction)
+                                .await?
+                                .iter()
+                                .map(|jt| jt.group)
+                                .collect::<Vec<_>>();
+
+                            Self::decrement_group_count(transaction, ids_to_decrement, 1).await?;
+
+                            TeamEntity::delete(ActiveTeam {
+                                id: Set(id),
+                                ..Default::default()
+                            })
+                            .exec(transaction)
+                            .await
+                        })
                     })
-                    .exec(transaction)
                     .await
-                })
             })
-            .await
+        })
+        .await
     }
 
     pub async fn get_groups(&self) -> DBResult<Vec<TeamGroup>> {
@@ -1145,37 +1661,52 @@ impl Client {
     }
 
     pub async fn create_group(&self, tag: String) -> Result<TeamGroup, CreateGroupError> {
-        let all_groups = self
-            .get_groups()
-            .await
-            .map_err(|e| CreateGroupError::DatabaseError(e.to_string()))?;
+        self.journaled("Create group", |connection, _| {
+            Box::pin(async move {
+                let all_groups = TeamGroupEntity
+                    .select()
+                    .all(connection)
+                    .await
+                    .map_err(|e| CreateGroupError::DatabaseError(e.to_string()))?;
 
-        if all_groups.iter().any(|x| x.name.eq_ignore_ascii_case(&tag)) {
-            return Err(CreateGroupError::DuplicateTag);
-        }
+                if all_groups.iter().any(|x| x.name.eq_ignore_ascii_case(&tag)) {
+                    return Err(CreateGroupError::DuplicateTag);
+                }
 
-        TeamGroupEntity::insert(ActiveTeamGroup {
-            name: Set(tag),
-            ..Default::default()
+                TeamGroupEntity::insert(ActiveTeamGroup {
+                    name: Set(tag),
+                    ..Default::default()
+                })
+                .exec_with_returning(connection)
+                .await
+                .map_err(|e| CreateGroupError::DatabaseError(e.to_string()))
+            })
         })
-        .exec_with_returning(&self.connection)
         .await
-        .map_err(|e| CreateGroupError::DatabaseError(e.to_string()))
     }
 
     pub async fn delete_group(&self, id: i32) -> DBResult<DeleteResult> {
-        TeamGroupEntity::delete_by_id(id)
-            .exec(&self.connection)
-            .await
+        self.journaled("Delete group", |connection, _| {
+            Box::pin(async move { TeamGroupEntity::delete_by_id(id).exec(connection).await })
+        })
+        .await
     }
 
+    /// Time slots during a closure of the field are left out.
     pub async fn get_time_slots(&self, field_id: i32) -> Result<Vec<TimeSlotExtension>, DbErr> {
+        let closures = ClosureFilter::load(&self.connection).await?;
+
         select_time_slot_extension()
             .filter(Condition::all().add(field::Column::Id.eq(field_id)))
             .into_model::<TimeSlotSelectionTypeAggregate>()
             .all(&self.connection)
             .await
-            .map(|v| v.into_iter().map(Into::into).collect())
+            .map(|v| {
+                v.into_iter()
+                    .map(Into::into)
+                    .filter(|time_slot_ext| !closures.is_closed(time_slot_ext))
+                    .collect()
+            })
     }
 
     async fn conflicts(
@@ -1208,9 +1739,10 @@ impl Client {
             ConflictTimeSlotSource::Field => {
                 Condition::all().add(time_slot::Column::FieldId.eq(id))
             }
-            ConflictTimeSlotSource::Schedule => {
-                Condition::all().add(schedule_game::Column::ScheduleId.eq(id))
-            }
+            ConflictTimeSlotSource::Schedule => Condition::all()
+                .add(schedule_game::Column::ScheduleId.eq(id))
+                // a bye spans a whole round and never occupies a field
+                .add(schedule_game::Column::IsBye.eq(false)),
         };
 
         if let Some(ids) = exclude_from_conflicts {
@@ -1228,13 +1760,24 @@ impl Client {
             }
         }
 
+        // only what overlaps; back-to-back spans do not
+        let (start_millis, end_millis) = (start.timestamp_millis(), end.timestamp_millis());
+        condition = match search {
+            ConflictTimeSlotSource::Field => condition
+                .add(time_slot::Column::Start.lt(end_millis))
+                .add(time_slot::Column::End.gt(start_millis)),
+            ConflictTimeSlotSource::Schedule => condition
+                .add(schedule_game::Column::Start.lt(end_millis))
+                .add(schedule_game::Column::End.gt(start_millis)),
+        };
+
         #[derive(FromQueryResult)]
         struct StartEnd {
-            start: String,
-            end: String,
+            start: i64,
+            end: i64,
         }
 
-        let time_slots = match search {
+        let overlap = match search {
             ConflictTimeSlotSource::Field => TimeSlotEntity::find()
                 .select_only()
                 .column(time_slot::Column::Start)
@@ -1242,7 +1785,7 @@ impl Client {
                 .inner_join(FieldEntity)
                 .filter(condition)
                 .into_model::<StartEnd>()
-                .all(connection)
+                .one(connection)
                 .await
                 .map_err(|e| TimeSlotError::DatabaseError(e.to_string()))?,
             ConflictTimeSlotSource::Schedule => ScheduleGameEntity::find()
@@ -1252,26 +1795,22 @@ impl Client {
                 .inner_join(ScheduleEntity)
                 .filter(condition)
                 .into_model::<StartEnd>()
-                .all(connection)
+                .one(connection)
                 .await
                 .map_err(|e| TimeSlotError::DatabaseError(e.to_string()))?,
         };
 
-        for time_slot in time_slots {
-            let o_start = DateTime::<Utc>::from_str(&time_slot.start) //, FMT)
-                .map_err(|e| {
-                    TimeSlotError::ParseError(format!("bad input: {e} (`{}`)", time_slot.start))
-                })?
-                .to_utc();
-            let o_end = DateTime::<Utc>::from_str(&time_slot.end) //, FMT)
-                .map_err(|e| {
-                    TimeSlotError::ParseError(format!("bad input: {e} (`{}`)", time_slot.end))
-                })?
-                .to_utc();
+        if let Some(overlap) = overlap {
+            let parse = |millis: i64| {
+                from_millis(millis).ok_or(TimeSlotError::ParseError(format!(
+                    "bad input: `{millis}` is out of range"
+                )))
+            };
 
-            if o_start < end && o_end > start {
-                return Err(TimeSlotError::Overlap { o_start, o_end });
-            }
+            return Err(TimeSlotError::Overlap {
+                o_start: parse(overlap.start)?,
+                o_end: parse(overlap.end)?,
+            });
         }
 
         Ok(())
@@ -1281,216 +1820,286 @@ impl Client {
         &self,
         input: CreateTimeSlotInput,
     ) -> Result<TimeSlotExtension, TimeSlotError> {
-        /*
-         * Potential denial of service if the database gets filled with lots
-         * of time slots.
-         */
-        Self::conflicts(
-            &self.connection,
-            input.field_id,
-            input.start,
-            input.end,
-            None::<&[i32; 0]>,
-        )
-        .await?;
-
-        let Some(reservation_type) = ReservationTypeEntity::find_by_id(input.reservation_type_id)
-            .one(&self.connection)
-            .await
-            .map_err(|e| TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?
-        else {
-            return Err(TimeSlotError::ReservationTypeDoesNotExist(
-                input.reservation_type_id,
-            ));
-        };
-
-        let has_custom_size = entity::reservation_type_field_size_join::Entity::find()
-            .filter(
-                entity::reservation_type_field_size_join::Column::Field
-                    .eq(input.field_id)
-                    .and(
-                        entity::reservation_type_field_size_join::Column::ReservationType
-                            .eq(input.reservation_type_id),
-                    ),
-            )
-            .one(&self.connection)
-            .await
-            .map_err(|e| TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?;
+        self.journaled("Create time slot", |connection, _| {
+            Box::pin(async move {
+                /*
+                 * Potential denial of service if the database gets filled with lots
+                 * of time slots.
+                 */
+                Self::conflicts(
+                    connection,
+                    input.field_id,
+                    input.start,
+                    input.end,
+                    None::<&[i32; 0]>,
+                )
+                .await?;
 
-        /*
-         * No conflicts; good to go.
-         */
+                let Some(reservation_type) =
+                    ReservationTypeEntity::find_by_id(input.reservation_type_id)
+                        .one(connection)
+                        .await
+                        .map_err(|e| {
+                            TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                        })?
+                else {
+                    return Err(TimeSlotError::ReservationTypeDoesNotExist(
+                        input.reservation_type_id,
+                    ));
+                };
+
+                let has_custom_size = entity::reservation_type_field_size_join::Entity::find()
+                    .filter(
+                        entity::reservation_type_field_size_join::Column::Field
+                            .eq(input.field_id)
+                            .and(
+                                entity::reservation_type_field_size_join::Column::ReservationType
+                                    .eq(input.reservation_type_id),
+                            ),
+                    )
+                    .one(connection)
+                    .await
+                    .map_err(|e| {
+                        TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?;
 
-        self.connection
-            .transaction(move |connection| {
-                Box::pin(async move {
-                    let time_slot = TimeSlotEntity::insert(ActiveTimeSlot {
-                        start: Set(input.start.to_utc().to_rfc3339()),
-                        end: Set(input.end.to_utc().to_rfc3339()),
-                        field_id: Set(input.field_id),
-                        ..Default::default()
-                    })
-                    .exec_with_returning(connection)
-                    .await?;
+                /*
+                 * No conflicts; good to go.
+                 */
+
+                connection
+                    .transaction(move |connection| {
+                        Box::pin(async move {
+                            let time_slot = TimeSlotEntity::insert(ActiveTimeSlot {
+                                start: Set(input.start.timestamp_millis()),
+                                end: Set(input.end.timestamp_millis()),
+                                field_id: Set(input.field_id),
+                                ..Default::default()
+                            })
+                            .exec_with_returning(connection)
+                            .await?;
 
-                    let new_join_table_record =
-                        entity::reservation_type_time_slot_join::ActiveModel {
-                            time_slot: Set(time_slot.id),
-                            reservation_type: Set(input.reservation_type_id),
-                        };
+                            let new_join_table_record =
+                                entity::reservation_type_time_slot_join::ActiveModel {
+                                    time_slot: Set(time_slot.id),
+                                    reservation_type: Set(input.reservation_type_id),
+                                };
 
-                    new_join_table_record.insert(connection).await?;
+                            new_join_table_record.insert(connection).await?;
 
-                    Ok(TimeSlotExtension {
-                        time_slot,
-                        reservation_type,
-                        custom_matches: has_custom_size.map(|jt_record| jt_record.size),
+                            Ok(TimeSlotExtension {
+                                time_slot,
+                                reservation_type,
+                                custom_matches: has_custom_size.map(|jt_record| jt_record.size),
+                            })
+                        })
+                    })
+                    .await
+                    .map_err(|e: TransactionError<DbErr>| match e {
+                        TransactionError::Connection(db) => {
+                            TimeSlotError::DatabaseError(format!("{db} {}:{}", line!(), column!()))
+                        }
+                        TransactionError::Transaction(transaction) => TimeSlotError::DatabaseError(
+                            format!("transaction error: {transaction} {}:{}", line!(), column!()),
+                        ),
                     })
-                })
-            })
-            .await
-            .map_err(|e: TransactionError<DbErr>| match e {
-                TransactionError::Connection(db) => {
-                    TimeSlotError::DatabaseError(format!("{db} {}:{}", line!(), column!()))
-                }
-                TransactionError::Transaction(transaction) => TimeSlotError::DatabaseError(
-                    format!("transaction error: {transaction} {}:{}", line!(), column!()),
-                ),
             })
+        })
+        .await
     }
 
+    /// Deletes the game `id` of `schedule_id`, or the time slot `id` when there is no
+    /// schedule. The result counts the game or time slot, so `0` means `id` did not exist.
     pub async fn delete_time_slot(
         &self,
         id: i32,
         schedule_id: Option<i32>,
-    ) -> Result<(), TransactionError<DbErr>> {
-        self.connection
-            .transaction(|connection| {
-                Box::pin(async move {
-                    if let Some(schedule_id) = schedule_id {
-                        ScheduleGameEntity::delete(ActiveScheduleGame {
-                            id: Set(id),
-                            ..Default::default()
-                        })
-                        .exec(connection)
-                        .await?;
+    ) -> Result<DeleteResult, TransactionError<DbErr>> {
+        self.journaled("Delete time slot", |connection, _| {
+            Box::pin(async move {
+                connection
+                    .transaction(|connection| {
+                        Box::pin(async move {
+                            if let Some(schedule_id) = schedule_id {
+                                let deleted = ScheduleGameEntity::delete(ActiveScheduleGame {
+                                    id: Set(id),
+                                    ..Default::default()
+                                })
+                                .exec(connection)
+                                .await?;
 
-                        ScheduleEntity::update(ActiveSchedule {
-                            id: Set(schedule_id),
-                            last_edit: Set(Utc::now().to_rfc3339()),
-                            ..Default::default()
-                        })
-                        .exec(connection)
-                        .await?;
+                                ScheduleEntity::update(ActiveSchedule {
+                                    id: Set(schedule_id),
+                                    last_edit: Set(Utc::now().to_rfc3339()),
+                                    ..Default::default()
+                                })
+                                .exec(connection)
+                                .await?;
 
-                        Ok(())
-                    } else {
-                        TimeSlotEntity::delete(ActiveTimeSlot {
-                            id: Set(id),
-                            ..Default::default()
+                                Ok(deleted)
+                            } else {
+                                let deleted = TimeSlotEntity::delete(ActiveTimeSlot {
+                                    id: Set(id),
+                                    ..Default::default()
+                                })
+                                .exec(connection)
+                                .await?;
+
+                                entity::reservation_type_time_slot_join::Entity::delete_many()
+                                    .filter(
+                                        entity::reservation_type_time_slot_join::Column::TimeSlot
+                                            .eq(id),
+                                    )
+                                    .exec(connection)
+                                    .await?;
+
+                                Ok(deleted)
+                            }
                         })
+                    })
+                    .await
+            })
+        })
+        .await
+    }
+
+    /// Moving a game of a schedule is checked with [`Client::check_schedule_edit`]. The
+    /// report of a time slot is always empty.
+    pub async fn move_time_slot(
+        &self,
+        input: MoveTimeSlotInput,
+    ) -> Result<EditReport, TimeSlotError> {
+        self.journaled("Move time slot", |connection, _| {
+            Box::pin(async move {
+                if let Some(field_id) = input.field_id {
+                    Self::conflicts(
+                        connection,
+                        field_id,
+                        input.new_start,
+                        input.new_end,
+                        Some([input.id]),
+                    )
+                    .await?;
+
+                    TimeSlotEntity::update_many()
+                        .col_expr(
+                            time_slot::Column::Start,
+                            Expr::value(input.new_start.timestamp_millis()),
+                        )
+                        .col_expr(
+                            time_slot::Column::End,
+                            Expr::value(input.new_end.timestamp_millis()),
+                        )
+                        .filter(time_slot::Column::Id.eq(input.id))
                         .exec(connection)
-                        .await?;
+                        .await
+                        .map_err(|e| {
+                            TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                        })?;
 
-                        entity::reservation_type_time_slot_join::Entity::delete_many()
-                            .filter(
-                                entity::reservation_type_time_slot_join::Column::TimeSlot.eq(id),
-                            )
-                            .exec(connection)
-                            .await?;
+                    Ok(EditReport::default())
+                } else if let Some(schedule_id) = input.schedule_id {
+                    let game = ScheduleGameEntity::find_by_id(input.id)
+                        .one(connection)
+                        .await
+                        .map_err(|e| {
+                            TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                        })?
+                        .ok_or(TimeSlotError::NotFound(input.id))?;
 
-                        Ok(())
+                    // a bye spans its whole round, like in `swap_schedule_games`
+                    if game.is_bye {
+                        return Err(TimeSlotError::IsBye(input.id));
                     }
-                })
-            })
-            .await
-            .map(|_| ())
-    }
 
-    pub async fn move_time_slot(&self, input: MoveTimeSlotInput) -> Result<(), TimeSlotError> {
-        if let Some(field_id) = input.field_id {
-            Self::conflicts(
-                &self.connection,
-                field_id,
-                input.new_start,
-                input.new_end,
-                Some([input.id]),
-            )
-            .await?;
+                    let report = schedule_edits::check(
+                        connection,
+                        schedule_id,
+                        &[ScheduleGameEdit::new(
+                            input.id,
+                            input.new_start,
+                            input.new_end,
+                            game.field_id,
+                        )],
+                        None,
+                    )
+                    .await?
+                    .enforce(input.force)?;
 
-            TimeSlotEntity::update_many()
-                .col_expr(
-                    time_slot::Column::Start,
-                    Expr::val(Value::String(Some(Box::new(input.new_start.to_rfc3339()))))
-                        .into_simple_expr(),
-                )
-                .col_expr(
-                    time_slot::Column::End,
-                    Expr::val(Value::String(Some(Box::new(input.new_end.to_rfc3339()))))
-                        .into_simple_expr(),
-                )
-                .filter(time_slot::Column::Id.eq(input.id))
-                .exec(&self.connection)
-                .await
-                .map_err(|e| {
-                    TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
-                })?;
-        } else if let Some(schedule_id) = input.schedule_id {
-            Self::conflicts_generic(
-                &self.connection,
-                schedule_id,
-                ConflictTimeSlotSource::Schedule,
-                input.new_start,
-                input.new_end,
-                Some([input.id]),
-            )
-            .await?;
+                    ScheduleGameEntity::update_many()
+                        .col_expr(
+                            schedule_game::Column::Start,
+                            Expr::value(input.new_start.timestamp_millis()),
+                        )
+                        .col_expr(
+                            schedule_game::Column::End,
+                            Expr::value(input.new_end.timestamp_millis()),
+                        )
+                        .col_expr(
+                            schedule_game::Column::Sequence,
+                            Expr::col(schedule_game::Column::Sequence).add(1),
+                        )
+                        .filter(schedule_game::Column::Id.eq(input.id))
+                        .exec(connection)
+                        .await
+                        .map_err(|e| {
+                            TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                        })?;
 
-            ScheduleGameEntity::update_many()
-                .col_expr(
-                    schedule_game::Column::Start,
-                    Expr::val(Value::String(Some(Box::new(input.new_start.to_rfc3339()))))
-                        .into_simple_expr(),
-                )
-                .col_expr(
-                    schedule_game::Column::End,
-                    Expr::val(Value::String(Some(Box::new(input.new_end.to_rfc3339()))))
-                        .into_simple_expr(),
-                )
-                .filter(schedule_game::Column::Id.eq(input.id))
-                .exec(&self.connection)
-                .await
-                .map_err(|e| {
-                    TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
-                })?;
+                    ScheduleEntity::update(ActiveSchedule {
+                        id: Set(schedule_id),
+                        last_edit: Set(Utc::now().to_rfc3339()),
+                        ..Default::default()
+                    })
+                    .exec(connection)
+                    .await
+                    .map_err(|e| {
+                        TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?;
 
-            ScheduleEntity::update(ActiveSchedule {
-                id: Set(schedule_id),
-                last_edit: Set(Utc::now().to_rfc3339()),
-                ..Default::default()
+                    Ok(report)
+                } else {
+                    Err(TimeSlotError::ParseError(
+                        "missing id argument for move".to_owned(),
+                    ))
+                }
             })
-            .exec(&self.connection)
-            .await
-            .map_err(|e| TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?;
-        } else {
-            return Err(TimeSlotError::ParseError(
-                "missing id argument for move".to_owned(),
-            ));
-        }
+        })
+        .await
+    }
 
-        Ok(())
+    /// Every issue with moving games of a schedule, without moving them. Edits that
+    /// have errors are rejected by the methods that move games, unless they are forced.
+    pub async fn check_schedule_edit(
+        &self,
+        schedule_id: i32,
+        edits: &[ScheduleGameEdit],
+        min_rest: Option<TimeDelta>,
+    ) -> Result<EditReport, TimeSlotError> {
+        schedule_edits::check(&self.connection, schedule_id, edits, min_rest).await
     }
 
+    /// Like [`Client::get_time_slots`], time slots during a field closure are left out.
     pub async fn list_reservations_between(
         &self,
         input: ListReservationsBetweenInput,
     ) -> DBResult<Vec<TimeSlotExtension>> {
+        let closures = ClosureFilter::load(&self.connection).await?;
+
         select_time_slot_extension()
-            .filter(time_slot::Column::Start.between(input.start, input.end))
+            .filter(
+                time_slot::Column::Start
+                    .between(input.start.timestamp_millis(), input.end.timestamp_millis()),
+            )
             .into_model::<TimeSlotSelectionTypeAggregate>()
             .all(&self.connection)
             .await
-            .map(|v| v.into_iter().map(Into::into).collect())
+            .map(|v| {
+                v.into_iter()
+                    .map(Into::into)
+                    .filter(|time_slot_ext| !closures.is_closed(time_slot_ext))
+                    .collect()
+            })
     }
 
     pub async fn load_all_teams(&self) -> DBResult<Vec<TeamExtension>> {
@@ -1504,126 +2113,211 @@ impl Client {
     }
 
     pub async fn edit_region(&self, input: EditRegionInput) -> Result<Region, EditRegionError> {
-        input.validate()?;
+        self.journaled("Edit region", |connection, _| {
+            Box::pin(async move {
+                input.validate()?;
 
-        let region_to_update = RegionEntity::find_by_id(input.id)
-            .one(&self.connection)
-            .await
-            .map_err(|e| EditRegionError::DatabaseError(e.to_string()))?
-            .ok_or(EditRegionError::NotFound(input.id))?;
+                let region_to_update = RegionEntity::find_by_id(input.id)
+                    .one(connection)
+                    .await
+                    .map_err(|e| EditRegionError::DatabaseError(e.to_string()))?
+                    .ok_or(EditRegionError::NotFound(input.id))?;
 
-        let mut active_model: ActiveRegion = region_to_update.into();
+                let mut active_model: ActiveRegion = region_to_update.into();
 
-        if let Some(name) = input.name {
-            active_model.title = Set(name.0);
-        }
+                if let Some(name) = input.name {
+                    active_model.title = Set(name.0);
+                }
 
-        /*
-         * Add more updated fields later!
-         */
+                if let Some(time_zone) = input.time_zone {
+                    active_model.time_zone = Set(time_zone.0);
+                }
 
-        active_model
-            .update(&self.connection)
-            .await
-            .map_err(|e| EditRegionError::DatabaseError(e.to_string()))?;
+                /*
+                 * Add more updated fields later!
+                 */
 
-        // It's okay to look up again because the value is hot and cached.
-        Ok(RegionEntity::find_by_id(input.id)
-            .one(&self.connection)
-            .await
-            .map_err(|e| EditRegionError::DatabaseError(e.to_string()))?
-            .expect("this will never fail to select because we updated an existing record"))
+                active_model
+                    .update(connection)
+                    .await
+                    .map_err(|e| EditRegionError::DatabaseError(e.to_string()))?;
+
+                // It's okay to look up again because the value is hot and cached.
+                Ok(RegionEntity::find_by_id(input.id)
+                    .one(connection)
+                    .await
+                    .map_err(|e| EditRegionError::DatabaseError(e.to_string()))?
+                    .expect("this will never fail to select because we updated an existing record"))
+            })
+        })
+        .await
     }
 
     pub async fn edit_team(&self, input: EditTeamInput) -> Result<TeamExtension, EditTeamError> {
-        input.validate()?;
-
-        self.connection
-            .transaction(|transaction| {
-                Box::pin(async move {
-                    let mut team_to_edit = TeamEntity::find_by_id(input.id)
-                        .one(transaction)
-                        .await
-                        .map_err(|e| {
-                            EditTeamError::DatabaseError(format!("{}:{} {e}", file!(), line!()))
-                        })?
-                        .ok_or(EditTeamError::NotFound(input.id))?;
-
-                    let tags = team_to_edit
-                        .find_related(TeamGroupEntity)
-                        .all(transaction)
-                        .await
-                        .map_err(|e| {
-                            EditTeamError::DatabaseError(format!("{}:{} {e}", file!(), line!()))
-                        })?
-                        .into_iter()
-                        .collect::<Vec<_>>();
-
-                    if let Some(new_tags) = input.tags {
-                        let new_tags = HashSet::from_iter(&new_tags);
-
-                        let hashset = tags.iter().map(|m| &m.name).collect::<HashSet<_>>();
-
-                        let deleted = hashset.difference(&new_tags);
-
-                        // make all the tags hot
-                        let groups = TeamGroupEntity::find()
-                            .filter(team_group::Column::Name.is_in(new_tags.iter().cloned()))
-                            .all(transaction)
-                            .await
-                            .map_err(|e| {
-                                EditTeamError::DatabaseError(format!("{}:{} {e}", file!(), line!()))
-                            })?;
-
-                        // tags that were removed
-                        TeamGroupEntity::update_many()
-                            .filter(team_group::Column::Name.is_in(deleted.cloned()))
-                            .col_expr(
-                                team_group::Column::Usages,
-                                Expr::sub(Expr::col(team_group::Column::Usages), 1),
-                            )
-                            .exec(transaction)
-                            .await
-                            .map_err(|e| {
-                                EditTeamError::DatabaseError(format!("{}:{} {e}", file!(), line!()))
-                            })?;
-
-                        // tags that were added
-                        TeamGroupEntity::update_many()
-                            .filter(
-                                team_group::Column::Name
-                                    .is_in(new_tags.difference(&hashset).cloned()),
-                            )
-                            .col_expr(
-                                team_group::Column::Usages,
-                                Expr::add(Expr::col(team_group::Column::Usages), 1),
-                            )
-                            .exec(transaction)
-                            .await
-                            .map_err(|e| {
-                                EditTeamError::DatabaseError(format!("{}:{} {e}", file!(), line!()))
-                            })?;
+        self.journaled("Edit team", |connection, _| {
+            Box::pin(async move {
+                input.validate()?;
+
+                connection
+                    .transaction(|transaction| {
+                        Box::pin(async move {
+                            let mut team_to_edit = TeamEntity::find_by_id(input.id)
+                                .one(transaction)
+                                .await
+                                .map_err(|e| {
+                                    EditTeamError::DatabaseError(format!(
+                                        "{}:{} {e}",
+                                        file!(),
+                                        line!()
+                                    ))
+                                })?
+                                .ok_or(EditTeamError::NotFound(input.id))?;
 
-                        team_group_join::Entity::delete_many()
-                            .filter(team_group_join::Column::Team.eq(input.id))
-                            .exec(transaction)
-                            .await
-                            .map_err(|e| {
-                                EditTeamError::DatabaseError(format!("{}:{} {e}", file!(), line!()))
-                            })?;
+                            let tags = team_to_edit
+                                .find_related(TeamGroupEntity)
+                                .all(transaction)
+                                .await
+                                .map_err(|e| {
+                                    EditTeamError::DatabaseError(format!(
+                                        "{}:{} {e}",
+                                        file!(),
+                                        line!()
+                                    ))
+                                })?
+                                .into_iter()
+                                .collect::<Vec<_>>();
+
+                            if let Some(new_tags) = input.tags {
+                                let new_tags = HashSet::from_iter(&new_tags);
+
+                                let hashset = tags.iter().map(|m| &m.name).collect::<HashSet<_>>();
+
+                                let deleted = hashset.difference(&new_tags);
+
+                                // make all the tags hot
+                                let groups = TeamGroupEntity::find()
+                                    .filter(
+                                        team_group::Column::Name.is_in(new_tags.iter().cloned()),
+                                    )
+                                    .all(transaction)
+                                    .await
+                                    .map_err(|e| {
+                                        EditTeamError::DatabaseError(format!(
+                                            "{}:{} {e}",
+                                            file!(),
+                                            line!()
+                                        ))
+                                    })?;
+
+                                // tags that were removed
+                                TeamGroupEntity::update_many()
+                                    .filter(team_group::Column::Name.is_in(deleted.cloned()))
+                                    .col_expr(
+                                        team_group::Column::Usages,
+                                        Expr::sub(Expr::col(team_group::Column::Usages), 1),
+                                    )
+                                    .exec(transaction)
+                                    .await
+                                    .map_err(|e| {
+                                        EditTeamError::DatabaseError(format!(
+                                            "{}:{} {e}",
+                                            file!(),
+                                            line!()
+                                        ))
+                                    })?;
+
+                                // tags that were added
+                                TeamGroupEntity::update_many()
+                                    .filter(
+                                        team_group::Column::Name
+                                            .is_in(new_tags.difference(&hashset).cloned()),
+                                    )
+                                    .col_expr(
+                                        team_group::Column::Usages,
+                                        Expr::add(Expr::col(team_group::Column::Usages), 1),
+                                    )
+                                    .exec(transaction)
+                                    .await
+                                    .map_err(|e| {
+                                        EditTeamError::DatabaseError(format!(
+                                            "{}:{} {e}",
+                                            file!(),
+                                            line!()
+                                        ))
+                                    })?;
+
+                                team_group_join::Entity::delete_many()
+                                    .filter(team_group_join::Column::Team.eq(input.id))
+                                    .exec(transaction)
+                                    .await
+                                    .map_err(|e| {
+                                        EditTeamError::DatabaseError(format!(
+                                            "{}:{} {e}",
+                                            file!(),
+                                            line!()
+                                        ))
+                                    })?;
+
+                                let mut active_models = Vec::with_capacity(groups.len());
+
+                                for group in groups {
+                                    active_models.push(team_group_join::ActiveModel {
+                                        group: Set(group.id),
+                                        team: Set(input.id),
+                                    });
+                                }
+
+                                if !active_models.is_empty() {
+                                    team_group_join::Entity::insert_many(active_models)
+                                        .exec(transaction)
+                                        .await
+                                        .map_err(|e| {
+                                            EditTeamError::DatabaseError(format!(
+                                                "{}:{} {e}",
+                                                file!(),
+                                                line!()
+                                            ))
+                                        })?;
+                                }
+                            }
 
-                        let mut active_models = Vec::with_capacity(groups.len());
+                            if let Some(new_name) = input.name {
+                                if team_to_edit.name != new_name.0 {
+                                    let mut active_team: ActiveTeam = team_to_edit.into();
+
+                                    active_team.name = Set(new_name.0);
+
+                                    team_to_edit =
+                                        active_team.update(transaction).await.map_err(|e| {
+                                            EditTeamError::DatabaseError(format!(
+                                                "{}:{} {e}",
+                                                file!(),
+                                                line!()
+                                            ))
+                                        })?;
+                                }
+                            }
 
-                        for group in groups {
-                            active_models.push(team_group_join::ActiveModel {
-                                group: Set(group.id),
-                                team: Set(input.id),
-                            });
-                        }
+                            if let Some(new_rating) = input.rating {
+                                if team_to_edit.rating != new_rating {
+                                    let mut active_team: ActiveTeam = team_to_edit.into();
+
+                                    active_team.rating = Set(new_rating);
+
+                                    team_to_edit =
+                                        active_team.update(transaction).await.map_err(|e| {
+                                            EditTeamError::DatabaseError(format!(
+                                                "{}:{} {e}",
+                                                file!(),
+                                                line!()
+                                            ))
+                                        })?;
+                                }
+                            }
 
-                        if !active_models.is_empty() {
-                            team_group_join::Entity::insert_many(active_models)
-                                .exec(transaction)
+                            let final_tags = team_to_edit
+                                .find_related(TeamGroupEntity)
+                                .all(transaction)
                                 .await
                                 .map_err(|e| {
                                     EditTeamError::DatabaseError(format!(
@@ -1632,58 +2326,44 @@ impl Client {
                                         line!()
                                     ))
                                 })?;
-                        }
-                    }
-
-                    if let Some(new_name) = input.name {
-                        if team_to_edit.name != new_name.0 {
-                            let mut active_team: ActiveTeam = team_to_edit.into();
-
-                            active_team.name = Set(new_name.0);
 
-                            team_to_edit = active_team.update(transaction).await.map_err(|e| {
-                                EditTeamError::DatabaseError(format!("{}:{} {e}", file!(), line!()))
-                            })?;
+                            Ok(TeamExtension {
+                                team: team_to_edit,
+                                tags: final_tags,
+                            })
+                        })
+                    })
+                    .await
+                    .map_err(|e| match e {
+                        TransactionError::Connection(db) => {
+                            EditTeamError::DatabaseError(format!("{}:{} {db}", file!(), line!()))
                         }
-                    }
-
-                    let final_tags = team_to_edit
-                        .find_related(TeamGroupEntity)
-                        .all(transaction)
-                        .await
-                        .map_err(|e| {
-                            EditTeamError::DatabaseError(format!("{}:{} {e}", file!(), line!()))
-                        })?;
-
-                    Ok(TeamExtension {
-                        team: team_to_edit,
-                        tags: final_tags,
+                        TransactionError::Transaction(t) => t,
                     })
-                })
-            })
-            .await
-            .map_err(|e| match e {
-                TransactionError::Connection(db) => {
-                    EditTeamError::DatabaseError(format!("{}:{} {db}", file!(), line!()))
-                }
-                TransactionError::Transaction(t) => t,
             })
+        })
+        .await
     }
 
     pub async fn create_target(&self) -> Result<TargetExtension, TransactionError<DbErr>> {
-        self.connection
-            .transaction(|transaction| {
-                Box::pin(async move {
-                    let target = TargetEntity::insert(ActiveTarget {
-                        ..Default::default()
-                    })
-                    .exec_with_returning(transaction)
-                    .await?;
+        self.journaled("Create target", |connection, _| {
+            Box::pin(async move {
+                connection
+                    .transaction(|transaction| {
+                        Box::pin(async move {
+                            let target = TargetEntity::insert(ActiveTarget {
+                                ..Default::default()
+                            })
+                            .exec_with_returning(transaction)
+                            .await?;
 
-                    TargetExtension::new(target, transaction).await
-                })
+                            TargetExtension::new(target, transaction).await
+                        })
+                    })
+                    .await
             })
-            .await
+        })
+        .await
     }
 
     pub async fn target_group_op(
@@ -1692,72 +2372,101 @@ impl Client {
         group_id: i32,
         op: TargetOp,
     ) -> Result<TargetExtension, TargetOpError> {
-        self.connection
-            .transaction(|transaction| {
-                Box::pin(async move {
-                    let target = TargetEntity::find_by_id(target_id)
-                        .one(transaction)
-                        .await
-                        .map_err(|e| {
-                            TargetOpError::DatabaseError(format!("{}:{} {e}", file!(), line!()))
-                        })?
-                        .ok_or(TargetOpError::TargetNotFound(target_id))?;
+        self.journaled("Edit target groups", |connection, _| {
+            Box::pin(async move {
+                connection
+                    .transaction(|transaction| {
+                        Box::pin(async move {
+                            let target = TargetEntity::find_by_id(target_id)
+                                .one(transaction)
+                                .await
+                                .map_err(|e| {
+                                    TargetOpError::DatabaseError(format!(
+                                        "{}:{} {e}",
+                                        file!(),
+                                        line!()
+                                    ))
+                                })?
+                                .ok_or(TargetOpError::TargetNotFound(target_id))?;
 
-                    let _group = TeamGroupEntity::find_by_id(group_id)
-                        .one(transaction)
-                        .await
-                        .map_err(|e| {
-                            TargetOpError::DatabaseError(format!("{}:{} {e}", file!(), line!()))
-                        })?
-                        .ok_or(TargetOpError::GroupNotFound(group_id))?;
-
-                    /*
-                     * Missing: checks on existing primary key in join table
-                     * for creation, and absence in join table for deletion.
-                     *
-                     * Not a problem because we control inputs but should be
-                     * fixed if this is deployed as a docker container.
-                     */
-
-                    match op {
-                        TargetOp::Insert => {
-                            target_group_join::Entity::insert(target_group_join::ActiveModel {
-                                group: Set(group_id),
-                                target: Set(target_id),
-                            })
-                            .exec(transaction)
-                            .await
-                            .map_err(|e| {
-                                TargetOpError::DatabaseError(format!("{}:{} {e}", file!(), line!()))
-                            })?;
-                        }
-                        TargetOp::Delete => {
-                            target_group_join::Entity::delete(target_group_join::ActiveModel {
-                                group: Set(group_id),
-                                target: Set(target_id),
-                            })
-                            .exec(transaction)
-                            .await
-                            .map_err(|e| {
-                                TargetOpError::DatabaseError(format!("{}:{} {e}", file!(), line!()))
-                            })?;
-                        }
-                    }
+                            let _group = TeamGroupEntity::find_by_id(group_id)
+                                .one(transaction)
+                                .await
+                                .map_err(|e| {
+                                    TargetOpError::DatabaseError(format!(
+                                        "{}:{} {e}",
+                                        file!(),
+                                        line!()
+                                    ))
+                                })?
+                                .ok_or(TargetOpError::GroupNotFound(group_id))?;
+
+                            /*
+                             * Missing: checks on existing primary key in join table
+                             * for creation, and absence in join table for deletion.
+                             *
+                             * Not a problem because we control inputs but should be
+                             * fixed if this is deployed as a docker container.
+                             */
+
+                            match op {
+                                TargetOp::Insert => {
+                                    target_group_join::Entity::insert(
+                                        target_group_join::ActiveModel {
+                                            group: Set(group_id),
+                                            target: Set(target_id),
+                                        },
+                                    )
+                                    .exec(transaction)
+                                    .await
+                                    .map_err(|e| {
+                                        TargetOpError::DatabaseError(format!(
+                                            "{}:{} {e}",
+                                            file!(),
+                                            line!()
+                                        ))
+                                    })?;
+                                }
+                                TargetOp::Delete => {
+                                    target_group_join::Entity::delete(
+                                        target_group_join::ActiveModel {
+                                            group: Set(group_id),
+                                            target: Set(target_id),
+                                        },
+                                    )
+                                    .exec(transaction)
+                                    .await
+                                    .map_err(|e| {
+                                        TargetOpError::DatabaseError(format!(
+                                            "{}:{} {e}",
+                                            file!(),
+                                            line!()
+                                        ))
+                                    })?;
+                                }
+                            }
 
-                    TargetExtension::new(target, transaction)
-                        .await
-                        .map_err(|e| {
-                            TargetOpError::DatabaseError(format!("{}:{} {e}", file!(), line!()))
+                            TargetExtension::new(target, transaction)
+                                .await
+                                .map_err(|e| {
+                                    TargetOpError::DatabaseError(format!(
+                                        "{}:{} {e}",
+                                        file!(),
+                                        line!()
+                                    ))
+                                })
                         })
-                })
-            })
-            .await
-            .map_err(|e| match e {
-                TransactionError::Connection(db) => {
-                    TargetOpError::DatabaseError(format!("{}:{} {db}", file!(), line!()))
-                }
-                TransactionError::Transaction(t) => t,
+                    })
+                    .await
+                    .map_err(|e| match e {
+                        TransactionError::Connection(db) => {
+                            TargetOpError::DatabaseError(format!("{}:{} {db}", file!(), line!()))
+                        }
+                        TransactionError::Transaction(t) => t,
+                    })
             })
+        })
+        .await
     }
 
     pub async fn get_targets(&self) -> DBResult<Vec<TargetExtension>> {
@@ -1766,10 +2475,15 @@ impl Client {
     }
 
     pub async fn delete_target(&self, id: i32) -> DBResult<()> {
-        TargetEntity::delete_by_id(id)
-            .exec(&self.connection)
-            .await
-            .map(|_| ())
+        self.journaled("Delete target", |connection, _| {
+            Box::pin(async move {
+                TargetEntity::delete_by_id(id)
+                    .exec(connection)
+                    .await
+                    .map(|_| ())
+            })
+        })
+        .await
     }
 
     pub async fn generate_pre_schedule_report(
@@ -1783,16 +2497,21 @@ impl Client {
         &self,
         input: CreateReservationTypeInput,
     ) -> Result<ReservationType, CreateReservationTypeError> {
-        input.validate()?;
-        ReservationTypeEntity::insert(ActiveReservationType {
-            name: Set(input.name.0),
-            description: Set(input.description),
-            color: Set(input.color.0),
-            ..Default::default()
+        self.journaled("Create reservation type", |connection, _| {
+            Box::pin(async move {
+                input.validate()?;
+                ReservationTypeEntity::insert(ActiveReservationType {
+                    name: Set(input.name.0),
+                    description: Set(input.description),
+                    color: Set(input.color.0),
+                    ..Default::default()
+                })
+                .exec_with_returning(connection)
+                .await
+                .map_err(|e| CreateReservationTypeError::DatabaseError(e.to_string()))
+            })
         })
-        .exec_with_returning(&self.connection)
         .await
-        .map_err(|e| CreateReservationTypeError::DatabaseError(e.to_string()))
     }
 
     pub async fn get_reservation_types(
@@ -1811,72 +2530,82 @@ impl Client {
     }
 
     pub async fn delete_reservation_type(&self, id: i32) -> Result<(), String> {
-        /*
-         * In SQLite, you cannot join on a DELETE.
-         * We must search by ID using a JOIN, which
-         * will cache the result and thus speed up the
-         * subsequent DELETE.
-         */
-
-        let time_slots_to_delete = TimeSlotEntity::find()
-            .join(
-                JoinType::LeftJoin,
-                time_slot::Relation::ReservationTypeTimeSlotJoin.def(),
-            )
-            .join(
-                JoinType::LeftJoin,
-                reservation_type_time_slot_join::Relation::ReservationType.def(),
-            )
-            .filter(reservation_type::Column::Id.eq(id))
-            .all(&self.connection)
-            .await
-            .map_err(|e| format!("{e} {}:{}", line!(), column!()))?;
-
-        let time_slot_ids = time_slots_to_delete
-            .iter()
-            .map(|t| t.id)
-            .collect::<Vec<_>>();
-
-        self.connection
-            .transaction(|connection| {
-                Box::pin(async move {
-                    TimeSlotEntity::delete_many()
-                        .filter(time_slot::Column::Id.is_in(time_slot_ids))
-                        .exec(connection)
-                        .await?;
-
-                    ReservationTypeEntity::delete_by_id(id)
-                        .exec(connection)
-                        .await
-                        .map(|_| ())
-                })
-            })
-            .await
-            .map_err(|e| match e {
-                TransactionError::Connection(e) => format!("{e} {}:{}", line!(), column!()),
-                TransactionError::Transaction(trans) => {
-                    format!("transaction: {trans} {}:{}", line!(), column!())
-                }
+        self.journaled("Delete reservation type", |connection, _| {
+            Box::pin(async move {
+                /*
+                 * In SQLite, you cannot join on a DELETE.
+                 * We must search by ID using a JOIN, which
+                 * will cache the result and thus speed up the
+                 * subsequent DELETE.
+                 */
+
+                let time_slots_to_delete = TimeSlotEntity::find()
+                    .join(
+                        JoinType::LeftJoin,
+                        time_slot::Relation::ReservationTypeTimeSlotJoin.def(),
+                    )
+                    .join(
+                        JoinType::LeftJoin,
+                        reservation_type_time_slot_join::Relation::ReservationType.def(),
+                    )
+                    .filter(reservation_type::Column::Id.eq(id))
+                    .all(connection)
+                    .await
+                    .map_err(|e| format!("{e} {}:{}", line!(), column!()))?;
+
+                let time_slot_ids = time_slots_to_delete
+                    .iter()
+                    .map(|t| t.id)
+                    .collect::<Vec<_>>();
+
+                connection
+                    .transaction(|connection| {
+                        Box::pin(async move {
+                            TimeSlotEntity::delete_many()
+                                .filter(time_slot::Column::Id.is_in(time_slot_ids))
+                                .exec(connection)
+                                .await?;
+
+                            ReservationTypeEntity::delete_by_id(id)
+                                .exec(connection)
+                                .await
+                                .map(|_| ())
+                        })
+                    })
+                    .await
+                    .map_err(|e| match e {
+                        TransactionError::Connection(e) => format!("{e} {}:{}", line!(), column!()),
+                        TransactionError::Transaction(trans) => {
+                            format!("transaction: {trans} {}:{}", line!(), column!())
+                        }
+                    })
             })
+        })
+        .await
     }
 
     pub async fn edit_reservation_type(
         &self,
         mut reservation_type: ReservationType,
     ) -> Result<(), CreateReservationTypeError> {
-        let name_to_validate = NameMax64(reservation_type.name);
-        name_to_validate.validate()?;
-        reservation_type.name = name_to_validate.0;
-
-        match reservation_type
-            .into_active_model()
-            .reset_all()
-            .update(&self.connection)
-            .await
-        {
-            Ok(..) => Ok(()),
-            Err(e) => Err(CreateReservationTypeError::DatabaseError(e.to_string())),
-        }
+        self.journaled("Edit reservation type", |connection, _| {
+            Box::pin(async move {
+                let name_to_validate = NameMax64(reservation_type.name);
+                name_to_validate.validate()?;
+                reservation_type.name = name_to_validate.0;
+
+                match reservation_type
+                    .into_active_model()
+                    .reset_all()
+                    .update(connection)
+                    .await
+                {
+                    Ok(..) => Ok(()),
+                    Err(e) => Err(CreateReservationTypeError::DatabaseError(e.to_string())),
+                }
+            })
+        })
+        .await
     }
 
     pub async fn get_supported_concurrency_for_field(
@@ -1939,43 +2668,48 @@ impl Client {
         &self,
         input: UpdateReservationTypeConcurrencyForFieldInput,
     ) -> DBResult<()> {
-        /*
-         * There is no UPSERT support in SeaORM :(
-         * At least we are hot with caching.
-         */
-
-        let maybe_join_table_record = reservation_type_field_size_join::Entity::find()
-            .filter(
-                Condition::all()
-                    .add(reservation_type_field_size_join::Column::Field.eq(input.field_id))
-                    .add(
-                        reservation_type_field_size_join::Column::ReservationType
-                            .eq(input.reservation_type_id),
-                    ),
-            )
-            .one(&self.connection)
-            .await?;
+        self.journaled("Edit field concurrency", |connection, _| {
+            Box::pin(async move {
+                /*
+                 * There is no UPSERT support in SeaORM :(
+                 * At least we are hot with caching.
+                 */
+
+                let maybe_join_table_record = reservation_type_field_size_join::Entity::find()
+                    .filter(
+                        Condition::all()
+                            .add(reservation_type_field_size_join::Column::Field.eq(input.field_id))
+                            .add(
+                                reservation_type_field_size_join::Column::ReservationType
+                                    .eq(input.reservation_type_id),
+                            ),
+                    )
+                    .one(connection)
+                    .await?;
 
-        if let Some(join_table_record) = maybe_join_table_record {
-            let mut to_update = join_table_record.into_active_model();
+                if let Some(join_table_record) = maybe_join_table_record {
+                    let mut to_update = join_table_record.into_active_model();
 
-            to_update.set(
-                reservation_type_field_size_join::Column::Size,
-                input.new_concurrency.into(),
-            );
+                    to_update.set(
+                        reservation_type_field_size_join::Column::Size,
+                        input.new_concurrency.into(),
+                    );
 
-            to_update.update(&self.connection).await?;
-        } else {
-            reservation_type_field_size_join::ActiveModel {
-                field: Set(input.field_id),
-                reservation_type: Set(input.reservation_type_id),
-                size: Set(input.new_concurrency),
-            }
-            .insert(&self.connection)
-            .await?;
-        }
+                    to_update.update(connection).await?;
+                } else {
+                    reservation_type_field_size_join::ActiveModel {
+                        field: Set(input.field_id),
+                        reservation_type: Set(input.reservation_type_id),
+                        size: Set(input.new_concurrency),
+                    }
+                    .insert(connection)
+                    .await?;
+                }
 
-        Ok(())
+                Ok(())
+            })
+        })
+        .await
     }
 
     pub async fn get_non_default_reservation_type_concurrency_associations(
@@ -2010,13 +2744,18 @@ impl Client {
         &self,
         input: UpdateTargetReservationTypeInput,
     ) -> DBResult<()> {
-        ActiveTarget {
-            id: Set(input.target_id),
-            maybe_reservation_type: Set(input.new_reservation_type_id),
-        }
-        .update(&self.connection)
+        self.journaled("Edit target reservation type", |connection, _| {
+            Box::pin(async move {
+                ActiveTarget {
+                    id: Set(input.target_id),
+                    maybe_reservation_type: Set(input.new_reservation_type_id),
+                }
+                .update(connection)
+                .await
+                .map(|_| ())
+            })
+        })
         .await
-        .map(|_| ())
     }
 
     pub async fn get_scheduled_inputs(
@@ -2027,6 +2766,18 @@ impl Client {
     > {
         let mut result = vec![];
 
+        let blackouts = BlackoutFilter::load(&self.connection).await.map_err(|e| {
+            GetScheduledInputsError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+        })?;
+
+        let closures = ClosureFilter::load(&self.connection).await.map_err(|e| {
+            GetScheduledInputsError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+        })?;
+
+        let time_zones = field_time_zones(&self.connection).await.map_err(|e| {
+            GetScheduledInputsError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+        })?;
+
         let reservation_types = ReservationTypeEntity::find()
             .all(&self.connection)
             .await
@@ -2060,15 +2811,23 @@ impl Client {
                 })?
                 .into_iter()
                 .map(Into::<TimeSlotExtension>::into)
+                .filter(|time_slot_extension| {
+                    !blackouts.is_blacked_out(time_slot_extension)
+                        && !closures.is_closed(time_slot_extension)
+                })
                 .group_by(|time_slot_extension| time_slot_extension.time_slot.field_id);
 
             let fields = field_id_with_time_slots
                 .into_iter()
-                .map(|(field_id, time_slots)| FieldExtension {
-                    field_id,
-                    time_slots: time_slots.collect_vec(),
+                .map(|(field_id, time_slots)| {
+                    FieldExtension::new(
+                        field_id,
+                        time_slots.collect_vec(),
+                        time_zones.get(&field_id).copied().unwrap_or(Tz::UTC),
+                    )
+                    .map_err(GetScheduledInputsError::UnusableTimeSlot)
                 })
-                .collect_vec();
+                .collect::<Result<Vec<_>, _>>()?;
 
             let mut teams = vec![];
 
@@ -2080,28 +2839,9 @@ impl Client {
             });
 
             for target in targets_for_this_reservation_type {
-                let teams_for_target = TeamEntity::find()
-                    .find_with_related(TeamGroupEntity)
-                    .filter(team_group::Column::Id.is_in(target.groups.iter().map(|g| g.id)))
-                    .group_by(team::Column::Id)
-                    .having(
-                        team_group::Column::Id
-                            .into_expr()
-                            .count_distinct()
-                            .eq(i32::try_from(target.groups.len()).unwrap()),
-                    )
-                    .all(&self.connection)
-                    .await
-                    .map_err(|e| {
-                        GetScheduledInputsError::DatabaseError(format!(
-                            "{e} {}:{}",
-                            line!(),
-                            column!()
-                        ))
-                    })?
-                    .into_iter()
-                    .map(|(team, tags)| TeamExtension::new(team, tags))
-                    .collect_vec();
+                let teams_for_target = target.teams(&self.connection).await.map_err(|e| {
+                    GetScheduledInputsError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                })?;
 
                 teams.push(TeamCollection::new(target.groups.clone(), teams_for_target));
             }
@@ -2129,20 +2869,28 @@ impl Client {
                 })
                 .collect_vec();
 
+            let rematch_spacing = stored_rematch_spacing(&reservation_type);
+
             if reservation_type.is_practice {
-                result.push(ScheduledInput::new_practice(
-                    i.try_into().unwrap(),
-                    teams,
-                    fields,
-                    coach_conflicts_to_keep_in_mind,
-                ));
+                result.push(
+                    ScheduledInput::new_practice(
+                        i.try_into().unwrap(),
+                        teams,
+                        fields,
+                        coach_conflicts_to_keep_in_mind,
+                    )
+                    .with_rematch_spacing(rematch_spacing),
+                );
             } else {
-                result.push(ScheduledInput::new(
-                    i.try_into().unwrap(),
-                    teams,
-                    fields,
-                    coach_conflicts_to_keep_in_mind,
-                ));
+                result.push(
+                    ScheduledInput::new(
+                        i.try_into().unwrap(),
+                        teams,
+                        fields,
+                        coach_conflicts_to_keep_in_mind,
+                    )
+                    .with_rematch_spacing(rematch_spacing),
+                );
             }
         }
 
@@ -2170,21 +2918,20 @@ impl Client {
         format!("New {random_adjective} Schedule")
     }
 
-    pub async fn save_schedule(
-        &self,
-        schedule: CompiledSchedule,
-    ) -> Result<Schedule, SaveScheduleError> {
+    fn schedule_games(
+        outputs: &[&grpc_server::proto::algo_input::ScheduledOutput],
+    ) -> Result<Vec<ActiveScheduleGame>, SaveScheduleError> {
         let mut active_games = vec![];
 
-        for output in schedule.outputs {
-            for reservation in output.time_slots {
+        for output in outputs {
+            for reservation in &output.time_slots {
                 active_games.push(ActiveScheduleGame {
                     start: Set(DateTime::from_timestamp(reservation.start, 0)
                         .ok_or(SaveScheduleError::InvalidDateError(0))?
-                        .to_rfc3339()),
+                        .timestamp_millis()),
                     end: Set(DateTime::from_timestamp(reservation.end, 0)
                         .ok_or(SaveScheduleError::InvalidDateError(1))?
-                        .to_rfc3339()),
+                        .timestamp_millis()),
                     team_one: Set(reservation
                         .booking
                         .as_ref()
@@ -2206,47 +2953,100 @@ impl Client {
                                 .ok()
                         })
                         .ok_or_else(|| SaveScheduleError::OverflowError("field id".into()))?),
+                    is_bye: Set(false),
                     ..Default::default()
                 });
             }
-        }
-
-        self.connection
-            .transaction(|connection| {
-                Box::pin(async move {
-                    let now = Local::now().to_rfc3339();
 
-                    let new_schedule = ActiveSchedule {
-                        name: Set(Self::generate_schedule_name()),
-                        created: Set(now.clone()),
-                        last_edit: Set(now),
-                        ..Default::default()
-                    }
-                    .insert(connection)
-                    .await?;
+            for bye in &output.byes {
+                active_games.push(ActiveScheduleGame {
+                    start: Set(DateTime::from_timestamp(bye.start, 0)
+                        .ok_or(SaveScheduleError::InvalidDateError(0))?
+                        .timestamp_millis()),
+                    end: Set(DateTime::from_timestamp(bye.end, 0)
+                        .ok_or(SaveScheduleError::InvalidDateError(1))?
+                        .timestamp_millis()),
+                    team_one: Set(bye.team.as_ref().map(TeamLike::unique_id)),
+                    team_two: Set(None),
+                    field_id: Set(None),
+                    is_bye: Set(true),
+                    ..Default::default()
+                });
+            }
+        }
 
-                    for game in &mut active_games {
-                        game.schedule_id = Set(new_schedule.id);
-                    }
+        Ok(active_games)
+    }
 
-                    ScheduleGameEntity::insert_many(active_games)
-                        .exec(connection)
-                        .await?;
+    /// Saves the best schedule in `schedule`. If the scheduler sent back alternatives,
+    /// each one is saved next to it as a sibling draft named after the best schedule.
+    ///
+    /// Returns the best schedule.
+    pub async fn save_schedule(
+        &self,
+        schedule: CompiledSchedule,
+    ) -> Result<Schedule, SaveScheduleError> {
+        self.journaled("Save schedule", |connection, _| {
+            Box::pin(async move {
+                let variants = schedule
+                    .variants()
+                    .iter()
+                    .map(|variant| Self::schedule_games(variant))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                connection
+                    .transaction(|connection| {
+                        Box::pin(async move {
+                            let now = Local::now().to_rfc3339();
+                            let name = Self::generate_schedule_name();
+
+                            let mut best = None;
+
+                            for (rank, mut active_games) in variants.into_iter().enumerate() {
+                                let new_schedule = ActiveSchedule {
+                                    name: Set(if rank == 0 {
+                                        name.clone()
+                                    } else {
+                                        format!("{name} (alternative {rank})")
+                                    }),
+                                    created: Set(now.clone()),
+                                    last_edit: Set(now.clone()),
+                                    ..Default::default()
+                                }
+                                .insert(connection)
+                                .await?;
+
+                                for game in &mut active_games {
+                                    game.schedule_id = Set(new_schedule.id);
+                                }
+
+                                if !active_games.is_empty() {
+                                    ScheduleGameEntity::insert_many(active_games)
+                                        .exec(connection)
+                                        .await?;
+                                }
+
+                                best.get_or_insert(new_schedule);
+                            }
 
-                    Ok(new_schedule)
-                })
-            })
-            .await
-            .map_err(|e: TransactionError<DbErr>| match e {
-                TransactionError::Connection(e) => {
-                    SaveScheduleError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
-                }
-                TransactionError::Transaction(e) => SaveScheduleError::DatabaseError(format!(
-                    "transaction failed: {e} {}:{}",
-                    line!(),
-                    column!()
-                )),
+                            Ok(best)
+                        })
+                    })
+                    .await
+                    .map_err(|e: TransactionError<DbErr>| match e {
+                        TransactionError::Connection(e) => SaveScheduleError::DatabaseError(
+                            format!("{e} {}:{}", line!(), column!()),
+                        ),
+                        TransactionError::Transaction(e) => SaveScheduleError::DatabaseError(
+                            format!("transaction failed: {e} {}:{}", line!(), column!()),
+                        ),
+                    })?
+                    .ok_or_else(|| {
+                        SaveScheduleError::DatabaseError("no schedule was saved".to_owned())
+                    })
             })
+        })
+        .await
     }
 
     pub async fn get_schedules(&self) -> DBResult<Vec<Schedule>> {
@@ -2257,39 +3057,49 @@ impl Client {
     }
 
     pub async fn delete_schedule(&self, id: i32) -> DBResult<()> {
-        ScheduleEntity::delete_by_id(id)
-            .exec(&self.connection)
-            .await
-            .map(|_| ())
+        self.journaled("Delete schedule", |connection, _| {
+            Box::pin(async move {
+                ScheduleEntity::delete_by_id(id)
+                    .exec(connection)
+                    .await
+                    .map(|_| ())
+            })
+        })
+        .await
     }
 
     pub async fn edit_schedule(
         &self,
         input: EditScheduleInput,
     ) -> Result<Schedule, EditScheduleError> {
-        input.validate()?;
-
-        if let Some(name) = input.name {
-            ActiveSchedule {
-                id: Set(input.id),
-                name: Set(name.0),
-                last_edit: Set(Utc::now().to_rfc3339()),
-                ..Default::default()
-            }
-            .update(&self.connection)
-            .await
-            .map_err(|e| {
-                EditScheduleError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
-            })?;
-        }
+        self.journaled("Edit schedule", |connection, _| {
+            Box::pin(async move {
+                input.validate()?;
+
+                if let Some(name) = input.name {
+                    ActiveSchedule {
+                        id: Set(input.id),
+                        name: Set(name.0),
+                        last_edit: Set(Utc::now().to_rfc3339()),
+                        ..Default::default()
+                    }
+                    .update(connection)
+                    .await
+                    .map_err(|e| {
+                        EditScheduleError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?;
+                }
 
-        ScheduleEntity::find_by_id(input.id)
-            .one(&self.connection)
-            .await
-            .map_err(|e| {
-                EditScheduleError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
-            })?
-            .ok_or(EditScheduleError::NotFound(input.id))
+                ScheduleEntity::find_by_id(input.id)
+                    .one(connection)
+                    .await
+                    .map_err(|e| {
+                        EditScheduleError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?
+                    .ok_or(EditScheduleError::NotFound(input.id))
+            })
+        })
+        .await
     }
 
     pub async fn get_schedule(&self, id: i32) -> Result<Schedule, LoadScheduleError> {
@@ -2319,183 +3129,566 @@ impl Client {
         Ok(results.remove(0))
     }
 
-    pub async fn get_team(&self, team_id: i32) -> Result<TeamExtension, LoadTeamsError> {
-        let mut teams_with_id = TeamEntity::find_by_id(team_id)
-            .find_with_related(TeamGroupEntity)
-            .all(&self.connection)
+    /// Copies the current games of `schedule_id` into a new revision, e.g. before the
+    /// schedule is published.
+    pub async fn snapshot_schedule(
+        &self,
+        schedule_id: i32,
+        label: Option<String>,
+    ) -> Result<schedule_revision::Model, ScheduleRevisionError> {
+        self.connection
+            .transaction(|transaction| {
+                Box::pin(async move {
+                    schedule_revisions::snapshot(transaction, schedule_id, label).await
+                })
+            })
             .await
-            .map_err(|e| LoadTeamsError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?
-            .into_iter()
-            .map(|(team, tags)| TeamExtension::new(team, tags))
-            .collect_vec();
+            .map_err(|e| match e {
+                TransactionError::Connection(e) => {
+                    ScheduleRevisionError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                }
+                TransactionError::Transaction(e) => e,
+            })
+    }
 
-        if teams_with_id.len() != 1 {
-            return Err(LoadTeamsError::NotFound(team_id, teams_with_id.len()));
-        }
+    /// Revisions of `schedule_id`, newest first.
+    pub async fn get_schedule_revisions(
+        &self,
+        schedule_id: i32,
+    ) -> Result<Vec<schedule_revision::Model>, ScheduleRevisionError> {
+        schedule_revisions::revisions(&self.connection, schedule_id)
+            .await
+            .map_err(|e| {
+                ScheduleRevisionError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+            })
+    }
 
-        Ok(teams_with_id.remove(0))
+    /// What changed from the games of `from` to the games of `to`.
+    pub async fn diff_schedules(
+        &self,
+        from: ScheduleSource,
+        to: ScheduleSource,
+    ) -> Result<ScheduleDiff, ScheduleRevisionError> {
+        schedule_revisions::diff(&self.connection, from, to).await
     }
 
-    pub async fn copy_time_slots(
+    /// Records the result of a saved game, see [`RecordGameResultInput`].
+    pub async fn record_game_result(
         &self,
-        input: CopyTimeSlotsInput,
-    ) -> Result<Vec<TimeSlotExtension>, CopyTimeSlotsError> {
-        let start = TimeSlotEntity::find_by_id(input.src_start_id)
-            .one(&self.connection)
-            .await
-            .map_err(|e| CopyTimeSlotsError::DatabaseError(e.to_string()))?
-            .ok_or(CopyTimeSlotsError::NotFound(input.src_start_id))?;
+        input: RecordGameResultInput,
+    ) -> Result<ScheduleGame, GameResultError> {
+        self.journaled("Record game result", |connection, _| {
+            Box::pin(async move { game_results::record(connection, input).await })
+        })
+        .await
+    }
 
-        let end = TimeSlotEntity::find_by_id(input.src_end_id)
-            .one(&self.connection)
-            .await
-            .map_err(|e| CopyTimeSlotsError::DatabaseError(e.to_string()))?
-            .ok_or(CopyTimeSlotsError::NotFound(input.src_end_id))?;
+    /// The standings of every target, from the results recorded in `schedule_id`.
+    pub async fn get_standings(
+        &self,
+        schedule_id: i32,
+        config: &StandingsConfig,
+    ) -> Result<Vec<Standings>, GameResultError> {
+        game_results::compute(&self.connection, schedule_id, config).await
+    }
 
-        if start.field_id != end.field_id {
-            return Err(CopyTimeSlotsError::FieldMismatch);
-        }
+    /// Cancels the games of a rainout, and proposes makeups for them in the free capacity
+    /// of the fields' time slots from now on. No other game moves, and the proposals are
+    /// only applied by [`Client::apply_makeups`].
+    pub async fn rain_out(&self, input: RainoutInput) -> Result<Rainout, RainoutError> {
+        self.journaled("Rainout", |connection, _| {
+            Box::pin(async move {
+                connection
+                    .transaction(|connection| {
+                        Box::pin(
+                            async move { rainouts::rain_out(connection, input, Utc::now()).await },
+                        )
+                    })
+                    .await
+                    .map_err(|e| match e {
+                        TransactionError::Connection(e) => {
+                            RainoutError::DatabaseError(e.to_string())
+                        }
+                        TransactionError::Transaction(e) => e,
+                    })
+            })
+        })
+        .await
+    }
 
-        let first_time_chrono = DateTime::parse_from_rfc3339(&start.start)
-            .expect("improperly stored date in database")
-            .to_utc();
+    /// Cancels the games of a schedule that a field closure covers, and proposes makeups for
+    /// them like [`Client::rain_out`].
+    pub async fn replace_closed_games(
+        &self,
+        closure_id: i32,
+        schedule_id: i32,
+    ) -> Result<Rainout, RainoutError> {
+        self.journaled("Replace closed games", |connection, _| {
+            Box::pin(async move {
+                connection
+                    .transaction(|connection| {
+                        Box::pin(async move {
+                            field_closures::replace_closed_games(
+                                connection,
+                                closure_id,
+                                schedule_id,
+                                Utc::now(),
+                            )
+                            .await
+                        })
+                    })
+                    .await
+                    .map_err(|e| match e {
+                        TransactionError::Connection(e) => {
+                            RainoutError::DatabaseError(e.to_string())
+                        }
+                        TransactionError::Transaction(e) => e,
+                    })
+            })
+        })
+        .await
+    }
 
-        let end_time_chrono = DateTime::parse_from_rfc3339(&end.start)
-            .expect("improperly stored date in database")
-            .to_utc();
+    /// Moves cancelled games of a schedule to their makeups, and puts them back on the
+    /// schedule. The makeups are checked like any other edit.
+    pub async fn apply_makeups(
+        &self,
+        schedule_id: i32,
+        proposals: &[MakeupProposal],
+        force: bool,
+    ) -> Result<EditReport, TimeSlotError> {
+        self.journaled("Apply makeups", |connection, _| {
+            Box::pin(async move {
+                let edits = proposals
+                    .iter()
+                    .map(|proposal| {
+                        ScheduleGameEdit::new(
+                            proposal.game_id(),
+                            proposal.start(),
+                            proposal.end(),
+                            Some(proposal.field_id()),
+                        )
+                        .reinstated()
+                    })
+                    .collect_vec();
 
-        if first_time_chrono > end_time_chrono {
-            return Err(CopyTimeSlotsError::OutOfOrder {
-                start: first_time_chrono,
-                end: end_time_chrono,
-            });
-        }
+                let report = schedule_edits::check(connection, schedule_id, &edits, None)
+                    .await?
+                    .enforce(force)?;
+
+                let proposals = proposals.to_vec();
+
+                connection
+                    .transaction(|connection| {
+                        Box::pin(async move {
+                            for proposal in proposals {
+                                ScheduleGameEntity::update_many()
+                                    .col_expr(
+                                        schedule_game::Column::Start,
+                                        Expr::value(proposal.start().timestamp_millis()),
+                                    )
+                                    .col_expr(
+                                        schedule_game::Column::End,
+                                        Expr::value(proposal.end().timestamp_millis()),
+                                    )
+                                    .col_expr(
+                                        schedule_game::Column::FieldId,
+                                        Expr::value(proposal.field_id()),
+                                    )
+                                    .col_expr(
+                                        schedule_game::Column::Status,
+                                        Expr::value(GameStatus::Scheduled.as_str()),
+                                    )
+                                    .col_expr(
+                                        schedule_game::Column::Sequence,
+                                        Expr::col(schedule_game::Column::Sequence).add(1),
+                                    )
+                                    .filter(schedule_game::Column::Id.eq(proposal.game_id()))
+                                    .exec(connection)
+                                    .await?;
+                            }
 
-        let chrono_delta = input.dst_start - first_time_chrono;
+                            ScheduleEntity::update(ActiveSchedule {
+                                id: Set(schedule_id),
+                                last_edit: Set(Utc::now().to_rfc3339()),
+                                ..Default::default()
+                            })
+                            .exec(connection)
+                            .await
+                        })
+                    })
+                    .await
+                    .map_err(|e| match e {
+                        TransactionError::Connection(db) | TransactionError::Transaction(db) => {
+                            TimeSlotError::DatabaseError(format!("{db} {}:{}", line!(), column!()))
+                        }
+                    })?;
 
-        let src_time_slots = select_time_slot_extension()
-            .filter(
-                Condition::all()
-                    .add(time_slot::Column::FieldId.eq(start.field_id))
-                    .add(time_slot::Column::Start.between(start.start, end.start)),
-            )
-            .order_by(time_slot::Column::Start, Order::Asc)
-            .into_model::<TimeSlotSelectionTypeAggregate>()
+                Ok(report)
+            })
+        })
+        .await
+    }
+
+    /// Checks a saved schedule against the current fields, time slots and coach conflicts.
+    /// Time slots hidden by a blackout count as unavailable.
+    pub async fn validate_schedule(
+        &self,
+        schedule_id: i32,
+        min_rest: Option<TimeDelta>,
+    ) -> Result<Vec<Violation>, ValidateScheduleError> {
+        let (_, games) = ScheduleEntity::find_by_id(schedule_id)
+            .find_with_related(ScheduleGameEntity)
             .all(&self.connection)
             .await
             .map_err(|e| {
-                CopyTimeSlotsError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                ValidateScheduleError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
             })?
-            .into_iter()
-            .map(Into::<TimeSlotExtension>::into)
-            .collect_vec();
+            .pop()
+            .ok_or(ValidateScheduleError::NotFound(schedule_id))?;
 
-        let models_to_insert = src_time_slots
-            .into_iter()
-            .map(|time_slot_ext| {
-                let start = DateTime::parse_from_rfc3339(&time_slot_ext.time_slot.start)
-                    .expect("improperly stored date in database (start)")
-                    .to_utc()
-                    + chrono_delta;
-
-                let end = DateTime::parse_from_rfc3339(&time_slot_ext.time_slot.end)
-                    .expect("improperly stored date in database (end)")
-                    .to_utc()
-                    + chrono_delta;
-
-                (
-                    ActiveTimeSlot {
-                        field_id: Set(time_slot_ext.time_slot.field_id),
-                        start: Set(start.to_rfc3339()),
-                        end: Set(end.to_rfc3339()),
-                        ..Default::default()
-                    },
-                    start,
-                    end,
-                    time_slot_ext,
-                )
+        let context = RuleContext::load(&self.connection).await.map_err(|e| {
+            ValidateScheduleError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+        })?;
+
+        context
+            .validate(&games, min_rest)
+            .map_err(ValidateScheduleError::MalformedGame)
+    }
+
+    /// Every row of the profile, see [`profile_bundle`].
+    pub async fn export_profile_bundle(&self) -> Result<ProfileBundle, ProfileBundleError> {
+        profile_bundle::export(&self.connection).await
+    }
+
+    /// Copies a bundle into this profile, which must be empty. Nothing is written if any
+    /// row of the bundle refers to something that is not in it.
+    pub async fn import_profile_bundle(
+        &self,
+        bundle: ProfileBundle,
+    ) -> Result<ProfileImportReport, ProfileBundleError> {
+        self.journaled("Import profile", |connection, _| {
+            Box::pin(async move {
+                connection
+                    .transaction(|transaction| {
+                        Box::pin(async move { profile_bundle::import(transaction, bundle).await })
+                    })
+                    .await
+                    .map_err(|e| match e {
+                        TransactionError::Connection(e) => ProfileBundleError::DatabaseError(
+                            format!("{e} {}:{}", line!(), column!()),
+                        ),
+                        TransactionError::Transaction(e) => e,
+                    })
             })
-            .collect_vec();
+        })
+        .await
+    }
 
-        let Some(first) = models_to_insert.first() else {
-            // there are no models to copy
-            return Ok(vec![]);
-        };
+    /// Resolves the games of a saved schedule for export, see [`schedule_export`].
+    pub async fn export_schedule_games(
+        &self,
+        schedule_id: i32,
+        filter: &ScheduleExportFilter,
+    ) -> Result<Vec<ExportedGame>, ExportScheduleError> {
+        schedule_export::load(&self.connection, schedule_id, filter).await
+    }
 
-        let last = models_to_insert.last().unwrap();
+    /// A saved schedule as CSV, one row per game.
+    pub async fn export_schedule_csv(
+        &self,
+        schedule_id: i32,
+        filter: &ScheduleExportFilter,
+    ) -> Result<String, ExportScheduleError> {
+        to_csv(&self.export_schedule_games(schedule_id, filter).await?)
+    }
 
-        let potential_conflicts = TimeSlotEntity::find()
-            .filter(
-                Condition::all()
-                    .add(time_slot::Column::FieldId.eq(start.field_id))
-                    .add(time_slot::Column::End.gt(first.1.to_rfc3339()))
-                    .add(time_slot::Column::Start.lt(last.2.to_rfc3339())),
-            )
-            .all(&self.connection)
+    /// A saved schedule as a printable HTML booklet, see [`booklet`].
+    pub async fn export_schedule_booklet(
+        &self,
+        schedule_id: i32,
+        filter: &ScheduleExportFilter,
+    ) -> Result<String, ExportScheduleError> {
+        let schedule = ScheduleEntity::find_by_id(schedule_id)
+            .one(&self.connection)
             .await
             .map_err(|e| {
-                CopyTimeSlotsError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
-            })?;
+                ExportScheduleError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+            })?
+            .ok_or(ExportScheduleError::NotFound(schedule_id))?;
 
-        for existing_time_slot in &potential_conflicts {
-            let o_start = DateTime::parse_from_rfc3339(&existing_time_slot.start)
-                .unwrap()
-                .to_utc();
-            let o_end = DateTime::parse_from_rfc3339(&existing_time_slot.end)
-                .unwrap()
-                .to_utc();
-
-            for (_, start, end, _) in &models_to_insert {
-                if &o_start < end && &o_end > start {
-                    return Err(CopyTimeSlotsError::Overlap { o_start, o_end });
-                }
+        let games = self.export_schedule_games(schedule_id, filter).await?;
+
+        booklet::render(&schedule, &games)
+    }
+
+    /// Calendars of a saved schedule, see [`calendar`]. An empty `owners` makes one
+    /// calendar for every team and field that has a game or a bye in the schedule.
+    pub async fn export_schedule_calendars(
+        &self,
+        schedule_id: i32,
+        owners: &[CalendarOwner],
+    ) -> Result<Vec<ScheduleCalendar>, ExportScheduleError> {
+        let games = self
+            .export_schedule_games(schedule_id, &ScheduleExportFilter::default())
+            .await?;
+        let byes = schedule_export::load_byes(&self.connection, schedule_id).await?;
+
+        let mut names = BTreeMap::new();
+
+        for bye in &byes {
+            names.insert(
+                CalendarOwner::Team(bye.team().team().id),
+                bye.team().team().name.clone(),
+            );
+        }
+
+        for game in &games {
+            for team in game.home().into_iter().chain(game.away()) {
+                names.insert(
+                    CalendarOwner::Team(team.team().id),
+                    team.team().name.clone(),
+                );
             }
+            names.insert(
+                CalendarOwner::Field(game.field().id),
+                game.field().name.clone(),
+            );
         }
 
-        let result = self
-            .connection
-            .transaction(|connection| {
-                Box::pin(async move {
-                    let mut ret_buf = Vec::with_capacity(models_to_insert.len());
-                    for (model, .., time_slot_ext) in models_to_insert {
-                        let new_time_slot = TimeSlotEntity::insert(model)
-                            .exec_with_returning(connection)
-                            .await?;
+        let owners = if owners.is_empty() {
+            names.keys().copied().collect_vec()
+        } else {
+            owners.to_vec()
+        };
 
-                        use reservation_type_time_slot_join::{
-                            ActiveModel as RTTJM, Entity as RTTJE,
-                        };
+        let stamp = Utc::now();
+        let mut calendars = vec![];
 
-                        RTTJE::insert(RTTJM {
-                            time_slot: Set(new_time_slot.id),
-                            reservation_type: Set(time_slot_ext.reservation_type.id),
-                        })
-                        .exec_with_returning(connection)
-                        .await?;
-
-                        ret_buf.push(TimeSlotExtension {
-                            custom_matches: time_slot_ext.custom_matches,
-                            reservation_type: time_slot_ext.reservation_type,
-                            time_slot: new_time_slot,
-                        });
-                    }
+        for owner in owners {
+            let name = match (names.get(&owner), owner) {
+                (Some(name), _) => name.clone(),
+                (None, CalendarOwner::Team(id)) => {
+                    TeamEntity::find_by_id(id)
+                        .one(&self.connection)
+                        .await
+                        .map_err(|e| {
+                            ExportScheduleError::DatabaseError(format!(
+                                "{e} {}:{}",
+                                line!(),
+                                column!()
+                            ))
+                        })?
+                        .ok_or(ExportScheduleError::TeamNotFound(id))?
+                        .name
+                }
+                (None, CalendarOwner::Field(id)) => {
+                    FieldEntity::find_by_id(id)
+                        .one(&self.connection)
+                        .await
+                        .map_err(|e| {
+                            ExportScheduleError::DatabaseError(format!(
+                                "{e} {}:{}",
+                                line!(),
+                                column!()
+                            ))
+                        })?
+                        .ok_or(ExportScheduleError::FieldNotFound(id))?
+                        .name
+                }
+            };
 
-                    Ok(ret_buf)
-                })
-            })
+            calendars.push(calendar::calendar(owner, name, &games, &byes, stamp));
+        }
+
+        Ok(calendars)
+    }
+
+    pub async fn get_team(&self, team_id: i32) -> Result<TeamExtension, LoadTeamsError> {
+        let mut teams_with_id = TeamEntity::find_by_id(team_id)
+            .find_with_related(TeamGroupEntity)
+            .all(&self.connection)
             .await
-            .map_err(|e: TransactionError<DbErr>| match e {
-                TransactionError::Connection(db) => {
-                    CopyTimeSlotsError::DatabaseError(format!("{db} {}:{}", line!(), column!()))
+            .map_err(|e| LoadTeamsError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?
+            .into_iter()
+            .map(|(team, tags)| TeamExtension::new(team, tags))
+            .collect_vec();
+
+        if teams_with_id.len() != 1 {
+            return Err(LoadTeamsError::NotFound(team_id, teams_with_id.len()));
+        }
+
+        Ok(teams_with_id.remove(0))
+    }
+
+    pub async fn copy_time_slots(
+        &self,
+        input: CopyTimeSlotsInput,
+    ) -> Result<Vec<TimeSlotExtension>, CopyTimeSlotsError> {
+        self.journaled("Copy time slots", |connection, _| {
+            Box::pin(async move {
+                let start = TimeSlotEntity::find_by_id(input.src_start_id)
+                    .one(connection)
+                    .await
+                    .map_err(|e| CopyTimeSlotsError::DatabaseError(e.to_string()))?
+                    .ok_or(CopyTimeSlotsError::NotFound(input.src_start_id))?;
+
+                let end = TimeSlotEntity::find_by_id(input.src_end_id)
+                    .one(connection)
+                    .await
+                    .map_err(|e| CopyTimeSlotsError::DatabaseError(e.to_string()))?
+                    .ok_or(CopyTimeSlotsError::NotFound(input.src_end_id))?;
+
+                if start.field_id != end.field_id {
+                    return Err(CopyTimeSlotsError::FieldMismatch);
                 }
-                TransactionError::Transaction(t) => CopyTimeSlotsError::DatabaseError(format!(
-                    "transaction failed: {t} {}:{}",
-                    line!(),
-                    column!()
-                )),
-            })?;
 
-        Ok(result)
+                let first_time_chrono = start
+                    .start_time()
+                    .ok_or(CopyTimeSlotsError::MalformedTimeSlot(start.id))?;
+
+                let end_time_chrono = end
+                    .start_time()
+                    .ok_or(CopyTimeSlotsError::MalformedTimeSlot(end.id))?;
+
+                if first_time_chrono > end_time_chrono {
+                    return Err(CopyTimeSlotsError::OutOfOrder {
+                        start: first_time_chrono,
+                        end: end_time_chrono,
+                    });
+                }
+
+                // measure the offset on the wall clock so that copies across a DST change keep their local times
+                let time_zone = field_time_zone(connection, start.field_id)
+                    .await
+                    .map_err(|e| {
+                        CopyTimeSlotsError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?;
+
+                let chrono_delta = input.dst_start.with_timezone(&time_zone).naive_local()
+                    - first_time_chrono.with_timezone(&time_zone).naive_local();
+
+                let src_time_slots = select_time_slot_extension()
+                    .filter(
+                        Condition::all()
+                            .add(time_slot::Column::FieldId.eq(start.field_id))
+                            .add(time_slot::Column::Start.between(start.start, end.start)),
+                    )
+                    .order_by(time_slot::Column::Start, Order::Asc)
+                    .into_model::<TimeSlotSelectionTypeAggregate>()
+                    .all(connection)
+                    .await
+                    .map_err(|e| {
+                        CopyTimeSlotsError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?
+                    .into_iter()
+                    .map(Into::<TimeSlotExtension>::into)
+                    .collect_vec();
+
+                let models_to_insert = src_time_slots
+                    .into_iter()
+                    .map(|time_slot_ext| {
+                        let malformed =
+                            || CopyTimeSlotsError::MalformedTimeSlot(time_slot_ext.time_slot.id);
+
+                        let start = time_slot_ext.time_slot.start_time().ok_or_else(malformed)?;
+                        let start = backend::shift_wall_clock(start, chrono_delta, &time_zone)
+                            .ok_or(CopyTimeSlotsError::OutOfRange(time_slot_ext.time_slot.id))?;
+
+                        let end = time_slot_ext.time_slot.end_time().ok_or_else(malformed)?;
+                        let end = backend::shift_wall_clock(end, chrono_delta, &time_zone)
+                            .ok_or(CopyTimeSlotsError::OutOfRange(time_slot_ext.time_slot.id))?;
+
+                        Ok((
+                            ActiveTimeSlot {
+                                field_id: Set(time_slot_ext.time_slot.field_id),
+                                start: Set(start.timestamp_millis()),
+                                end: Set(end.timestamp_millis()),
+                                ..Default::default()
+                            },
+                            start,
+                            end,
+                            time_slot_ext,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, CopyTimeSlotsError>>()?;
+
+                let Some(first) = models_to_insert.first() else {
+                    // there are no models to copy
+                    return Ok(vec![]);
+                };
+
+                let last = models_to_insert.last().unwrap();
+
+                let potential_conflicts = TimeSlotEntity::find()
+                    .filter(
+                        Condition::all()
+                            .add(time_slot::Column::FieldId.eq(start.field_id))
+                            .add(time_slot::Column::End.gt(first.1.timestamp_millis()))
+                            .add(time_slot::Column::Start.lt(last.2.timestamp_millis())),
+                    )
+                    .all(connection)
+                    .await
+                    .map_err(|e| {
+                        CopyTimeSlotsError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?;
+
+                for existing_time_slot in &potential_conflicts {
+                    let malformed = || CopyTimeSlotsError::MalformedTimeSlot(existing_time_slot.id);
+                    let o_start = existing_time_slot.start_time().ok_or_else(malformed)?;
+                    let o_end = existing_time_slot.end_time().ok_or_else(malformed)?;
+
+                    for (_, start, end, _) in &models_to_insert {
+                        if &o_start < end && &o_end > start {
+                            return Err(CopyTimeSlotsError::Overlap { o_start, o_end });
+                        }
+                    }
+                }
+
+                let result = connection
+                    .transaction(|connection| {
+                        Box::pin(async move {
+                            let mut ret_buf = Vec::with_capacity(models_to_insert.len());
+                            for (model, .., time_slot_ext) in models_to_insert {
+                                let new_time_slot = TimeSlotEntity::insert(model)
+                                    .exec_with_returning(connection)
+                                    .await?;
+
+                                use reservation_type_time_slot_join::{
+                                    ActiveModel as RTTJM, Entity as RTTJE,
+                                };
+
+                                RTTJE::insert(RTTJM {
+                                    time_slot: Set(new_time_slot.id),
+                                    reservation_type: Set(time_slot_ext.reservation_type.id),
+                                })
+                                .exec_with_returning(connection)
+                                .await?;
+
+                                ret_buf.push(TimeSlotExtension {
+                                    custom_matches: time_slot_ext.custom_matches,
+                                    reservation_type: time_slot_ext.reservation_type,
+                                    time_slot: new_time_slot,
+                                });
+                            }
+
+                            Ok(ret_buf)
+                        })
+                    })
+                    .await
+                    .map_err(|e: TransactionError<DbErr>| match e {
+                        TransactionError::Connection(db) => CopyTimeSlotsError::DatabaseError(
+                            format!("{db} {}:{}", line!(), column!()),
+                        ),
+                        TransactionError::Transaction(t) => CopyTimeSlotsError::DatabaseError(
+                            format!("transaction failed: {t} {}:{}", line!(), column!()),
+                        ),
+                    })?;
+
+                Ok(result)
+            })
+        })
+        .await
     }
 
     pub async fn delete_time_slots(
@@ -2503,152 +3696,186 @@ impl Client {
         start_id: i32,
         end_id: i32,
     ) -> Result<(), DeleteTimeSlotsError> {
-        let start = TimeSlotEntity::find_by_id(start_id)
-            .one(&self.connection)
-            .await
-            .map_err(|e| DeleteTimeSlotsError::DatabaseError(e.to_string()))?
-            .ok_or(DeleteTimeSlotsError::NotFound(start_id))?;
+        self.journaled("Delete time slots", |connection, _| {
+            Box::pin(async move {
+                let start = TimeSlotEntity::find_by_id(start_id)
+                    .one(connection)
+                    .await
+                    .map_err(|e| DeleteTimeSlotsError::DatabaseError(e.to_string()))?
+                    .ok_or(DeleteTimeSlotsError::NotFound(start_id))?;
 
-        let end = TimeSlotEntity::find_by_id(end_id)
-            .one(&self.connection)
-            .await
-            .map_err(|e| DeleteTimeSlotsError::DatabaseError(e.to_string()))?
-            .ok_or(DeleteTimeSlotsError::NotFound(end_id))?;
+                let end = TimeSlotEntity::find_by_id(end_id)
+                    .one(connection)
+                    .await
+                    .map_err(|e| DeleteTimeSlotsError::DatabaseError(e.to_string()))?
+                    .ok_or(DeleteTimeSlotsError::NotFound(end_id))?;
 
-        if start.field_id != end.field_id {
-            return Err(DeleteTimeSlotsError::FieldMismatch);
-        }
+                if start.field_id != end.field_id {
+                    return Err(DeleteTimeSlotsError::FieldMismatch);
+                }
 
-        let first_time_chrono = DateTime::parse_from_rfc3339(&start.start)
-            .expect("improperly stored date in database")
-            .to_utc();
+                let first_time_chrono = start
+                    .start_time()
+                    .ok_or(DeleteTimeSlotsError::MalformedTimeSlot(start.id))?;
 
-        let end_time_chrono = DateTime::parse_from_rfc3339(&end.start)
-            .expect("improperly stored date in database")
-            .to_utc();
+                let end_time_chrono = end
+                    .start_time()
+                    .ok_or(DeleteTimeSlotsError::MalformedTimeSlot(end.id))?;
 
-        if first_time_chrono > end_time_chrono {
-            return Err(DeleteTimeSlotsError::OutOfOrder {
-                start: first_time_chrono,
-                end: end_time_chrono,
-            });
-        }
+                if first_time_chrono > end_time_chrono {
+                    return Err(DeleteTimeSlotsError::OutOfOrder {
+                        start: first_time_chrono,
+                        end: end_time_chrono,
+                    });
+                }
 
-        TimeSlotEntity::delete_many()
-            .filter(
-                Condition::all()
-                    .add(time_slot::Column::FieldId.eq(start.field_id))
-                    .add(time_slot::Column::Start.between(start.start, end.start)),
-            )
-            .exec(&self.connection)
-            .await
-            .map_err(|e| {
-                DeleteTimeSlotsError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                TimeSlotEntity::delete_many()
+                    .filter(
+                        Condition::all()
+                            .add(time_slot::Column::FieldId.eq(start.field_id))
+                            .add(time_slot::Column::Start.between(start.start, end.start)),
+                    )
+                    .exec(connection)
+                    .await
+                    .map_err(|e| {
+                        DeleteTimeSlotsError::DatabaseError(format!(
+                            "{e} {}:{}",
+                            line!(),
+                            column!()
+                        ))
+                    })
+                    .map(|_| ())
             })
-            .map(|_| ())
+        })
+        .await
     }
 
     pub async fn create_coaching_conflict(
         &self,
         input: CreateCoachConflictInput,
     ) -> Result<CoachConflict, CoachConflictError> {
-        let model = ActiveCoachConflict {
-            coach_name: Set(input.coach_name),
-            region: Set(input.region_id),
-            ..Default::default()
-        }
-        .insert(&self.connection)
-        .await
-        .map_err(|e| CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?;
+        self.journaled("Create coach conflict", |connection, _| {
+            Box::pin(async move {
+                let model = ActiveCoachConflict {
+                    coach_name: Set(input.coach_name),
+                    region: Set(input.region_id),
+                    ..Default::default()
+                }
+                .insert(connection)
+                .await
+                .map_err(|e| {
+                    CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                })?;
 
-        Ok(CoachConflict {
-            id: model.id,
-            coach_name: model.coach_name,
-            region: model.region,
-            teams: vec![],
+                Ok(CoachConflict {
+                    id: model.id,
+                    coach_name: model.coach_name,
+                    region: model.region,
+                    teams: vec![],
+                })
+            })
         })
+        .await
     }
 
     pub async fn delete_coaching_conflict(&self, id: i32) -> Result<(), CoachConflictError> {
-        let maybe_deleted = CoachConflictEntity::delete_by_id(id)
-            .exec(&self.connection)
-            .await
-            .map_err(|e| {
-                CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
-            })?;
+        self.journaled("Delete coach conflict", |connection, _| {
+            Box::pin(async move {
+                let maybe_deleted = CoachConflictEntity::delete_by_id(id)
+                    .exec(connection)
+                    .await
+                    .map_err(|e| {
+                        CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?;
 
-        if maybe_deleted.rows_affected != 1 {
-            return Err(CoachConflictError::CoachConflictNotFound(id));
-        }
+                if maybe_deleted.rows_affected != 1 {
+                    return Err(CoachConflictError::CoachConflictNotFound(id));
+                }
 
-        Ok(())
+                Ok(())
+            })
+        })
+        .await
     }
 
     pub async fn coaching_conflict_team_op(
         &self,
         input: CoachConflictTeamInput,
     ) -> Result<(), CoachConflictError> {
-        let coach_conflict = CoachConflictEntity::find_by_id(input.coach_conflict_id)
-            .one(&self.connection)
-            .await
-            .map_err(|e| {
-                CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
-            })?
-            .ok_or(CoachConflictError::CoachConflictNotFound(
-                input.coach_conflict_id,
-            ))?;
-
-        let team = TeamEntity::find_by_id(input.team_id)
-            .one(&self.connection)
-            .await
-            .map_err(|e| {
-                CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
-            })?
-            .ok_or(CoachConflictError::TeamNotFound(input.coach_conflict_id))?;
+        self.journaled("Edit coach conflict teams", |connection, _| {
+            Box::pin(async move {
+                let coach_conflict = CoachConflictEntity::find_by_id(input.coach_conflict_id)
+                    .one(connection)
+                    .await
+                    .map_err(|e| {
+                        CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?
+                    .ok_or(CoachConflictError::CoachConflictNotFound(
+                        input.coach_conflict_id,
+                    ))?;
 
-        if coach_conflict.region != team.region_owner {
-            return Err(CoachConflictError::RegionMismatch);
-        }
+                let team = TeamEntity::find_by_id(input.team_id)
+                    .one(connection)
+                    .await
+                    .map_err(|e| {
+                        CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?
+                    .ok_or(CoachConflictError::TeamNotFound(input.coach_conflict_id))?;
 
-        use coach_conflict_team_join as CCTJ;
+                if coach_conflict.region != team.region_owner {
+                    return Err(CoachConflictError::RegionMismatch);
+                }
 
-        let join_table_record = CCTJ::Entity::find()
-            .filter(
-                Condition::all()
-                    .add(CCTJ::Column::CoachConflict.eq(coach_conflict.id))
-                    .add(CCTJ::Column::Team.eq(team.id)),
-            )
-            .one(&self.connection)
-            .await
-            .map_err(|e| {
-                CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
-            })?;
+                use coach_conflict_team_join as CCTJ;
 
-        match input.op {
-            CoachConflictTeamInputOp::Create if join_table_record.is_none() => {
-                CCTJ::ActiveModel {
-                    coach_conflict: Set(coach_conflict.id),
-                    team: Set(team.id),
-                }
-                .insert(&self.connection)
-                .await
-                .map_err(|e| {
-                    CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
-                })?;
-            }
-            CoachConflictTeamInputOp::Delete if join_table_record.is_some() => {
-                join_table_record
-                    .unwrap()
-                    .delete(&self.connection)
+                let join_table_record = CCTJ::Entity::find()
+                    .filter(
+                        Condition::all()
+                            .add(CCTJ::Column::CoachConflict.eq(coach_conflict.id))
+                            .add(CCTJ::Column::Team.eq(team.id)),
+                    )
+                    .one(connection)
                     .await
                     .map_err(|e| {
                         CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
                     })?;
-            }
-            _ => (),
-        }
 
-        Ok(())
+                match input.op {
+                    CoachConflictTeamInputOp::Create if join_table_record.is_none() => {
+                        CCTJ::ActiveModel {
+                            coach_conflict: Set(coach_conflict.id),
+                            team: Set(team.id),
+                        }
+                        .insert(connection)
+                        .await
+                        .map_err(|e| {
+                            CoachConflictError::DatabaseError(format!(
+                                "{e} {}:{}",
+                                line!(),
+                                column!()
+                            ))
+                        })?;
+                    }
+                    CoachConflictTeamInputOp::Delete if join_table_record.is_some() => {
+                        join_table_record
+                            .unwrap()
+                            .delete(connection)
+                            .await
+                            .map_err(|e| {
+                                CoachConflictError::DatabaseError(format!(
+                                    "{e} {}:{}",
+                                    line!(),
+                                    column!()
+                                ))
+                            })?;
+                    }
+                    _ => (),
+                }
+
+                Ok(())
+            })
+        })
+        .await
     }
 
     pub async fn coaching_conflict_rename(
@@ -2656,31 +3883,36 @@ impl Client {
         id: i32,
         new_name: String,
     ) -> Result<(), CoachConflictError> {
-        let model = CoachConflictEntity::find_by_id(id)
-            .one(&self.connection)
-            .await
-            .map_err(|e| {
-                CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
-            })?
-            .ok_or(CoachConflictError::CoachConflictNotFound(id))?;
-
-        if model
-            .coach_name
-            .as_ref()
-            .is_some_and(|name| name == &new_name)
-        {
-            return Ok(());
-        }
+        self.journaled("Rename coach conflict", |connection, _| {
+            Box::pin(async move {
+                let model = CoachConflictEntity::find_by_id(id)
+                    .one(connection)
+                    .await
+                    .map_err(|e| {
+                        CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?
+                    .ok_or(CoachConflictError::CoachConflictNotFound(id))?;
+
+                if model
+                    .coach_name
+                    .as_ref()
+                    .is_some_and(|name| name == &new_name)
+                {
+                    return Ok(());
+                }
 
-        let mut active_model = model.into_active_model();
+                let mut active_model = model.into_active_model();
 
-        active_model.set(coach_conflict::Column::CoachName, new_name.into());
+                active_model.set(coach_conflict::Column::CoachName, new_name.into());
 
-        active_model.update(&self.connection).await.map_err(|e| {
-            CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
-        })?;
+                active_model.update(connection).await.map_err(|e| {
+                    CoachConflictError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                })?;
 
-        Ok(())
+                Ok(())
+            })
+        })
+        .await
     }
 
     pub async fn get_coach_conflicts(
@@ -2755,80 +3987,353 @@ impl Client {
         reservation_type_id: i32,
         is_practice: bool,
     ) -> DBResult<ReservationType> {
-        ReservationTypeEntity::update(ActiveReservationType {
-            id: Set(reservation_type_id),
-            is_practice: Set(is_practice),
-            ..Default::default()
+        self.journaled("Edit reservation type practice", |connection, _| {
+            Box::pin(async move {
+                ReservationTypeEntity::update(ActiveReservationType {
+                    id: Set(reservation_type_id),
+                    is_practice: Set(is_practice),
+                    ..Default::default()
+                })
+                .exec(connection)
+                .await
+            })
         })
-        .exec(&self.connection)
         .await
     }
 
-    pub async fn swap_schedule_games(&self, a: i32, b: i32) -> Result<bool, TimeSlotError> {
-        let game_one = ScheduleGameEntity::find_by_id(a)
-            .one(&self.connection)
-            .await
-            .map_err(|e| TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?;
+    /// `None` lets pairings of this reservation type meet again at any time.
+    pub async fn set_reservation_type_rematch_spacing(
+        &self,
+        reservation_type_id: i32,
+        rematch_spacing: Option<RematchSpacing>,
+    ) -> DBResult<ReservationType> {
+        self.journaled("Edit reservation type rematch spacing", |connection, _| {
+            Box::pin(async move {
+                let (days, rounds) = match rematch_spacing {
+                    Some(RematchSpacing::Days(days)) => (Some(days.into()), None),
+                    Some(RematchSpacing::Rounds(rounds)) => (None, Some(rounds.into())),
+                    None => (None, None),
+                };
+
+                ReservationTypeEntity::update(ActiveReservationType {
+                    id: Set(reservation_type_id),
+                    rematch_spacing_days: Set(days),
+                    rematch_spacing_rounds: Set(rounds),
+                    ..Default::default()
+                })
+                .exec(connection)
+                .await
+            })
+        })
+        .await
+    }
 
-        let game_two = ScheduleGameEntity::find_by_id(b)
-            .one(&self.connection)
-            .await
-            .map_err(|e| TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?;
+    /// Swaps the times and fields of two games of the same schedule. `None` if either
+    /// game does not exist or is a bye, in which case nothing changes.
+    pub async fn swap_schedule_games(
+        &self,
+        a: i32,
+        b: i32,
+        force: bool,
+    ) -> Result<Option<EditReport>, TimeSlotError> {
+        self.journaled("Swap schedule games", |connection, _| {
+            Box::pin(async move {
+                let game_one = ScheduleGameEntity::find_by_id(a)
+                    .one(connection)
+                    .await
+                    .map_err(|e| {
+                        TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?;
 
-        let (Some(game_one), Some(game_two)) = (game_one, game_two) else {
-            return Ok(false);
-        };
+                let game_two = ScheduleGameEntity::find_by_id(b)
+                    .one(connection)
+                    .await
+                    .map_err(|e| {
+                        TimeSlotError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?;
 
-        if let Some(field_id) = game_one.field_id {
-            Self::conflicts(
-                &self.connection,
-                field_id,
-                DateTime::<Utc>::from_str(&game_two.start).unwrap(),
-                DateTime::<Utc>::from_str(&game_two.end).unwrap(),
-                Some([game_one.id, game_two.id]),
-            )
-            .await?;
-        }
+                let (Some(game_one), Some(game_two)) = (game_one, game_two) else {
+                    return Ok(None);
+                };
+
+                if game_one.is_bye
+                    || game_two.is_bye
+                    || game_one.schedule_id != game_two.schedule_id
+                {
+                    return Ok(None);
+                }
+
+                let parse = |game: &ScheduleGame| {
+                    game.start_time()
+                        .zip(game.end_time())
+                        .ok_or(TimeSlotError::MalformedGame(game.id))
+                };
+
+                let (one_start, one_end) = parse(&game_one)?;
+                let (two_start, two_end) = parse(&game_two)?;
+
+                let report = schedule_edits::check(
+                    connection,
+                    game_one.schedule_id,
+                    &[
+                        ScheduleGameEdit::new(game_one.id, two_start, two_end, game_two.field_id),
+                        ScheduleGameEdit::new(game_two.id, one_start, one_end, game_one.field_id),
+                    ],
+                    None,
+                )
+                .await?
+                .enforce(force)?;
+
+                let game_one_clone = game_one.clone();
+
+                let mut game_one_am = game_one.into_active_model();
 
-        if let Some(field_id) = game_two.field_id {
-            Self::conflicts(
-                &self.connection,
-                field_id,
-                DateTime::<Utc>::from_str(&game_one.start).unwrap(),
-                DateTime::<Utc>::from_str(&game_one.end).unwrap(),
-                Some([game_two.id, game_one.id]),
+                game_one_am.start = Set(game_two.start);
+                game_one_am.end = Set(game_two.end);
+                game_one_am.field_id = Set(game_two.field_id);
+                game_one_am.sequence = Set(game_one_clone.sequence + 1);
+
+                let mut game_two_am = game_two.into_active_model();
+
+                game_two_am.start = Set(game_one_clone.start);
+                game_two_am.end = Set(game_one_clone.end);
+                game_two_am.field_id = Set(game_one_clone.field_id);
+                game_two_am.sequence = Set(game_two_am.sequence.as_ref() + 1);
+
+                connection
+                    .transaction(|connection| {
+                        Box::pin(async move {
+                            game_one_am.update(connection).await?;
+                            game_two_am.update(connection).await
+                        })
+                    })
+                    .await
+                    .map_err(|e| match e {
+                        TransactionError::Connection(db) | TransactionError::Transaction(db) => {
+                            TimeSlotError::DatabaseError(format!("{db} {}:{}", line!(), column!()))
+                        }
+                    })?;
+
+                Ok(Some(report))
+            })
+        })
+        .await
+    }
+
+    pub async fn create_blackout(
+        &self,
+        input: CreateBlackoutInput,
+    ) -> Result<Blackout, BlackoutError> {
+        self.journaled("Create blackout", |connection, _| {
+            Box::pin(async move {
+                input.validate()?;
+
+                ActiveBlackout {
+                    name: Set(input.name.0),
+                    region: Set(input.region_id),
+                    reservation_type: Set(input.reservation_type_id),
+                    start: Set(input.start.timestamp_millis()),
+                    end: Set(input.end.timestamp_millis()),
+                    ..Default::default()
+                }
+                .insert(connection)
+                .await
+                .map_err(|e| BlackoutError::DatabaseError(format!("{e} {}:{}", line!(), column!())))
+            })
+        })
+        .await
+    }
+
+    /// Load the blackouts that apply to a region, including those that apply to every region.
+    /// Passing [`Option#None`] loads every blackout.
+    pub async fn get_blackouts(
+        &self,
+        region_id: Option<i32>,
+    ) -> Result<Vec<Blackout>, BlackoutError> {
+        let query = if let Some(region_id) = region_id {
+            BlackoutEntity::find().filter(
+                Condition::any()
+                    .add(blackout::Column::Region.eq(region_id))
+                    .add(blackout::Column::Region.is_null()),
             )
-            .await?;
-        }
+        } else {
+            BlackoutEntity::find()
+        };
 
-        let game_one_clone = game_one.clone();
+        query
+            .order_by_asc(blackout::Column::Start)
+            .all(&self.connection)
+            .await
+            .map_err(|e| BlackoutError::DatabaseError(format!("{e} {}:{}", line!(), column!())))
+    }
 
-        let mut game_one_am = game_one.into_active_model();
+    pub async fn delete_blackout(&self, id: i32) -> Result<(), BlackoutError> {
+        self.journaled("Delete blackout", |connection, _| {
+            Box::pin(async move {
+                let maybe_deleted = BlackoutEntity::delete_by_id(id)
+                    .exec(connection)
+                    .await
+                    .map_err(|e| {
+                        BlackoutError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?;
 
-        game_one_am.start = Set(game_two.start.clone());
-        game_one_am.end = Set(game_two.end.clone());
-        game_one_am.field_id = Set(game_two.field_id);
+                if maybe_deleted.rows_affected != 1 {
+                    return Err(BlackoutError::NotFound(id));
+                }
 
-        let mut game_two_am = game_two.into_active_model();
+                Ok(())
+            })
+        })
+        .await
+    }
 
-        game_two_am.start = Set(game_one_clone.start);
-        game_two_am.end = Set(game_one_clone.end);
-        game_one_am.field_id = Set(game_one_clone.field_id);
+    /// Closes a field, and reports the time slots and games that the closure covers. The
+    /// time slots are kept, but hidden until the closure is deleted. See
+    /// [`Client::delete_closed_time_slots`] to delete them instead, and
+    /// [`Client::replace_closed_games`] to place the games somewhere else.
+    pub async fn close_field(
+        &self,
+        input: CreateFieldClosureInput,
+    ) -> Result<FieldClosureReport, FieldClosureError> {
+        self.journaled("Close field", |connection, _| {
+            Box::pin(async move {
+                input.validate()?;
+
+                FieldEntity::find_by_id(input.field_id())
+                    .one(connection)
+                    .await
+                    .map_err(|e| {
+                        FieldClosureError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?
+                    .ok_or(FieldClosureError::FieldNotFound(input.field_id()))?;
 
-        self.connection
-            .transaction(|connection| {
-                Box::pin(async move {
-                    game_one_am.update(connection).await?;
-                    game_two_am.update(connection).await
-                })
+                let closure = ActiveFieldClosure {
+                    field_id: Set(input.field_id()),
+                    start: Set(input.start().timestamp_millis()),
+                    end: Set(input.end().timestamp_millis()),
+                    reason: Set(input.reason().map(str::to_owned)),
+                    ..Default::default()
+                }
+                .insert(connection)
+                .await
+                .map_err(|e| {
+                    FieldClosureError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                })?;
+
+                field_closures::report(connection, closure)
+                    .await
+                    .map_err(|e| {
+                        FieldClosureError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })
             })
+        })
+        .await
+    }
+
+    /// Load the closures of a field. Passing [`Option#None`] loads every closure.
+    pub async fn get_field_closures(
+        &self,
+        field_id: Option<i32>,
+    ) -> Result<Vec<FieldClosure>, FieldClosureError> {
+        let query = if let Some(field_id) = field_id {
+            FieldClosureEntity::find().filter(field_closure::Column::FieldId.eq(field_id))
+        } else {
+            FieldClosureEntity::find()
+        };
+
+        query
+            .order_by_asc(field_closure::Column::Start)
+            .all(&self.connection)
             .await
-            .map_err(|e| match e {
-                TransactionError::Connection(db) | TransactionError::Transaction(db) => {
-                    TimeSlotError::DatabaseError(format!("{db} {}:{}", line!(), column!()))
+            .map_err(|e| FieldClosureError::DatabaseError(format!("{e} {}:{}", line!(), column!())))
+    }
+
+    /// The time slots and games that a closure covers right now.
+    pub async fn get_field_closure_report(
+        &self,
+        id: i32,
+    ) -> Result<FieldClosureReport, FieldClosureError> {
+        let closure = FieldClosureEntity::find_by_id(id)
+            .one(&self.connection)
+            .await
+            .map_err(|e| {
+                FieldClosureError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+            })?
+            .ok_or(FieldClosureError::NotFound(id))?;
+
+        field_closures::report(&self.connection, closure)
+            .await
+            .map_err(|e| FieldClosureError::DatabaseError(format!("{e} {}:{}", line!(), column!())))
+    }
+
+    /// Deletes the time slots that a closure covers, instead of keeping them hidden.
+    /// Returns how many were deleted.
+    pub async fn delete_closed_time_slots(&self, id: i32) -> Result<u64, FieldClosureError> {
+        self.journaled("Delete closed time slots", |connection, _| {
+            Box::pin(async move {
+                let closure = FieldClosureEntity::find_by_id(id)
+                    .one(connection)
+                    .await
+                    .map_err(|e| {
+                        FieldClosureError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?
+                    .ok_or(FieldClosureError::NotFound(id))?;
+
+                let time_slot_ids = field_closures::report(connection, closure)
+                    .await
+                    .map_err(|e| {
+                        FieldClosureError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?
+                    .time_slots()
+                    .iter()
+                    .map(|time_slot_ext| time_slot_ext.time_slot.id)
+                    .collect_vec();
+
+                connection
+                    .transaction(|connection| {
+                        Box::pin(async move {
+                            entity::reservation_type_time_slot_join::Entity::delete_many()
+                                .filter(
+                                    entity::reservation_type_time_slot_join::Column::TimeSlot
+                                        .is_in(time_slot_ids.clone()),
+                                )
+                                .exec(connection)
+                                .await?;
+
+                            TimeSlotEntity::delete_many()
+                                .filter(time_slot::Column::Id.is_in(time_slot_ids))
+                                .exec(connection)
+                                .await
+                                .map(|result| result.rows_affected)
+                        })
+                    })
+                    .await
+                    .map_err(|e| {
+                        FieldClosureError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })
+            })
+        })
+        .await
+    }
+
+    /// Reopens the field. Time slots that the closure hid are available again.
+    pub async fn delete_field_closure(&self, id: i32) -> Result<(), FieldClosureError> {
+        self.journaled("Delete field closure", |connection, _| {
+            Box::pin(async move {
+                let maybe_deleted = FieldClosureEntity::delete_by_id(id)
+                    .exec(connection)
+                    .await
+                    .map_err(|e| {
+                        FieldClosureError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+                    })?;
+
+                if maybe_deleted.rows_affected != 1 {
+                    return Err(FieldClosureError::NotFound(id));
                 }
-            })?;
 
-        Ok(true)
+                Ok(())
+            })
+        })
+        .await
     }
 }
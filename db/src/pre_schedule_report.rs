@@ -17,8 +17,8 @@ use sea_orm::{
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    select_time_slot_extension, PreScheduleReportError, TargetExtension, TimeSlotExtension,
-    TimeSlotSelectionTypeAggregate,
+    select_time_slot_extension, BlackoutFilter, BlackoutImpact, ClosureFilter,
+    PreScheduleReportError, TargetExtension, TimeSlotExtension, TimeSlotSelectionTypeAggregate,
 };
 
 use crate::entity_local_exports::{FieldEntity, TargetEntity, TeamEntity, TeamGroup};
@@ -203,6 +203,7 @@ pub struct PreScheduleReport {
     total_matches_required: u64,
     total_matches_supplied: u64,
     interregional: bool,
+    blackout_impact: Vec<BlackoutImpact>,
 }
 
 fn ncr(n: u64, r: u64) -> u64 {
@@ -231,21 +232,86 @@ pub struct PreScheduleReportInput {
     total_matches_supplied: Option<u64>,
 }
 
+impl PreScheduleReportInput {
+    pub const fn new(matches_to_play: NonZeroU8, interregional: bool) -> Self {
+        Self {
+            matches_to_play,
+            interregional,
+            total_matches_supplied: None,
+        }
+    }
+}
+
+impl Display for PreScheduleReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} matches required, {} supplied ({})",
+            self.total_matches_required,
+            self.total_matches_supplied,
+            if self.interregional {
+                "interregional"
+            } else {
+                "regional"
+            },
+        )?;
+
+        for entry in &self.target_match_count {
+            let groups = entry
+                .target
+                .groups
+                .iter()
+                .map(|group| group.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            writeln!(
+                f,
+                "{} target {} [{groups}]: requires {}, supplied {}",
+                if entry.supplied.satisfies(&entry.required) {
+                    "ok  "
+                } else {
+                    "WARN"
+                },
+                entry.target.target.id,
+                entry.required,
+                entry.supplied,
+            )?;
+        }
+
+        if !self.target_has_duplicates.is_empty() {
+            writeln!(
+                f,
+                "WARN targets {:?} use the same groups",
+                self.target_has_duplicates
+            )?;
+        }
+
+        for impact in &self.blackout_impact {
+            writeln!(f, "     {impact}")?;
+        }
+
+        Ok(())
+    }
+}
+
 impl PreScheduleReport {
     /// Build a PreScheduleReport from its components
     ///
     /// # Parameters
     /// - `target_duplicates`: A list of bundled team & group data to guide the processor
     /// - `all_targets`: A list of all targets and extended data
-    /// - `all_time_slots`: A list of all time slots and extended data
+    /// - `all_time_slots`: A list of all time slots and extended data, excluding blacked-out slots
     /// - `region_id_from_field_id`: Dependency injection to delegate region lookup responsibility to the caller
     /// - `input`: The input payload given by the client  
+    /// - `blackout_impact`: The capacity removed by each blackout
     pub fn new(
         target_duplicates: Vec<DuplicateEntry>,
         all_targets: &[TargetExtension],
         all_time_slots: &[TimeSlotExtension],
         region_id_from_field_id: impl Fn(i32) -> i32,
         input: PreScheduleReportInput,
+        blackout_impact: Vec<BlackoutImpact>,
     ) -> Self {
         let target_has_duplicates = target_duplicates
             .iter()
@@ -423,6 +489,7 @@ impl PreScheduleReport {
             total_matches_required: total_matches_required * input.matches_to_play.get() as u64,
             total_matches_supplied,
             interregional: input.interregional,
+            blackout_impact,
         }
     }
 
@@ -519,6 +586,22 @@ impl PreScheduleReport {
                 PreScheduleReportError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
             })?;
 
+        let blackouts = BlackoutFilter::load(connection).await.map_err(|e| {
+            PreScheduleReportError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+        })?;
+
+        let closures = ClosureFilter::load(connection).await.map_err(|e| {
+            PreScheduleReportError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+        })?;
+
+        // closed time slots are not capacity, and not something a blackout took away either
+        let all_time_slots = all_time_slots
+            .into_iter()
+            .filter(|time_slot_ext| !closures.is_closed(time_slot_ext))
+            .collect();
+
+        let (all_time_slots, blackout_impact) = blackouts.partition(all_time_slots);
+
         if input.total_matches_supplied.is_none() {
             let result: u64 = all_time_slots
                 .iter()
@@ -551,6 +634,7 @@ impl PreScheduleReport {
             &all_time_slots,
             |field_id| field_to_region[&field_id],
             input,
+            blackout_impact,
         ))
     }
 }
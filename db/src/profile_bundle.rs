@@ -0,0 +1,735 @@
+//! Portable copies of a whole profile, to hand a league setup to another admin or to
+//! carry it across Fieldz versions.
+//!
+//! A [`ProfileBundle`] holds every row of every table, as JSON. Ids are only meaningful
+//! inside the bundle: importing inserts every row with a fresh id and rewrites the
+//! references to it, so a bundle can be imported into any empty profile.
+//!
+//! The rows are the entity models of the build that wrote the bundle. When a column is
+//! added or changed, bump [`PROFILE_BUNDLE_VERSION`] and add a step to [`upgrade`] that
+//! rewrites bundles of the previous version, so that older bundles keep importing.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use entity::{
+    coach_conflict_team_join, reservation_type_field_size_join, reservation_type_time_slot_join,
+    schedule_revision, schedule_revision_game, target_group_join, team_group_join,
+};
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel, NotSet, Set,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::entity_local_exports::{
+    Blackout, BlackoutEntity, CoachConflictEntity, CoachConflictModel, Field, FieldClosure,
+    FieldClosureEntity, FieldEntity, Region, RegionEntity, ReservationType, ReservationTypeEntity,
+    Schedule, ScheduleEntity, ScheduleGame, ScheduleGameEntity, Target, TargetEntity, Team,
+    TeamEntity, TeamGroup, TeamGroupEntity, TimeSlot, TimeSlotEntity,
+};
+use crate::errors::ProfileBundleError;
+
+/// Bump this when the format changes, and teach [`upgrade`] to read the older version.
+pub const PROFILE_BUNDLE_VERSION: u32 = 5;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileBundle {
+    version: u32,
+    /// RFC 3339
+    exported: String,
+    regions: Vec<Region>,
+    fields: Vec<Field>,
+    teams: Vec<Team>,
+    groups: Vec<TeamGroup>,
+    team_groups: Vec<team_group_join::Model>,
+    reservation_types: Vec<ReservationType>,
+    field_sizes: Vec<reservation_type_field_size_join::Model>,
+    time_slots: Vec<TimeSlot>,
+    time_slot_types: Vec<reservation_type_time_slot_join::Model>,
+    targets: Vec<Target>,
+    target_groups: Vec<target_group_join::Model>,
+    coach_conflicts: Vec<CoachConflictModel>,
+    coach_conflict_teams: Vec<coach_conflict_team_join::Model>,
+    blackouts: Vec<Blackout>,
+    field_closures: Vec<FieldClosure>,
+    schedules: Vec<Schedule>,
+    schedule_games: Vec<ScheduleGame>,
+    schedule_revisions: Vec<schedule_revision::Model>,
+    schedule_revision_games: Vec<schedule_revision_game::Model>,
+}
+
+/// How many rows of each kind an import created.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileImportReport {
+    regions: usize,
+    fields: usize,
+    teams: usize,
+    groups: usize,
+    reservation_types: usize,
+    time_slots: usize,
+    targets: usize,
+    coach_conflicts: usize,
+    blackouts: usize,
+    field_closures: usize,
+    schedules: usize,
+    schedule_games: usize,
+    schedule_revisions: usize,
+}
+
+impl Display for ProfileImportReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Regions: {}", self.regions)?;
+        writeln!(f, "Fields: {}", self.fields)?;
+        writeln!(f, "Teams: {}", self.teams)?;
+        writeln!(f, "Groups: {}", self.groups)?;
+        writeln!(f, "Reservation types: {}", self.reservation_types)?;
+        writeln!(f, "Time slots: {}", self.time_slots)?;
+        writeln!(f, "Targets: {}", self.targets)?;
+        writeln!(f, "Coach conflicts: {}", self.coach_conflicts)?;
+        writeln!(f, "Blackouts: {}", self.blackouts)?;
+        writeln!(f, "Field closures: {}", self.field_closures)?;
+        writeln!(f, "Schedules: {}", self.schedules)?;
+        writeln!(f, "Schedule games: {}", self.schedule_games)?;
+        writeln!(f, "Schedule revisions: {}", self.schedule_revisions)
+    }
+}
+
+/// Only the version is read first, so that an older bundle can be upgraded before the
+/// rest of it is parsed.
+#[derive(Deserialize)]
+struct Versioned {
+    version: u32,
+}
+
+/// Rewrites a bundle of any supported version into the shape of [`PROFILE_BUNDLE_VERSION`],
+/// one version at a time.
+fn upgrade(bundle: serde_json::Value) -> Result<serde_json::Value, ProfileBundleError> {
+    let Versioned { version } = serde_json::from_value(bundle.clone())
+        .map_err(|e| ProfileBundleError::Malformed(e.to_string()))?;
+
+    match version {
+        PROFILE_BUNDLE_VERSION => Ok(bundle),
+        // add a step for every version bump here
+        1 => upgrade(from_v1(bundle)),
+        2 => upgrade(from_v2(bundle)?),
+        3 => upgrade(from_v3(bundle)?),
+        4 => upgrade(from_v4(bundle)?),
+        _ => Err(ProfileBundleError::UnsupportedVersion(version)),
+    }
+}
+
+/// Version 2 added results to schedule games.
+fn from_v1(mut bundle: serde_json::Value) -> serde_json::Value {
+    if let Some(games) = bundle
+        .get_mut("schedule_games")
+        .and_then(serde_json::Value::as_array_mut)
+    {
+        for game in games
+            .iter_mut()
+            .filter_map(serde_json::Value::as_object_mut)
+        {
+            game.insert("status".to_owned(), "scheduled".into());
+            game.insert("home_score".to_owned(), serde_json::Value::Null);
+            game.insert("away_score".to_owned(), serde_json::Value::Null);
+        }
+    }
+
+    bundle["version"] = 2.into();
+    bundle
+}
+
+/// Rewrites the RFC 3339 `columns` of every row of `tables` as UNIX milliseconds.
+fn text_to_millis(
+    bundle: &mut serde_json::Value,
+    tables: &[&str],
+    columns: &[&str],
+) -> Result<(), ProfileBundleError> {
+    for table in tables {
+        let Some(rows) = bundle
+            .get_mut(table)
+            .and_then(serde_json::Value::as_array_mut)
+        else {
+            continue;
+        };
+
+        for row in rows.iter_mut().filter_map(serde_json::Value::as_object_mut) {
+            for &column in columns {
+                let Some(value) = row.get(column).and_then(serde_json::Value::as_str) else {
+                    continue;
+                };
+
+                let millis = DateTime::parse_from_rfc3339(value)
+                    .map_err(|e| ProfileBundleError::Malformed(format!("{table}: `{value}`: {e}")))?
+                    .timestamp_millis();
+
+                row.insert(column.to_owned(), millis.into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Version 3 stores the start and end of time slots and schedule games as UNIX
+/// milliseconds instead of RFC 3339 text.
+fn from_v2(mut bundle: serde_json::Value) -> Result<serde_json::Value, ProfileBundleError> {
+    text_to_millis(
+        &mut bundle,
+        &["time_slots", "schedule_games"],
+        &["start", "end"],
+    )?;
+
+    bundle["version"] = 3.into();
+    Ok(bundle)
+}
+
+/// Version 4 added rematch spacing to reservation types, field closures and schedule
+/// revisions, and stores the start and end of blackouts as UNIX milliseconds too.
+fn from_v3(mut bundle: serde_json::Value) -> Result<serde_json::Value, ProfileBundleError> {
+    if let Some(reservation_types) = bundle
+        .get_mut("reservation_types")
+        .and_then(serde_json::Value::as_array_mut)
+    {
+        for reservation_type in reservation_types
+            .iter_mut()
+            .filter_map(serde_json::Value::as_object_mut)
+        {
+            reservation_type.insert("rematch_spacing_days".to_owned(), serde_json::Value::Null);
+            reservation_type.insert("rematch_spacing_rounds".to_owned(), serde_json::Value::Null);
+        }
+    }
+
+    text_to_millis(&mut bundle, &["blackouts"], &["start", "end"])?;
+
+    for table in [
+        "field_closures",
+        "schedule_revisions",
+        "schedule_revision_games",
+    ] {
+        bundle[table] = serde_json::Value::Array(vec![]);
+    }
+
+    bundle["version"] = 4.into();
+    Ok(bundle)
+}
+
+/// Version 5 stores the times of schedule revisions as UNIX milliseconds too.
+fn from_v4(mut bundle: serde_json::Value) -> Result<serde_json::Value, ProfileBundleError> {
+    text_to_millis(&mut bundle, &["schedule_revisions"], &["created"])?;
+    text_to_millis(&mut bundle, &["schedule_revision_games"], &["start", "end"])?;
+
+    bundle["version"] = 5.into();
+    Ok(bundle)
+}
+
+impl ProfileBundle {
+    pub fn to_json(&self) -> Result<String, ProfileBundleError> {
+        serde_json::to_string_pretty(self).map_err(|e| ProfileBundleError::Malformed(e.to_string()))
+    }
+
+    /// Reads a bundle written by this or an older build.
+    pub fn from_json(json: &str) -> Result<Self, ProfileBundleError> {
+        let bundle =
+            serde_json::from_str(json).map_err(|e| ProfileBundleError::Malformed(e.to_string()))?;
+
+        serde_json::from_value(upgrade(bundle)?)
+            .map_err(|e| ProfileBundleError::Malformed(e.to_string()))
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+pub(crate) async fn export<C>(connection: &C) -> Result<ProfileBundle, ProfileBundleError>
+where
+    C: ConnectionTrait,
+{
+    Ok(ProfileBundle {
+        version: PROFILE_BUNDLE_VERSION,
+        exported: Utc::now().to_rfc3339(),
+        regions: RegionEntity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        fields: FieldEntity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        teams: TeamEntity::find().all(connection).await.map_err(db_error)?,
+        groups: TeamGroupEntity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        team_groups: team_group_join::Entity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        reservation_types: ReservationTypeEntity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        field_sizes: reservation_type_field_size_join::Entity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        time_slots: TimeSlotEntity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        time_slot_types: reservation_type_time_slot_join::Entity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        targets: TargetEntity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        target_groups: target_group_join::Entity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        coach_conflicts: CoachConflictEntity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        coach_conflict_teams: coach_conflict_team_join::Entity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        blackouts: BlackoutEntity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        field_closures: FieldClosureEntity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        schedules: ScheduleEntity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        schedule_games: ScheduleGameEntity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        schedule_revisions: schedule_revision::Entity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+        schedule_revision_games: schedule_revision_game::Entity::find()
+            .all(connection)
+            .await
+            .map_err(db_error)?,
+    })
+}
+
+fn db_error(e: DbErr) -> ProfileBundleError {
+    ProfileBundleError::DatabaseError(e.to_string())
+}
+
+/// Bundle ids to the ids of the rows that were created for them.
+struct IdMap {
+    table: &'static str,
+    ids: HashMap<i32, i32>,
+}
+
+impl IdMap {
+    fn new(table: &'static str) -> Self {
+        Self {
+            table,
+            ids: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, old: i32, new: i32) {
+        self.ids.insert(old, new);
+    }
+
+    fn get(&self, old: i32) -> Result<i32, ProfileBundleError> {
+        self.ids
+            .get(&old)
+            .copied()
+            .ok_or(ProfileBundleError::MissingReference {
+                table: self.table.to_owned(),
+                id: old,
+            })
+    }
+
+    fn get_optional(&self, old: Option<i32>) -> Result<Option<i32>, ProfileBundleError> {
+        old.map(|old| self.get(old)).transpose()
+    }
+
+    /// For references that are not foreign keys, such as those of revisions, which may
+    /// point at rows that were deleted since. Those become negative, so that they never
+    /// match a row that is created later.
+    fn get_or_negated(&self, old: i32) -> i32 {
+        self.ids.get(&old).copied().unwrap_or(-old.abs())
+    }
+}
+
+/// Inserts every row of `bundle`. Run this in a transaction, since a bundle with a
+/// missing reference fails halfway through.
+pub(crate) async fn import<C>(
+    connection: &C,
+    bundle: ProfileBundle,
+) -> Result<ProfileImportReport, ProfileBundleError>
+where
+    C: ConnectionTrait,
+{
+    if RegionEntity::find()
+        .one(connection)
+        .await
+        .map_err(db_error)?
+        .is_some()
+        || ReservationTypeEntity::find()
+            .one(connection)
+            .await
+            .map_err(db_error)?
+            .is_some()
+    {
+        return Err(ProfileBundleError::NotEmpty);
+    }
+
+    let mut report = ProfileImportReport::default();
+
+    let mut regions = IdMap::new("region");
+    for region in bundle.regions {
+        let old = region.id;
+        let mut active = region.into_active_model();
+        active.id = NotSet;
+        regions.insert(old, active.insert(connection).await.map_err(db_error)?.id);
+        report.regions += 1;
+    }
+
+    let mut reservation_types = IdMap::new("reservation_type");
+    for reservation_type in bundle.reservation_types {
+        let old = reservation_type.id;
+        let mut active = reservation_type.into_active_model();
+        active.id = NotSet;
+        reservation_types.insert(old, active.insert(connection).await.map_err(db_error)?.id);
+        report.reservation_types += 1;
+    }
+
+    let mut groups = IdMap::new("team_group");
+    for group in bundle.groups {
+        let old = group.id;
+        let mut active = group.into_active_model();
+        active.id = NotSet;
+        groups.insert(old, active.insert(connection).await.map_err(db_error)?.id);
+        report.groups += 1;
+    }
+
+    let mut fields = IdMap::new("field");
+    for field in bundle.fields {
+        let old = field.id;
+        let mut active = field.into_active_model();
+        active.id = NotSet;
+        active.region_owner = Set(regions.get(*active.region_owner.as_ref())?);
+        fields.insert(old, active.insert(connection).await.map_err(db_error)?.id);
+        report.fields += 1;
+    }
+
+    let mut teams = IdMap::new("team");
+    for team in bundle.teams {
+        let old = team.id;
+        let mut active = team.into_active_model();
+        active.id = NotSet;
+        active.region_owner = Set(regions.get(*active.region_owner.as_ref())?);
+        teams.insert(old, active.insert(connection).await.map_err(db_error)?.id);
+        report.teams += 1;
+    }
+
+    for join in bundle.team_groups {
+        team_group_join::ActiveModel {
+            team: Set(teams.get(join.team)?),
+            group: Set(groups.get(join.group)?),
+        }
+        .insert(connection)
+        .await
+        .map_err(db_error)?;
+    }
+
+    for join in bundle.field_sizes {
+        reservation_type_field_size_join::ActiveModel {
+            field: Set(fields.get(join.field)?),
+            reservation_type: Set(reservation_types.get(join.reservation_type)?),
+            size: Set(join.size),
+        }
+        .insert(connection)
+        .await
+        .map_err(db_error)?;
+    }
+
+    let mut time_slots = IdMap::new("time_slot");
+    for time_slot in bundle.time_slots {
+        let old = time_slot.id;
+        let mut active = time_slot.into_active_model();
+        active.id = NotSet;
+        active.field_id = Set(fields.get(*active.field_id.as_ref())?);
+        time_slots.insert(old, active.insert(connection).await.map_err(db_error)?.id);
+        report.time_slots += 1;
+    }
+
+    for join in bundle.time_slot_types {
+        reservation_type_time_slot_join::ActiveModel {
+            time_slot: Set(time_slots.get(join.time_slot)?),
+            reservation_type: Set(reservation_types.get(join.reservation_type)?),
+        }
+        .insert(connection)
+        .await
+        .map_err(db_error)?;
+    }
+
+    let mut targets = IdMap::new("target");
+    for target in bundle.targets {
+        let old = target.id;
+        let mut active = target.into_active_model();
+        active.id = NotSet;
+        active.maybe_reservation_type =
+            Set(reservation_types.get_optional(*active.maybe_reservation_type.as_ref())?);
+        targets.insert(old, active.insert(connection).await.map_err(db_error)?.id);
+        report.targets += 1;
+    }
+
+    for join in bundle.target_groups {
+        target_group_join::ActiveModel {
+            target: Set(targets.get(join.target)?),
+            group: Set(groups.get(join.group)?),
+        }
+        .insert(connection)
+        .await
+        .map_err(db_error)?;
+    }
+
+    let mut coach_conflicts = IdMap::new("coach_conflict");
+    for coach_conflict in bundle.coach_conflicts {
+        let old = coach_conflict.id;
+        let mut active = coach_conflict.into_active_model();
+        active.id = NotSet;
+        active.region = Set(regions.get(*active.region.as_ref())?);
+        coach_conflicts.insert(old, active.insert(connection).await.map_err(db_error)?.id);
+        report.coach_conflicts += 1;
+    }
+
+    for join in bundle.coach_conflict_teams {
+        coach_conflict_team_join::ActiveModel {
+            coach_conflict: Set(coach_conflicts.get(join.coach_conflict)?),
+            team: Set(teams.get(join.team)?),
+        }
+        .insert(connection)
+        .await
+        .map_err(db_error)?;
+    }
+
+    for blackout in bundle.blackouts {
+        let mut active = blackout.into_active_model();
+        active.id = NotSet;
+        active.region = Set(regions.get_optional(*active.region.as_ref())?);
+        active.reservation_type =
+            Set(reservation_types.get_optional(*active.reservation_type.as_ref())?);
+        active.insert(connection).await.map_err(db_error)?;
+        report.blackouts += 1;
+    }
+
+    for closure in bundle.field_closures {
+        let mut active = closure.into_active_model();
+        active.id = NotSet;
+        active.field_id = Set(fields.get(*active.field_id.as_ref())?);
+        active.insert(connection).await.map_err(db_error)?;
+        report.field_closures += 1;
+    }
+
+    let mut schedules = IdMap::new("schedule");
+    for schedule in bundle.schedules {
+        let old = schedule.id;
+        let mut active = schedule.into_active_model();
+        active.id = NotSet;
+        schedules.insert(old, active.insert(connection).await.map_err(db_error)?.id);
+        report.schedules += 1;
+    }
+
+    let mut games = IdMap::new("schedule_game");
+    for game in bundle.schedule_games {
+        let old = game.id;
+        let mut active = game.into_active_model();
+        active.id = NotSet;
+        active.schedule_id = Set(schedules.get(*active.schedule_id.as_ref())?);
+        active.team_one = Set(teams.get_optional(*active.team_one.as_ref())?);
+        active.team_two = Set(teams.get_optional(*active.team_two.as_ref())?);
+        active.field_id = Set(fields.get_optional(*active.field_id.as_ref())?);
+        games.insert(old, active.insert(connection).await.map_err(db_error)?.id);
+        report.schedule_games += 1;
+    }
+
+    let mut revisions = IdMap::new("schedule_revision");
+    for revision in bundle.schedule_revisions {
+        let old = revision.id;
+        let mut active = revision.into_active_model();
+        active.id = NotSet;
+        active.schedule_id = Set(schedules.get(*active.schedule_id.as_ref())?);
+        revisions.insert(old, active.insert(connection).await.map_err(db_error)?.id);
+        report.schedule_revisions += 1;
+    }
+
+    for game in bundle.schedule_revision_games {
+        let mut active = game.into_active_model();
+        active.id = NotSet;
+        active.revision_id = Set(revisions.get(*active.revision_id.as_ref())?);
+        active.game_id = Set(games.get_or_negated(*active.game_id.as_ref()));
+        active.team_one = Set(active.team_one.as_ref().map(|id| teams.get_or_negated(id)));
+        active.team_two = Set(active.team_two.as_ref().map(|id| teams.get_or_negated(id)));
+        active.field_id = Set(active.field_id.as_ref().map(|id| fields.get_or_negated(id)));
+        active.insert(connection).await.map_err(db_error)?;
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProfileBundle, ProfileImportReport};
+    use crate::errors::ProfileBundleError;
+    use crate::fixtures::{client, field, region, time_slot};
+    use crate::{
+        Client, CoachConflictTeamInput, CoachConflictTeamInputOp, CreateCoachConflictInput,
+        CreateTeamInput,
+    };
+
+    /// Every team with its groups, and every coach with their teams, by name.
+    async fn league(client: &Client) -> (Vec<(String, Vec<String>)>, Vec<Vec<String>>) {
+        let region = client.get_regions().await.unwrap()[0].id;
+
+        let teams = client
+            .get_teams_with_tags(region)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|team_ext| {
+                let tags = team_ext.tags.iter().map(|tag| tag.name.clone()).collect();
+                (team_ext.team.name, tags)
+            })
+            .collect();
+
+        let coaches = client
+            .get_coach_conflicts(region)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|coach_conflict| {
+                coach_conflict
+                    .teams
+                    .into_iter()
+                    .map(|team| team.name)
+                    .collect()
+            })
+            .collect();
+
+        (teams, coaches)
+    }
+
+    #[tokio::test]
+    async fn round_trips_through_json() {
+        let source = client().await;
+        let region = region(&source).await;
+        let field = field(&source, region).await;
+        time_slot(&source, field, 9).await;
+
+        source.create_group("U10".to_owned()).await.unwrap();
+        let mut teams = vec![];
+        for name in ["Rockies", "Purple Dragons"] {
+            let team = source
+                .create_team(CreateTeamInput::new(
+                    name,
+                    region,
+                    vec!["U10".to_owned()],
+                    None,
+                ))
+                .await
+                .unwrap();
+            teams.push(team.team.id);
+        }
+
+        let coach_conflict = source
+            .create_coaching_conflict(CreateCoachConflictInput {
+                region_id: region,
+                coach_name: Some("Sam Lee".to_owned()),
+            })
+            .await
+            .unwrap();
+        for team_id in teams {
+            source
+                .coaching_conflict_team_op(CoachConflictTeamInput {
+                    coach_conflict_id: coach_conflict.id,
+                    team_id,
+                    op: CoachConflictTeamInputOp::Create,
+                })
+                .await
+                .unwrap();
+        }
+
+        let bundle = source.export_profile_bundle().await.unwrap();
+        let json = bundle.to_json().unwrap();
+        assert_eq!(ProfileBundle::from_json(&json).unwrap(), bundle);
+
+        let target = client().await;
+        let report = target
+            .import_profile_bundle(ProfileBundle::from_json(&json).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            report,
+            ProfileImportReport {
+                regions: 1,
+                fields: 1,
+                teams: 2,
+                groups: 1,
+                reservation_types: 1,
+                time_slots: 1,
+                coach_conflicts: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(league(&target).await, league(&source).await);
+        assert_eq!(
+            target.get_time_slots(field.0).await.unwrap().len(),
+            source.get_time_slots(field.0).await.unwrap().len()
+        );
+
+        // only an empty profile takes a bundle
+        assert!(matches!(
+            target.import_profile_bundle(bundle).await,
+            Err(ProfileBundleError::NotEmpty)
+        ));
+    }
+
+    #[test]
+    fn upgrades_revision_times_to_millis() {
+        let bundle = serde_json::json!({
+            "version": 4,
+            "schedule_revisions": [{ "created": "2024-06-01T12:30:00+00:00" }],
+            "schedule_revision_games": [{
+                "start": "2024-06-01T10:00:00-07:00",
+                "end": "2024-06-01T11:00:00-07:00",
+            }],
+        });
+
+        let bundle = super::upgrade(bundle).unwrap();
+
+        assert_eq!(bundle["version"], super::PROFILE_BUNDLE_VERSION);
+        assert_eq!(
+            bundle["schedule_revisions"][0]["created"],
+            1_717_245_000_000_i64
+        );
+        assert_eq!(
+            bundle["schedule_revision_games"][0]["start"],
+            1_717_261_200_000_i64
+        );
+        assert_eq!(
+            bundle["schedule_revision_games"][0]["end"],
+            1_717_264_800_000_i64
+        );
+    }
+}
@@ -0,0 +1,512 @@
+//! Rainouts: calling off the games on some fields for whole days, and finding makeups for
+//! them in the availability that is left.
+//!
+//! Makeups are only proposed. Nothing moves until they are applied, which checks them like
+//! any other edit, see [`schedule_edits`](crate::schedule_edits). The games of a
+//! [closed field](crate::field_closures) are called off the same way.
+
+use std::collections::{BTreeSet, HashMap};
+
+use backend::makeups::{place_makeups, FixedGame, MakeupGame, MakeupSlot};
+use backend::{AvailabilityWindow, Tz};
+use chrono::{serde::ts_milliseconds, DateTime, NaiveDate, Utc};
+use itertools::Itertools;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, ModelTrait, QueryFilter, QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::entity_local_exports::{ScheduleEntity, ScheduleGame, ScheduleGameEntity};
+use crate::errors::RainoutError;
+use crate::schedule_edits::RuleContext;
+use crate::time_zones::field_time_zone;
+use crate::timestamps::Timespan;
+use crate::{
+    field, schedule_game, select_time_slot_extension, FieldEntity, GameStatus, TimeSlotExtension,
+    TimeSlotSelectionTypeAggregate,
+};
+
+/// Calls off every game of `schedule_id` on `field_ids` during `dates`, which are calendar
+/// days in the time zone of each field's region.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RainoutInput {
+    schedule_id: i32,
+    field_ids: Vec<i32>,
+    dates: Vec<NaiveDate>,
+}
+
+impl RainoutInput {
+    pub fn new(schedule_id: i32, field_ids: Vec<i32>, dates: Vec<NaiveDate>) -> Self {
+        Self {
+            schedule_id,
+            field_ids,
+            dates,
+        }
+    }
+}
+
+/// Where a cancelled game could be made up.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MakeupProposal {
+    game_id: i32,
+    field_id: i32,
+    #[serde(with = "ts_milliseconds")]
+    start: DateTime<Utc>,
+    #[serde(with = "ts_milliseconds")]
+    end: DateTime<Utc>,
+}
+
+impl MakeupProposal {
+    pub fn new(game_id: i32, field_id: i32, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self {
+            game_id,
+            field_id,
+            start,
+            end,
+        }
+    }
+
+    pub fn game_id(&self) -> i32 {
+        self.game_id
+    }
+
+    pub fn field_id(&self) -> i32 {
+        self.field_id
+    }
+
+    pub fn start(&self) -> DateTime<Utc> {
+        self.start
+    }
+
+    pub fn end(&self) -> DateTime<Utc> {
+        self.end
+    }
+}
+
+/// Games that were called off, and where they could be made up.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rainout {
+    cancelled: Vec<ScheduleGame>,
+    proposals: Vec<MakeupProposal>,
+    /// Cancelled games that did not fit anywhere
+    unplaced: Vec<i32>,
+}
+
+impl Rainout {
+    pub fn cancelled(&self) -> &[ScheduleGame] {
+        &self.cancelled
+    }
+
+    pub fn proposals(&self) -> &[MakeupProposal] {
+        &self.proposals
+    }
+
+    pub fn unplaced(&self) -> &[i32] {
+        &self.unplaced
+    }
+}
+
+fn db_error(e: DbErr) -> RainoutError {
+    RainoutError::DatabaseError(e.to_string())
+}
+
+fn window_of(game: &ScheduleGame) -> Result<AvailabilityWindow, RainoutError> {
+    game.window().ok_or(RainoutError::MalformedGame(game.id))
+}
+
+/// Memoized [`field_time_zone`].
+struct TimeZones(HashMap<i32, Tz>);
+
+impl TimeZones {
+    async fn get<C>(&mut self, connection: &C, field_id: i32) -> Result<Tz, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if let Some(time_zone) = self.0.get(&field_id) {
+            return Ok(*time_zone);
+        }
+
+        let time_zone = field_time_zone(connection, field_id).await?;
+        self.0.insert(field_id, time_zone);
+
+        Ok(time_zone)
+    }
+}
+
+/// Cancels the games of the rainout and proposes makeups for them. Makeups start after
+/// `not_before`, and never on a field that is rained out on that day.
+pub(crate) async fn rain_out<C>(
+    connection: &C,
+    input: RainoutInput,
+    not_before: DateTime<Utc>,
+) -> Result<Rainout, RainoutError>
+where
+    C: ConnectionTrait,
+{
+    let closed = input
+        .field_ids
+        .iter()
+        .flat_map(|field_id| input.dates.iter().map(move |date| (*field_id, *date)))
+        .collect::<BTreeSet<_>>();
+
+    call_off(
+        connection,
+        input.schedule_id,
+        |field_id, window, time_zone| closed.contains(&(field_id, window.local_date(time_zone))),
+        not_before,
+    )
+    .await
+}
+
+/// Cancels the scheduled games of `schedule_id` that `is_closed` returns `true` for, given
+/// their field, window and the time zone of the field, and proposes makeups for them in
+/// time slots that are not closed either.
+pub(crate) async fn call_off<C, F>(
+    connection: &C,
+    schedule_id: i32,
+    is_closed: F,
+    not_before: DateTime<Utc>,
+) -> Result<Rainout, RainoutError>
+where
+    C: ConnectionTrait,
+    F: Fn(i32, &AvailabilityWindow, &Tz) -> bool,
+{
+    let schedule = ScheduleEntity::find_by_id(schedule_id)
+        .one(connection)
+        .await
+        .map_err(db_error)?
+        .ok_or(RainoutError::ScheduleNotFound(schedule_id))?;
+
+    let games = schedule
+        .find_related(ScheduleGameEntity)
+        .all(connection)
+        .await
+        .map_err(db_error)?;
+
+    let mut time_zones = TimeZones(HashMap::new());
+    let mut cancelled = vec![];
+
+    for game in &games {
+        let Some(field_id) = game.field_id else {
+            continue;
+        };
+
+        if game.is_bye || GameStatus::of(game) != GameStatus::Scheduled {
+            continue;
+        }
+
+        let time_zone = time_zones
+            .get(connection, field_id)
+            .await
+            .map_err(db_error)?;
+
+        if is_closed(field_id, &window_of(game)?, &time_zone) {
+            cancelled.push(game.id);
+        }
+    }
+
+    if !cancelled.is_empty() {
+        ScheduleGameEntity::update_many()
+            .col_expr(
+                schedule_game::Column::Status,
+                Expr::value(GameStatus::Cancelled.as_str()),
+            )
+            // so that calendar apps replace the event with the cancelled one
+            .col_expr(
+                schedule_game::Column::Sequence,
+                Expr::col(schedule_game::Column::Sequence).add(1),
+            )
+            .filter(schedule_game::Column::Id.is_in(cancelled.clone()))
+            .exec(connection)
+            .await
+            .map_err(db_error)?;
+    }
+
+    let games = games
+        .into_iter()
+        .map(|mut game| {
+            if cancelled.contains(&game.id) {
+                game.status = GameStatus::Cancelled.as_str().to_owned();
+                game.sequence += 1;
+            }
+            game
+        })
+        .collect_vec();
+
+    let context = RuleContext::load(connection).await.map_err(db_error)?;
+
+    let field_regions = FieldEntity::find()
+        .select_only()
+        .column(field::Column::Id)
+        .column(field::Column::RegionOwner)
+        .into_tuple::<(i32, i32)>()
+        .all(connection)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+    let mut slots = vec![];
+    // the region and reservation type of every slot, which a makeup has to keep
+    let mut slot_kinds = vec![];
+
+    for field in context.fields.values() {
+        let Some(region) = field_regions.get(&field.field_id) else {
+            continue;
+        };
+
+        for time_slot in &field.time_slots {
+            let window = time_slot.time_slot.window().ok_or_else(|| {
+                RainoutError::DatabaseError(format!(
+                    "time slot {} has a malformed start or end",
+                    time_slot.time_slot.id
+                ))
+            })?;
+
+            let time_zone = time_zones
+                .get(connection, field.field_id)
+                .await
+                .map_err(db_error)?;
+
+            if window.start() < not_before
+                || is_closed(field.field_id, &window, &time_zone)
+                || time_slot.reservation_type.is_practice
+            {
+                continue;
+            }
+
+            slots.push(MakeupSlot::new(
+                field.field_id,
+                window,
+                u8::try_from(time_slot.matches_played()).unwrap_or(u8::MAX),
+            ));
+            slot_kinds.push((*region, time_slot.reservation_type.id));
+        }
+    }
+
+    let booked_time_slots = select_time_slot_extension()
+        .into_model::<TimeSlotSelectionTypeAggregate>()
+        .all(connection)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(Into::<TimeSlotExtension>::into)
+        .into_group_map_by(|time_slot| time_slot.time_slot().field_id);
+
+    let mut fixed = vec![];
+    let mut makeups = vec![];
+
+    for game in &games {
+        if game.is_bye {
+            continue;
+        }
+
+        let (Some(field_id), Some(team_one)) = (game.field_id, game.team_one) else {
+            continue;
+        };
+
+        let window = window_of(game)?;
+
+        if !cancelled.contains(&game.id) {
+            if GameStatus::of(game) != GameStatus::Cancelled {
+                fixed.push(FixedGame::new(
+                    field_id,
+                    window,
+                    [game.team_one, game.team_two]
+                        .into_iter()
+                        .flatten()
+                        .collect(),
+                ));
+            }
+            continue;
+        }
+
+        // a makeup is played in the same region and kind of time slot as the original. The
+        // original slot may be closed now, so it is looked up among every time slot; if it no
+        // longer exists, the game is left unplaced rather than put in any kind of slot.
+        let reservation_type = booked_time_slots
+            .get(&field_id)
+            .into_iter()
+            .flatten()
+            .find(|time_slot| {
+                time_slot.time_slot().start <= game.start && game.end <= time_slot.time_slot().end
+            })
+            .map(|time_slot| time_slot.reservation_type().id);
+        let kind = field_regions.get(&field_id).copied().zip(reservation_type);
+
+        let eligible = (0..slots.len())
+            .filter(|&i| kind == Some(slot_kinds[i]))
+            .collect();
+
+        makeups.push(MakeupGame::new(
+            game.id,
+            team_one,
+            game.team_two.unwrap_or(team_one),
+            window.end() - window.start(),
+            eligible,
+        ));
+    }
+
+    let plan = place_makeups(&slots, &fixed, &context.coach_conflicts, &makeups);
+
+    Ok(Rainout {
+        cancelled: games
+            .into_iter()
+            .filter(|game| cancelled.contains(&game.id))
+            .collect(),
+        proposals: plan
+            .assignments()
+            .iter()
+            .map(|assignment| MakeupProposal {
+                game_id: assignment.game(),
+                field_id: assignment.field(),
+                start: assignment.window().start(),
+                end: assignment.window().end(),
+            })
+            .collect(),
+        unplaced: plan.unplaced().to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use sea_orm::EntityTrait;
+
+    use super::{rain_out, RainoutInput};
+    use crate::entity_local_exports::ScheduleGameEntity;
+    use crate::errors::RainoutError;
+    use crate::fixtures::{at, client, field, game, region_in, schedule, team, time_slot};
+    use crate::{CreateFieldInput, GameStatus};
+
+    #[tokio::test]
+    async fn cancels_games_on_the_local_day_and_proposes_makeups() {
+        let client = client().await;
+        let region = region_in(&client, "America/Los_Angeles").await;
+        let field = field(&client, region).await;
+        let other = client
+            .create_field(CreateFieldInput::new("Other", region))
+            .await
+            .unwrap()
+            .id;
+        time_slot(&client, field, 16).await;
+        for hour in [16, 18] {
+            time_slot(&client, (other, field.1), hour).await;
+        }
+
+        let a = team(&client, region, "A").await;
+        let b = team(&client, region, "B").await;
+        let c = team(&client, region, "C").await;
+        let d = team(&client, region, "D").await;
+        let schedule = schedule(&client).await;
+        let rained = game(&client, schedule, field.0, (a, Some(b)), 16).await;
+        // 10 PM on May 31 in Los Angeles
+        game(&client, schedule, field.0, (c, Some(d)), 5).await;
+        game(&client, schedule, other, (c, Some(d)), 16).await;
+
+        let june = |day| NaiveDate::from_ymd_opt(2024, 6, day).unwrap();
+
+        let rainout = rain_out(
+            &client.connection,
+            RainoutInput::new(schedule, vec![field.0], vec![june(1)]),
+            at(0),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            rainout
+                .cancelled()
+                .iter()
+                .map(|game| game.id)
+                .collect::<Vec<_>>(),
+            [rained]
+        );
+        assert!(rainout.unplaced().is_empty());
+        assert_eq!(
+            rainout
+                .proposals()
+                .iter()
+                .map(|proposal| (proposal.game_id(), proposal.field_id(), proposal.start()))
+                .collect::<Vec<_>>(),
+            [(rained, other, at(18))]
+        );
+
+        // a game that was called off already is not cancelled twice
+        let again = rain_out(
+            &client.connection,
+            RainoutInput::new(schedule, vec![field.0], vec![june(1)]),
+            at(0),
+        )
+        .await
+        .unwrap();
+        assert!(again.cancelled().is_empty());
+
+        client
+            .apply_makeups(schedule, rainout.proposals(), false)
+            .await
+            .unwrap();
+
+        let made_up = ScheduleGameEntity::find_by_id(rained)
+            .one(&client.connection)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(GameStatus::of(&made_up), GameStatus::Scheduled);
+        assert_eq!(made_up.field_id, Some(other));
+        assert_eq!(made_up.start, at(18).timestamp_millis());
+        assert_eq!(made_up.sequence, 2);
+    }
+
+    #[tokio::test]
+    async fn makeups_stay_in_the_region_of_the_game() {
+        let client = client().await;
+        let region = region_in(&client, "America/Los_Angeles").await;
+        let elsewhere = region_in(&client, "America/Los_Angeles").await;
+        let field = field(&client, region).await;
+        // books the same reservation type
+        let other = client
+            .create_field(CreateFieldInput::new("Other", elsewhere))
+            .await
+            .unwrap()
+            .id;
+        time_slot(&client, field, 16).await;
+        time_slot(&client, (other, field.1), 18).await;
+
+        let a = team(&client, region, "A").await;
+        let b = team(&client, region, "B").await;
+        let schedule = schedule(&client).await;
+        let rained = game(&client, schedule, field.0, (a, Some(b)), 16).await;
+
+        let rainout = rain_out(
+            &client.connection,
+            RainoutInput::new(
+                schedule,
+                vec![field.0],
+                vec![NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()],
+            ),
+            at(0),
+        )
+        .await
+        .unwrap();
+
+        assert!(rainout.proposals().is_empty());
+        assert_eq!(rainout.unplaced(), [rained]);
+    }
+
+    #[tokio::test]
+    async fn missing_schedule_is_not_found() {
+        let client = client().await;
+
+        assert!(matches!(
+            rain_out(
+                &client.connection,
+                RainoutInput::new(1, vec![], vec![]),
+                at(0)
+            )
+            .await,
+            Err(RainoutError::ScheduleNotFound(1))
+        ));
+    }
+}
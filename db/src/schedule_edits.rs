@@ -0,0 +1,544 @@
+//! Checks edits to the games of a saved schedule before they are written.
+//!
+//! An edit is checked with the same rules as [`Client::validate_schedule`](crate::Client),
+//! on the games of the schedule as they would be after the edit. Only violations that the
+//! edit introduces are reported, so a schedule that is already broken can still be fixed
+//! one game at a time.
+
+use std::collections::{BTreeMap, HashMap};
+
+use backend::validation::{ScheduleValidator, Violation};
+use backend::{AvailabilityWindow, Booking, Reservation, Tz};
+use chrono::{serde::ts_milliseconds, DateTime, TimeDelta, Utc};
+use itertools::Itertools;
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait, ModelTrait, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+use crate::entity_local_exports::{
+    CoachConflictEntity, ScheduleEntity, ScheduleGame, ScheduleGameEntity, TeamEntity,
+};
+use crate::errors::TimeSlotError;
+use crate::timestamps::Timespan;
+use crate::{
+    field, field_time_zones, select_time_slot_extension, BlackoutFilter, ClosureFilter,
+    CoachConflict, FieldExtension, GameStatus, TeamModelWrapper, TimeSlotExtension,
+    TimeSlotSelectionTypeAggregate,
+};
+
+/// Where a game of a schedule would be played after an edit.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleGameEdit {
+    game_id: i32,
+    #[serde(with = "ts_milliseconds")]
+    start: DateTime<Utc>,
+    #[serde(with = "ts_milliseconds")]
+    end: DateTime<Utc>,
+    field_id: Option<i32>,
+    /// Puts a cancelled game back on the schedule, see [`ScheduleGameEdit::reinstated`]
+    #[serde(default)]
+    reinstate: bool,
+}
+
+impl ScheduleGameEdit {
+    pub fn new(
+        game_id: i32,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        field_id: Option<i32>,
+    ) -> Self {
+        Self {
+            game_id,
+            start,
+            end,
+            field_id,
+            reinstate: false,
+        }
+    }
+
+    /// The game is played again, like the makeup of a rainout. Cancelled games are not
+    /// checked otherwise, since they do not book a field.
+    pub fn reinstated(mut self) -> Self {
+        self.reinstate = true;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum EditSeverity {
+    /// Applied anyway
+    Warning,
+    /// Only applied when the edit is forced
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EditIssueKind {
+    /// A rule of the schedule that the edit breaks
+    Rule(Violation),
+    /// The game already has a result, which stays with it
+    HasResult(GameStatus),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditIssue {
+    severity: EditSeverity,
+    /// The edited games that the issue is about
+    games: Vec<i32>,
+    kind: EditIssueKind,
+}
+
+impl EditIssue {
+    pub fn severity(&self) -> EditSeverity {
+        self.severity
+    }
+
+    pub fn games(&self) -> &[i32] {
+        &self.games
+    }
+
+    pub fn kind(&self) -> &EditIssueKind {
+        &self.kind
+    }
+}
+
+/// Every issue with an edit, errors first.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditReport {
+    issues: Vec<EditIssue>,
+    /// `true` if the edit was applied despite its errors
+    forced: bool,
+}
+
+impl EditReport {
+    pub fn issues(&self) -> &[EditIssue] {
+        &self.issues
+    }
+
+    pub fn forced(&self) -> bool {
+        self.forced
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| issue.severity == EditSeverity::Error)
+    }
+
+    /// An edit with errors is rejected, unless it is forced.
+    pub(crate) fn enforce(mut self, force: bool) -> Result<Self, TimeSlotError> {
+        if !self.has_errors() {
+            return Ok(self);
+        }
+
+        if !force {
+            return Err(TimeSlotError::Rejected(self));
+        }
+
+        self.forced = true;
+        Ok(self)
+    }
+}
+
+/// Every field with the time slots that are neither blacked out nor closed, every team and
+/// every coach conflict: what the rules of a schedule are checked against.
+pub(crate) struct RuleContext {
+    pub(crate) fields: BTreeMap<i32, FieldExtension>,
+    pub(crate) teams: HashMap<i32, TeamModelWrapper>,
+    pub(crate) coach_conflicts: Vec<CoachConflict>,
+}
+
+impl RuleContext {
+    pub(crate) async fn load<C>(connection: &C) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let blackouts = BlackoutFilter::load(connection).await?;
+        let closures = ClosureFilter::load(connection).await?;
+        let time_zones = field_time_zones(connection).await?;
+
+        let fields = select_time_slot_extension()
+            .order_by(field::Column::Id, sea_orm::Order::Asc)
+            .into_model::<TimeSlotSelectionTypeAggregate>()
+            .all(connection)
+            .await?
+            .into_iter()
+            .map(Into::<TimeSlotExtension>::into)
+            .filter(|time_slot_extension| {
+                !blackouts.is_blacked_out(time_slot_extension)
+                    && !closures.is_closed(time_slot_extension)
+            })
+            .group_by(|time_slot_extension| time_slot_extension.time_slot.field_id)
+            .into_iter()
+            .map(|(field_id, time_slots)| {
+                FieldExtension::new(
+                    field_id,
+                    time_slots.collect_vec(),
+                    time_zones.get(&field_id).copied().unwrap_or(Tz::UTC),
+                )
+                .map(|field| (field_id, field))
+                .map_err(|id| {
+                    DbErr::Custom(format!(
+                        "time slot {id} has a malformed start or end, or more matches than can be \
+                         scheduled"
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        let teams = TeamEntity::find()
+            .all(connection)
+            .await?
+            .into_iter()
+            .map(|team| (team.id, TeamModelWrapper(team)))
+            .collect();
+
+        let coach_conflicts = CoachConflictEntity::find()
+            .find_with_related(TeamEntity)
+            .all(connection)
+            .await?
+            .into_iter()
+            .map(|(coach_conflict, teams)| CoachConflict {
+                coach_name: coach_conflict.coach_name,
+                id: coach_conflict.id,
+                region: coach_conflict.region,
+                teams,
+            })
+            .collect();
+
+        Ok(Self {
+            fields,
+            teams,
+            coach_conflicts,
+        })
+    }
+
+    /// `None` for games that do not book a field, like byes and cancelled games. Fails with
+    /// the id of a game with a malformed start or end.
+    fn reservation(
+        &self,
+        game: &ScheduleGame,
+    ) -> Result<Option<Reservation<TeamModelWrapper, FieldExtension>>, i32> {
+        if game.is_bye || GameStatus::of(game) == GameStatus::Cancelled {
+            return Ok(None);
+        }
+
+        let Some(field_id) = game.field_id else {
+            return Ok(None);
+        };
+
+        let booking = match (
+            game.team_one.and_then(|id| self.teams.get(&id)),
+            game.team_two.and_then(|id| self.teams.get(&id)),
+        ) {
+            (Some(home_team), Some(away_team)) => Booking::Booked {
+                home_team: home_team.clone(),
+                away_team: away_team.clone(),
+            },
+            (Some(team), None) => Booking::Practice(team.clone()),
+            _ => return Ok(None),
+        };
+
+        let availability = game.window().ok_or(game.id)?;
+
+        // a field without time slots makes every game on it unavailable
+        let field = self
+            .fields
+            .get(&field_id)
+            .cloned()
+            .unwrap_or_else(|| FieldExtension::without_time_slots(field_id));
+
+        Ok(Some(Reservation::new(field, availability, booking)))
+    }
+
+    /// Every violation in `games`, see [`ScheduleValidator`].
+    pub(crate) fn validate(
+        &self,
+        games: &[ScheduleGame],
+        min_rest: Option<TimeDelta>,
+    ) -> Result<Vec<Violation>, i32> {
+        let mut reservations = vec![];
+
+        for game in games {
+            reservations.extend(self.reservation(game)?);
+        }
+
+        let fields = self.fields.values().cloned().collect_vec();
+
+        Ok(ScheduleValidator::new(&fields, &self.coach_conflicts)
+            .with_min_rest(min_rest)
+            .validate(&reservations))
+    }
+}
+
+fn db_error(e: DbErr) -> TimeSlotError {
+    TimeSlotError::DatabaseError(e.to_string())
+}
+
+fn malformed(game_id: i32) -> TimeSlotError {
+    TimeSlotError::MalformedGame(game_id)
+}
+
+/// The windows that a violation is about, to find the edited games it involves.
+fn windows(violation: &Violation) -> Vec<&AvailabilityWindow> {
+    match violation {
+        Violation::TeamDoubleBooked { first, second, .. }
+        | Violation::CoachConflict { first, second, .. }
+        | Violation::RestPeriodBreached { first, second, .. } => vec![first, second],
+        Violation::OutsideFieldAvailability { availability, .. }
+        | Violation::ConcurrencyExceeded { availability, .. } => vec![availability],
+    }
+}
+
+fn severity(violation: &Violation) -> EditSeverity {
+    match violation {
+        Violation::RestPeriodBreached { .. } => EditSeverity::Warning,
+        Violation::TeamDoubleBooked { .. }
+        | Violation::CoachConflict { .. }
+        | Violation::OutsideFieldAvailability { .. }
+        | Violation::ConcurrencyExceeded { .. } => EditSeverity::Error,
+    }
+}
+
+/// Every issue with applying `edits` to the games of `schedule_id`. Nothing is written.
+pub(crate) async fn check<C>(
+    connection: &C,
+    schedule_id: i32,
+    edits: &[ScheduleGameEdit],
+    min_rest: Option<TimeDelta>,
+) -> Result<EditReport, TimeSlotError>
+where
+    C: ConnectionTrait,
+{
+    let schedule = ScheduleEntity::find_by_id(schedule_id)
+        .one(connection)
+        .await
+        .map_err(db_error)?
+        .ok_or(TimeSlotError::ScheduleNotFound(schedule_id))?;
+
+    let before = schedule
+        .find_related(ScheduleGameEntity)
+        .all(connection)
+        .await
+        .map_err(db_error)?;
+
+    let mut after = before.clone();
+    let mut issues = vec![];
+
+    for edit in edits {
+        let Some(game) = after.iter_mut().find(|game| game.id == edit.game_id) else {
+            return Err(TimeSlotError::NotFound(edit.game_id));
+        };
+
+        game.start = edit.start.timestamp_millis();
+        game.end = edit.end.timestamp_millis();
+        game.field_id = edit.field_id;
+
+        if edit.reinstate {
+            game.status = GameStatus::Scheduled.as_str().to_owned();
+        }
+
+        match GameStatus::of(game) {
+            GameStatus::Scheduled => {}
+            status => issues.push(EditIssue {
+                severity: EditSeverity::Warning,
+                games: vec![game.id],
+                kind: EditIssueKind::HasResult(status),
+            }),
+        }
+    }
+
+    let context = RuleContext::load(connection).await.map_err(db_error)?;
+
+    let mut existing = context.validate(&before, min_rest).map_err(malformed)?;
+    let edited = after
+        .iter()
+        .filter(|game| edits.iter().any(|edit| edit.game_id == game.id))
+        .filter_map(|game| Some((game.id, game.window()?)))
+        .collect_vec();
+
+    for violation in context.validate(&after, min_rest).map_err(malformed)? {
+        // the edit did not cause what was already broken
+        if let Some(i) = existing.iter().position(|other| *other == violation) {
+            existing.swap_remove(i);
+            continue;
+        }
+
+        let games = windows(&violation)
+            .into_iter()
+            .flat_map(|window| {
+                edited
+                    .iter()
+                    .filter(move |(_, availability)| availability == window)
+                    .map(|(id, _)| *id)
+            })
+            .sorted()
+            .dedup()
+            .collect();
+
+        issues.push(EditIssue {
+            severity: severity(&violation),
+            games,
+            kind: EditIssueKind::Rule(violation),
+        });
+    }
+
+    issues.sort_by_key(|issue| std::cmp::Reverse(issue.severity));
+
+    Ok(EditReport {
+        issues,
+        forced: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use backend::validation::Violation;
+    use chrono::TimeDelta;
+    use sea_orm::{ActiveModelTrait, Set};
+
+    use super::{EditIssueKind, EditReport, EditSeverity, ScheduleGameEdit};
+    use crate::entity_local_exports::ActiveScheduleGame;
+    use crate::errors::TimeSlotError;
+    use crate::fixtures::{at, client, field, game, region, schedule, team, time_slot};
+    use crate::{Client, GameStatus, MoveTimeSlotInput, RecordGameResultInput};
+
+    /// Each issue as its severity, what kind it is and the edited games it is about.
+    fn issues(report: &EditReport) -> Vec<(EditSeverity, &'static str, Vec<i32>)> {
+        report
+            .issues()
+            .iter()
+            .map(|issue| {
+                let kind = match issue.kind() {
+                    EditIssueKind::Rule(Violation::TeamDoubleBooked { .. }) => "double booked",
+                    EditIssueKind::Rule(Violation::CoachConflict { .. }) => "coach conflict",
+                    EditIssueKind::Rule(Violation::OutsideFieldAvailability { .. }) => "outside",
+                    EditIssueKind::Rule(Violation::ConcurrencyExceeded { .. }) => "concurrency",
+                    EditIssueKind::Rule(Violation::RestPeriodBreached { .. }) => "rest",
+                    EditIssueKind::HasResult(_) => "result",
+                };
+                (issue.severity(), kind, issue.games().to_vec())
+            })
+            .collect()
+    }
+
+    /// Two games of team A on a field with time slots at 9, 10 and 11 o'clock, and a
+    /// game that is already outside of them.
+    async fn league(client: &Client) -> (i32, i32, [i32; 3]) {
+        let region = region(client).await;
+        let field = field(client, region).await;
+        for hour in [9, 10, 11] {
+            time_slot(client, field, hour).await;
+        }
+
+        let a = team(client, region, "A").await;
+        let b = team(client, region, "B").await;
+        let c = team(client, region, "C").await;
+
+        let schedule = schedule(client).await;
+        let games = [
+            game(client, schedule, field.0, (a, Some(b)), 9).await,
+            game(client, schedule, field.0, (a, Some(c)), 11).await,
+            game(client, schedule, field.0, (b, Some(c)), 15).await,
+        ];
+
+        (schedule, field.0, games)
+    }
+
+    #[tokio::test]
+    async fn reports_what_an_edit_breaks() {
+        let client = client().await;
+        let (schedule, field, [first, second, _]) = league(&client).await;
+
+        let check = |hour: u32, min_rest: Option<TimeDelta>| {
+            let edit = ScheduleGameEdit::new(second, at(hour), at(hour + 1), Some(field));
+            let client = &client;
+            async move {
+                client
+                    .check_schedule_edit(schedule, &[edit], min_rest)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // the game at 15 o'clock was broken before, and is not blamed on the edit
+        assert_eq!(issues(&check(11, None).await), []);
+
+        assert_eq!(
+            issues(&check(9, None).await),
+            [
+                (EditSeverity::Error, "double booked", vec![second]),
+                (EditSeverity::Error, "concurrency", vec![second]),
+            ]
+        );
+        assert_eq!(
+            issues(&check(13, None).await),
+            [(EditSeverity::Error, "outside", vec![second])]
+        );
+        assert_eq!(
+            issues(&check(10, Some(TimeDelta::hours(1))).await),
+            [(EditSeverity::Warning, "rest", vec![second])]
+        );
+
+        // a result stays with its game wherever it goes
+        client
+            .record_game_result(RecordGameResultInput::new(
+                first,
+                GameStatus::Played,
+                Some(1),
+                Some(0),
+            ))
+            .await
+            .unwrap();
+        let edit = ScheduleGameEdit::new(first, at(9), at(10), Some(field));
+        let report = client
+            .check_schedule_edit(schedule, &[edit], None)
+            .await
+            .unwrap();
+        assert_eq!(
+            issues(&report),
+            [(EditSeverity::Warning, "result", vec![first])]
+        );
+        assert!(report.clone().enforce(false).is_ok());
+
+        // only errors need the edit to be forced
+        assert!(matches!(
+            check(9, None).await.enforce(false),
+            Err(TimeSlotError::Rejected(_))
+        ));
+        assert!(check(9, None).await.enforce(true).unwrap().forced());
+    }
+
+    #[tokio::test]
+    async fn byes_cannot_be_moved() {
+        let client = client().await;
+        let region = region(&client).await;
+        let a = team(&client, region, "A").await;
+        let schedule = schedule(&client).await;
+
+        let bye = ActiveScheduleGame {
+            schedule_id: Set(schedule),
+            start: Set(at(0).timestamp_millis()),
+            end: Set((at(0) + TimeDelta::days(7)).timestamp_millis()),
+            team_one: Set(Some(a)),
+            is_bye: Set(true),
+            ..Default::default()
+        }
+        .insert(&client.connection)
+        .await
+        .unwrap()
+        .id;
+
+        assert!(matches!(
+            client
+                .move_time_slot(MoveTimeSlotInput::new_game(
+                    bye,
+                    schedule,
+                    at(1),
+                    at(1) + TimeDelta::days(7),
+                    true,
+                ))
+                .await,
+            Err(TimeSlotError::IsBye(id)) if id == bye
+        ));
+    }
+}
@@ -0,0 +1,489 @@
+//! Exports of saved schedules. Games are stored with raw team and field ids and UTC
+//! times; an export resolves the names, shows times in the zone of the field's region,
+//! and finds the reservation type from the time slot that the game was booked into.
+//!
+//! Byes have no field or time to hand to anyone, so they are left out of the games and
+//! loaded on their own with [`load_byes`]; team calendars show them as all-day events.
+//! Cancelled games are kept and marked, so that nobody mistakes a missing game for a
+//! mistake.
+
+use std::collections::HashMap;
+
+use backend::Tz;
+use chrono::{DateTime, NaiveDate};
+use itertools::Itertools;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+use crate::entity_local_exports::{
+    Field, FieldEntity, Region, RegionEntity, ReservationType, ScheduleEntity, ScheduleGame,
+    ScheduleGameEntity, TeamEntity, TeamGroupEntity,
+};
+use crate::errors::ExportScheduleError;
+use crate::timestamps::Timespan;
+use crate::{
+    parse_time_zone, select_time_slot_extension, GameStatus, TeamExtension, TimeSlotExtension,
+    TimeSlotSelectionTypeAggregate,
+};
+
+/// Narrows an export down. Every filter that is set must match.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleExportFilter {
+    /// Games where this team plays, home or away
+    team_id: Option<i32>,
+    field_id: Option<i32>,
+    /// Games where either team is tagged with this group
+    group_id: Option<i32>,
+    /// First local date to include
+    from: Option<NaiveDate>,
+    /// Last local date to include
+    to: Option<NaiveDate>,
+}
+
+impl ScheduleExportFilter {
+    pub fn new(
+        team_id: Option<i32>,
+        field_id: Option<i32>,
+        group_id: Option<i32>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Self {
+        Self {
+            team_id,
+            field_id,
+            group_id,
+            from,
+            to,
+        }
+    }
+
+    pub fn team_id(&self) -> Option<i32> {
+        self.team_id
+    }
+
+    pub fn field_id(&self) -> Option<i32> {
+        self.field_id
+    }
+
+    pub(crate) fn matches(&self, game: &ExportedGame) -> bool {
+        let teams = || game.home.iter().chain(game.away.iter());
+
+        self.team_id
+            .is_none_or(|id| teams().any(|team| team.team().id == id))
+            && self.field_id.is_none_or(|id| game.field.id == id)
+            && self
+                .group_id
+                .is_none_or(|id| teams().any(|team| team.tags().iter().any(|tag| tag.id == id)))
+            && self.from.is_none_or(|from| game.start.date_naive() >= from)
+            && self.to.is_none_or(|to| game.start.date_naive() <= to)
+    }
+}
+
+/// A saved game with everything resolved, with times in the zone of its field's region.
+#[derive(Clone, Debug)]
+pub struct ExportedGame {
+    game: ScheduleGame,
+    start: DateTime<Tz>,
+    end: DateTime<Tz>,
+    field: Field,
+    region: Option<Region>,
+    /// [`Option#None`] if the time slot that the game was booked into no longer exists
+    reservation_type: Option<ReservationType>,
+    home: Option<TeamExtension>,
+    /// [`Option#None`] for practices
+    away: Option<TeamExtension>,
+}
+
+impl ExportedGame {
+    pub fn game(&self) -> &ScheduleGame {
+        &self.game
+    }
+
+    pub fn start(&self) -> &DateTime<Tz> {
+        &self.start
+    }
+
+    pub fn end(&self) -> &DateTime<Tz> {
+        &self.end
+    }
+
+    pub fn field(&self) -> &Field {
+        &self.field
+    }
+
+    pub fn region(&self) -> Option<&Region> {
+        self.region.as_ref()
+    }
+
+    pub fn reservation_type(&self) -> Option<&ReservationType> {
+        self.reservation_type.as_ref()
+    }
+
+    pub fn home(&self) -> Option<&TeamExtension> {
+        self.home.as_ref()
+    }
+
+    pub fn away(&self) -> Option<&TeamExtension> {
+        self.away.as_ref()
+    }
+}
+
+/// A saved bye of one team, on the date of its round in the zone of the team's region.
+#[derive(Clone, Debug)]
+pub(crate) struct ExportedBye {
+    game: ScheduleGame,
+    date: NaiveDate,
+    team: TeamExtension,
+}
+
+impl ExportedBye {
+    pub(crate) fn game(&self) -> &ScheduleGame {
+        &self.game
+    }
+
+    pub(crate) fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    pub(crate) fn team(&self) -> &TeamExtension {
+        &self.team
+    }
+}
+
+const CSV_HEADERS: [&str; 8] = [
+    "date",
+    "start",
+    "end",
+    "field",
+    "reservation_type",
+    "home",
+    "away",
+    "status",
+];
+
+/// One line of a CSV export. Fields are in the order of [`CSV_HEADERS`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleExportRow {
+    date: String,
+    start: String,
+    end: String,
+    field: String,
+    reservation_type: String,
+    home: String,
+    away: String,
+    /// `cancelled` for games that will not be played, empty otherwise
+    status: String,
+}
+
+impl From<&ExportedGame> for ScheduleExportRow {
+    fn from(value: &ExportedGame) -> Self {
+        let name = |team: Option<&TeamExtension>| {
+            team.map(|team| team.team().name.clone())
+                .unwrap_or_default()
+        };
+
+        Self {
+            date: value.start.format("%Y-%m-%d").to_string(),
+            start: value.start.format("%H:%M").to_string(),
+            end: value.end.format("%H:%M").to_string(),
+            field: value.field.name.clone(),
+            reservation_type: value
+                .reservation_type
+                .as_ref()
+                .map(|reservation_type| reservation_type.name.clone())
+                .unwrap_or_default(),
+            home: name(value.home()),
+            away: name(value.away()),
+            status: match GameStatus::of(&value.game) {
+                GameStatus::Cancelled => GameStatus::Cancelled.as_str().to_owned(),
+                _ => String::new(),
+            },
+        }
+    }
+}
+
+/// Loads the games of a schedule that pass `filter`, ordered by start time then field.
+pub(crate) async fn load<C>(
+    connection: &C,
+    schedule_id: i32,
+    filter: &ScheduleExportFilter,
+) -> Result<Vec<ExportedGame>, ExportScheduleError>
+where
+    C: ConnectionTrait,
+{
+    let db_error = |e: sea_orm::DbErr| {
+        ExportScheduleError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+    };
+
+    let schedule = ScheduleEntity::find_by_id(schedule_id)
+        .one(connection)
+        .await
+        .map_err(db_error)?
+        .ok_or(ExportScheduleError::NotFound(schedule_id))?;
+
+    let games = schedule
+        .find_related(ScheduleGameEntity)
+        .order_by_asc(entity::schedule_game::Column::Start)
+        .all(connection)
+        .await
+        .map_err(db_error)?;
+
+    let fields = FieldEntity::find()
+        .find_also_related(RegionEntity)
+        .all(connection)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|(field, region)| {
+            let time_zone = region
+                .as_ref()
+                .map(|region| parse_time_zone(&region.time_zone))
+                .unwrap_or(Tz::UTC);
+            (field.id, (field, region, time_zone))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let time_slots = select_time_slot_extension()
+        .into_model::<TimeSlotSelectionTypeAggregate>()
+        .all(connection)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(Into::<TimeSlotExtension>::into)
+        .into_group_map_by(|time_slot| time_slot.time_slot().field_id);
+
+    let teams = TeamEntity::find()
+        .find_with_related(TeamGroupEntity)
+        .all(connection)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|(team, tags)| (team.id, TeamExtension::new(team, tags)))
+        .collect::<HashMap<_, _>>();
+
+    let mut exported = vec![];
+
+    for game in games {
+        if game.is_bye {
+            continue;
+        }
+
+        let Some((field, region, time_zone)) = game.field_id.and_then(|id| fields.get(&id)) else {
+            continue;
+        };
+
+        let (Some(start), Some(end)) = (game.start_time(), game.end_time()) else {
+            return Err(ExportScheduleError::MalformedGame(game.id));
+        };
+
+        let reservation_type = time_slots
+            .get(&field.id)
+            .into_iter()
+            .flatten()
+            .find(|time_slot| {
+                time_slot.time_slot().start <= game.start && game.end <= time_slot.time_slot().end
+            })
+            .map(|time_slot| time_slot.reservation_type().clone());
+
+        let exported_game = ExportedGame {
+            start: start.with_timezone(time_zone),
+            end: end.with_timezone(time_zone),
+            field: field.clone(),
+            region: region.clone(),
+            reservation_type,
+            home: game.team_one.and_then(|id| teams.get(&id)).cloned(),
+            away: game.team_two.and_then(|id| teams.get(&id)).cloned(),
+            game,
+        };
+
+        if filter.matches(&exported_game) {
+            exported.push(exported_game);
+        }
+    }
+
+    exported.sort_by(|a, b| {
+        a.start
+            .cmp(&b.start)
+            .then_with(|| a.field.name.cmp(&b.field.name))
+    });
+
+    Ok(exported)
+}
+
+/// Loads the byes of a schedule, ordered by date. Byes of teams that no longer exist are
+/// left out.
+pub(crate) async fn load_byes<C>(
+    connection: &C,
+    schedule_id: i32,
+) -> Result<Vec<ExportedBye>, ExportScheduleError>
+where
+    C: ConnectionTrait,
+{
+    let db_error = |e: sea_orm::DbErr| {
+        ExportScheduleError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+    };
+
+    let games = ScheduleGameEntity::find()
+        .filter(entity::schedule_game::Column::ScheduleId.eq(schedule_id))
+        .filter(entity::schedule_game::Column::IsBye.eq(true))
+        .order_by_asc(entity::schedule_game::Column::Start)
+        .all(connection)
+        .await
+        .map_err(db_error)?;
+
+    let time_zones = RegionEntity::find()
+        .all(connection)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|region| (region.id, parse_time_zone(&region.time_zone)))
+        .collect::<HashMap<_, _>>();
+
+    let teams = TeamEntity::find()
+        .find_with_related(TeamGroupEntity)
+        .all(connection)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|(team, tags)| (team.id, TeamExtension::new(team, tags)))
+        .collect::<HashMap<_, _>>();
+
+    let mut byes = vec![];
+
+    for game in games {
+        let Some(team) = game.team_one.and_then(|id| teams.get(&id)) else {
+            continue;
+        };
+
+        let Some(start) = game.start_time() else {
+            return Err(ExportScheduleError::MalformedGame(game.id));
+        };
+
+        let time_zone = time_zones
+            .get(&team.team().region_owner)
+            .copied()
+            .unwrap_or(Tz::UTC);
+
+        byes.push(ExportedBye {
+            date: start.with_timezone(&time_zone).date_naive(),
+            team: team.clone(),
+            game,
+        });
+    }
+
+    Ok(byes)
+}
+
+/// Writes `games` as CSV, with a header row even if there are no games.
+pub fn to_csv(games: &[ExportedGame]) -> Result<String, ExportScheduleError> {
+    let csv_error = |e: csv::Error| ExportScheduleError::Csv(e.to_string());
+
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(vec![]);
+
+    writer.write_record(CSV_HEADERS).map_err(csv_error)?;
+
+    for game in games {
+        writer
+            .serialize(ScheduleExportRow::from(game))
+            .map_err(csv_error)?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| ExportScheduleError::Csv(e.to_string()))?;
+
+    String::from_utf8(bytes).map_err(|e| ExportScheduleError::Csv(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::ScheduleExportFilter;
+    use crate::errors::ExportScheduleError;
+    use crate::fixtures::{client, field, game, region_in, schedule, team, time_slot};
+    use crate::{GameStatus, RecordGameResultInput};
+
+    #[tokio::test]
+    async fn exports_games_in_the_region_time_zone() {
+        let client = client().await;
+        let region = region_in(&client, "America/Los_Angeles").await;
+        let field = field(&client, region).await;
+        time_slot(&client, field, 16).await;
+
+        let a = team(&client, region, "A").await;
+        let b = team(&client, region, "B").await;
+        let c = team(&client, region, "C").await;
+
+        let schedule = schedule(&client).await;
+        // 19:00 on the day before, in Los Angeles
+        game(&client, schedule, field.0, (a, Some(c)), 2).await;
+        game(&client, schedule, field.0, (a, Some(b)), 16).await;
+        let cancelled = game(&client, schedule, field.0, (b, Some(c)), 18).await;
+        game(&client, schedule, field.0, (c, None), 20).await;
+
+        client
+            .record_game_result(RecordGameResultInput::new(
+                cancelled,
+                GameStatus::Cancelled,
+                None,
+                None,
+            ))
+            .await
+            .unwrap();
+
+        let csv = client
+            .export_schedule_csv(schedule, &ScheduleExportFilter::default())
+            .await
+            .unwrap();
+
+        // only the game inside the time slot knows its reservation type
+        assert_eq!(
+            csv,
+            "date,start,end,field,reservation_type,home,away,status
+2024-05-31,19:00,20:00,Field,,A,C,
+2024-06-01,09:00,10:00,Field,U10,A,B,
+2024-06-01,11:00,12:00,Field,,B,C,cancelled
+2024-06-01,13:00,14:00,Field,,C,,
+"
+        );
+
+        let day_before = NaiveDate::from_ymd_opt(2024, 5, 31);
+        let filters = [
+            (
+                ScheduleExportFilter::new(Some(c), None, None, None, None),
+                3,
+            ),
+            (
+                ScheduleExportFilter::new(None, None, None, None, day_before),
+                1,
+            ),
+            (
+                ScheduleExportFilter::new(Some(b), None, None, day_before, day_before),
+                0,
+            ),
+        ];
+
+        for (filter, games) in filters {
+            let exported = client
+                .export_schedule_games(schedule, &filter)
+                .await
+                .unwrap();
+            assert_eq!(exported.len(), games, "{filter:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_schedule_is_not_found() {
+        let client = client().await;
+
+        assert!(matches!(
+            client
+                .export_schedule_csv(1, &ScheduleExportFilter::default())
+                .await,
+            Err(ExportScheduleError::NotFound(1))
+        ));
+    }
+}
@@ -0,0 +1,755 @@
+//! Revisions of a saved schedule, and what changed between two of them.
+//!
+//! A revision is a copy of every game of a schedule at the moment it was taken, e.g. when
+//! the schedule was published to coaches. Either side of a diff can be a revision or the
+//! current games of any schedule, so drafts can be compared with each other as well.
+//!
+//! Games on both sides are paired up before they are compared: first by game id, which
+//! only matches within the same schedule, then by the teams that play, then by the time
+//! and field that they are booked into. Whatever is left over was added or removed.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use backend::Tz;
+use chrono::{DateTime, Utc};
+use entity::{schedule_revision, schedule_revision_game};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::entity_local_exports::{RegionEntity, ScheduleEntity, ScheduleGame, ScheduleGameEntity};
+use crate::errors::ScheduleRevisionError;
+use crate::time_zones::{field_time_zones, parse_time_zone};
+use crate::timestamps::Timespan;
+use crate::{field, schedule_game, team, FieldEntity, TeamEntity};
+
+/// One side of a diff.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScheduleSource {
+    /// The games of a schedule as they are now
+    Schedule(i32),
+    Revision(i32),
+}
+
+impl fmt::Display for ScheduleSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Schedule(id) => write!(f, "schedule #{id}"),
+            Self::Revision(id) => write!(f, "revision #{id}"),
+        }
+    }
+}
+
+/// A game as it is on one side of a diff. Names and the time zone are looked up when the
+/// diff is made. Names are [`None`] for teams and fields that no longer exist.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffGame {
+    game_id: i32,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    team_one: Option<i32>,
+    team_one_name: Option<String>,
+    team_two: Option<i32>,
+    team_two_name: Option<String>,
+    field_id: Option<i32>,
+    field_name: Option<String>,
+    is_bye: bool,
+    /// Of the field's region, or of the team's region for byes. Only used for display.
+    #[serde(skip)]
+    time_zone: Tz,
+}
+
+impl DiffGame {
+    fn new(
+        game_id: i32,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        team_one: Option<i32>,
+        team_two: Option<i32>,
+        field_id: Option<i32>,
+        is_bye: bool,
+    ) -> Self {
+        Self {
+            game_id,
+            start,
+            end,
+            team_one,
+            team_one_name: None,
+            team_two,
+            team_two_name: None,
+            field_id,
+            field_name: None,
+            is_bye,
+            time_zone: Tz::UTC,
+        }
+    }
+
+    pub fn game_id(&self) -> i32 {
+        self.game_id
+    }
+
+    pub fn start(&self) -> DateTime<Utc> {
+        self.start
+    }
+
+    pub fn end(&self) -> DateTime<Utc> {
+        self.end
+    }
+
+    pub fn team_one(&self) -> Option<i32> {
+        self.team_one
+    }
+
+    pub fn team_two(&self) -> Option<i32> {
+        self.team_two
+    }
+
+    pub fn field_id(&self) -> Option<i32> {
+        self.field_id
+    }
+
+    pub fn is_bye(&self) -> bool {
+        self.is_bye
+    }
+
+    /// Teams regardless of home and away, for pairing
+    fn matchup(&self) -> Option<(Option<i32>, Option<i32>, bool)> {
+        if self.team_one.is_none() && self.team_two.is_none() {
+            return None;
+        }
+
+        let (a, b) = if self.team_one <= self.team_two {
+            (self.team_one, self.team_two)
+        } else {
+            (self.team_two, self.team_one)
+        };
+
+        Some((a, b, self.is_bye))
+    }
+
+    fn slot(&self) -> Option<(DateTime<Utc>, i32)> {
+        self.field_id.map(|field| (self.start, field))
+    }
+}
+
+impl fmt::Display for DiffGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = |id: Option<i32>, name: &Option<String>| match (id, name) {
+            (_, Some(name)) => name.clone(),
+            (Some(id), None) => format!("#{id}"),
+            (None, None) => "TBD".to_owned(),
+        };
+
+        if self.is_bye {
+            write!(f, "{} (bye)", name(self.team_one, &self.team_one_name))?;
+        } else {
+            write!(
+                f,
+                "{} vs {}",
+                name(self.team_one, &self.team_one_name),
+                name(self.team_two, &self.team_two_name)
+            )?;
+        }
+
+        write!(
+            f,
+            " at {}",
+            self.start
+                .with_timezone(&self.time_zone)
+                .format("%Y-%m-%d %H:%M %Z")
+        )?;
+
+        if self.field_id.is_some() {
+            write!(f, " on {}", name(self.field_id, &self.field_name))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum GameChangeKind {
+    /// Start or end moved
+    Time,
+    Field,
+    /// Different teams, or home and away swapped
+    Teams,
+}
+
+/// A game that is on both sides of a diff, but not the same.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameChange {
+    before: DiffGame,
+    after: DiffGame,
+    changes: Vec<GameChangeKind>,
+}
+
+impl GameChange {
+    pub fn before(&self) -> &DiffGame {
+        &self.before
+    }
+
+    pub fn after(&self) -> &DiffGame {
+        &self.after
+    }
+
+    pub fn changes(&self) -> &[GameChangeKind] {
+        &self.changes
+    }
+}
+
+/// What changed between two schedules or revisions. Games are sorted by start.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleDiff {
+    from: ScheduleSource,
+    to: ScheduleSource,
+    added: Vec<DiffGame>,
+    removed: Vec<DiffGame>,
+    changed: Vec<GameChange>,
+}
+
+impl ScheduleDiff {
+    pub fn from(&self) -> ScheduleSource {
+        self.from
+    }
+
+    pub fn to(&self) -> ScheduleSource {
+        self.to
+    }
+
+    pub fn added(&self) -> &[DiffGame] {
+        &self.added
+    }
+
+    pub fn removed(&self) -> &[DiffGame] {
+        &self.removed
+    }
+
+    pub fn changed(&self) -> &[GameChange] {
+        &self.changed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// A plain text summary, one line per game, that can be sent to coaches as is.
+impl fmt::Display for ScheduleDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Changes from {} to {}", self.from, self.to)?;
+
+        if self.is_empty() {
+            return writeln!(f, "No changes");
+        }
+
+        for game in &self.added {
+            writeln!(f, "Added: {game}")?;
+        }
+
+        for game in &self.removed {
+            writeln!(f, "Removed: {game}")?;
+        }
+
+        for change in &self.changed {
+            let kinds = change
+                .changes
+                .iter()
+                .map(|kind| match kind {
+                    GameChangeKind::Time => "time",
+                    GameChangeKind::Field => "field",
+                    GameChangeKind::Teams => "teams",
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            writeln!(
+                f,
+                "Changed ({kinds}): {} -> {}",
+                change.before, change.after
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn db_error(e: DbErr) -> ScheduleRevisionError {
+    ScheduleRevisionError::DatabaseError(e.to_string())
+}
+
+fn from_game(game: &ScheduleGame) -> Result<DiffGame, ScheduleRevisionError> {
+    let (Some(start), Some(end)) = (game.start_time(), game.end_time()) else {
+        return Err(ScheduleRevisionError::MalformedGame(game.id));
+    };
+
+    Ok(DiffGame::new(
+        game.id,
+        start,
+        end,
+        game.team_one,
+        game.team_two,
+        game.field_id,
+        game.is_bye,
+    ))
+}
+
+/// Copies every game of `schedule_id` into a new revision.
+pub(crate) async fn snapshot<C>(
+    connection: &C,
+    schedule_id: i32,
+    label: Option<String>,
+) -> Result<schedule_revision::Model, ScheduleRevisionError>
+where
+    C: ConnectionTrait,
+{
+    ScheduleEntity::find_by_id(schedule_id)
+        .one(connection)
+        .await
+        .map_err(db_error)?
+        .ok_or(ScheduleRevisionError::ScheduleNotFound(schedule_id))?;
+
+    let revision = schedule_revision::ActiveModel {
+        schedule_id: Set(schedule_id),
+        label: Set(label),
+        created: Set(Utc::now().timestamp_millis()),
+        ..Default::default()
+    }
+    .insert(connection)
+    .await
+    .map_err(db_error)?;
+
+    let games = ScheduleGameEntity::find()
+        .filter(schedule_game::Column::ScheduleId.eq(schedule_id))
+        .all(connection)
+        .await
+        .map_err(db_error)?;
+
+    let games = games
+        .iter()
+        .map(|game| {
+            let game = from_game(game)?;
+
+            Ok(schedule_revision_game::ActiveModel {
+                revision_id: Set(revision.id),
+                game_id: Set(game.game_id),
+                start: Set(game.start.timestamp_millis()),
+                end: Set(game.end.timestamp_millis()),
+                team_one: Set(game.team_one),
+                team_two: Set(game.team_two),
+                field_id: Set(game.field_id),
+                is_bye: Set(game.is_bye),
+                ..Default::default()
+            })
+        })
+        .collect::<Result<Vec<_>, ScheduleRevisionError>>()?;
+
+    if !games.is_empty() {
+        schedule_revision_game::Entity::insert_many(games)
+            .exec(connection)
+            .await
+            .map_err(db_error)?;
+    }
+
+    Ok(revision)
+}
+
+/// Revisions of `schedule_id`, newest first.
+pub(crate) async fn revisions<C>(
+    connection: &C,
+    schedule_id: i32,
+) -> Result<Vec<schedule_revision::Model>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    schedule_revision::Entity::find()
+        .filter(schedule_revision::Column::ScheduleId.eq(schedule_id))
+        .order_by_desc(schedule_revision::Column::Id)
+        .all(connection)
+        .await
+}
+
+async fn load<C>(
+    connection: &C,
+    source: ScheduleSource,
+) -> Result<Vec<DiffGame>, ScheduleRevisionError>
+where
+    C: ConnectionTrait,
+{
+    match source {
+        ScheduleSource::Schedule(id) => {
+            let (_, games) = ScheduleEntity::find_by_id(id)
+                .find_with_related(ScheduleGameEntity)
+                .all(connection)
+                .await
+                .map_err(db_error)?
+                .pop()
+                .ok_or(ScheduleRevisionError::ScheduleNotFound(id))?;
+
+            games.iter().map(from_game).collect()
+        }
+        ScheduleSource::Revision(id) => {
+            let (_, games) = schedule_revision::Entity::find_by_id(id)
+                .find_with_related(schedule_revision_game::Entity)
+                .all(connection)
+                .await
+                .map_err(db_error)?
+                .pop()
+                .ok_or(ScheduleRevisionError::RevisionNotFound(id))?;
+
+            games
+                .iter()
+                .map(|game| {
+                    let (Some(start), Some(end)) = (game.start_time(), game.end_time()) else {
+                        return Err(ScheduleRevisionError::MalformedGame(game.game_id));
+                    };
+
+                    Ok(DiffGame::new(
+                        game.game_id,
+                        start,
+                        end,
+                        game.team_one,
+                        game.team_two,
+                        game.field_id,
+                        game.is_bye,
+                    ))
+                })
+                .collect()
+        }
+    }
+}
+
+/// Moves pairs of games for which `same` holds out of `before` and `after`. When a game
+/// could be paired with several, the closest in time is picked.
+fn pair_by(
+    before: &mut Vec<DiffGame>,
+    after: &mut Vec<DiffGame>,
+    pairs: &mut Vec<(DiffGame, DiffGame)>,
+    same: impl Fn(&DiffGame, &DiffGame) -> bool,
+) {
+    let mut unpaired = vec![];
+
+    for game in before.drain(..) {
+        let closest = after
+            .iter()
+            .enumerate()
+            .filter(|(_, other)| same(&game, other))
+            .min_by_key(|(_, other)| (other.start - game.start).abs())
+            .map(|(index, _)| index);
+
+        match closest {
+            Some(index) => pairs.push((game, after.remove(index))),
+            None => unpaired.push(game),
+        }
+    }
+
+    *before = unpaired;
+}
+
+/// Whether `key` is set and the same for both games.
+fn same_key<K: PartialEq>(
+    key: impl Fn(&DiffGame) -> Option<K>,
+) -> impl Fn(&DiffGame, &DiffGame) -> bool {
+    move |a, b| key(a).is_some_and(|a| Some(a) == key(b))
+}
+
+fn diff_games(
+    from: ScheduleSource,
+    to: ScheduleSource,
+    mut before: Vec<DiffGame>,
+    mut after: Vec<DiffGame>,
+) -> ScheduleDiff {
+    before.sort_by_key(|game| (game.start, game.game_id));
+    after.sort_by_key(|game| (game.start, game.game_id));
+
+    let mut pairs = vec![];
+
+    // SQLite hands out the id of a deleted game again, so a game with the same id has to
+    // keep either its teams or its time and field to count as the same game
+    pair_by(&mut before, &mut after, &mut pairs, |a, b| {
+        a.game_id == b.game_id
+            && (same_key(DiffGame::matchup)(a, b) || same_key(DiffGame::slot)(a, b))
+    });
+    pair_by(
+        &mut before,
+        &mut after,
+        &mut pairs,
+        same_key(DiffGame::matchup),
+    );
+    pair_by(
+        &mut before,
+        &mut after,
+        &mut pairs,
+        same_key(DiffGame::slot),
+    );
+
+    let mut changed = pairs
+        .into_iter()
+        .filter_map(|(before, after)| {
+            let mut changes = vec![];
+
+            if before.start != after.start || before.end != after.end {
+                changes.push(GameChangeKind::Time);
+            }
+
+            if before.field_id != after.field_id {
+                changes.push(GameChangeKind::Field);
+            }
+
+            if (before.team_one, before.team_two, before.is_bye)
+                != (after.team_one, after.team_two, after.is_bye)
+            {
+                changes.push(GameChangeKind::Teams);
+            }
+
+            (!changes.is_empty()).then_some(GameChange {
+                before,
+                after,
+                changes,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    changed.sort_by_key(|change| (change.after.start, change.after.game_id));
+
+    ScheduleDiff {
+        from,
+        to,
+        added: after,
+        removed: before,
+        changed,
+    }
+}
+
+/// Compares the games of `from` with the games of `to`.
+pub(crate) async fn diff<C>(
+    connection: &C,
+    from: ScheduleSource,
+    to: ScheduleSource,
+) -> Result<ScheduleDiff, ScheduleRevisionError>
+where
+    C: ConnectionTrait,
+{
+    let before = load(connection, from).await?;
+    let after = load(connection, to).await?;
+
+    let mut diff = diff_games(from, to, before, after);
+
+    let teams = TeamEntity::find()
+        .select_only()
+        .column(team::Column::Id)
+        .column(team::Column::Name)
+        .column(team::Column::RegionOwner)
+        .into_tuple::<(i32, String, i32)>()
+        .all(connection)
+        .await
+        .map_err(db_error)?;
+
+    let fields = FieldEntity::find()
+        .select_only()
+        .column(field::Column::Id)
+        .column(field::Column::Name)
+        .into_tuple::<(i32, String)>()
+        .all(connection)
+        .await
+        .map_err(db_error)?;
+
+    let region_time_zones = RegionEntity::find()
+        .all(connection)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|region| (region.id, parse_time_zone(&region.time_zone)))
+        .collect::<HashMap<_, _>>();
+
+    let field_time_zones = field_time_zones(connection).await.map_err(db_error)?;
+
+    let team_time_zones = teams
+        .iter()
+        .filter_map(|(id, _, region)| Some((*id, *region_time_zones.get(region)?)))
+        .collect::<HashMap<_, _>>();
+    let teams = teams
+        .into_iter()
+        .map(|(id, name, _)| (id, name))
+        .collect::<HashMap<_, _>>();
+    let fields = fields.into_iter().collect::<HashMap<_, _>>();
+
+    let games = diff.added.iter_mut().chain(diff.removed.iter_mut()).chain(
+        diff.changed
+            .iter_mut()
+            .flat_map(|change| [&mut change.before, &mut change.after]),
+    );
+
+    for game in games {
+        let lookup = |names: &HashMap<i32, String>, id: Option<i32>| {
+            id.and_then(|id| names.get(&id).cloned())
+        };
+
+        game.team_one_name = lookup(&teams, game.team_one);
+        game.team_two_name = lookup(&teams, game.team_two);
+        game.field_name = lookup(&fields, game.field_id);
+        game.time_zone = game
+            .field_id
+            .and_then(|id| field_time_zones.get(&id))
+            .or_else(|| game.team_one.and_then(|id| team_time_zones.get(&id)))
+            .copied()
+            .unwrap_or(Tz::UTC);
+    }
+
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+
+    use entity::schedule_revision_game;
+
+    use super::{diff, snapshot, DiffGame, GameChangeKind, ScheduleSource};
+    use crate::entity_local_exports::{ActiveScheduleGame, ScheduleGameEntity};
+    use crate::errors::ScheduleRevisionError;
+    use crate::fixtures::{at, client, field, game, region_in, schedule, team};
+
+    #[tokio::test]
+    async fn diffs_a_revision_against_later_edits() {
+        let client = client().await;
+        let region = region_in(&client, "America/Los_Angeles").await;
+        let (north, _) = field(&client, region).await;
+        let (south, _) = field(&client, region).await;
+
+        let mut teams = vec![];
+        for name in ["A", "B", "C", "D", "E", "F"] {
+            teams.push(team(&client, region, name).await);
+        }
+
+        let schedule = schedule(&client).await;
+        let moved = game(&client, schedule, north, (teams[0], Some(teams[1])), 18).await;
+        let refielded = game(&client, schedule, north, (teams[2], Some(teams[3])), 19).await;
+        let removed = game(&client, schedule, north, (teams[4], Some(teams[5])), 20).await;
+
+        let revision = snapshot(&client.connection, schedule, Some("Published".to_owned()))
+            .await
+            .unwrap();
+
+        ActiveScheduleGame {
+            id: Set(moved),
+            start: Set(at(21).timestamp_millis()),
+            end: Set(at(22).timestamp_millis()),
+            ..Default::default()
+        }
+        .update(&client.connection)
+        .await
+        .unwrap();
+        ActiveScheduleGame {
+            id: Set(refielded),
+            field_id: Set(Some(south)),
+            ..Default::default()
+        }
+        .update(&client.connection)
+        .await
+        .unwrap();
+        ScheduleGameEntity::delete_by_id(removed)
+            .exec(&client.connection)
+            .await
+            .unwrap();
+        let added = game(&client, schedule, south, (teams[0], Some(teams[2])), 22).await;
+
+        let diff = diff(
+            &client.connection,
+            ScheduleSource::Revision(revision.id),
+            ScheduleSource::Schedule(schedule),
+        )
+        .await
+        .unwrap();
+
+        let ids = |games: &[DiffGame]| games.iter().map(DiffGame::game_id).collect::<Vec<_>>();
+        assert_eq!(ids(diff.added()), [added]);
+        assert_eq!(ids(diff.removed()), [removed]);
+        assert_eq!(
+            diff.changed()
+                .iter()
+                .map(|change| (change.after().game_id(), change.changes().to_vec()))
+                .collect::<Vec<_>>(),
+            [
+                (refielded, vec![GameChangeKind::Field]),
+                (moved, vec![GameChangeKind::Time]),
+            ]
+        );
+
+        // 18:00 UTC is 11:00 in Los Angeles in June
+        assert_eq!(
+            diff.to_string(),
+            format!(
+                "Changes from revision #{} to schedule #{schedule}
+Added: A vs C at 2024-06-01 15:00 PDT on Field
+Removed: E vs F at 2024-06-01 13:00 PDT on Field
+Changed (field): C vs D at 2024-06-01 12:00 PDT on Field -> C vs D at 2024-06-01 12:00 PDT on Field
+Changed (time): A vs B at 2024-06-01 11:00 PDT on Field -> A vs B at 2024-06-01 14:00 PDT on Field
+",
+                revision.id
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn unchanged_schedule_has_an_empty_diff() {
+        let client = client().await;
+        let region = region_in(&client, "Europe/Berlin").await;
+        let (field, _) = field(&client, region).await;
+        let home = team(&client, region, "Home").await;
+
+        let schedule = schedule(&client).await;
+        game(&client, schedule, field, (home, None), 9).await;
+
+        let revision = snapshot(&client.connection, schedule, None).await.unwrap();
+        let diff = diff(
+            &client.connection,
+            ScheduleSource::Revision(revision.id),
+            ScheduleSource::Schedule(schedule),
+        )
+        .await
+        .unwrap();
+
+        assert!(diff.is_empty());
+        assert!(diff.to_string().ends_with("No changes\n"));
+    }
+
+    #[tokio::test]
+    async fn malformed_game_leaves_no_revision() {
+        let client = client().await;
+        let region = region_in(&client, "Europe/Berlin").await;
+        let (field, _) = field(&client, region).await;
+        let home = team(&client, region, "Home").await;
+
+        let schedule = schedule(&client).await;
+        game(&client, schedule, field, (home, None), 9).await;
+        let malformed = game(&client, schedule, field, (home, None), 10).await;
+
+        ActiveScheduleGame {
+            id: Set(malformed),
+            start: Set(i64::MAX),
+            ..Default::default()
+        }
+        .update(&client.connection)
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            client.snapshot_schedule(schedule, None).await,
+            Err(ScheduleRevisionError::MalformedGame(id)) if id == malformed
+        ));
+        assert!(client
+            .get_schedule_revisions(schedule)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(schedule_revision_game::Entity::find()
+            .all(&client.connection)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
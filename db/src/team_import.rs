@@ -0,0 +1,736 @@
+//! Bulk team import from CSV. Every row becomes a team; its groups are created when
+//! they do not exist yet, and teams that share a coach within a region are put in the
+//! same coach conflict, which is reused if the region already has one for that coach.
+//!
+//! An import is all or nothing: if any row is invalid, nothing is written.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Display;
+
+use entity::{coach_conflict, coach_conflict_team_join, team_group, team_group_join};
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, Set};
+use serde::{Deserialize, Serialize};
+
+use crate::entity_local_exports::{
+    ActiveCoachConflict, ActiveTeam, ActiveTeamGroup, CoachConflictEntity, Region, TeamGroupEntity,
+};
+use crate::errors::{ImportTeamsError, NameMax64ValidationError};
+use crate::{NameMax64, Validator};
+
+/// The headers of the columns to read. Optional columns that are missing from the
+/// file are skipped.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamImportColumns {
+    name: String,
+    /// Region title or id. [`Option#None`] puts every team in [`ImportTeamsInput`]'s region.
+    region: Option<String>,
+    /// Group tags, separated by `;` or `,`
+    groups: Option<String>,
+    coach: Option<String>,
+}
+
+impl Default for TeamImportColumns {
+    fn default() -> Self {
+        Self {
+            name: "name".to_owned(),
+            region: Some("region".to_owned()),
+            groups: Some("groups".to_owned()),
+            coach: Some("coach".to_owned()),
+        }
+    }
+}
+
+impl TeamImportColumns {
+    pub fn new(
+        name: impl Into<String>,
+        region: Option<String>,
+        groups: Option<String>,
+        coach: Option<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            region,
+            groups,
+            coach,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportTeamsInput {
+    csv: String,
+    #[serde(default)]
+    columns: TeamImportColumns,
+    /// Used for rows without a region
+    #[serde(default)]
+    region_id: Option<i32>,
+    /// Only preview the import
+    #[serde(default)]
+    dry_run: bool,
+}
+
+impl ImportTeamsInput {
+    pub fn new(
+        csv: impl Into<String>,
+        columns: TeamImportColumns,
+        region_id: Option<i32>,
+        dry_run: bool,
+    ) -> Self {
+        Self {
+            csv: csv.into(),
+            columns,
+            region_id,
+            dry_run,
+        }
+    }
+
+    pub const fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+}
+
+/// A team that will be, or was, created. `line` is the line of the row in the file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamImportRow {
+    line: u64,
+    name: String,
+    region_id: i32,
+    groups: Vec<String>,
+    coach: Option<String>,
+}
+
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TeamImportRowErrorKind {
+    #[error("team name: {0}")]
+    Name(NameMax64ValidationError),
+    #[error("group `{group}`: {error}")]
+    Group {
+        group: String,
+        error: NameMax64ValidationError,
+    },
+    #[error("coach name: {0}")]
+    Coach(NameMax64ValidationError),
+    #[error("no region was given")]
+    MissingRegion,
+    #[error("region `{0}` does not exist")]
+    UnknownRegion(String),
+    #[error("the same team is already on line {0}")]
+    Duplicate(u64),
+    #[error("could not read the row: {0}")]
+    Malformed(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamImportRowError {
+    line: u64,
+    kind: TeamImportRowErrorKind,
+}
+
+impl Display for TeamImportRowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TeamImportCoach {
+    region_id: i32,
+    coach_name: String,
+    /// [`Option#None`] if the coach conflict is new
+    coach_conflict_id: Option<i32>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeamImportReport {
+    teams: Vec<TeamImportRow>,
+    errors: Vec<TeamImportRowError>,
+    groups_created: Vec<String>,
+    coaches_created: Vec<TeamImportCoach>,
+    coaches_merged: Vec<TeamImportCoach>,
+    /// `false` for a dry run, or if any row had an error
+    committed: bool,
+}
+
+impl TeamImportReport {
+    pub fn errors(&self) -> &[TeamImportRowError] {
+        &self.errors
+    }
+
+    pub const fn committed(&self) -> bool {
+        self.committed
+    }
+}
+
+impl Display for TeamImportReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} teams, {} new groups, {} new coach conflicts, {} merged into existing ones",
+            self.teams.len(),
+            self.groups_created.len(),
+            self.coaches_created.len(),
+            self.coaches_merged.len(),
+        )?;
+
+        if !self.groups_created.is_empty() {
+            writeln!(f, "new groups: {}", self.groups_created.join(", "))?;
+        }
+
+        for error in &self.errors {
+            writeln!(f, "{error}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Spreadsheet apps often start a CSV file with a byte order mark, which would otherwise
+/// end up in the first header.
+const BOM: char = '\u{feff}';
+
+fn header_index(headers: &csv::StringRecord, column: &str) -> Option<usize> {
+    headers
+        .iter()
+        .position(|header| header.trim().eq_ignore_ascii_case(column.trim()))
+}
+
+fn validate(name: &str) -> Result<String, NameMax64ValidationError> {
+    let name = NameMax64(name.trim().to_owned());
+    name.validate()?;
+    Ok(name.0)
+}
+
+/// Reads every row of `input`, without touching the database. `regions` are used to
+/// look up the region column by title or id.
+pub(crate) fn parse(
+    input: &ImportTeamsInput,
+    regions: &[Region],
+) -> Result<(Vec<TeamImportRow>, Vec<TeamImportRowError>), ImportTeamsError> {
+    let csv = input.csv.strip_prefix(BOM).unwrap_or(&input.csv);
+
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(csv.as_bytes());
+
+    let headers = reader
+        .headers()
+        .map_err(|e| ImportTeamsError::Csv(e.to_string()))?
+        .clone();
+
+    let name_index = header_index(&headers, &input.columns.name)
+        .ok_or_else(|| ImportTeamsError::MissingColumn(input.columns.name.clone()))?;
+
+    let optional_index = |column: &Option<String>| {
+        column
+            .as_deref()
+            .and_then(|column| header_index(&headers, column))
+    };
+
+    let region_index = optional_index(&input.columns.region);
+    let groups_index = optional_index(&input.columns.groups);
+    let coach_index = optional_index(&input.columns.coach);
+
+    let mut teams = vec![];
+    let mut errors = vec![];
+    let mut seen: HashMap<(i32, String), u64> = HashMap::new();
+
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(TeamImportRowError {
+                    line: e.position().map_or(0, |position| position.line()),
+                    kind: TeamImportRowErrorKind::Malformed(e.to_string()),
+                });
+                continue;
+            }
+        };
+
+        let line = record.position().map_or(0, |position| position.line());
+
+        let cell = |index: Option<usize>| {
+            index
+                .and_then(|index| record.get(index))
+                .filter(|cell| !cell.is_empty())
+        };
+
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+
+        let mut fail = |kind| errors.push(TeamImportRowError { line, kind });
+
+        let name = match validate(cell(Some(name_index)).unwrap_or_default()) {
+            Ok(name) => name,
+            Err(e) => {
+                fail(TeamImportRowErrorKind::Name(e));
+                continue;
+            }
+        };
+
+        let region_id = match cell(region_index) {
+            Some(region) => {
+                let found = regions.iter().find(|candidate| {
+                    candidate.title.trim().eq_ignore_ascii_case(region)
+                        || region.parse() == Ok(candidate.id)
+                });
+
+                match found {
+                    Some(region) => region.id,
+                    None => {
+                        fail(TeamImportRowErrorKind::UnknownRegion(region.to_owned()));
+                        continue;
+                    }
+                }
+            }
+            None => match input.region_id {
+                Some(region_id) => region_id,
+                None => {
+                    fail(TeamImportRowErrorKind::MissingRegion);
+                    continue;
+                }
+            },
+        };
+
+        let mut groups = vec![];
+        let mut bad_group = false;
+
+        for group in cell(groups_index)
+            .unwrap_or_default()
+            .split([';', ','])
+            .map(str::trim)
+            .filter(|group| !group.is_empty())
+        {
+            match validate(group) {
+                Ok(group) => {
+                    if !groups
+                        .iter()
+                        .any(|existing: &String| existing.eq_ignore_ascii_case(&group))
+                    {
+                        groups.push(group);
+                    }
+                }
+                Err(error) => {
+                    fail(TeamImportRowErrorKind::Group {
+                        group: group.to_owned(),
+                        error,
+                    });
+                    bad_group = true;
+                }
+            }
+        }
+
+        if bad_group {
+            continue;
+        }
+
+        let coach = match cell(coach_index).map(validate).transpose() {
+            Ok(coach) => coach,
+            Err(e) => {
+                fail(TeamImportRowErrorKind::Coach(e));
+                continue;
+            }
+        };
+
+        if let Some(first_line) = seen.insert((region_id, name.to_lowercase()), line) {
+            fail(TeamImportRowErrorKind::Duplicate(first_line));
+            continue;
+        }
+
+        teams.push(TeamImportRow {
+            line,
+            name,
+            region_id,
+            groups,
+            coach,
+        });
+    }
+
+    Ok((teams, errors))
+}
+
+/// What an import would change, given the groups and coach conflicts that already exist.
+pub(crate) async fn plan<C>(
+    connection: &C,
+    teams: Vec<TeamImportRow>,
+    errors: Vec<TeamImportRowError>,
+) -> Result<TeamImportReport, ImportTeamsError>
+where
+    C: ConnectionTrait,
+{
+    let existing_groups = TeamGroupEntity::find()
+        .all(connection)
+        .await
+        .map_err(|e| ImportTeamsError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?;
+
+    let mut groups_created = BTreeMap::new();
+
+    for group in teams.iter().flat_map(|team| &team.groups) {
+        let key = group.to_lowercase();
+        if !existing_groups
+            .iter()
+            .any(|existing| existing.name.to_lowercase() == key)
+        {
+            groups_created.entry(key).or_insert_with(|| group.clone());
+        }
+    }
+
+    let region_ids = teams
+        .iter()
+        .map(|team| team.region_id)
+        .collect::<BTreeSet<_>>();
+
+    let existing_coaches = CoachConflictEntity::find()
+        .filter(coach_conflict::Column::Region.is_in(region_ids))
+        .all(connection)
+        .await
+        .map_err(|e| ImportTeamsError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?;
+
+    let mut coaches = BTreeMap::new();
+
+    for team in &teams {
+        let Some(ref coach_name) = team.coach else {
+            continue;
+        };
+
+        coaches
+            .entry((team.region_id, coach_name.to_lowercase()))
+            .or_insert_with(|| TeamImportCoach {
+                region_id: team.region_id,
+                coach_name: coach_name.clone(),
+                coach_conflict_id: existing_coaches
+                    .iter()
+                    .find(|existing| {
+                        existing.region == team.region_id
+                            && existing
+                                .coach_name
+                                .as_deref()
+                                .is_some_and(|name| name.trim().eq_ignore_ascii_case(coach_name))
+                    })
+                    .map(|existing| existing.id),
+            });
+    }
+
+    let (coaches_merged, coaches_created) = coaches
+        .into_values()
+        .partition(|coach| coach.coach_conflict_id.is_some());
+
+    Ok(TeamImportReport {
+        teams,
+        errors,
+        groups_created: groups_created.into_values().collect(),
+        coaches_created,
+        coaches_merged,
+        committed: false,
+    })
+}
+
+/// Writes a planned import. Must be run inside the transaction that planned it, so that
+/// the groups and coaches it found still exist, and a failure half way through leaves
+/// nothing behind.
+pub(crate) async fn commit<C>(
+    connection: &C,
+    mut report: TeamImportReport,
+) -> Result<TeamImportReport, ImportTeamsError>
+where
+    C: ConnectionTrait,
+{
+    for group in &report.groups_created {
+        ActiveTeamGroup {
+            name: Set(group.clone()),
+            ..Default::default()
+        }
+        .insert(connection)
+        .await
+        .map_err(|e| ImportTeamsError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?;
+    }
+
+    let group_ids = TeamGroupEntity::find()
+        .all(connection)
+        .await
+        .map_err(|e| ImportTeamsError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?
+        .into_iter()
+        .map(|group| (group.name.to_lowercase(), group.id))
+        .collect::<HashMap<_, _>>();
+
+    let mut coach_ids = HashMap::new();
+
+    for coach in &report.coaches_merged {
+        let coach_conflict_id = coach
+            .coach_conflict_id
+            .ok_or_else(|| ImportTeamsError::CoachNotFound(coach.coach_name.clone()))?;
+
+        coach_ids.insert(
+            (coach.region_id, coach.coach_name.to_lowercase()),
+            coach_conflict_id,
+        );
+    }
+
+    for coach in &mut report.coaches_created {
+        let model = ActiveCoachConflict {
+            coach_name: Set(Some(coach.coach_name.clone())),
+            region: Set(coach.region_id),
+            ..Default::default()
+        }
+        .insert(connection)
+        .await
+        .map_err(|e| ImportTeamsError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?;
+
+        coach.coach_conflict_id = Some(model.id);
+        coach_ids.insert((coach.region_id, coach.coach_name.to_lowercase()), model.id);
+    }
+
+    let mut usages: BTreeMap<i32, i32> = BTreeMap::new();
+
+    for row in &report.teams {
+        let team = ActiveTeam {
+            name: Set(row.name.clone()),
+            region_owner: Set(row.region_id),
+            ..Default::default()
+        }
+        .insert(connection)
+        .await
+        .map_err(|e| ImportTeamsError::DatabaseError(format!("{e} {}:{}", line!(), column!())))?;
+
+        for group in &row.groups {
+            let group_id = *group_ids
+                .get(&group.to_lowercase())
+                .ok_or_else(|| ImportTeamsError::GroupNotFound(group.clone()))?;
+
+            team_group_join::ActiveModel {
+                team: Set(team.id),
+                group: Set(group_id),
+            }
+            .insert(connection)
+            .await
+            .map_err(|e| {
+                ImportTeamsError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+            })?;
+
+            *usages.entry(group_id).or_default() += 1;
+        }
+
+        if let Some(ref coach) = row.coach {
+            let coach_conflict_id = *coach_ids
+                .get(&(row.region_id, coach.to_lowercase()))
+                .ok_or_else(|| ImportTeamsError::CoachNotFound(coach.clone()))?;
+
+            coach_conflict_team_join::ActiveModel {
+                coach_conflict: Set(coach_conflict_id),
+                team: Set(team.id),
+            }
+            .insert(connection)
+            .await
+            .map_err(|e| {
+                ImportTeamsError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+            })?;
+        }
+    }
+
+    for (group_id, count) in usages {
+        TeamGroupEntity::update_many()
+            .filter(team_group::Column::Id.eq(group_id))
+            .col_expr(
+                team_group::Column::Usages,
+                migration::Expr::add(migration::Expr::col(team_group::Column::Usages), count),
+            )
+            .exec(connection)
+            .await
+            .map_err(|e| {
+                ImportTeamsError::DatabaseError(format!("{e} {}:{}", line!(), column!()))
+            })?;
+    }
+
+    report.committed = true;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, plan, ImportTeamsInput, TeamImportColumns, TeamImportRowErrorKind, BOM};
+    use crate::entity_local_exports::Region;
+    use crate::fixtures::{client, region};
+    use crate::CreateCoachConflictInput;
+
+    const CSV: &str = "name,region,groups,coach
+Rockies,North,U10; Boys,Sam Lee
+Purple Dragons,North,U10; Girls,Sam Lee
+Green Machine,North,U10; Boys,
+";
+
+    fn north() -> [Region; 1] {
+        [Region {
+            id: 1,
+            title: "North".to_owned(),
+            time_zone: "UTC".to_owned(),
+        }]
+    }
+
+    fn input(csv: impl Into<String>) -> ImportTeamsInput {
+        ImportTeamsInput::new(csv, TeamImportColumns::default(), None, true)
+    }
+
+    #[test]
+    fn reads_files_with_and_without_a_byte_order_mark() {
+        let (teams, errors) = parse(&input(CSV), &north()).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(
+            teams
+                .iter()
+                .map(|team| (team.name.as_str(), team.region_id, team.coach.as_deref()))
+                .collect::<Vec<_>>(),
+            [
+                ("Rockies", 1, Some("Sam Lee")),
+                ("Purple Dragons", 1, Some("Sam Lee")),
+                ("Green Machine", 1, None),
+            ]
+        );
+        assert_eq!(teams[0].groups, ["U10", "Boys"]);
+
+        let with_bom = parse(&input(format!("{BOM}{CSV}")), &north()).unwrap();
+        assert_eq!(with_bom, (teams, errors));
+    }
+
+    #[test]
+    fn reports_bad_rows() {
+        let csv = "name,region
+Rockies,North
+rockies,North
+Dragons,South
+";
+        let (teams, errors) = parse(&input(csv), &north()).unwrap();
+        assert_eq!(teams.len(), 1);
+        assert_eq!(
+            errors
+                .into_iter()
+                .map(|error| (error.line, error.kind))
+                .collect::<Vec<_>>(),
+            [
+                (3, TeamImportRowErrorKind::Duplicate(2)),
+                (4, TeamImportRowErrorKind::UnknownRegion("South".to_owned())),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn plans_new_groups_and_reuses_coach_conflicts() {
+        let client = client().await;
+        let region = region(&client).await;
+
+        client.create_group("U10".to_owned()).await.unwrap();
+        let sam_lee = client
+            .create_coaching_conflict(CreateCoachConflictInput {
+                region_id: region,
+                coach_name: Some("sam lee".to_owned()),
+            })
+            .await
+            .unwrap();
+
+        let csv = CSV.replace("North", &region.to_string()) + "Red Rubies,,Girls,Alex Kim\n";
+        let input = ImportTeamsInput::new(csv, TeamImportColumns::default(), Some(region), true);
+
+        let (teams, errors) = parse(&input, &client.get_regions().await.unwrap()).unwrap();
+        let report = plan(&client.connection, teams, errors).await.unwrap();
+
+        assert!(report.errors.is_empty());
+        assert_eq!(report.teams.len(), 4);
+        assert_eq!(report.groups_created, ["Boys", "Girls"]);
+        assert_eq!(
+            report
+                .coaches_merged
+                .iter()
+                .map(|coach| (coach.coach_name.as_str(), coach.coach_conflict_id))
+                .collect::<Vec<_>>(),
+            [("Sam Lee", Some(sam_lee.id))]
+        );
+        assert_eq!(
+            report
+                .coaches_created
+                .iter()
+                .map(|coach| coach.coach_name.as_str())
+                .collect::<Vec<_>>(),
+            ["Alex Kim"]
+        );
+        assert!(!report.committed);
+    }
+
+    #[tokio::test]
+    async fn commits_teams_groups_and_coaches() {
+        let client = client().await;
+        let region = region(&client).await;
+
+        client.create_group("U10".to_owned()).await.unwrap();
+        let sam_lee = client
+            .create_coaching_conflict(CreateCoachConflictInput {
+                region_id: region,
+                coach_name: Some("Sam Lee".to_owned()),
+            })
+            .await
+            .unwrap();
+
+        let csv = CSV.replace("North", &region.to_string());
+        let dry_run = ImportTeamsInput::new(&csv, TeamImportColumns::default(), None, true);
+        assert!(!client.import_teams(dry_run).await.unwrap().committed);
+        assert!(client.get_teams(region).await.unwrap().is_empty());
+
+        let input = ImportTeamsInput::new(csv, TeamImportColumns::default(), None, false);
+        let report = client.import_teams(input).await.unwrap();
+        assert!(report.committed);
+        assert_eq!(report.coaches_created, []);
+
+        let mut teams = client
+            .get_teams_with_tags(region)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|team_ext| {
+                let mut tags = team_ext
+                    .tags
+                    .into_iter()
+                    .map(|tag| tag.name)
+                    .collect::<Vec<_>>();
+                tags.sort();
+                format!("{}: {}", team_ext.team.name, tags.join(", "))
+            })
+            .collect::<Vec<_>>();
+        teams.sort();
+        assert_eq!(
+            teams,
+            [
+                "Green Machine: Boys, U10",
+                "Purple Dragons: Girls, U10",
+                "Rockies: Boys, U10",
+            ]
+        );
+
+        let mut groups = client
+            .get_groups()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|group| (group.name, group.usages))
+            .collect::<Vec<_>>();
+        groups.sort();
+        assert_eq!(
+            groups,
+            [
+                ("Boys".to_owned(), 2),
+                ("Girls".to_owned(), 1),
+                ("U10".to_owned(), 3)
+            ]
+        );
+
+        let coaches = client.get_coach_conflicts(region).await.unwrap();
+        assert_eq!(coaches.len(), 1);
+        assert_eq!(coaches[0].id, sam_lee.id);
+        let mut coached = coaches[0]
+            .teams
+            .iter()
+            .map(|team| team.name.as_str())
+            .collect::<Vec<_>>();
+        coached.sort();
+        assert_eq!(coached, ["Purple Dragons", "Rockies"]);
+    }
+}
@@ -0,0 +1,74 @@
+//! Every region has an IANA time zone. Dates are always stored in UTC; anything
+//! that reasons about calendar days or wall-clock times (copying a week of time
+//! slots, day-based rules, exports) converts through the zone of the field's region.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use backend::Tz;
+use entity::{field, region};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, JoinType, QueryFilter, QuerySelect,
+    RelationTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::entity_local_exports::FieldEntity;
+use crate::{TimeZoneValidationError, Validator};
+
+/// An IANA time zone name, such as `America/Los_Angeles`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(transparent)]
+pub struct TimeZoneName(pub(crate) String);
+
+impl Validator for TimeZoneName {
+    type Error = TimeZoneValidationError;
+    fn validate(&self) -> Result<(), Self::Error> {
+        Tz::from_str(&self.0)
+            .map(|_| ())
+            .map_err(|_| TimeZoneValidationError::Unknown(self.0.clone()))
+    }
+}
+
+/// Read a time zone that was stored by this crate. Values are validated before they
+/// are written, so falling back to UTC only happens if the database was edited by hand.
+pub(crate) fn parse_time_zone(name: &str) -> Tz {
+    Tz::from_str(name).unwrap_or(Tz::UTC)
+}
+
+pub(crate) async fn field_time_zone<C>(connection: &C, field_id: i32) -> Result<Tz, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let time_zone = FieldEntity::find()
+        .select_only()
+        .column(region::Column::TimeZone)
+        .join(JoinType::Join, field::Relation::Region.def())
+        .filter(field::Column::Id.eq(field_id))
+        .into_tuple::<String>()
+        .one(connection)
+        .await?
+        .ok_or(DbErr::RecordNotFound(format!("field {field_id}")))?;
+
+    Ok(parse_time_zone(&time_zone))
+}
+
+/// The zone of every field that belongs to a region, by field id.
+pub(crate) async fn field_time_zones<C>(connection: &C) -> Result<HashMap<i32, Tz>, DbErr>
+where
+    C: ConnectionTrait,
+{
+    let time_zones = FieldEntity::find()
+        .select_only()
+        .column(field::Column::Id)
+        .column(region::Column::TimeZone)
+        .join(JoinType::Join, field::Relation::Region.def())
+        .into_tuple::<(i32, String)>()
+        .all(connection)
+        .await?
+        .into_iter()
+        .map(|(field_id, time_zone)| (field_id, parse_time_zone(&time_zone)))
+        .collect();
+
+    Ok(time_zones)
+}
@@ -0,0 +1,62 @@
+//! `start` and `end` of time slots, schedule games, blackouts, field closures and revision
+//! games are stored as UNIX milliseconds, so that they compare and index as numbers whatever offset they
+//! were entered with.
+
+use backend::AvailabilityWindow;
+use chrono::{DateTime, Utc};
+use entity::schedule_revision_game;
+
+use crate::entity_local_exports::{Blackout, FieldClosure, ScheduleGame, TimeSlot};
+
+/// `None` for values outside of what [`DateTime`] can hold, which only a hand edit of the
+/// database can produce.
+pub(crate) fn from_millis(millis: i64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp_millis(millis)
+}
+
+pub(crate) trait Timespan {
+    fn millis(&self) -> (i64, i64);
+
+    fn start_time(&self) -> Option<DateTime<Utc>> {
+        from_millis(self.millis().0)
+    }
+
+    fn end_time(&self) -> Option<DateTime<Utc>> {
+        from_millis(self.millis().1)
+    }
+
+    /// `None` if the span is out of range, or does not end after it starts.
+    fn window(&self) -> Option<AvailabilityWindow> {
+        AvailabilityWindow::new(self.start_time()?, self.end_time()?).ok()
+    }
+}
+
+impl Timespan for TimeSlot {
+    fn millis(&self) -> (i64, i64) {
+        (self.start, self.end)
+    }
+}
+
+impl Timespan for ScheduleGame {
+    fn millis(&self) -> (i64, i64) {
+        (self.start, self.end)
+    }
+}
+
+impl Timespan for Blackout {
+    fn millis(&self) -> (i64, i64) {
+        (self.start, self.end)
+    }
+}
+
+impl Timespan for FieldClosure {
+    fn millis(&self) -> (i64, i64) {
+        (self.start, self.end)
+    }
+}
+
+impl Timespan for schedule_revision_game::Model {
+    fn millis(&self) -> (i64, i64) {
+        (self.start, self.end)
+    }
+}
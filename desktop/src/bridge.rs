@@ -1,9 +1,18 @@
 use std::borrow::Cow;
 use std::path::Path;
 
+use backend::standings::StandingsConfig;
+use backend::validation::Violation;
+use backend::RematchSpacing;
 use backend::ScheduledInput;
 use base64::Engine;
-use db::{errors::*, CoachConflictTeamInput, CreateCoachConflictInput, NameMax64, RegionMetadata};
+use db::{
+    errors::*, AuditQuery, CalendarOwner, CoachConflictTeamInput, CreateBlackoutInput,
+    CreateCoachConflictInput, CreateFieldClosureInput, EditReport, FieldClosureReport,
+    ImportTeamsInput, MakeupProposal, NameMax64, ProfileBundle, ProfileImportReport, Rainout,
+    RainoutInput, RecordGameResultInput, RegionMetadata, ScheduleDiff, ScheduleExportFilter,
+    ScheduleGameEdit, ScheduleSource, Standings, TeamImportReport,
+};
 use db::{
     CoachConflict, CopyTimeSlotsInput, CreateFieldInput, CreateRegionInput,
     CreateReservationTypeInput, CreateTeamInput, CreateTimeSlotInput, EditRegionInput,
@@ -334,7 +343,7 @@ pub(crate) async fn create_time_slot(
 pub(crate) async fn move_time_slot(
     app: AppHandle,
     input: MoveTimeSlotInput,
-) -> Result<(), TimeSlotError> {
+) -> Result<EditReport, TimeSlotError> {
     let state = app.state::<SafeAppState>();
     let lock = state.0.lock().await;
     let client = lock.database.as_ref().ok_or(TimeSlotError::NoDatabase)?;
@@ -358,6 +367,7 @@ pub(crate) async fn delete_time_slot(
     client
         .delete_time_slot(id, schedule_id)
         .await
+        .map(|_| ())
         .map_err(|e| e.to_string())
 }
 
@@ -668,6 +678,7 @@ pub(crate) async fn generate_schedule_payload(
 pub(crate) async fn schedule(
     app: AppHandle,
     authorization_token: String,
+    top_k: Option<u8>,
 ) -> Result<db::schedule::Model, ScheduleRequestError> {
     let state = app.state::<SafeAppState>();
     let lock = state.0.lock().await;
@@ -676,11 +687,18 @@ pub(crate) async fn schedule(
         .as_ref()
         .ok_or(ScheduleRequestError::NoDatabase)?;
 
-    let input = client
+    let mut input = client
         .get_scheduled_inputs()
         .await
         .map_err(|e| ScheduleRequestError::DatabaseError(e.to_string()))?;
 
+    if let Some(top_k) = top_k {
+        input = input
+            .into_iter()
+            .map(|scheduled_input| scheduled_input.with_top_k(top_k))
+            .collect();
+    }
+
     let scheduled_output = send_grpc_schedule_request(&input, authorization_token).await?;
 
     // force error conversion with `?`
@@ -1094,9 +1112,16 @@ pub(crate) async fn set_active_profile(
 
     let db_config = db::Config::new(db_path);
 
-    lock.database = Some(db::Client::new(&db_config).await.map_err(|e| {
+    let database = db::Client::new(&db_config).await.map_err(|e| {
         SelectProfileError::DatabaseInitError(format!("{e:?} {}:{}", line!(), column!()))
-    })?);
+    })?;
+
+    database
+        .set_audit_user(lock.audit_user.clone())
+        .await
+        .map_err(|e| SelectProfileError::DatabaseInitError(e.to_string()))?;
+
+    lock.database = Some(database);
 
     let active_profile_record = name
         .clone()
@@ -1346,7 +1371,11 @@ pub async fn set_reservation_type_practice(
 }
 
 #[tauri::command]
-pub async fn swap_schedule_games(app: AppHandle, a: i32, b: i32) -> Result<bool, String> {
+pub async fn set_reservation_type_rematch_spacing(
+    app: AppHandle,
+    reservation_type_id: i32,
+    rematch_spacing: Option<RematchSpacing>,
+) -> Result<db::reservation_type::Model, String> {
     let state = app.state::<SafeAppState>();
     let lock = state.0.lock().await;
     let client = lock
@@ -1355,7 +1384,539 @@ pub async fn swap_schedule_games(app: AppHandle, a: i32, b: i32) -> Result<bool,
         .ok_or("database was not initialized".to_owned())?;
 
     client
-        .swap_schedule_games(a, b)
+        .set_reservation_type_rematch_spacing(reservation_type_id, rematch_spacing)
         .await
         .map_err(|e| format!("{}:{} {e}", file!(), line!()))
 }
+
+#[tauri::command]
+pub async fn swap_schedule_games(
+    app: AppHandle,
+    a: i32,
+    b: i32,
+    force: Option<bool>,
+) -> Result<Option<EditReport>, TimeSlotError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(TimeSlotError::NoDatabase)?;
+
+    client
+        .swap_schedule_games(a, b, force.unwrap_or_default())
+        .await
+}
+
+#[tauri::command]
+pub(crate) async fn rain_out(app: AppHandle, input: RainoutInput) -> Result<Rainout, RainoutError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(RainoutError::NoDatabase)?;
+
+    client.rain_out(input).await
+}
+
+#[tauri::command]
+pub(crate) async fn replace_closed_games(
+    app: AppHandle,
+    closure_id: i32,
+    schedule_id: i32,
+) -> Result<Rainout, RainoutError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(RainoutError::NoDatabase)?;
+
+    client.replace_closed_games(closure_id, schedule_id).await
+}
+
+#[tauri::command]
+pub(crate) async fn apply_makeups(
+    app: AppHandle,
+    schedule_id: i32,
+    proposals: Vec<MakeupProposal>,
+    force: Option<bool>,
+) -> Result<EditReport, TimeSlotError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(TimeSlotError::NoDatabase)?;
+
+    client
+        .apply_makeups(schedule_id, &proposals, force.unwrap_or_default())
+        .await
+}
+
+#[tauri::command]
+pub(crate) async fn check_schedule_edit(
+    app: AppHandle,
+    schedule_id: i32,
+    edits: Vec<ScheduleGameEdit>,
+    min_rest_minutes: Option<i64>,
+) -> Result<EditReport, TimeSlotError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(TimeSlotError::NoDatabase)?;
+
+    client
+        .check_schedule_edit(
+            schedule_id,
+            &edits,
+            min_rest_minutes.map(chrono::TimeDelta::minutes),
+        )
+        .await
+}
+
+#[tauri::command]
+pub(crate) async fn create_blackout(
+    app: AppHandle,
+    input: CreateBlackoutInput,
+) -> Result<db::blackout::Model, BlackoutError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(BlackoutError::NoDatabase)?;
+
+    client.create_blackout(input).await
+}
+
+#[tauri::command]
+pub(crate) async fn get_blackouts(
+    app: AppHandle,
+    region_id: Option<i32>,
+) -> Result<Vec<db::blackout::Model>, BlackoutError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(BlackoutError::NoDatabase)?;
+
+    client.get_blackouts(region_id).await
+}
+
+#[tauri::command]
+pub(crate) async fn delete_blackout(app: AppHandle, id: i32) -> Result<(), BlackoutError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(BlackoutError::NoDatabase)?;
+
+    client.delete_blackout(id).await
+}
+
+#[tauri::command]
+pub(crate) async fn close_field(
+    app: AppHandle,
+    input: CreateFieldClosureInput,
+) -> Result<FieldClosureReport, FieldClosureError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock
+        .database
+        .as_ref()
+        .ok_or(FieldClosureError::NoDatabase)?;
+
+    client.close_field(input).await
+}
+
+#[tauri::command]
+pub(crate) async fn get_field_closures(
+    app: AppHandle,
+    field_id: Option<i32>,
+) -> Result<Vec<db::field_closure::Model>, FieldClosureError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock
+        .database
+        .as_ref()
+        .ok_or(FieldClosureError::NoDatabase)?;
+
+    client.get_field_closures(field_id).await
+}
+
+#[tauri::command]
+pub(crate) async fn get_field_closure_report(
+    app: AppHandle,
+    id: i32,
+) -> Result<FieldClosureReport, FieldClosureError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock
+        .database
+        .as_ref()
+        .ok_or(FieldClosureError::NoDatabase)?;
+
+    client.get_field_closure_report(id).await
+}
+
+#[tauri::command]
+pub(crate) async fn delete_closed_time_slots(
+    app: AppHandle,
+    id: i32,
+) -> Result<u64, FieldClosureError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock
+        .database
+        .as_ref()
+        .ok_or(FieldClosureError::NoDatabase)?;
+
+    client.delete_closed_time_slots(id).await
+}
+
+#[tauri::command]
+pub(crate) async fn delete_field_closure(app: AppHandle, id: i32) -> Result<(), FieldClosureError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock
+        .database
+        .as_ref()
+        .ok_or(FieldClosureError::NoDatabase)?;
+
+    client.delete_field_closure(id).await
+}
+
+#[tauri::command]
+pub(crate) async fn validate_schedule(
+    app: AppHandle,
+    schedule_id: i32,
+    min_rest_minutes: Option<i64>,
+) -> Result<Vec<Violation>, ValidateScheduleError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock
+        .database
+        .as_ref()
+        .ok_or(ValidateScheduleError::NoDatabase)?;
+
+    client
+        .validate_schedule(
+            schedule_id,
+            min_rest_minutes.map(chrono::TimeDelta::minutes),
+        )
+        .await
+}
+
+/// Writes every scheduler input of the active profile to `path`, see [`backend::problem`].
+#[tauri::command]
+pub(crate) async fn export_problem_file(app: AppHandle, path: String) -> Result<(), String> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock
+        .database
+        .as_ref()
+        .ok_or("database was not initialized".to_owned())?;
+
+    let inputs = client
+        .get_scheduled_inputs()
+        .await
+        .map_err(|e| format!("{}:{} {e}", file!(), line!()))?;
+
+    let json = backend::problem::ProblemFile::new(&inputs)
+        .to_json()
+        .map_err(|e| format!("{}:{} {e}", file!(), line!()))?;
+
+    std::fs::write(path, json).map_err(|e| format!("{}:{} {e}", file!(), line!()))
+}
+
+#[tauri::command]
+pub(crate) async fn import_teams(
+    app: AppHandle,
+    input: ImportTeamsInput,
+) -> Result<TeamImportReport, ImportTeamsError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(ImportTeamsError::NoDatabase)?;
+
+    client.import_teams(input).await
+}
+
+/// Writes the games of a saved schedule that pass `filter` to `path` as CSV.
+#[tauri::command]
+pub(crate) async fn export_schedule_csv(
+    app: AppHandle,
+    schedule_id: i32,
+    filter: ScheduleExportFilter,
+    path: String,
+) -> Result<(), ExportScheduleError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock
+        .database
+        .as_ref()
+        .ok_or(ExportScheduleError::NoDatabase)?;
+
+    let csv = client.export_schedule_csv(schedule_id, &filter).await?;
+
+    std::fs::write(path, csv).map_err(|e| ExportScheduleError::IOError(e.to_string()))
+}
+
+/// Writes an `.ics` calendar for each of `owners` into `directory`, or one for every
+/// team and field of the schedule if `owners` is empty. Returns the paths written.
+#[tauri::command]
+pub(crate) async fn export_schedule_calendars(
+    app: AppHandle,
+    schedule_id: i32,
+    owners: Vec<CalendarOwner>,
+    directory: String,
+) -> Result<Vec<String>, ExportScheduleError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock
+        .database
+        .as_ref()
+        .ok_or(ExportScheduleError::NoDatabase)?;
+
+    let calendars = client
+        .export_schedule_calendars(schedule_id, &owners)
+        .await?;
+
+    let mut paths = vec![];
+
+    for calendar in calendars {
+        let path = Path::new(&directory).join(calendar.file_name());
+
+        std::fs::write(&path, calendar.ics())
+            .map_err(|e| ExportScheduleError::IOError(e.to_string()))?;
+
+        paths.push(path.to_string_lossy().into_owned());
+    }
+
+    Ok(paths)
+}
+
+/// Writes the games of a saved schedule that pass `filter` to `path` as a printable
+/// HTML booklet.
+#[tauri::command]
+pub(crate) async fn export_schedule_booklet(
+    app: AppHandle,
+    schedule_id: i32,
+    filter: ScheduleExportFilter,
+    path: String,
+) -> Result<(), ExportScheduleError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock
+        .database
+        .as_ref()
+        .ok_or(ExportScheduleError::NoDatabase)?;
+
+    let html = client.export_schedule_booklet(schedule_id, &filter).await?;
+
+    std::fs::write(path, html).map_err(|e| ExportScheduleError::IOError(e.to_string()))
+}
+
+/// Writes every row of the active profile to `path`, see [`db::ProfileBundle`].
+#[tauri::command]
+pub(crate) async fn export_profile(app: AppHandle, path: String) -> Result<(), ProfileBundleError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock
+        .database
+        .as_ref()
+        .ok_or(ProfileBundleError::NoDatabase)?;
+
+    let json = client.export_profile_bundle().await?.to_json()?;
+
+    std::fs::write(path, json).map_err(|e| ProfileBundleError::IOError(e.to_string()))
+}
+
+#[derive(Debug, Error, Serialize, Deserialize)]
+pub enum ImportProfileError {
+    #[error(transparent)]
+    CreateProfile(#[from] CreateProfileError),
+    #[error(transparent)]
+    Bundle(#[from] ProfileBundleError),
+    #[error("could not initialize the new profile: {0}")]
+    DatabaseInitError(String),
+}
+
+/// Creates a profile called `name` from the bundle at `path`. The profile is removed
+/// again if the bundle could not be imported.
+#[tauri::command]
+pub(crate) async fn import_profile(
+    app: AppHandle,
+    name: NameMax64,
+    path: String,
+) -> Result<ProfileImportReport, ImportProfileError> {
+    let json =
+        std::fs::read_to_string(path).map_err(|e| ProfileBundleError::IOError(e.to_string()))?;
+
+    let bundle = ProfileBundle::from_json(&json)?;
+
+    let name = create_new_profile(app.clone(), name).await?;
+
+    let profile_directory = app
+        .path_resolver()
+        .app_data_dir()
+        .ok_or(CreateProfileError::MissingAppData)?
+        .join("profiles")
+        .join(&name);
+
+    let db_path = format!(
+        "sqlite:{}?mode=rwc",
+        profile_directory.join("data.sqlite").to_string_lossy()
+    );
+
+    let result = match db::Client::new(&db::Config::new(db_path)).await {
+        Ok(client) => client
+            .import_profile_bundle(bundle)
+            .await
+            .map_err(ImportProfileError::from),
+        Err(e) => Err(ImportProfileError::DatabaseInitError(format!(
+            "{e:?} {}:{}",
+            line!(),
+            column!()
+        ))),
+    };
+
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(&profile_directory)
+            .inspect_err(|e| eprintln!("could not clean up {name}: {e:?}"));
+    }
+
+    result
+}
+
+/// Undoes the last edit of the active profile, returning the step that was undone.
+#[tauri::command]
+pub(crate) async fn undo(app: AppHandle) -> Result<Option<db::journal_step::Model>, JournalError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(JournalError::NoDatabase)?;
+
+    client.undo().await
+}
+
+/// Redoes the edit that was undone last, returning the step that was redone.
+#[tauri::command]
+pub(crate) async fn redo(app: AppHandle) -> Result<Option<db::journal_step::Model>, JournalError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(JournalError::NoDatabase)?;
+
+    client.redo().await
+}
+
+#[tauri::command]
+pub(crate) async fn get_journal_history(
+    app: AppHandle,
+) -> Result<Vec<db::journal_step::Model>, JournalError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(JournalError::NoDatabase)?;
+
+    client.journal_history().await
+}
+
+/// Attributes the edits that follow, in this and any profile selected later, to `user`.
+/// Called by the webview whenever someone signs in or out.
+#[tauri::command]
+pub(crate) async fn set_audit_user(app: AppHandle, user: Option<String>) -> Result<(), AuditError> {
+    let state = app.state::<SafeAppState>();
+    let mut lock = state.0.lock().await;
+
+    lock.audit_user = user.clone();
+
+    match lock.database.as_ref() {
+        Some(client) => client.set_audit_user(user).await,
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_audit_log(
+    app: AppHandle,
+    query: AuditQuery,
+) -> Result<Vec<db::audit_log::Model>, AuditError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(AuditError::NoDatabase)?;
+
+    client.get_audit_log(&query).await
+}
+
+/// Writes the audit log entries that match `query` to `path` as CSV.
+#[tauri::command]
+pub(crate) async fn export_audit_log_csv(
+    app: AppHandle,
+    query: AuditQuery,
+    path: String,
+) -> Result<(), AuditError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(AuditError::NoDatabase)?;
+
+    let csv = client.export_audit_log_csv(&query).await?;
+
+    std::fs::write(path, csv).map_err(|e| AuditError::IOError(e.to_string()))
+}
+
+/// Copies the current games of `schedule_id` into a new revision.
+#[tauri::command]
+pub(crate) async fn snapshot_schedule(
+    app: AppHandle,
+    schedule_id: i32,
+    label: Option<String>,
+) -> Result<db::schedule_revision::Model, ScheduleRevisionError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock
+        .database
+        .as_ref()
+        .ok_or(ScheduleRevisionError::NoDatabase)?;
+
+    client.snapshot_schedule(schedule_id, label).await
+}
+
+#[tauri::command]
+pub(crate) async fn get_schedule_revisions(
+    app: AppHandle,
+    schedule_id: i32,
+) -> Result<Vec<db::schedule_revision::Model>, ScheduleRevisionError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock
+        .database
+        .as_ref()
+        .ok_or(ScheduleRevisionError::NoDatabase)?;
+
+    client.get_schedule_revisions(schedule_id).await
+}
+
+#[tauri::command]
+pub(crate) async fn diff_schedules(
+    app: AppHandle,
+    from: ScheduleSource,
+    to: ScheduleSource,
+) -> Result<ScheduleDiff, ScheduleRevisionError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock
+        .database
+        .as_ref()
+        .ok_or(ScheduleRevisionError::NoDatabase)?;
+
+    client.diff_schedules(from, to).await
+}
+
+#[tauri::command]
+pub(crate) async fn record_game_result(
+    app: AppHandle,
+    input: RecordGameResultInput,
+) -> Result<db::schedule_game::Model, GameResultError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(GameResultError::NoDatabase)?;
+
+    client.record_game_result(input).await
+}
+
+/// Standings of every target from the results of `schedule_id`. Leaving out `config`
+/// uses 3/1/0 points and head-to-head, goal difference and goals for as tie-breakers.
+#[tauri::command]
+pub(crate) async fn get_standings(
+    app: AppHandle,
+    schedule_id: i32,
+    config: Option<StandingsConfig>,
+) -> Result<Vec<Standings>, GameResultError> {
+    let state = app.state::<SafeAppState>();
+    let lock = state.0.lock().await;
+    let client = lock.database.as_ref().ok_or(GameResultError::NoDatabase)?;
+
+    client
+        .get_standings(schedule_id, &config.unwrap_or_default())
+        .await
+}
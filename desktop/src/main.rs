@@ -21,6 +21,8 @@ struct State {
     database: Option<Client>,
     connection_pool: Option<reqwest::Client>,
     metadata_store: Option<Store<Wry>>,
+    /// The signed-in account, which edits are attributed to in the audit log
+    audit_user: Option<String>,
 }
 
 pub(crate) const ACTIVE_PROFILE_BIN_KEY: &str = "active_profile";
@@ -180,7 +182,39 @@ fn main() -> Result<()> {
             delete_profile,
             rename_profile,
             set_reservation_type_practice,
+            set_reservation_type_rematch_spacing,
             swap_schedule_games,
+            check_schedule_edit,
+            create_blackout,
+            get_blackouts,
+            delete_blackout,
+            close_field,
+            get_field_closures,
+            get_field_closure_report,
+            delete_closed_time_slots,
+            delete_field_closure,
+            validate_schedule,
+            export_problem_file,
+            import_teams,
+            export_schedule_csv,
+            export_schedule_calendars,
+            export_schedule_booklet,
+            export_profile,
+            import_profile,
+            undo,
+            redo,
+            get_journal_history,
+            set_audit_user,
+            get_audit_log,
+            export_audit_log_csv,
+            snapshot_schedule,
+            get_schedule_revisions,
+            diff_schedules,
+            record_game_result,
+            get_standings,
+            rain_out,
+            replace_closed_games,
+            apply_makeups,
         ])
         .run(tauri::generate_context!())
         .inspect_err(|e| {
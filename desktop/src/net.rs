@@ -46,67 +46,8 @@ where
         .enumerate()
         .map(
             |(i, non_message)| grpc_server::proto::algo_input::ScheduledInput {
-                fields: non_message
-                    .fields()
-                    .iter()
-                    .map(|field| grpc_server::proto::algo_input::Field {
-                        unique_id: field.unique_id().try_into().expect("field id"),
-                        time_slots: field
-                            .time_slots()
-                            .as_ref()
-                            .iter()
-                            .map(|(time_slot, concurrency)| {
-                                grpc_server::proto::algo_input::TimeSlot {
-                                    start: time_slot.0,
-                                    end: time_slot.1,
-                                    concurrency: *concurrency as u32,
-                                }
-                            })
-                            .collect(),
-                    })
-                    .collect(),
-                team_groups: non_message
-                    .team_groups()
-                    .iter()
-                    .map(
-                        |team_group| grpc_server::proto::algo_input::PlayableTeamCollection {
-                            teams: team_group
-                                .teams()
-                                .as_ref()
-                                .iter()
-                                .map(|team| grpc_server::proto::algo_input::Team {
-                                    unique_id: team.unique_id().try_into().expect("team id"),
-                                })
-                                .collect(),
-                        },
-                    )
-                    .collect(),
                 unique_id: i as u32,
-                coach_conflicts: non_message
-                    .coach_conflicts()
-                    .iter()
-                    .map(
-                        |coach_conflict| grpc_server::proto::algo_input::CoachConflict {
-                            region_id: coach_conflict
-                                .region_id()
-                                .try_into()
-                                .expect("coach conflict region id"),
-                            unique_id: coach_conflict
-                                .unique_id()
-                                .try_into()
-                                .expect("coach conflict id"),
-                            teams: coach_conflict
-                                .teams()
-                                .as_ref()
-                                .iter()
-                                .map(|team| grpc_server::proto::algo_input::Team {
-                                    unique_id: team.unique_id().try_into().expect("team id"),
-                                })
-                                .collect(),
-                        },
-                    )
-                    .collect(),
-                is_practice: non_message.is_practice(),
+                ..non_message.into()
             },
         )
         .collect::<Vec<_>>();
@@ -1,4 +1,5 @@
 use std::net::SocketAddr;
+use std::num::NonZeroUsize;
 
 use grpc_server::server::scheduler::{ScheduleManager, SchedulerServer};
 use tonic::transport::Server;
@@ -39,7 +40,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .set_serving::<SchedulerServer<ScheduleManager>>()
         .await;
 
-    let scheduler = SchedulerServer::new(ScheduleManager);
+    // solves running at once over every request, one less than the cores by default
+    let manager = match std::env::var("MAX_CONCURRENT_SOLVES") {
+        Ok(limit) => ScheduleManager::new(limit.parse::<NonZeroUsize>().map_err(Box::new)?),
+        Err(_) => ScheduleManager::default(),
+    };
+
+    let scheduler = SchedulerServer::new(manager);
 
     tracing::info!("Using server: {scheduler:?}");
     tracing::info!("Health check active");
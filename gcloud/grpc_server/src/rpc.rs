@@ -1,10 +1,14 @@
 use std::fmt::Debug;
+use std::num::NonZeroUsize;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::Instant;
 
 use algo_input::scheduler_server::Scheduler;
 use algo_input::{ScheduledInput, ScheduledOutput};
-use backend::{CoachConflictLike, FieldLike, PlayableTeamCollection, TeamLike};
+use backend::{CoachConflictLike, FieldLike, PlayableTeamCollection, SolveOptions, TeamLike, Tz};
+use tokio::sync::{mpsc, Semaphore};
+use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::{Stream, StreamExt};
 use tonic::{Request, Response, Status};
 
@@ -14,8 +18,51 @@ pub mod algo_input {
     tonic::include_proto!("algo_input");
 }
 
-#[derive(Debug)]
-pub struct ScheduleManager;
+/// How many finished schedules may wait to be sent before solvers block.
+const OUTPUT_BUFFER: usize = 16;
+
+/// Most alternatives a single input may ask for. More alternatives mean more solves,
+/// so a large `top_k` would let one request hold a permit for much longer.
+const MAX_TOP_K: u8 = 5;
+
+#[derive(Debug, Clone)]
+pub struct ScheduleManager {
+    /// Shared by every request, so this caps the number of solves running on the server.
+    solver_permits: Arc<Semaphore>,
+    /// Threads of each tree search. Permits times runners never exceeds the solver cores.
+    runners: NonZeroUsize,
+}
+
+/// Every available core but one, which is left to serve requests.
+fn solver_cores() -> NonZeroUsize {
+    let cores = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+
+    NonZeroUsize::new(cores - 1).unwrap_or(NonZeroUsize::MIN)
+}
+
+impl ScheduleManager {
+    /// Splits the solver cores evenly between up to `max_concurrent_solves` solves. There
+    /// are never more solves than cores.
+    pub fn new(max_concurrent_solves: NonZeroUsize) -> Self {
+        Self::with_cores(max_concurrent_solves, solver_cores())
+    }
+
+    fn with_cores(max_concurrent_solves: NonZeroUsize, cores: NonZeroUsize) -> Self {
+        let solves = max_concurrent_solves.min(cores);
+
+        Self {
+            solver_permits: Arc::new(Semaphore::new(solves.get())),
+            runners: NonZeroUsize::new(cores.get() / solves.get()).unwrap_or(NonZeroUsize::MIN),
+        }
+    }
+}
+
+impl Default for ScheduleManager {
+    /// One single-threaded solve per solver core.
+    fn default() -> Self {
+        Self::new(solver_cores())
+    }
+}
 
 impl TeamLike for algo_input::Team {
     fn unique_id(&self) -> i32 {
@@ -23,6 +70,10 @@ impl TeamLike for algo_input::Team {
             .try_into()
             .expect("unique team id could not fit in a 32-bit int")
     }
+
+    fn rating(&self) -> Option<i32> {
+        self.rating
+    }
 }
 
 impl FieldLike for algo_input::Field {
@@ -48,6 +99,21 @@ impl FieldLike for algo_input::Field {
             })
             .collect::<Vec<_>>()
     }
+
+    /// Zones are checked with [`unknown_time_zone`] when the input arrives.
+    fn time_zone(&self) -> Tz {
+        self.time_zone.parse().unwrap_or(Tz::UTC)
+    }
+}
+
+/// The first field whose time zone is not a known IANA name. Such inputs are rejected,
+/// rather than solved in UTC with every rule that counts days shifted. An empty zone
+/// means UTC.
+fn unknown_time_zone(input: &algo_input::ScheduledInput) -> Option<&algo_input::Field> {
+    input
+        .fields
+        .iter()
+        .find(|field| !field.time_zone.is_empty() && field.time_zone.parse::<Tz>().is_err())
 }
 
 impl PlayableTeamCollection for algo_input::PlayableTeamCollection {
@@ -98,6 +164,102 @@ impl From<algo_input::ScheduledInput>
             value.fields,
             value.coach_conflicts,
         )
+        .with_rematch_spacing(value.rematch_spacing.and_then(|spacing| spacing.into()))
+        .with_top_k(value.top_k.try_into().unwrap_or(u8::MAX).min(MAX_TOP_K))
+    }
+}
+
+fn team_message(team: &impl TeamLike) -> algo_input::Team {
+    algo_input::Team {
+        unique_id: team.unique_id().try_into().expect("team id"),
+        rating: team.rating(),
+    }
+}
+
+impl<T, P, F, C> From<&backend::ScheduledInput<T, P, F, C>> for algo_input::ScheduledInput
+where
+    T: TeamLike + Clone + Debug + PartialEq + Send,
+    P: PlayableTeamCollection<Team = T> + Send,
+    F: FieldLike + Clone + Debug + PartialEq + Send,
+    C: CoachConflictLike + Send,
+{
+    fn from(value: &backend::ScheduledInput<T, P, F, C>) -> Self {
+        Self {
+            fields: value
+                .fields()
+                .iter()
+                .cloned()
+                .map(algo_input::Field::generic_from_impl)
+                .collect(),
+            team_groups: value
+                .team_groups()
+                .iter()
+                .map(|team_group| algo_input::PlayableTeamCollection {
+                    teams: team_group
+                        .teams()
+                        .as_ref()
+                        .iter()
+                        .map(team_message)
+                        .collect(),
+                })
+                .collect(),
+            unique_id: value
+                .unique_id()
+                .try_into()
+                .expect("ScheduledInput unique_id"),
+            coach_conflicts: value
+                .coach_conflicts()
+                .iter()
+                .map(|coach_conflict| algo_input::CoachConflict {
+                    region_id: coach_conflict
+                        .region_id()
+                        .try_into()
+                        .expect("coach conflict region id"),
+                    unique_id: coach_conflict
+                        .unique_id()
+                        .try_into()
+                        .expect("coach conflict id"),
+                    teams: coach_conflict
+                        .teams()
+                        .as_ref()
+                        .iter()
+                        .map(team_message)
+                        .collect(),
+                })
+                .collect(),
+            is_practice: value.is_practice(),
+            rematch_spacing: value.rematch_spacing().map(Into::into),
+            top_k: value.top_k().into(),
+        }
+    }
+}
+
+impl From<algo_input::RematchSpacing> for Option<backend::RematchSpacing> {
+    fn from(value: algo_input::RematchSpacing) -> Self {
+        use algo_input::rematch_spacing::Unit;
+
+        // anything wider than a season already can't be met, so clamp instead of failing
+        match value.unit? {
+            Unit::Days(days) => Some(backend::RematchSpacing::Days(
+                days.try_into().unwrap_or(u16::MAX),
+            )),
+            Unit::Rounds(rounds) => Some(backend::RematchSpacing::Rounds(
+                rounds.try_into().unwrap_or(u16::MAX),
+            )),
+        }
+    }
+}
+
+impl From<backend::RematchSpacing> for algo_input::RematchSpacing {
+    fn from(value: backend::RematchSpacing) -> Self {
+        use algo_input::rematch_spacing::Unit;
+
+        Self {
+            unit: Some(match value {
+                backend::RematchSpacing::Days(days) => Unit::Days(days.into()),
+                backend::RematchSpacing::Rounds(rounds) => Unit::Rounds(rounds.into()),
+            }),
+        }
     }
 }
 
@@ -123,6 +285,7 @@ impl algo_input::Field {
                     concurrency: *concurrency as u32,
                 })
                 .collect::<Vec<_>>(),
+            time_zone: value.time_zone().name().to_owned(),
         }
     }
 }
@@ -148,14 +311,17 @@ where
                         } => Some(algo_input::reservation::Booked {
                             home_team: Some(algo_input::Team {
                                 unique_id: home_team.unique_id().try_into().expect("home team"),
+                                rating: home_team.rating(),
                             }),
                             away_team: Some(algo_input::Team {
                                 unique_id: away_team.unique_id().try_into().expect("away team"),
+                                rating: away_team.rating(),
                             }),
                         }),
                         Practice(team) => Some(algo_input::reservation::Booked {
                             home_team: Some(algo_input::Team {
                                 unique_id: team.unique_id().try_into().expect("practice team"),
+                                rating: team.rating(),
                             }),
                             away_team: None,
                         }),
@@ -171,10 +337,38 @@ where
                     }
                 })
                 .collect::<Vec<_>>(),
+            byes: value
+                .byes()
+                .iter()
+                .map(|bye| algo_input::Bye {
+                    team: Some(algo_input::Team {
+                        unique_id: bye.team().unique_id().try_into().expect("bye team"),
+                        rating: bye.team().rating(),
+                    }),
+                    start: bye.start(),
+                    end: bye.end(),
+                })
+                .collect::<Vec<_>>(),
             unique_id: value
                 .unique_id()
                 .try_into()
                 .expect("ScheduledOutput unique_id"),
+            rank: value.rank().into(),
+            metrics: Some(value.metrics().into()),
+        }
+    }
+}
+
+impl From<&backend::ScheduleMetrics> for algo_input::ScheduleMetrics {
+    fn from(value: &backend::ScheduleMetrics) -> Self {
+        Self {
+            fillage: value.fillage(),
+            rematch_spacing: value
+                .rematch_spacing()
+                .iter()
+                .map(|(days, occurrences)| ((*days).into(), *occurrences))
+                .collect(),
+            score: value.score().into(),
         }
     }
 }
@@ -243,33 +437,147 @@ impl Scheduler for ScheduleManager {
 
         let mut stream = request.into_inner();
 
-        let output = async_stream::try_stream! {
-            while let Some(schedule_payload) = stream.next().await {
-                let schedule_payload: algo_input::ScheduledInput = schedule_payload?;
+        let (sender, receiver) = mpsc::channel(OUTPUT_BUFFER);
+        let solver_permits = self.solver_permits.clone();
+        let options = SolveOptions::default().with_runners(Some(self.runners));
 
-                let backend_payload: backend::ScheduledInput<_, _, _, _> = schedule_payload.into();
+        // Inputs are independent, so each one is solved on the blocking pool as soon as a
+        // permit frees up, and its outputs are streamed back as soon as they are ready.
+        // Permits come from the manager, so they are shared with every other call.
+        tokio::spawn(async move {
+            while let Some(schedule_payload) = stream.next().await {
+                if sender.is_closed() {
+                    tracing::warn!("Client hung up, its remaining inputs are not solved");
+                    return;
+                }
 
-                tracing::info!("Received payload (fields: {}, teams: {}, practice: {})", backend_payload.fields().as_ref().len(), backend_payload.teams_len(), backend_payload.is_practice());
+                let schedule_payload: algo_input::ScheduledInput = match schedule_payload {
+                    Ok(schedule_payload) => schedule_payload,
+                    Err(status) => {
+                        let _ = sender.send(Err(status)).await;
+                        return;
+                    }
+                };
+
+                let unique_id = schedule_payload.unique_id;
+
+                if let Some(field) = unknown_time_zone(&schedule_payload) {
+                    let message = format!(
+                        "input {unique_id}: field {} has unknown time zone `{}`",
+                        field.unique_id, field.time_zone
+                    );
+                    tracing::error!("{message}");
+                    let _ = sender.send(Err(Status::invalid_argument(message))).await;
+                    return;
+                }
 
-                let start = Instant::now();
+                let backend_payload: backend::ScheduledInput<_, _, _, _> = schedule_payload.into();
 
-                let result = backend::schedule(backend_payload);
+                tracing::info!(
+                    "Received payload {unique_id} (fields: {}, teams: {}, practice: {})",
+                    backend_payload.fields().as_ref().len(),
+                    backend_payload.teams_len(),
+                    backend_payload.is_practice()
+                );
+
+                // a solve cannot be stopped once it runs, so one that nobody will receive
+                // must not start, nor hold up the queue for a permit
+                let permit = tokio::select! {
+                    permit = solver_permits.clone().acquire_owned() => {
+                        let Ok(permit) = permit else {
+                            unreachable!("the solver semaphore is never closed");
+                        };
+                        permit
+                    }
+                    () = sender.closed() => {
+                        tracing::warn!("Client hung up before {unique_id} was solved");
+                        return;
+                    }
+                };
+
+                let sender = sender.clone();
+
+                tokio::spawn(async move {
+                    let start = Instant::now();
+
+                    let result = tokio::task::spawn_blocking(move || {
+                        let _permit = permit;
+                        backend::schedule_with(backend_payload, &options)
+                    })
+                    .await;
+
+                    let end = Instant::now();
+
+                    tracing::info!("Scheduled {unique_id} in {:?}", end.duration_since(start));
+
+                    let outputs = match result {
+                        Ok(Ok(outputs)) => outputs,
+                        Ok(Err(e)) => {
+                            tracing::error!("{e}");
+                            let _ = sender
+                                .send(Err(Status::new(
+                                    tonic::Code::Cancelled,
+                                    format!("input {unique_id}: {e}"),
+                                )))
+                                .await;
+                            return;
+                        }
+                        Err(e) => {
+                            tracing::error!("Solver for {unique_id} did not finish: {e}");
+                            let _ = sender
+                                .send(Err(Status::internal(format!(
+                                    "input {unique_id}: solver did not finish"
+                                ))))
+                                .await;
+                            return;
+                        }
+                    };
 
-                let end = Instant::now();
+                    for output in outputs {
+                        if sender.send(Ok(output.into())).await.is_err() {
+                            tracing::warn!("Client hung up before {unique_id} was sent");
+                            return;
+                        }
+                    }
+                });
+            }
+        });
 
-                tracing::info!("Scheduled in {:?}", end.duration_since(start));
+        let output = ReceiverStream::new(receiver);
 
-                if let Err(ref e) = result {
-                    tracing::error!("{e}");
-                }
+        Ok(Response::new(Box::pin(output) as Self::ScheduleStream))
+    }
+}
 
-                yield
-                    result
-                    .map_err(|e| Status::new(tonic::Code::Cancelled, e.to_string()))?
-                    .into();
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::ScheduleManager;
+
+    #[test]
+    fn solves_never_use_more_threads_than_cores() {
+        for cores in 1..=16 {
+            for max_concurrent_solves in 1..=20 {
+                let manager = ScheduleManager::with_cores(
+                    NonZeroUsize::new(max_concurrent_solves).unwrap(),
+                    NonZeroUsize::new(cores).unwrap(),
+                );
+
+                let solves = manager.solver_permits.available_permits();
+
+                assert!(solves >= 1);
+                assert!(solves <= max_concurrent_solves);
+                assert!(
+                    solves * manager.runners.get() <= cores,
+                    "{solves} solves of {} runners on {cores} cores",
+                    manager.runners
+                );
             }
-        };
+        }
 
-        Ok(Response::new(Box::pin(output) as Self::ScheduleStream))
+        // a single solve gets every core
+        let manager = ScheduleManager::with_cores(NonZeroUsize::MIN, NonZeroUsize::new(8).unwrap());
+        assert_eq!(manager.runners.get(), 8);
     }
 }
@@ -1,10 +1,13 @@
 export interface Region {
 	id: number;
 	title: string;
+	/** IANA time zone, e.g. `America/Los_Angeles` */
+	time_zone: string;
 }
 
 export interface CreateRegionInput {
 	title: string;
+	time_zone?: string;
 }
 
 export interface Field {
@@ -33,12 +36,14 @@ export interface Team {
 	id: number;
 	name: string;
 	region_owner: number;
+	rating: number | null;
 }
 
 export interface CreateTeamInput {
 	name: string;
 	region_id: number;
 	tags: string[];
+	rating?: number;
 }
 
 export interface TeamGroup {
@@ -46,11 +51,12 @@ export interface TeamGroup {
 	name: string;
 }
 
+/** `start` and `end` are UNIX milliseconds */
 export interface TimeSlot {
 	id: number;
 	field_id: number;
-	start: string;
-	end: string;
+	start: number;
+	end: number;
 }
 
 export interface TimeSlotExtension {
@@ -89,7 +95,10 @@ export type MoveTimeSlotInput = {
 	id: number;
 	new_start: number;
 	new_end: number;
-} & ({ field_id: number; schedule_id?: never } | { schedule_id: number; field_id?: never });
+} & (
+	| { field_id: number; schedule_id?: never }
+	| { schedule_id: number; field_id?: never; force?: boolean }
+);
 
 export interface ListReservationsBetweenInput {
 	start: number;
@@ -135,6 +144,23 @@ export async function eventFromGame(
 	input: ScheduleGame,
 	teamGetter: (id: number) => Promise<TeamExtension>
 ): Promise<CalendarEvent> {
+	if (input.is_bye && Number.isInteger(input.team_one)) {
+		const team = await teamGetter(input.team_one!);
+
+		// a bye spans its whole round, so it cannot be moved like a game
+		return {
+			allDay: true,
+			editable: false,
+			display: 'auto',
+			id: String(input.id),
+			resources: [],
+			start: new Date(input.start),
+			end: new Date(input.end),
+			backgroundColor: '#808080',
+			title: `${team.team.name} – Bye`
+		};
+	}
+
 	let title = 'Empty';
 
 	let practice = false;
@@ -164,12 +190,15 @@ export async function eventFromGame(
 export interface EditRegionInput {
 	id: number;
 	name?: string;
+	time_zone?: string;
 }
 
 export interface EditTeamInput {
 	id: number;
 	name?: string;
 	tags?: string[];
+	/** `null` clears the rating */
+	rating?: number | null;
 }
 
 export interface Target {
@@ -281,6 +310,7 @@ export interface PreScheduleReport {
 	total_matches_required: number;
 	total_matches_supplied: number;
 	interregional: boolean;
+	blackout_impact: BlackoutImpact[];
 }
 
 export interface PreScheduleReportInput {
@@ -295,8 +325,13 @@ export interface ReservationType {
 	default_sizing: number;
 	description?: string;
 	is_practice: boolean;
+	/** At most one of the two is set, see `RematchSpacing` */
+	rematch_spacing_days?: number | null;
+	rematch_spacing_rounds?: number | null;
 }
 
+export type RematchSpacing = { Days: number } | { Rounds: number };
+
 export interface CreateReservationTypeInput {
 	name: string;
 	color: string;
@@ -339,6 +374,8 @@ export const SHOW_SCHEDULER_URL_WHILE_WAITING: boolean = false;
 export interface FieldExtension {
 	field_id: number;
 	time_slots: TimeSlotExtension[];
+	/** IANA name of the zone of the field's region */
+	time_zone: string;
 }
 
 export interface PlayableTeamCollection {
@@ -349,6 +386,7 @@ export interface PlayableTeamCollection {
 export interface ScheduledInput {
 	team_groups: PlayableTeamCollection[];
 	fields: FieldExtension[];
+	top_k: number;
 }
 
 export interface Schedule {
@@ -378,14 +416,32 @@ export interface Delta {
 	inWeeks: boolean;
 }
 
+/** `start` and `end` are UNIX milliseconds */
 export interface ScheduleGame {
 	id: number;
 	schedule_id: number;
-	start: string;
-	end: string;
+	start: number;
+	end: number;
 	team_one?: number;
 	team_two?: number;
 	field_id?: number;
+	/** `team_one` sits out the round spanning `start` to `end` */
+	is_bye: boolean;
+	/** How many times the game was moved */
+	sequence: number;
+	status: GameStatus;
+	home_score?: number;
+	away_score?: number;
+}
+
+export type GameStatus = 'scheduled' | 'played' | 'home_forfeit' | 'away_forfeit' | 'cancelled';
+
+export interface RecordGameResultInput {
+	game_id: number;
+	status: GameStatus;
+	/** Required for played games, not allowed otherwise */
+	home_score?: number;
+	away_score?: number;
 }
 
 export interface OAuthAccessTokenExchange {
@@ -407,6 +463,312 @@ export interface RegionMetadata {
 	time_slot_count: number;
 }
 
+export interface Blackout {
+	id: number;
+	name: string;
+	region?: number;
+	reservation_type?: number;
+	/** UNIX milliseconds */
+	start: number;
+	/** UNIX milliseconds */
+	end: number;
+}
+
+export interface CreateBlackoutInput {
+	name: string;
+	region_id?: number;
+	reservation_type_id?: number;
+	start: number;
+	end: number;
+}
+
+export interface BlackoutImpact {
+	blackout: Blackout;
+	time_slots_removed: number;
+	matches_removed: number;
+}
+
+/** UNIX seconds */
+export interface AvailabilityWindow {
+	start: number;
+	end: number;
+}
+
+export type Violation =
+	| { TeamDoubleBooked: { team: number; first: AvailabilityWindow; second: AvailabilityWindow } }
+	| {
+			CoachConflict: {
+				coach_conflict: number;
+				team_one: number;
+				team_two: number;
+				first: AvailabilityWindow;
+				second: AvailabilityWindow;
+			};
+	  }
+	| { OutsideFieldAvailability: { field: number; availability: AvailabilityWindow } }
+	| {
+			ConcurrencyExceeded: {
+				field: number;
+				availability: AvailabilityWindow;
+				booked: number;
+				allowed: number;
+			};
+	  }
+	| {
+			RestPeriodBreached: {
+				team: number;
+				first: AvailabilityWindow;
+				second: AvailabilityWindow;
+				rest_minutes: number;
+			};
+	  };
+
+export interface ScheduleGameEdit {
+	game_id: number;
+	start: number;
+	end: number;
+	field_id?: number;
+}
+
+export type EditSeverity = 'Warning' | 'Error';
+
+export type EditIssueKind = { Rule: Violation } | { HasResult: GameStatus };
+
+export interface EditIssue {
+	severity: EditSeverity;
+	games: number[];
+	kind: EditIssueKind;
+}
+
+/** Errors first. `forced` is set when an edit was applied despite its errors */
+export interface EditReport {
+	issues: EditIssue[];
+	forced: boolean;
+}
+
+export interface TeamImportColumns {
+	name: string;
+	region?: string;
+	groups?: string;
+	coach?: string;
+}
+
+export interface ImportTeamsInput {
+	csv: string;
+	columns?: TeamImportColumns;
+	region_id?: number;
+	dry_run?: boolean;
+}
+
+export interface TeamImportRow {
+	line: number;
+	name: string;
+	region_id: number;
+	groups: string[];
+	coach?: string;
+}
+
+export type NameMax64ValidationError = 'EmptyName' | { NameTooLong: { len: number } };
+
+export type TeamImportRowErrorKind =
+	| { Name: NameMax64ValidationError }
+	| { Group: { group: string; error: NameMax64ValidationError } }
+	| { Coach: NameMax64ValidationError }
+	| 'MissingRegion'
+	| { UnknownRegion: string }
+	| { Duplicate: number }
+	| { Malformed: string };
+
+export interface TeamImportRowError {
+	line: number;
+	kind: TeamImportRowErrorKind;
+}
+
+export interface TeamImportCoach {
+	region_id: number;
+	coach_name: string;
+	coach_conflict_id?: number;
+}
+
+export interface TeamImportReport {
+	teams: TeamImportRow[];
+	errors: TeamImportRowError[];
+	groups_created: string[];
+	coaches_created: TeamImportCoach[];
+	coaches_merged: TeamImportCoach[];
+	committed: boolean;
+}
+
+export interface ScheduleExportFilter {
+	team_id?: number;
+	field_id?: number;
+	group_id?: number;
+	/** Local date, `YYYY-MM-DD` */
+	from?: string;
+	/** Local date, `YYYY-MM-DD` */
+	to?: string;
+}
+
+export type CalendarOwner = { Team: number } | { Field: number };
+
+export interface ProfileImportReport {
+	regions: number;
+	fields: number;
+	teams: number;
+	groups: number;
+	reservation_types: number;
+	time_slots: number;
+	targets: number;
+	coach_conflicts: number;
+	blackouts: number;
+	field_closures: number;
+	schedules: number;
+	schedule_games: number;
+	schedule_revisions: number;
+}
+
+export interface JournalStep {
+	id: number;
+	label: string;
+	created: string;
+	undone: boolean;
+}
+
+export interface AuditQuery {
+	entity?: string;
+	entity_id?: number;
+	/** First UTC date, YYYY-MM-DD */
+	from?: string;
+	/** Last UTC date, YYYY-MM-DD */
+	to?: string;
+}
+
+export interface AuditLogEntry {
+	id: number;
+	entity: string;
+	entity_id?: number;
+	action: 'insert' | 'update' | 'delete';
+	/** The row as JSON, if it existed before */
+	before?: string;
+	/** The row as JSON, if it exists after */
+	after?: string;
+	timestamp: string;
+	user?: string;
+}
+
+/** `created` is UNIX milliseconds */
+export interface ScheduleRevision {
+	id: number;
+	schedule_id: number;
+	label?: string;
+	created: number;
+}
+
+export type ScheduleSource = { Schedule: number } | { Revision: number };
+
+export interface DiffGame {
+	game_id: number;
+	start: string;
+	end: string;
+	team_one?: number;
+	team_one_name?: string;
+	team_two?: number;
+	team_two_name?: string;
+	field_id?: number;
+	field_name?: string;
+	is_bye: boolean;
+}
+
+export type GameChangeKind = 'Time' | 'Field' | 'Teams';
+
+export interface GameChange {
+	before: DiffGame;
+	after: DiffGame;
+	changes: GameChangeKind[];
+}
+
+export interface ScheduleDiff {
+	from: ScheduleSource;
+	to: ScheduleSource;
+	added: DiffGame[];
+	removed: DiffGame[];
+	changed: GameChange[];
+}
+
+export type TieBreaker = 'HeadToHead' | 'GoalDifference' | 'GoalsFor';
+
+export interface StandingsConfig {
+	points_for_win: number;
+	points_for_draw: number;
+	points_for_loss: number;
+	points_for_forfeit: number;
+	forfeit_score: number;
+	tie_breakers: TieBreaker[];
+}
+
+export interface StandingsLine {
+	team: Team;
+	/** Teams tied on points and every tie-breaker share a rank */
+	rank: number;
+	played: number;
+	wins: number;
+	draws: number;
+	losses: number;
+	goals_for: number;
+	goals_against: number;
+	points: number;
+}
+
+export interface Standings {
+	target_id: number;
+	groups: TeamGroup[];
+	rows: StandingsLine[];
+}
+
+/** Calendar days are in the time zone of each field's region, as `YYYY-MM-DD` */
+export interface RainoutInput {
+	schedule_id: number;
+	field_ids: number[];
+	dates: string[];
+}
+
+export interface MakeupProposal {
+	game_id: number;
+	field_id: number;
+	start: number;
+	end: number;
+}
+
+export interface Rainout {
+	cancelled: ScheduleGame[];
+	proposals: MakeupProposal[];
+	unplaced: number[];
+}
+
+export interface FieldClosure {
+	id: number;
+	field_id: number;
+	/** UNIX milliseconds */
+	start: number;
+	/** UNIX milliseconds */
+	end: number;
+	reason?: string;
+}
+
+export interface CreateFieldClosureInput {
+	field_id: number;
+	start: number;
+	end: number;
+	reason?: string;
+}
+
+/** Closed time slots are hidden until the closure is deleted, unless they are deleted */
+export interface FieldClosureReport {
+	closure: FieldClosure;
+	time_slots: TimeSlotExtension[];
+	games: ScheduleGame[];
+}
+
 export function handleProfileCreationError(
 	e: any,
 	toastStore: import('@skeletonlabs/skeleton').ToastStore,
@@ -471,6 +833,40 @@ export function handleProfileCreationError(
 	});
 }
 
+export function describeEditIssue(issue: EditIssue): string {
+	const kind =
+		'Rule' in issue.kind
+			? Object.keys(issue.kind.Rule)[0]
+			: `game already has a result (${issue.kind.HasResult})`;
+
+	return `${issue.severity}: ${kind}, games ${issue.games.join(', ')}`;
+}
+
+/**
+ * Runs an edit of a saved schedule. If the edit breaks the rules of the schedule, asks
+ * whether to force it. `undefined` if the edit was not applied.
+ */
+export async function applyScheduleEdit<T>(
+	edit: (force: boolean) => Promise<T>,
+	ask: typeof import('@tauri-apps/api/dialog').ask
+): Promise<T | undefined> {
+	try {
+		return await edit(false);
+	} catch (e: any) {
+		if (e === null || typeof e !== 'object' || !('Rejected' in e)) {
+			throw e;
+		}
+
+		const report: EditReport = e.Rejected;
+		const force = await ask(report.issues.map(describeEditIssue).join('\n'), {
+			title: 'This edit breaks the rules of the schedule. Apply it anyway?',
+			type: 'warning'
+		});
+
+		return force ? await edit(true) : undefined;
+	}
+}
+
 const ROUTES_WITH_PROFILE_QUERY_STATE = ['/region', '/reservations', '/schedules/view'];
 
 export function isRouteSafeToPersist(route: URL): boolean {